@@ -0,0 +1,203 @@
+//! Headless batch mode
+//!
+//! Loads a scene without opening a window, checks it for collisions and/or simulates a number of
+//! physics steps, then writes a JSON report with the results and per-step world state hashes so
+//! editor-authored scenarios can be checked in automated regression runs. With `--verify` it replays a
+//! determinism recording saved by the editor instead, checking that this build and platform compute the
+//! recorded states bit for bit.
+
+use crate::qphysics::QPhysicsPlugin;
+use crate::qphysics::components::{QMotion, QTransform};
+use crate::qphysics::resources::QPhysicsConfig;
+use crate::qphysics::systems::{hash_body_states, hash_world_state};
+use crate::save_load::components::{QPhysicsRecording, SceneFile, SerializableQShape};
+use crate::save_load::resources::LaunchOptions;
+use crate::save_load::systems::{
+    geometry_collision, load_project_from_file, load_recording_from_file, load_shapes_from_file, spawn_scene_shape,
+    spawn_snapshot_body,
+};
+use crate::shapes::components::ShapeLayer;
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+use qmath::vec2::QVec2;
+use serde::Serialize;
+
+/// Result of a batch run, written as JSON
+#[derive(Serialize, Debug)]
+pub struct BatchReport {
+    /// Path of the scene or project file that was run
+    pub scene: String,
+    pub shape_count: usize,
+    /// Checksum of the loaded shapes, see [`SceneFile::compute_checksum`]
+    pub scene_checksum: String,
+    /// Colliding shape pairs, `None` unless collisions were requested
+    pub collisions: Option<Vec<BatchCollision>>,
+    /// Number of simulated physics steps
+    pub steps: u32,
+    /// World state hash after each step, see [`hash_world_state`]
+    pub step_hashes: Vec<String>,
+}
+
+/// Result of replaying a determinism recording, written as JSON
+#[derive(Serialize, Debug)]
+pub struct VerifyReport {
+    /// Path of the recording that was replayed
+    pub recording: String,
+    /// Number of recorded steps
+    pub steps: usize,
+    /// Whether every step reproduced its recorded state hash
+    pub deterministic: bool,
+    /// First step whose state hash differs from the recording
+    pub diverged_at: Option<usize>,
+}
+
+/// A pair of colliding shapes, identified by their index in the scene file
+#[derive(Serialize, Debug)]
+pub struct BatchCollision {
+    pub a: usize,
+    pub b: usize,
+    pub a_name: String,
+    pub b_name: String,
+    /// Separation vector moving `a` out of `b`, if qgeometry can compute one for the pair
+    pub separation: Option<QVec2>,
+}
+
+/// Run the batch mode for the given launch options and write the report
+pub fn run(options: &LaunchOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let file_path = options.file_path.as_deref().ok_or("no scene file to run")?;
+    if options.verify {
+        return verify(file_path, options.output_path.as_deref());
+    }
+    let shapes = if options.opens_project() {
+        load_project_from_file(file_path)?.scene.shapes
+    } else {
+        load_shapes_from_file(file_path)?
+    };
+
+    let collisions = options.collisions.then(|| find_collisions(&shapes));
+    let report = BatchReport {
+        scene: file_path.to_string(),
+        shape_count: shapes.len(),
+        scene_checksum: format_hash(SceneFile::new(shapes.clone()).compute_checksum()),
+        collisions,
+        steps: options.steps,
+        step_hashes: simulate(shapes, options.steps)?.into_iter().map(format_hash).collect(),
+    };
+
+    write_report(&report, options.output_path.as_deref())
+}
+
+/// Replay a determinism recording and write the report, failing if the replay diverged
+fn verify(file_path: &str, output_path: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let recording = load_recording_from_file(file_path)?;
+    let diverged_at = replay(&recording);
+    let report = VerifyReport {
+        recording: file_path.to_string(),
+        steps: recording.hashes.len(),
+        deterministic: diverged_at.is_none(),
+        diverged_at,
+    };
+    write_report(&report, output_path)?;
+    match diverged_at {
+        Some(step) => Err(format!("the replay diverged from the recording at step {}", step).into()),
+        None => Ok(()),
+    }
+}
+
+/// Write a report as JSON to the output file, or standard output if there is none
+fn write_report(report: &impl Serialize, output_path: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let json = serde_json::to_string_pretty(report)?;
+    match output_path {
+        Some(output_path) => std::fs::write(output_path, json + "\n")?,
+        None => println!("{}", json),
+    }
+    Ok(())
+}
+
+/// Find every colliding pair of shapes, skipping generated shapes like the editor does
+fn find_collisions(shapes: &[SerializableQShape]) -> Vec<BatchCollision> {
+    let mut collisions = Vec::new();
+    for (i, a) in shapes.iter().enumerate() {
+        for (j, b) in shapes.iter().enumerate().skip(i + 1) {
+            if a.editor_shape.layer == ShapeLayer::Generated || b.editor_shape.layer == ShapeLayer::Generated {
+                continue;
+            }
+            let (collide, separation) = geometry_collision(&a.data, &b.data);
+            if collide {
+                collisions.push(BatchCollision {
+                    a: i,
+                    b: j,
+                    a_name: a.editor_shape.name.clone(),
+                    b_name: b.editor_shape.name.clone(),
+                    separation,
+                });
+            }
+        }
+    }
+    collisions
+}
+
+/// Simulate the shapes' physics bodies for a number of steps, returning the world state hash after each
+///
+/// The fixed update schedule is run directly instead of through the app loop, so the result doesn't
+/// depend on wall clock time.
+fn simulate(shapes: Vec<SerializableQShape>, steps: u32) -> Result<Vec<u64>, Box<dyn std::error::Error>> {
+    if steps == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, QPhysicsPlugin));
+    let world = app.world_mut();
+    {
+        let mut commands = world.commands();
+        for shape in shapes {
+            spawn_scene_shape(&mut commands, shape);
+        }
+    }
+    world.flush();
+
+    let mut hashes = Vec::with_capacity(steps as usize);
+    for _ in 0..steps {
+        world.run_schedule(FixedUpdate);
+        let hash = |bodies: Query<(Entity, &QTransform, &QMotion)>| hash_world_state(&bodies);
+        hashes.push(world.run_system_once(hash)?);
+    }
+    Ok(hashes)
+}
+
+/// Replay the recorded inputs from the recorded world, returning the first step whose state hash differs.
+/// Bodies are hashed in recording order, like the editor's recorder does.
+fn replay(recording: &QPhysicsRecording) -> Option<usize> {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, QPhysicsPlugin));
+    let world = app.world_mut();
+    let entities: Vec<Entity> = {
+        let mut commands = world.commands();
+        recording
+            .world
+            .bodies
+            .iter()
+            .map(|body| spawn_snapshot_body(&mut commands, body))
+            .collect()
+    };
+    world.flush();
+
+    for (step, (input, recorded)) in recording.inputs.iter().zip(recording.hashes.iter()).enumerate() {
+        *world.resource_mut::<QPhysicsConfig>() = input.clone();
+        world.run_schedule(FixedUpdate);
+        let hash = hash_body_states(entities.iter().filter_map(|entity| {
+            let entity = world.get_entity(*entity).ok()?;
+            Some((entity.get::<QTransform>()?, entity.get::<QMotion>()?))
+        }));
+        if hash != *recorded {
+            return Some(step);
+        }
+    }
+    None
+}
+
+/// Hashes are written as hex strings so JSON readers without 64-bit integers keep them exact
+fn format_hash(hash: u64) -> String {
+    format!("{:016x}", hash)
+}
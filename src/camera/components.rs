@@ -7,4 +7,82 @@ pub struct CameraMovement {
     pub dragging: bool,
     /// The previous mouse position when dragging started
     pub last_mouse_position: Vec2,
+    /// Button that started the drag, releasing it ends the drag
+    pub drag_button: Option<MouseButton>,
+    /// World units per second the view moved during the drag, kept gliding after release with pan inertia
+    pub velocity: Vec2,
+}
+
+/// Pose the camera is animated towards, removed once it is reached
+#[derive(Component, Debug, Clone, Copy)]
+pub struct CameraTarget {
+    /// World position the camera centers on
+    pub position: Vec2,
+    /// Camera scale, in world units per pixel
+    pub zoom: f32,
+    /// World position the animation started from
+    pub start_position: Vec2,
+    /// Camera scale the animation started from
+    pub start_zoom: f32,
+    /// Seconds since the animation started
+    pub elapsed: f32,
+}
+
+impl CameraTarget {
+    /// Animate from the current camera transform towards `position` and `zoom`
+    pub fn new(from: &Transform, position: Vec2, zoom: f32) -> Self {
+        Self {
+            position,
+            zoom,
+            start_position: from.translation.truncate(),
+            start_zoom: from.scale.x,
+            elapsed: 0.0,
+        }
+    }
+}
+
+/// Events to trigger moving the camera back to the home pose of [`CameraSettings`]
+///
+/// [`CameraSettings`]: super::resources::CameraSettings
+#[derive(Message, Clone, Copy)]
+pub struct ResetViewEvent;
+
+/// Events to trigger framing the selected shapes, or every shape when `all` is set, like F and Shift+F
+#[derive(Message, Clone, Copy)]
+pub struct FrameShapesEvent {
+    pub all: bool,
+}
+
+/// Physics body the camera keeps centered, removed when the body disappears or the user pans
+#[derive(Component, Debug, Clone, Copy)]
+pub struct CameraFollow {
+    /// Entity carrying the followed body's `QTransform`
+    pub target: Entity,
+}
+
+/// Events to start or stop following a physics body with the camera
+#[derive(Message, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CameraFollowEvent {
+    /// Follow the first selected shape that is a physics body
+    FollowSelected,
+    /// Stop following
+    Stop,
+}
+
+/// Camera of the second viewport shown while the view is split, with its own pan and zoom
+#[derive(Component, Default)]
+pub struct SecondaryCamera {
+    /// Whether the camera is currently being dragged
+    pub dragging: bool,
+    /// The previous mouse position when dragging started
+    pub last_mouse_position: Vec2,
+}
+
+/// Debug shake applied to the camera after a large collision impulse
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct CameraShake {
+    /// Shake strength from 0 to 1, decaying over time
+    pub trauma: f32,
+    /// Offset currently added to the camera translation, removed before the next frame's input
+    pub offset: Vec2,
 }
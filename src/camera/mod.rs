@@ -1,5 +1,6 @@
 pub mod components;
 pub mod plugin;
+pub mod resources;
 pub mod systems;
 
 pub use plugin::CameraControlPlugin;
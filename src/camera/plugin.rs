@@ -1,7 +1,9 @@
 //! Camera control plugin
 //!
-//! This module implements simple pan and zoom camera controls using mouse input.
+//! This module implements simple pan and zoom camera controls using mouse and keyboard input.
 
+use super::components::{CameraFollowEvent, FrameShapesEvent, ResetViewEvent};
+use super::resources::{CameraBookmarks, CameraSettings, SplitView};
 use super::systems::*;
 use bevy::prelude::*;
 use bevy_egui::EguiStartupSet;
@@ -11,7 +13,33 @@ pub struct CameraControlPlugin;
 
 impl Plugin for CameraControlPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(PreStartup, setup.before(EguiStartupSet::InitContexts))
-            .add_systems(Update, (camera_pan, camera_zoom));
+        app.init_resource::<CameraSettings>()
+            .init_resource::<CameraBookmarks>()
+            .init_resource::<SplitView>()
+            .add_message::<ResetViewEvent>()
+            .add_message::<FrameShapesEvent>()
+            .add_message::<CameraFollowEvent>()
+            .add_systems(PreStartup, setup.before(EguiStartupSet::InitContexts))
+            .add_systems(
+                Update,
+                (
+                    remove_camera_shake,
+                    update_split_view,
+                    camera_pan,
+                    camera_pan_inertia,
+                    camera_keyboard_pan,
+                    camera_zoom,
+                    zoom_preset_keys,
+                    frame_shapes,
+                    reset_view,
+                    camera_bookmarks,
+                    handle_camera_follow_request,
+                    follow_body,
+                    animate_camera,
+                    camera_shake,
+                    secondary_camera_input,
+                )
+                    .chain(),
+            );
     }
 }
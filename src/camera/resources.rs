@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Resource containing camera settings
+#[derive(Resource, Debug, Clone)]
+pub struct CameraSettings {
+    /// World position the Home command centers the camera on
+    pub home_position: Vec2,
+    /// Camera scale the Home command restores, in world units per pixel
+    pub home_zoom: f32,
+    /// Seconds camera movements take to ease into place, 0 jumps instantly
+    pub easing_duration: f32,
+    /// Smallest camera scale, i.e. the closest zoom
+    pub min_zoom: f32,
+    /// Largest camera scale, i.e. the farthest zoom
+    pub max_zoom: f32,
+    /// Fraction the zoom changes by per mouse wheel step
+    pub zoom_speed: f32,
+    /// Multiplier applied to mouse drag and keyboard panning
+    pub pan_sensitivity: f32,
+    /// Mouse button that pans the view while dragged
+    pub pan_button: MouseButton,
+    /// Whether the view keeps gliding after a drag is released, slowing down as if it had momentum
+    pub pan_inertia: bool,
+    /// Whether scrolling up zooms out instead of in
+    pub invert_wheel: bool,
+    /// Whether dragging with the left button while Space is held pans the view
+    pub space_drag_pan: bool,
+    /// Whether touchpad two-finger scrolling pans the view, zooming only while Ctrl is held
+    pub touchpad_pan: bool,
+    /// Seconds the camera takes to catch up with a followed body, 0 sticks to it
+    pub follow_smoothing: f32,
+    /// Distance in pixels a followed body may move from the view center before the camera moves
+    pub follow_dead_zone: f32,
+    /// Whether collision impulses above `shake_impulse_threshold` shake the camera (debug aid)
+    pub shake_enabled: bool,
+    /// Smallest contact impulse that shakes the camera
+    pub shake_impulse_threshold: f32,
+    /// Largest shake offset in pixels
+    pub shake_intensity: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            home_position: Vec2::ZERO,
+            home_zoom: 0.05,
+            easing_duration: 0.25,
+            min_zoom: 0.001,
+            max_zoom: 10.0,
+            zoom_speed: 0.1,
+            pan_sensitivity: 1.0,
+            pan_button: MouseButton::Middle,
+            pan_inertia: false,
+            invert_wheel: false,
+            space_drag_pan: true,
+            touchpad_pan: false,
+            follow_smoothing: 0.2,
+            follow_dead_zone: 50.0,
+            shake_enabled: false,
+            shake_impulse_threshold: 100.0,
+            shake_intensity: 12.0,
+        }
+    }
+}
+
+impl CameraSettings {
+    /// Whether Space is held to pan with the left button, so left clicks shouldn't edit the scene
+    pub fn space_pan_active(&self, keyboard_input: &ButtonInput<KeyCode>) -> bool {
+        self.space_drag_pan && keyboard_input.pressed(KeyCode::Space)
+    }
+
+    /// Whether the right button pans the view, so right clicks shouldn't edit the scene
+    pub fn right_button_pans(&self) -> bool {
+        self.pan_button == MouseButton::Right
+    }
+
+    /// Limit a camera scale to the configured zoom range
+    pub fn clamp_zoom(&self, zoom: f32) -> f32 {
+        // Not `f32::clamp`, which panics while the range is being edited into min > max
+        zoom.max(self.min_zoom).min(self.max_zoom)
+    }
+}
+
+/// Camera pose stored in a view bookmark
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct CameraBookmark {
+    pub position: Vec2,
+    /// Camera transform scale, smaller is zoomed in
+    pub zoom: f32,
+}
+
+/// Resource holding the numbered view bookmarks, stored with Ctrl+1..9 and recalled with 1..9
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Copy, Default)]
+pub struct CameraBookmarks {
+    /// Bookmark for each number key, slot 0 belongs to key 1
+    pub slots: [Option<CameraBookmark>; CameraBookmarks::COUNT],
+}
+
+impl CameraBookmarks {
+    /// Number of bookmark slots, one per number key 1..9
+    pub const COUNT: usize = 9;
+}
+
+/// Direction the window is divided in when the view is split
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SplitOrientation {
+    /// Main view on the left, second view on the right
+    #[default]
+    Horizontal,
+    /// Main view on top, second view below
+    Vertical,
+}
+
+/// Resource controlling the second viewport
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct SplitView {
+    /// Whether the window shows a second viewport next to the main one
+    pub enabled: bool,
+    pub orientation: SplitOrientation,
+}
@@ -1,7 +1,50 @@
-use super::components::CameraMovement;
-use bevy::input::mouse::MouseWheel;
+use super::components::{
+    CameraFollow, CameraFollowEvent, CameraMovement, CameraShake, CameraTarget, FrameShapesEvent, ResetViewEvent,
+    SecondaryCamera,
+};
+use super::resources::{CameraBookmark, CameraBookmarks, CameraSettings, SplitOrientation, SplitView};
+use crate::qphysics::components::{QCollisionShape, QObject, QTransform};
+use crate::qphysics::messages::QImpactEvent;
+use crate::shapes::components::{
+    AnyQShape, EditorShape, QBboxData, QCircleData, QLineData, QPointData, QPolygonData, ShapeLayer,
+};
+use crate::shapes::resources::ShapeDrawingState;
+use bevy::camera::Viewport;
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
+use bevy_egui::EguiContexts;
+
+/// Fraction of the framed bounds added around them when fitting the view
+const FRAME_MARGIN: f32 = 0.1;
+/// Keyboard panning speed in pixels per second, so it feels the same at every zoom
+const KEYBOARD_PAN_SPEED: f32 = 600.0;
+/// Keyboard panning speed multiplier while Shift is held
+const KEYBOARD_PAN_FAST_MULTIPLIER: f32 = 3.0;
+/// Rate per second at which the glide after a drag slows down with pan inertia
+const PAN_INERTIA_DAMPING: f32 = 5.0;
+/// Speed in pixels per second below which the glide stops
+const PAN_INERTIA_MIN_SPEED: f32 = 10.0;
+/// Radians the view rotates per pixel of horizontal Alt+middle-drag
+const ROTATE_SENSITIVITY: f32 = 0.005;
+/// Camera scale shown as 100% zoom, the scale of the default view
+pub const REFERENCE_ZOOM: f32 = 0.05;
+/// Zoom percentages the +/- keys step through
+pub const ZOOM_PRESETS: [f32; 11] = [5.0, 10.0, 25.0, 50.0, 75.0, 100.0, 150.0, 200.0, 400.0, 800.0, 1600.0];
+/// Trauma lost per second, so a full shake lasts about a second
+const SHAKE_DECAY: f32 = 1.0;
+/// Number keys selecting the view bookmark slots, in slot order
+const BOOKMARK_KEYS: [KeyCode; CameraBookmarks::COUNT] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
 
 pub fn setup(mut commands: Commands) {
     // Spawn a 2D camera with a component to track panning state.
@@ -11,64 +54,652 @@ pub fn setup(mut commands: Commands) {
 /// Check whether a primary window exists and return it, otherwise return early from caller.
 // no helper needed — inline `windows.single()` is used in callers.
 
-/// System to handle camera panning with the middle mouse button.
+/// Whether the cursor is over the part of the window a camera renders to
+pub fn cursor_in_viewport(camera: &Camera, cursor_position: Option<Vec2>) -> bool {
+    match (camera.logical_viewport_rect(), cursor_position) {
+        (Some(rect), Some(cursor_position)) => rect.contains(cursor_position),
+        _ => true,
+    }
+}
+
+/// Zoom percentage of a camera scale, larger is zoomed in
+pub fn zoom_percent(zoom: f32) -> f32 {
+    REFERENCE_ZOOM / zoom * 100.0
+}
+
+/// Camera scale showing the given zoom percentage
+pub fn zoom_from_percent(percent: f32) -> f32 {
+    REFERENCE_ZOOM * 100.0 / percent
+}
+
+/// Rotation of the camera around the view axis in radians, counterclockwise
+pub fn camera_angle(transform: &Transform) -> f32 {
+    transform.rotation.to_euler(EulerRot::ZYX).0
+}
+
+/// System to handle camera panning with the middle mouse button or Space+left drag, or rotating while Alt
+/// is held.
 pub fn camera_pan(
-    mut camera_query: Query<(&mut Transform, &mut CameraMovement), With<Camera2d>>,
-    mouse_button_input: Res<ButtonInput<MouseButton>>, windows: Query<&Window, With<PrimaryWindow>>,
+    mut commands: Commands, mut camera_query: Query<(Entity, &Camera, &mut Transform, &mut CameraMovement)>,
+    mouse_button_input: Res<ButtonInput<MouseButton>>, keyboard_input: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>, camera_settings: Res<CameraSettings>, time: Res<Time>,
+    mut egui_contexts: EguiContexts,
 ) {
     let window = match windows.single() {
         Ok(w) => w,
         Err(_) => return,
     };
 
-    let Ok((mut camera_transform, mut camera_movement)) = camera_query.single_mut() else {
+    let Ok((entity, camera, mut camera_transform, mut camera_movement)) = camera_query.single_mut() else {
         return;
     };
 
-    if mouse_button_input.just_pressed(MouseButton::Middle) {
+    // Clicks on egui panels belong to the UI, and a space typed into a text field shouldn't turn clicks
+    // into panning. A drag that already started keeps going over the UI.
+    let (pointer_over_ui, typing) = egui_contexts.ctx_mut().map_or((false, false), |ctx| {
+        (ctx.wants_pointer_input(), ctx.wants_keyboard_input())
+    });
+    let space_pan = camera_settings.space_pan_active(&keyboard_input) && !typing;
+    let drag_button = if mouse_button_input.just_pressed(camera_settings.pan_button) {
+        Some(camera_settings.pan_button)
+    } else if space_pan && mouse_button_input.just_pressed(MouseButton::Left) {
+        Some(MouseButton::Left)
+    } else {
+        None
+    };
+
+    let in_viewport = cursor_in_viewport(camera, window.cursor_position()) && !pointer_over_ui;
+    if let Some(button) = drag_button.filter(|_| in_viewport) {
+        // Dragging takes over from a running animation or a followed body
+        commands.entity(entity).remove::<(CameraTarget, CameraFollow)>();
         camera_movement.dragging = true;
+        camera_movement.drag_button = Some(button);
+        camera_movement.velocity = Vec2::ZERO;
         if let Some(mouse_position) = window.cursor_position() {
             camera_movement.last_mouse_position = mouse_position;
         }
-    } else if mouse_button_input.just_released(MouseButton::Middle) {
+    } else if camera_movement
+        .drag_button
+        .is_some_and(|button| mouse_button_input.just_released(button))
+    {
         camera_movement.dragging = false;
+        camera_movement.drag_button = None;
     }
 
     if camera_movement.dragging {
         if let Some(current_mouse_position) = window.cursor_position() {
             let delta = current_mouse_position - camera_movement.last_mouse_position;
-            camera_transform.translation.x -= delta.x * camera_transform.scale.x;
-            camera_transform.translation.y += delta.y * camera_transform.scale.y;
+            if keyboard_input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]) {
+                camera_transform.rotate_z(-delta.x * ROTATE_SENSITIVITY);
+                camera_movement.velocity = Vec2::ZERO;
+            } else {
+                // Cursor coordinates grow downwards, and the drag is along the rotated screen axes
+                let delta = delta * camera_settings.pan_sensitivity * camera_transform.scale.x;
+                let offset = camera_transform.rotation * Vec3::new(-delta.x, delta.y, 0.0);
+                camera_transform.translation += offset;
+                // Average over a few frames so the glide doesn't depend on the last frame's jitter
+                if time.delta_secs() > 0.0 {
+                    let velocity = offset.truncate() / time.delta_secs();
+                    camera_movement.velocity = camera_movement.velocity.lerp(velocity, 0.5);
+                }
+            }
             camera_movement.last_mouse_position = current_mouse_position;
         }
     }
 }
 
+/// System to keep the view gliding after a drag is released when pan inertia is enabled. Animations and
+/// followed bodies take over from the glide.
+pub fn camera_pan_inertia(
+    mut camera_query: Query<(
+        &mut Transform,
+        &mut CameraMovement,
+        Has<CameraTarget>,
+        Has<CameraFollow>,
+    )>,
+    camera_settings: Res<CameraSettings>, time: Res<Time>,
+) {
+    let Ok((mut transform, mut movement, animating, following)) = camera_query.single_mut() else {
+        return;
+    };
+    if movement.dragging || movement.velocity == Vec2::ZERO {
+        return;
+    }
+    if !camera_settings.pan_inertia || animating || following {
+        movement.velocity = Vec2::ZERO;
+        return;
+    }
+
+    transform.translation += movement.velocity.extend(0.0) * time.delta_secs();
+    movement.velocity *= (-PAN_INERTIA_DAMPING * time.delta_secs()).exp();
+    if movement.velocity.length() < PAN_INERTIA_MIN_SPEED * transform.scale.x {
+        movement.velocity = Vec2::ZERO;
+    }
+}
+
+/// System to pan the camera with the arrow keys or WASD, faster while Shift is held. Ctrl shortcuts don't pan.
+pub fn camera_keyboard_pan(
+    mut commands: Commands, mut camera_query: Query<(Entity, &mut Transform), With<CameraMovement>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>, time: Res<Time>, camera_settings: Res<CameraSettings>,
+    mut egui_contexts: EguiContexts,
+) {
+    if egui_contexts.ctx_mut().is_ok_and(|ctx| ctx.wants_keyboard_input()) {
+        return;
+    }
+    // Leave Ctrl shortcuts such as Ctrl+D to their own systems
+    let modifiers = [
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+    ];
+    if keyboard_input.any_pressed(modifiers) {
+        return;
+    }
+    let Ok((entity, mut camera_transform)) = camera_query.single_mut() else {
+        return;
+    };
+
+    let mut direction = Vec2::ZERO;
+    if keyboard_input.any_pressed([KeyCode::ArrowLeft, KeyCode::KeyA]) {
+        direction.x -= 1.0;
+    }
+    if keyboard_input.any_pressed([KeyCode::ArrowRight, KeyCode::KeyD]) {
+        direction.x += 1.0;
+    }
+    if keyboard_input.any_pressed([KeyCode::ArrowDown, KeyCode::KeyS]) {
+        direction.y -= 1.0;
+    }
+    if keyboard_input.any_pressed([KeyCode::ArrowUp, KeyCode::KeyW]) {
+        direction.y += 1.0;
+    }
+    if direction == Vec2::ZERO {
+        return;
+    }
+
+    let mut speed = KEYBOARD_PAN_SPEED * camera_settings.pan_sensitivity * camera_transform.scale.x;
+    if keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        speed *= KEYBOARD_PAN_FAST_MULTIPLIER;
+    }
+    // Keys move along the screen axes, which differ from the world axes under a rotated view
+    let delta = direction.normalize() * speed * time.delta_secs();
+    commands.entity(entity).remove::<(CameraTarget, CameraFollow)>();
+    camera_transform.translation += camera_transform.rotation * delta.extend(0.0);
+}
+
 /// System to handle camera zooming with mouse wheel.
 pub fn camera_zoom(
-    mut camera_query: Query<&mut Transform, With<Camera2d>>, mut mouse_wheel_events: MessageReader<MouseWheel>,
-    windows: Query<&Window, With<PrimaryWindow>>,
+    mut commands: Commands,
+    mut camera_query: Query<(Entity, &Camera, &mut Transform, Option<&CameraTarget>), With<CameraMovement>>,
+    mut mouse_wheel_events: MessageReader<MouseWheel>, keyboard_input: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>, camera_settings: Res<CameraSettings>,
+    mut egui_contexts: EguiContexts,
 ) {
-    let _window = match windows.single() {
+    // Scrolling a list in an egui panel shouldn't zoom the world
+    if egui_contexts.ctx_mut().is_ok_and(|ctx| ctx.wants_pointer_input()) {
+        mouse_wheel_events.clear();
+        return;
+    }
+    let window = match windows.single() {
         Ok(w) => w,
         Err(_) => return,
     };
 
-    let Ok(mut camera_transform) = camera_query.single_mut() else {
+    let Ok((entity, camera, mut camera_transform, target)) = camera_query.single_mut() else {
         return;
     };
+    // Scrolling over the second viewport zooms that one instead
+    if !cursor_in_viewport(camera, window.cursor_position()) {
+        mouse_wheel_events.clear();
+        return;
+    }
 
+    // Wheel steps accumulate on the running animation so fast scrolling isn't lost
+    let (mut position, mut zoom) = match target {
+        Some(target) => (target.position, target.zoom),
+        None => (camera_transform.translation.truncate(), camera_transform.scale.x),
+    };
+    let ctrl_pressed = keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    let mut zoomed = false;
     for event in mouse_wheel_events.read() {
-        let zoom_factor = if event.y > 0.0 {
-            0.9
-        } else if event.y < 0.0 {
-            1.1
+        // Touchpads scroll in pixels, mouse wheels in lines
+        if camera_settings.touchpad_pan && event.unit == MouseScrollUnit::Pixel && !ctrl_pressed {
+            let delta = Vec2::new(event.x, event.y) * camera_settings.pan_sensitivity * camera_transform.scale.x;
+            let offset = camera_transform.rotation * Vec3::new(-delta.x, delta.y, 0.0);
+            camera_transform.translation += offset;
+            // Panning takes over from a running animation, a later wheel step starts a new one
+            position = camera_transform.translation.truncate();
+            commands.entity(entity).remove::<(CameraTarget, CameraFollow)>();
+            continue;
+        }
+        let scroll = if camera_settings.invert_wheel {
+            -event.y
         } else {
+            event.y
+        };
+        let zoom_factor = if scroll > 0.0 {
+            1.0 - camera_settings.zoom_speed
+        } else if scroll < 0.0 {
+            1.0 + camera_settings.zoom_speed
+        } else {
+            continue;
+        };
+        zoom *= zoom_factor;
+        zoomed = true;
+    }
+    if zoomed {
+        // Limit how far the user can zoom in or out.
+        let zoom = camera_settings.clamp_zoom(zoom);
+        commands
+            .entity(entity)
+            .insert(CameraTarget::new(&camera_transform, position, zoom));
+    }
+
+    let zoom = camera_settings.clamp_zoom(camera_transform.scale.x);
+    camera_transform.scale = Vec3::new(zoom, zoom, camera_transform.scale.z);
+}
+
+/// System to frame the selected shapes with F, or all shapes with Shift+F or a [`FrameShapesEvent`]
+pub fn frame_shapes(
+    mut commands: Commands, keyboard_input: Res<ButtonInput<KeyCode>>, mut events: MessageReader<FrameShapesEvent>,
+    shapes: Query<(
+        &EditorShape,
+        Option<&QPointData>,
+        Option<&QLineData>,
+        Option<&QBboxData>,
+        Option<&QCircleData>,
+        Option<&QPolygonData>,
+    )>,
+    camera_query: Query<(Entity, &Camera, &Transform), With<CameraMovement>>,
+    windows: Query<&Window, With<PrimaryWindow>>, camera_settings: Res<CameraSettings>,
+    mut egui_contexts: EguiContexts,
+) {
+    // Typing an F into a text field shouldn't move the view
+    let key_pressed = keyboard_input.just_pressed(KeyCode::KeyF)
+        && !egui_contexts.ctx_mut().is_ok_and(|ctx| ctx.wants_keyboard_input());
+    let event = events.read().last().copied();
+    let frame_all = if key_pressed {
+        keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+    } else if let Some(event) = event {
+        event.all
+    } else {
+        return;
+    };
+
+    let mut bounds: Option<Rect> = None;
+    for (shape, point, line, bbox, circle, polygon) in shapes.iter() {
+        // Generated shapes are visualizations of the others
+        if shape.layer == ShapeLayer::Generated || !(frame_all || shape.selected) {
+            continue;
+        }
+        let Some(shape) = AnyQShape::from_components(point, line, bbox, circle, polygon) else {
             continue;
         };
-        camera_transform.scale *= zoom_factor;
+        let shape_bbox = shape.get_bbox();
+        let min = shape_bbox.left_bottom().pos();
+        let max = shape_bbox.right_top().pos();
+        let shape_rect = Rect::new(min.x.to_num(), min.y.to_num(), max.x.to_num(), max.y.to_num());
+        bounds = Some(bounds.map_or(shape_rect, |bounds| bounds.union(shape_rect)));
+    }
+
+    let (Some(bounds), Ok(window), Ok((entity, camera, transform))) = (bounds, windows.single(), camera_query.single())
+    else {
+        return;
+    };
+    // Only part of the window is ours while the view is split
+    let view_size = camera.logical_viewport_size().unwrap_or(window.size());
+    // The camera scale is world units per pixel, pick the one fitting both axes of the rotated view
+    let (sin, cos) = camera_angle(transform).sin_cos();
+    let size = bounds.size();
+    let size = Vec2::new(
+        cos.abs() * size.x + sin.abs() * size.y,
+        sin.abs() * size.x + cos.abs() * size.y,
+    ) * (1.0 + 2.0 * FRAME_MARGIN);
+    let zoom = camera_settings.clamp_zoom((size.x / view_size.x).max(size.y / view_size.y));
+    commands
+        .entity(entity)
+        .insert(CameraTarget::new(transform, bounds.center(), zoom));
+}
+
+/// System to move the camera back to the home pose with the Home key or a [`ResetViewEvent`]
+pub fn reset_view(
+    mut commands: Commands, keyboard_input: Res<ButtonInput<KeyCode>>, mut events: MessageReader<ResetViewEvent>,
+    camera_settings: Res<CameraSettings>, camera_query: Query<(Entity, &Transform), With<CameraMovement>>,
+    mut egui_contexts: EguiContexts,
+) {
+    let key_pressed = keyboard_input.just_pressed(KeyCode::Home)
+        && !egui_contexts.ctx_mut().is_ok_and(|ctx| ctx.wants_keyboard_input());
+    if events.read().count() == 0 && !key_pressed {
+        return;
+    }
+    let Ok((camera, transform)) = camera_query.single() else {
+        return;
+    };
+    let zoom = camera_settings.clamp_zoom(camera_settings.home_zoom);
+    commands
+        .entity(camera)
+        .insert(CameraTarget::new(transform, camera_settings.home_position, zoom));
+}
+
+/// System to step the zoom through [`ZOOM_PRESETS`] with the +/- keys
+pub fn zoom_preset_keys(
+    mut commands: Commands, keyboard_input: Res<ButtonInput<KeyCode>>, camera_settings: Res<CameraSettings>,
+    camera_query: Query<(Entity, &Transform, Option<&CameraTarget>), With<CameraMovement>>,
+    shape_drawing_state: Res<ShapeDrawingState>, mut egui_contexts: EguiContexts,
+) {
+    let zoom_in = keyboard_input.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]);
+    let zoom_out = keyboard_input.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]);
+    if zoom_in == zoom_out {
+        return;
+    }
+    // Minus is part of the numbers typed while drawing
+    if shape_drawing_state.accepts_typed_input() || egui_contexts.ctx_mut().is_ok_and(|ctx| ctx.wants_keyboard_input())
+    {
+        return;
     }
+    let Ok((camera, transform, target)) = camera_query.single() else {
+        return;
+    };
+
+    // Step from where a running animation ends, so repeated presses keep going
+    let (position, zoom) = target.map_or((transform.translation.truncate(), transform.scale.x), |target| {
+        (target.position, target.zoom)
+    });
+    let percent = zoom_percent(zoom);
+    // Slightly off values, e.g. 99.99%, count as being on the preset
+    let preset = if zoom_in {
+        ZOOM_PRESETS.iter().find(|preset| **preset > percent * 1.001)
+    } else {
+        ZOOM_PRESETS.iter().rev().find(|preset| **preset < percent / 1.001)
+    };
+    let Some(preset) = preset else {
+        return;
+    };
+    let zoom = camera_settings.clamp_zoom(zoom_from_percent(*preset));
+    commands
+        .entity(camera)
+        .insert(CameraTarget::new(transform, position, zoom));
+}
 
-    // Limit how far the user can zoom in or out.
-    camera_transform.scale = camera_transform.scale.clamp(Vec3::splat(0.01), Vec3::splat(0.1));
+/// System to store the current view in a bookmark with Ctrl+1..9 and jump back to it with 1..9
+pub fn camera_bookmarks(
+    mut commands: Commands, keyboard_input: Res<ButtonInput<KeyCode>>, mut bookmarks: ResMut<CameraBookmarks>,
+    camera_settings: Res<CameraSettings>, camera_query: Query<(Entity, &Transform), With<CameraMovement>>,
+    shape_drawing_state: Res<ShapeDrawingState>, mut egui_contexts: EguiContexts,
+) {
+    let Some(slot) = BOOKMARK_KEYS.iter().position(|key| keyboard_input.just_pressed(*key)) else {
+        return;
+    };
+    // Typing a number into a text field or for the shape being drawn shouldn't move the view
+    if shape_drawing_state.accepts_typed_input() || egui_contexts.ctx_mut().is_ok_and(|ctx| ctx.wants_keyboard_input())
+    {
+        return;
+    }
+    let Ok((camera, transform)) = camera_query.single() else {
+        return;
+    };
+
+    if keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        bookmarks.slots[slot] = Some(CameraBookmark {
+            position: transform.translation.truncate(),
+            zoom: transform.scale.x,
+        });
+        info!("Stored view bookmark {}", slot + 1);
+    } else if let Some(bookmark) = bookmarks.slots[slot] {
+        let zoom = camera_settings.clamp_zoom(bookmark.zoom);
+        commands
+            .entity(camera)
+            .insert(CameraTarget::new(transform, bookmark.position, zoom));
+    }
+}
+
+/// System to start following the selected physics body, or stop following, on a [`CameraFollowEvent`]
+pub fn handle_camera_follow_request(
+    mut commands: Commands, mut events: MessageReader<CameraFollowEvent>,
+    bodies: Query<(Entity, &EditorShape), (With<QObject>, With<QTransform>, With<QCollisionShape>)>,
+    camera_query: Query<Entity, With<CameraMovement>>,
+) {
+    let Ok(camera) = camera_query.single() else {
+        return;
+    };
+    for event in events.read() {
+        match event {
+            CameraFollowEvent::FollowSelected => match bodies.iter().find(|(_, shape)| shape.selected) {
+                Some((target, _)) => {
+                    commands
+                        .entity(camera)
+                        .remove::<CameraTarget>()
+                        .insert(CameraFollow { target });
+                }
+                None => warn!("No selected physics body to follow"),
+            },
+            CameraFollowEvent::Stop => {
+                commands.entity(camera).remove::<CameraFollow>();
+            }
+        }
+    }
+}
+
+/// System to keep the followed body inside the dead zone around the view center
+pub fn follow_body(
+    mut commands: Commands, mut camera_query: Query<(Entity, &mut Transform, &CameraFollow)>,
+    bodies: Query<(&QTransform, &QCollisionShape)>, camera_settings: Res<CameraSettings>, time: Res<Time>,
+) {
+    for (entity, mut transform, follow) in camera_query.iter_mut() {
+        let Ok((body_transform, collision_shape)) = bodies.get(follow.target) else {
+            commands.entity(entity).remove::<CameraFollow>();
+            continue;
+        };
+        // Collision shapes are stored in world space with the transform applied on top of them
+        let centroid = body_transform.apply_to(collision_shape).get_centroid().pos();
+        let body_position = Vec2::new(centroid.x.to_num(), centroid.y.to_num());
+        let camera_position = transform.translation.truncate();
+        let offset = body_position - camera_position;
+        // The dead zone is in pixels so it covers the same part of the screen at every zoom
+        let dead_zone = camera_settings.follow_dead_zone * transform.scale.x;
+        let distance = offset.length();
+        if distance <= dead_zone {
+            continue;
+        }
+        let desired = camera_position + offset * ((distance - dead_zone) / distance);
+        let t = if camera_settings.follow_smoothing > 0.0 {
+            1.0 - (-time.delta_secs() / camera_settings.follow_smoothing).exp()
+        } else {
+            1.0
+        };
+        let position = camera_position.lerp(desired, t);
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    }
+}
+
+/// System to spawn or remove the second camera and divide the window between both viewports
+pub fn update_split_view(
+    mut commands: Commands, split_view: Res<SplitView>, windows: Query<&Window, With<PrimaryWindow>>,
+    mut main_camera: Query<(&mut Camera, &Transform), (With<CameraMovement>, Without<SecondaryCamera>)>,
+    mut secondary_camera: Query<(Entity, &mut Camera), With<SecondaryCamera>>,
+) {
+    let (Ok(window), Ok((mut camera, transform))) = (windows.single(), main_camera.single_mut()) else {
+        return;
+    };
+
+    if !split_view.enabled {
+        camera.viewport = None;
+        for (entity, _) in secondary_camera.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+
+    let size = window.physical_size();
+    let (first_size, second_position) = match split_view.orientation {
+        SplitOrientation::Horizontal => (UVec2::new(size.x / 2, size.y), UVec2::new(size.x / 2, 0)),
+        SplitOrientation::Vertical => (UVec2::new(size.x, size.y / 2), UVec2::new(0, size.y / 2)),
+    };
+    // Zero sized viewports are invalid, e.g. while the window is minimized
+    if first_size.min_element() == 0 {
+        return;
+    }
+    camera.viewport = Some(Viewport {
+        physical_position: UVec2::ZERO,
+        physical_size: first_size,
+        ..default()
+    });
+    let second_viewport = Viewport {
+        physical_position: second_position,
+        physical_size: size - second_position,
+        ..default()
+    };
+
+    match secondary_camera.single_mut() {
+        Ok((_, mut secondary)) => secondary.viewport = Some(second_viewport),
+        Err(_) => {
+            // Start as an overview of the area around the main view
+            let mut secondary_transform = *transform;
+            secondary_transform.scale *= Vec3::new(4.0, 4.0, 1.0);
+            commands.spawn((
+                Camera2d,
+                Camera {
+                    order: 1,
+                    viewport: Some(second_viewport),
+                    ..default()
+                },
+                secondary_transform,
+                SecondaryCamera::default(),
+            ));
+        }
+    }
+}
+
+/// System to pan the second viewport with the middle mouse button or Space+left drag, and zoom it with
+/// the mouse wheel
+pub fn secondary_camera_input(
+    mut camera_query: Query<(&Camera, &mut Transform, &mut SecondaryCamera)>,
+    mouse_button_input: Res<ButtonInput<MouseButton>>, keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut mouse_wheel_events: MessageReader<MouseWheel>, windows: Query<&Window, With<PrimaryWindow>>,
+    camera_settings: Res<CameraSettings>, mut egui_contexts: EguiContexts,
+) {
+    let (Ok(window), Ok((camera, mut transform, mut secondary))) = (windows.single(), camera_query.single_mut()) else {
+        mouse_wheel_events.clear();
+        return;
+    };
+    let cursor_position = window.cursor_position();
+    let pointer_over_ui = egui_contexts.ctx_mut().is_ok_and(|ctx| ctx.wants_pointer_input());
+    let hovered = cursor_position.is_some() && cursor_in_viewport(camera, cursor_position) && !pointer_over_ui;
+
+    let pan_pressed = mouse_button_input.just_pressed(camera_settings.pan_button)
+        || (camera_settings.space_pan_active(&keyboard_input) && mouse_button_input.just_pressed(MouseButton::Left));
+    if pan_pressed && hovered {
+        secondary.dragging = true;
+        secondary.last_mouse_position = cursor_position.unwrap_or_default();
+    } else if mouse_button_input.any_just_released([camera_settings.pan_button, MouseButton::Left]) {
+        secondary.dragging = false;
+    }
+    if let (true, Some(cursor_position)) = (secondary.dragging, cursor_position) {
+        let delta = (cursor_position - secondary.last_mouse_position) * camera_settings.pan_sensitivity;
+        let offset = transform.rotation * Vec3::new(-delta.x, delta.y, 0.0) * transform.scale.x;
+        transform.translation += offset;
+        secondary.last_mouse_position = cursor_position;
+    }
+
+    for event in mouse_wheel_events.read() {
+        if !hovered {
+            continue;
+        }
+        let scroll = if camera_settings.invert_wheel {
+            -event.y
+        } else {
+            event.y
+        };
+        let zoom_factor = if scroll > 0.0 {
+            1.0 - camera_settings.zoom_speed
+        } else if scroll < 0.0 {
+            1.0 + camera_settings.zoom_speed
+        } else {
+            continue;
+        };
+        let zoom = camera_settings.clamp_zoom(transform.scale.x * zoom_factor);
+        transform.scale = Vec3::new(zoom, zoom, transform.scale.z);
+    }
+}
+
+/// System to ease the camera towards its target pose over [`CameraSettings::easing_duration`]
+pub fn animate_camera(
+    mut commands: Commands, mut camera_query: Query<(Entity, &mut Transform, &mut CameraTarget)>,
+    camera_settings: Res<CameraSettings>, time: Res<Time>,
+) {
+    for (entity, mut transform, mut target) in camera_query.iter_mut() {
+        target.elapsed += time.delta_secs();
+        let progress = if camera_settings.easing_duration > 0.0 {
+            (target.elapsed / camera_settings.easing_duration).min(1.0)
+        } else {
+            1.0
+        };
+        // Ease out cubic: start fast and settle gently on the target
+        let t = 1.0 - (1.0 - progress).powi(3);
+        let position = target.start_position.lerp(target.position, t);
+        // Interpolate the zoom in log space so zooming in and out feel equally fast
+        let zoom = (target.start_zoom.ln() + (target.zoom.ln() - target.start_zoom.ln()) * t).exp();
+
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+        transform.scale = Vec3::new(zoom, zoom, transform.scale.z);
+        if progress >= 1.0 {
+            commands.entity(entity).remove::<CameraTarget>();
+        }
+    }
+}
+
+/// System to take the previous frame's shake offset back out of the camera before input moves it
+pub fn remove_camera_shake(mut camera_query: Query<(&mut Transform, &mut CameraShake)>) {
+    for (mut transform, mut shake) in camera_query.iter_mut() {
+        transform.translation -= shake.offset.extend(0.0);
+        shake.offset = Vec2::ZERO;
+    }
+}
+
+/// System to shake the camera after collision impulses above the configured threshold
+pub fn camera_shake(
+    mut commands: Commands, mut impacts: MessageReader<QImpactEvent>, camera_settings: Res<CameraSettings>,
+    mut camera_query: Query<(Entity, &mut Transform, Option<&mut CameraShake>), With<CameraMovement>>, time: Res<Time>,
+) {
+    let Ok((entity, mut transform, shake)) = camera_query.single_mut() else {
+        impacts.clear();
+        return;
+    };
+    let threshold = camera_settings.shake_impulse_threshold;
+    let hit = impacts.read().any(|impact| impact.impulse.to_num::<f32>() >= threshold);
+    if !camera_settings.shake_enabled {
+        if shake.is_some() {
+            commands.entity(entity).remove::<CameraShake>();
+        }
+        return;
+    }
+
+    let Some(mut shake) = shake else {
+        if hit {
+            commands.entity(entity).insert(CameraShake {
+                trauma: 1.0,
+                offset: Vec2::ZERO,
+            });
+        }
+        return;
+    };
+    if hit {
+        shake.trauma = 1.0;
+    }
+    shake.trauma = (shake.trauma - SHAKE_DECAY * time.delta_secs()).max(0.0);
+    if shake.trauma <= 0.0 {
+        commands.entity(entity).remove::<CameraShake>();
+        return;
+    }
+    // Squared trauma makes small shakes subtle, incommensurate frequencies keep the motion irregular
+    let seconds = time.elapsed_secs();
+    let direction = Vec2::new(
+        (seconds * 47.0).sin() + (seconds * 31.0).cos(),
+        (seconds * 53.0).cos() + (seconds * 37.0).sin(),
+    );
+    let offset = direction * 0.5 * shake.trauma * shake.trauma * camera_settings.shake_intensity * transform.scale.x;
+    transform.translation += offset.extend(0.0);
+    shake.offset = offset;
 }
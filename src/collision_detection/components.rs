@@ -11,3 +11,7 @@ pub struct SeparationVectorVisualization;
 /// Component to mark entities that represent Minkowski difference visualization
 #[derive(Component)]
 pub struct MinkowskiDifferenceVisualization;
+
+/// Component to mark entities that represent Minkowski sum visualization
+#[derive(Component)]
+pub struct MinkowskiSumVisualization;
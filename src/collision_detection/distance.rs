@@ -0,0 +1,78 @@
+//! Closest points between two shapes
+//!
+//! Shapes are reduced to their outline segments, with circles kept as their center and radius so the
+//! distance to them is exact.
+
+use crate::shapes::components::AnyQShape;
+use crate::util::closest_on_segment;
+use qgeometry::shape::QShapeCommon;
+use qmath::prelude::*;
+use qmath::vec2::QVec2;
+
+/// Outline of a shape: its segments, grown outward by `radius`
+struct Outline {
+    segments: Vec<(QVec2, QVec2)>,
+    radius: Q64,
+}
+
+impl Outline {
+    fn new(shape: AnyQShape) -> Self {
+        let (points, closed, radius) = match shape {
+            AnyQShape::Circle(circle) => (vec![circle.get_centroid().pos()], false, circle.radius()),
+            AnyQShape::Point(_) | AnyQShape::Line(_) => (shape.vertices(), false, Q64::ZERO),
+            AnyQShape::Bbox(_) | AnyQShape::Polygon(_) => (shape.vertices(), true, Q64::ZERO),
+        };
+        let segments = match points.len() {
+            0 => Vec::new(),
+            1 => vec![(points[0], points[0])],
+            count => {
+                let edges = if closed { count } else { count - 1 };
+                (0..edges).map(|i| (points[i], points[(i + 1) % count])).collect()
+            }
+        };
+        Self { segments, radius }
+    }
+}
+
+/// Closest pair of points between two segments that don't cross
+fn closest_between_segments(a: (QVec2, QVec2), b: (QVec2, QVec2)) -> (QVec2, QVec2) {
+    [
+        (a.0, closest_on_segment(a.0, b)),
+        (a.1, closest_on_segment(a.1, b)),
+        (closest_on_segment(b.0, a), b.0),
+        (closest_on_segment(b.1, a), b.1),
+    ]
+    .into_iter()
+    .min_by_key(|(from, to)| to.saturating_sub(*from).length())
+    .unwrap_or((a.0, b.0))
+}
+
+/// Closest points of two shapes, the first on `a` and the second on `b`. `None` when they collide.
+pub fn closest_points(a: AnyQShape, b: AnyQShape) -> Option<(QVec2, QVec2)> {
+    if a.is_collide(b) {
+        return None;
+    }
+    let (outline_a, outline_b) = (Outline::new(a), Outline::new(b));
+    let (from, to) = outline_a
+        .segments
+        .iter()
+        .flat_map(|&segment_a| {
+            outline_b
+                .segments
+                .iter()
+                .map(move |&segment_b| closest_between_segments(segment_a, segment_b))
+        })
+        .min_by_key(|(from, to)| to.saturating_sub(*from).length())?;
+
+    // Move the points from the circles' centers out to their boundaries
+    let offset = to.saturating_sub(from);
+    let length = offset.length();
+    if length <= outline_a.radius + outline_b.radius {
+        return None;
+    }
+    let along = |distance: Q64| QVec2::new(offset.x * distance / length, offset.y * distance / length);
+    Some((
+        from.saturating_add(along(outline_a.radius)),
+        to.saturating_sub(along(outline_b.radius)),
+    ))
+}
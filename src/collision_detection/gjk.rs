@@ -0,0 +1,242 @@
+//! Step-by-step trace of GJK and EPA
+//!
+//! Runs GJK on the Minkowski difference of two convex vertex sets and, when they collide, EPA for the
+//! penetration vector, recording the simplex, support point and search direction of every iteration so
+//! they can be stepped through and drawn. The shapes are treated as the convex hulls of their vertices.
+
+use crate::util::{cross, dot};
+use qmath::prelude::*;
+use qmath::vec2::QVec2;
+
+/// Iterations of either algorithm before giving up, in case the fixed-point math doesn't converge
+const MAX_ITERATIONS: usize = 32;
+
+/// Phase of the collision algorithm a step belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GjkPhase {
+    Gjk,
+    Epa,
+}
+
+/// One iteration of GJK or EPA
+#[derive(Debug, Clone)]
+pub struct GjkStep {
+    pub phase: GjkPhase,
+    /// GJK simplex or EPA polytope after the step, in the Minkowski difference's coordinates
+    pub simplex: Vec<QVec2>,
+    /// Direction the support point was searched in
+    pub direction: QVec2,
+    /// Support point found in `direction`
+    pub support: Option<QVec2>,
+    /// What happened in the step
+    pub description: String,
+}
+
+fn negate(v: QVec2) -> QVec2 {
+    QVec2::ZERO.saturating_sub(v)
+}
+
+/// Perpendicular of `edge` pointing to the side of `towards`
+fn perpendicular_towards(edge: QVec2, towards: QVec2) -> QVec2 {
+    let perpendicular = QVec2::new(-edge.y, edge.x);
+    if dot(perpendicular, towards) < Q64::ZERO {
+        negate(perpendicular)
+    } else {
+        perpendicular
+    }
+}
+
+/// Vertex of `points` farthest in `direction`
+fn farthest(points: &[QVec2], direction: QVec2) -> QVec2 {
+    points
+        .iter()
+        .copied()
+        .max_by(|a, b| dot(*a, direction).cmp(&dot(*b, direction)))
+        .unwrap_or(QVec2::ZERO)
+}
+
+/// Support point of the Minkowski difference `a - b` in `direction`
+fn support(a: &[QVec2], b: &[QVec2], direction: QVec2) -> QVec2 {
+    farthest(a, direction).saturating_sub(farthest(b, negate(direction)))
+}
+
+fn format_vec(v: QVec2) -> String {
+    format!("({:.3}, {:.3})", v.x.to_num::<f32>(), v.y.to_num::<f32>())
+}
+
+/// Trace GJK between the vertex sets `a` and `b`, followed by EPA when they collide
+pub fn trace_gjk_epa(a: &[QVec2], b: &[QVec2]) -> Vec<GjkStep> {
+    let mut steps = Vec::new();
+    if a.is_empty() || b.is_empty() {
+        return steps;
+    }
+
+    let centroid = |points: &[QVec2]| {
+        let count = Q64::from_num(points.len());
+        let sum = points.iter().fold(QVec2::ZERO, |sum, point| sum.saturating_add(*point));
+        QVec2::new(sum.x / count, sum.y / count)
+    };
+    let mut direction = centroid(a).saturating_sub(centroid(b));
+    if direction == QVec2::ZERO {
+        direction = QVec2::new(Q64::ONE, Q64::ZERO);
+    }
+
+    let first = support(a, b, direction);
+    let mut simplex = vec![first];
+    steps.push(GjkStep {
+        phase: GjkPhase::Gjk,
+        simplex: simplex.clone(),
+        direction,
+        support: Some(first),
+        description: format!("Start the simplex with the support point {}", format_vec(first)),
+    });
+    direction = negate(first);
+
+    for _ in 0..MAX_ITERATIONS {
+        if direction == QVec2::ZERO {
+            steps.push(GjkStep {
+                phase: GjkPhase::Gjk,
+                simplex: simplex.clone(),
+                direction,
+                support: None,
+                description: "The origin lies on the simplex: the shapes touch".to_string(),
+            });
+            return steps;
+        }
+        let point = support(a, b, direction);
+        if dot(point, direction) < Q64::ZERO {
+            steps.push(GjkStep {
+                phase: GjkPhase::Gjk,
+                simplex: simplex.clone(),
+                direction,
+                support: Some(point),
+                description: format!(
+                    "Support point {} doesn't pass the origin: no collision",
+                    format_vec(point)
+                ),
+            });
+            return steps;
+        }
+        simplex.push(point);
+
+        // The newest point is last; keep the feature of the simplex closest to the origin
+        let newest = point;
+        let to_origin = negate(newest);
+        let description = if let [c, b, _] = simplex[..] {
+            let (ab, ac) = (b.saturating_sub(newest), c.saturating_sub(newest));
+            let ab_normal = perpendicular_towards(ab, negate(ac));
+            let ac_normal = perpendicular_towards(ac, negate(ab));
+            if dot(ab_normal, to_origin) > Q64::ZERO {
+                simplex.remove(0);
+                direction = ab_normal;
+                "Origin outside the newest edge: drop the oldest point"
+            } else if dot(ac_normal, to_origin) > Q64::ZERO {
+                simplex.remove(1);
+                direction = ac_normal;
+                "Origin outside the other new edge: drop the middle point"
+            } else {
+                steps.push(GjkStep {
+                    phase: GjkPhase::Gjk,
+                    simplex: simplex.clone(),
+                    direction,
+                    support: Some(point),
+                    description: format!(
+                        "Added {}. The simplex encloses the origin: collision",
+                        format_vec(point)
+                    ),
+                });
+                trace_epa(a, b, simplex, &mut steps);
+                return steps;
+            }
+        } else {
+            let edge = simplex[0].saturating_sub(newest);
+            direction = perpendicular_towards(edge, to_origin);
+            "Search perpendicular to the edge, towards the origin"
+        };
+        steps.push(GjkStep {
+            phase: GjkPhase::Gjk,
+            simplex: simplex.clone(),
+            direction,
+            support: Some(point),
+            description: format!("Added {}. {}", format_vec(point), description),
+        });
+    }
+    steps.push(GjkStep {
+        phase: GjkPhase::Gjk,
+        simplex,
+        direction,
+        support: None,
+        description: format!("No result after {} iterations", MAX_ITERATIONS),
+    });
+    steps
+}
+
+/// Expand the GJK triangle enclosing the origin until the edge closest to the origin is on the boundary of
+/// the Minkowski difference
+fn trace_epa(a: &[QVec2], b: &[QVec2], mut polytope: Vec<QVec2>, steps: &mut Vec<GjkStep>) {
+    // Counter-clockwise winding, so edge normals point outwards
+    if cross(
+        polytope[1].saturating_sub(polytope[0]),
+        polytope[2].saturating_sub(polytope[0]),
+    ) < Q64::ZERO
+    {
+        polytope.reverse();
+    }
+    let tolerance = Q64::from_num(1e-3);
+
+    for _ in 0..MAX_ITERATIONS {
+        // Edge closest to the origin
+        let mut closest: Option<(usize, QVec2, Q64)> = None;
+        for i in 0..polytope.len() {
+            let edge = polytope[(i + 1) % polytope.len()].saturating_sub(polytope[i]);
+            let length = edge.length();
+            if length == Q64::ZERO {
+                continue;
+            }
+            let normal = QVec2::new(edge.y / length, -edge.x / length);
+            let distance = dot(normal, polytope[i]);
+            if closest.is_none_or(|(_, _, closest)| distance < closest) {
+                closest = Some((i, normal, distance));
+            }
+        }
+        let Some((index, normal, distance)) = closest else {
+            break;
+        };
+
+        let point = support(a, b, normal);
+        if dot(point, normal) - distance < tolerance {
+            let penetration = QVec2::new(normal.x * distance, normal.y * distance);
+            steps.push(GjkStep {
+                phase: GjkPhase::Epa,
+                simplex: polytope,
+                direction: normal,
+                support: Some(point),
+                description: format!(
+                    "The closest edge is on the boundary: penetration {} at depth {:.3}",
+                    format_vec(penetration),
+                    distance.to_num::<f32>()
+                ),
+            });
+            return;
+        }
+        polytope.insert(index + 1, point);
+        steps.push(GjkStep {
+            phase: GjkPhase::Epa,
+            simplex: polytope.clone(),
+            direction: normal,
+            support: Some(point),
+            description: format!(
+                "Expand the closest edge, at distance {:.3}, with {}",
+                distance.to_num::<f32>(),
+                format_vec(point)
+            ),
+        });
+    }
+    steps.push(GjkStep {
+        phase: GjkPhase::Epa,
+        simplex: polytope,
+        direction: QVec2::ZERO,
+        support: None,
+        description: "EPA didn't converge".to_string(),
+    });
+}
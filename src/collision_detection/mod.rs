@@ -4,6 +4,8 @@
 //! and visualizing bounding boxes for colliding shapes.
 
 pub mod components;
+pub mod distance;
+pub mod gjk;
 pub mod plugin;
 pub mod resources;
 pub mod systems;
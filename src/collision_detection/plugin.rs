@@ -2,9 +2,10 @@
 //!
 //! Registers systems for collision detection and visualization.
 
-use super::resources::CollisionDetectionSettings;
+use super::resources::{ClosestPoints, CollisionBroadPhase, CollisionDetectionSettings, CollisionVisuals, GjkDebugger};
 use super::systems::*;
 use bevy::prelude::*;
+use bevy_egui::EguiPrimaryContextPass;
 
 /// `CollisionDetectionPlugin` registers systems for collision detection and visualization.
 pub struct CollisionDetectionPlugin;
@@ -12,13 +13,25 @@ pub struct CollisionDetectionPlugin;
 impl Plugin for CollisionDetectionPlugin {
     fn build(&self, app: &mut App) {
         // Register collision detection and visualization systems
-        app.init_resource::<CollisionDetectionSettings>().add_systems(
-            PostUpdate,
-            (
-                detect_collisions,
-                compute_minkowski_difference,
-                visualize_minkowski_difference,
-            ),
-        );
+        app.init_resource::<CollisionDetectionSettings>()
+            .init_resource::<CollisionBroadPhase>()
+            .init_resource::<CollisionVisuals>()
+            .init_resource::<GjkDebugger>()
+            .init_resource::<ClosestPoints>()
+            .add_systems(
+                Update,
+                ((update_gjk_debugger, draw_gjk_debugger).chain(), update_closest_points),
+            )
+            .add_systems(EguiPrimaryContextPass, draw_closest_points_label)
+            .add_systems(
+                PostUpdate,
+                (
+                    detect_collisions,
+                    compute_minkowski_difference,
+                    visualize_minkowski_difference,
+                    compute_minkowski_sum,
+                    visualize_minkowski_sum,
+                ),
+            );
     }
 }
@@ -1,4 +1,7 @@
+use super::gjk::GjkStep;
 use bevy::prelude::*;
+use qmath::vec2::QVec2;
+use std::collections::HashMap;
 
 /// Resource containing coordinate system settings
 #[derive(Resource, Debug, Clone)]
@@ -6,6 +9,17 @@ pub struct CollisionDetectionSettings {
     pub shape_color_bbox: Color,
     pub shape_color_seperation_vector: Color,
     pub shape_color_minkowski_difference: Color,
+    pub shape_color_minkowski_sum: Color,
+    /// Whether to show the Minkowski difference of two selected polygons
+    pub show_minkowski_difference: bool,
+    /// Whether to show the Minkowski sum of two selected polygons
+    pub show_minkowski_sum: bool,
+    pub shape_color_gjk: Color,
+    /// Whether to step through GJK and EPA for two selected shapes
+    pub show_gjk_steps: bool,
+    pub shape_color_closest_points: Color,
+    /// Whether to show the shortest segment between two selected shapes
+    pub show_closest_points: bool,
 }
 
 impl Default for CollisionDetectionSettings {
@@ -14,6 +28,92 @@ impl Default for CollisionDetectionSettings {
             shape_color_bbox: Color::srgba(1.0, 0.0, 0.0, 0.7),
             shape_color_seperation_vector: Color::srgba(1.0, 0.0, 0.0, 0.7),
             shape_color_minkowski_difference: Color::srgba(1.0, 0.0, 0.0, 0.7),
+            shape_color_minkowski_sum: Color::srgba(0.0, 0.6, 1.0, 0.7),
+            show_minkowski_difference: true,
+            show_minkowski_sum: false,
+            shape_color_gjk: Color::srgba(1.0, 0.8, 0.0, 0.9),
+            show_gjk_steps: false,
+            shape_color_closest_points: Color::srgba(0.0, 0.8, 0.3, 0.9),
+            show_closest_points: false,
+        }
+    }
+}
+
+/// Resource mapping colliding shapes and pairs to the entities visualizing them, so the visualizations are
+/// reused while a collision lasts instead of being respawned every frame
+#[derive(Resource, Debug, Default)]
+pub struct CollisionVisuals {
+    /// Bounding box visualization of each colliding shape
+    pub bboxes: HashMap<Entity, Entity>,
+    /// Separation vector visualization of each colliding pair
+    pub separations: HashMap<(Entity, Entity), Entity>,
+}
+
+/// Resource holding the GJK and EPA trace of the two selected shapes and the step being shown
+#[derive(Resource, Debug, Default)]
+pub struct GjkDebugger {
+    /// The traced shapes, the trace restarts from the first step when they change
+    pub pair: Option<(Entity, Entity)>,
+    pub steps: Vec<GjkStep>,
+    pub current: usize,
+}
+
+/// Resource holding the closest points of the two selected shapes, `None` when they collide or the
+/// measurement is off
+#[derive(Resource, Debug, Default)]
+pub struct ClosestPoints {
+    pub points: Option<(QVec2, QVec2)>,
+}
+
+/// Bounding box of a shape in the broad phase
+#[derive(Debug, Clone, Copy)]
+struct BroadPhaseEntry {
+    entity: Entity,
+    min: QVec2,
+    max: QVec2,
+}
+
+/// Resource holding the sweep-and-prune broad phase over the shapes' bounding boxes
+///
+/// The shapes stay sorted by the left edge of their bounding box between frames. Shapes move little from
+/// one frame to the next, so the order is nearly sorted and re-sorting it is close to linear.
+#[derive(Resource, Debug, Default)]
+pub struct CollisionBroadPhase {
+    entries: Vec<BroadPhaseEntry>,
+    /// Pairs of shapes whose bounding boxes overlap, found by the last update
+    pub pairs: Vec<(Entity, Entity)>,
+}
+
+impl CollisionBroadPhase {
+    /// Replace the bounding boxes, given as entity, min and max corner, and find the overlapping pairs
+    pub fn update(&mut self, boxes: impl IntoIterator<Item = (Entity, QVec2, QVec2)>) {
+        let mut boxes: HashMap<Entity, (QVec2, QVec2)> = boxes
+            .into_iter()
+            .map(|(entity, min, max)| (entity, (min, max)))
+            .collect();
+        self.entries.retain_mut(|entry| match boxes.remove(&entry.entity) {
+            Some((min, max)) => {
+                (entry.min, entry.max) = (min, max);
+                true
+            }
+            None => false,
+        });
+        self.entries.extend(
+            boxes
+                .into_iter()
+                .map(|(entity, (min, max))| BroadPhaseEntry { entity, min, max }),
+        );
+        // The standard sort is a merge sort that takes advantage of the existing order
+        self.entries.sort_by(|a, b| a.min.x.cmp(&b.min.x));
+
+        self.pairs.clear();
+        for (i, a) in self.entries.iter().enumerate() {
+            // Only shapes starting before this one ends can overlap it
+            for b in self.entries[i + 1..].iter().take_while(|b| b.min.x <= a.max.x) {
+                if a.min.y <= b.max.y && b.min.y <= a.max.y {
+                    self.pairs.push((a.entity, b.entity));
+                }
+            }
         }
     }
 }
@@ -2,270 +2,118 @@
 //!
 //! This module defines the systems used for collision detection and visualization.
 
-use super::components::{CollisionVisualization, MinkowskiDifferenceVisualization, SeparationVectorVisualization};
-use super::resources::CollisionDetectionSettings;
-use crate::shapes::components::{EditorShape, QBboxData, QCircleData, QLineData, QPointData, QPolygonData, ShapeLayer};
+use super::components::{
+    CollisionVisualization, MinkowskiDifferenceVisualization, MinkowskiSumVisualization, SeparationVectorVisualization,
+};
+use super::distance::closest_points;
+use super::gjk::{GjkPhase, trace_gjk_epa};
+use super::resources::{ClosestPoints, CollisionBroadPhase, CollisionDetectionSettings, CollisionVisuals, GjkDebugger};
+use crate::camera::components::CameraMovement;
+use crate::coordinate::resources::CoordinateSettings;
+use crate::shapes::components::{
+    AnyQShape, EditorShape, QBboxData, QCircleData, QLineData, QPointData, QPolygonData, ShapeLayer,
+};
+use crate::shapes::hull::convex_hull;
+use crate::util;
 use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
 use qgeometry::algorithm::get_minkowski_difference;
-use qgeometry::shape::{QLine, QPoint, QShapeCommon};
+use qgeometry::shape::{QLine, QPoint, QPolygon, QShapeCommon};
 use qmath::vec2::QVec2;
+use std::collections::HashMap;
 
 /// System to detect collisions between shapes
+///
+/// Colliding shapes get a bounding box visualization and colliding pairs a separation vector. The
+/// visualization entities are kept in [`CollisionVisuals`] and updated in place while the collision lasts.
 pub fn detect_collisions(
-    // Query all shapes with their components
-    shapes: Query<(
-        Entity,
-        &EditorShape,
-        Option<&QPointData>,
-        Option<&QLineData>,
-        Option<&QBboxData>,
-        Option<&QCircleData>,
-        Option<&QPolygonData>,
-    )>,
+    // Query all shapes with their components, except the visualizations
+    shapes: Query<
+        (
+            Entity,
+            &EditorShape,
+            Option<&QPointData>,
+            Option<&QLineData>,
+            Option<&QBboxData>,
+            Option<&QCircleData>,
+            Option<&QPolygonData>,
+        ),
+        (Without<CollisionVisualization>, Without<SeparationVectorVisualization>),
+    >,
     collision_detection_settings: Res<CollisionDetectionSettings>,
-    // Query existing collision visualizations to clean them up
-    mut visualization_query: Query<Entity, With<CollisionVisualization>>,
-    // Query existing separation vector visualizations to clean them up
-    mut separation_vector_query: Query<Entity, With<SeparationVectorVisualization>>,
+    // Existing visualizations, updated when the shapes move
+    mut bbox_visualizations: Query<&mut QBboxData, With<CollisionVisualization>>,
+    mut separation_visualizations: Query<&mut QLineData, With<SeparationVectorVisualization>>,
+    mut visuals: ResMut<CollisionVisuals>,
+    mut broad_phase: ResMut<CollisionBroadPhase>,
     // Add commands to spawn/despawn entities for visualization
     mut commands: Commands,
 ) {
-    // Clean up existing collision visualizations
-    for entity in visualization_query.iter_mut() {
-        commands.entity(entity).despawn();
-    }
-
-    // Clean up existing separation vector visualizations
-    for entity in separation_vector_query.iter_mut() {
-        commands.entity(entity).despawn();
-    }
+    // Broad phase: only shapes with overlapping bounding boxes can collide. Generated shapes are the
+    // visualizations themselves and are left out.
+    broad_phase.update(
+        shapes
+            .iter()
+            .filter(|(_, shape, ..)| shape.layer != ShapeLayer::Generated)
+            .filter_map(|(entity, _, point, line, bbox, circle, polygon)| {
+                let bbox = AnyQShape::from_components(point, line, bbox, circle, polygon)?.get_bbox();
+                Some((entity, bbox.left_bottom().pos(), bbox.right_top().pos()))
+            }),
+    );
 
-    // Get all shape entities
-    let shape_entities: Vec<_> = shapes.iter().collect();
+    // Narrow phase on the candidate pairs
+    let shape_of = |entity: Entity| {
+        let (_, _, point, line, bbox, circle, polygon) = shapes.get(entity).ok()?;
+        AnyQShape::from_components(point, line, bbox, circle, polygon)
+    };
+    let mut colliding_bboxes = HashMap::new();
+    let mut separation_lines = HashMap::new();
+    for &(entity_a, entity_b) in &broad_phase.pairs {
+        let (Some(shape_a), Some(shape_b)) = (shape_of(entity_a), shape_of(entity_b)) else {
+            continue;
+        };
+        if !shape_a.is_collide(shape_b) {
+            continue;
+        }
 
-    // Check collisions between all pairs of shapes
-    for i in 0..shape_entities.len() {
-        for j in (i + 1)..shape_entities.len() {
-            let (_, shape_a, point_a, line_a, bbox_a, circle_a, polygon_a) = shape_entities[i];
-            let (_, shape_b, point_b, line_b, bbox_b, circle_b, polygon_b) = shape_entities[j];
+        // Mark both shapes as colliding
+        colliding_bboxes.insert(entity_a, shape_a.get_bbox());
+        colliding_bboxes.insert(entity_b, shape_b.get_bbox());
 
-            // Skip if either shape is on auxiliary layer (to avoid checking visualization shapes)
-            if shape_a.layer == ShapeLayer::Generated || shape_b.layer == ShapeLayer::Generated {
-                continue;
-            }
+        // Separation vector from the center of the second shape, if available
+        if let Some(vector) = shape_a.try_get_seperation_vector(shape_b)
+            && vector != QVec2::ZERO
+        {
+            let start = shape_b.get_centroid().pos();
+            let line = QLine::new_from_parts(start, start.saturating_add(vector));
+            separation_lines.insert((entity_a, entity_b), line);
+        }
+    }
 
-            // Check if shapes collide
-            let collision_detected = if let (Some(point), _) = (point_a, point_b) {
-                if let Some(other_point) = point_b {
-                    point.data.is_collide(&other_point.data)
-                } else if let Some(other_line) = line_b {
-                    point.data.is_collide(&other_line.data)
-                } else if let Some(other_bbox) = bbox_b {
-                    point.data.is_collide(&other_bbox.data)
-                } else if let Some(other_circle) = circle_b {
-                    point.data.is_collide(&other_circle.data)
-                } else if let Some(other_polygon) = polygon_b {
-                    point.data.is_collide(&other_polygon.data)
-                } else {
-                    false
-                }
-            } else if let (Some(line), _) = (line_a, line_b) {
-                if let Some(other_point) = point_b {
-                    line.data.is_collide(&other_point.data)
-                } else if let Some(other_line) = line_b {
-                    line.data.is_collide(&other_line.data)
-                } else if let Some(other_bbox) = bbox_b {
-                    line.data.is_collide(&other_bbox.data)
-                } else if let Some(other_circle) = circle_b {
-                    line.data.is_collide(&other_circle.data)
-                } else if let Some(other_polygon) = polygon_b {
-                    line.data.is_collide(&other_polygon.data)
-                } else {
-                    false
-                }
-            } else if let (Some(bbox), _) = (bbox_a, bbox_b) {
-                if let Some(other_point) = point_b {
-                    bbox.data.is_collide(&other_point.data)
-                } else if let Some(other_line) = line_b {
-                    bbox.data.is_collide(&other_line.data)
-                } else if let Some(other_bbox) = bbox_b {
-                    bbox.data.is_collide(&other_bbox.data)
-                } else if let Some(other_circle) = circle_b {
-                    bbox.data.is_collide(&other_circle.data)
-                } else if let Some(other_polygon) = polygon_b {
-                    bbox.data.is_collide(&other_polygon.data)
-                } else {
-                    false
-                }
-            } else if let (Some(circle), _) = (circle_a, circle_b) {
-                if let Some(other_point) = point_b {
-                    circle.data.is_collide(&other_point.data)
-                } else if let Some(other_line) = line_b {
-                    circle.data.is_collide(&other_line.data)
-                } else if let Some(other_bbox) = bbox_b {
-                    circle.data.is_collide(&other_bbox.data)
-                } else if let Some(other_circle) = circle_b {
-                    circle.data.is_collide(&other_circle.data)
-                } else if let Some(other_polygon) = polygon_b {
-                    circle.data.is_collide(&other_polygon.data)
-                } else {
-                    false
-                }
-            } else if let (Some(polygon), _) = (polygon_a, polygon_b) {
-                if let Some(other_point) = point_b {
-                    polygon.data.is_collide(&other_point.data)
-                } else if let Some(other_line) = line_b {
-                    polygon.data.is_collide(&other_line.data)
-                } else if let Some(other_bbox) = bbox_b {
-                    polygon.data.is_collide(&other_bbox.data)
-                } else if let Some(other_circle) = circle_b {
-                    polygon.data.is_collide(&other_circle.data)
-                } else if let Some(other_polygon) = polygon_b {
-                    polygon.data.is_collide(&other_polygon.data)
-                } else {
-                    false
+    // Bounding boxes of the colliding shapes: drop the ones that stopped colliding, update the rest in place
+    visuals.bboxes.retain(|shape, visualization| {
+        let keep = colliding_bboxes.contains_key(shape);
+        if !keep {
+            commands.entity(*visualization).despawn();
+        }
+        keep
+    });
+    for (shape, data) in colliding_bboxes {
+        let existing = visuals
+            .bboxes
+            .get(&shape)
+            .and_then(|&entity| bbox_visualizations.get_mut(entity).ok());
+        match existing {
+            Some(mut bbox) => {
+                let moved = bbox.data.left_bottom().pos() != data.left_bottom().pos()
+                    || bbox.data.right_top().pos() != data.right_top().pos();
+                if moved {
+                    bbox.data = data;
                 }
-            } else {
-                false
-            };
-
-            // If collision detected, create visualization for both shapes
-            if collision_detected {
-                // Calculate separation vector
-                let separation_vector = if let (Some(point), _) = (point_a, point_b) {
-                    if let Some(other_point) = point_b {
-                        point.data.try_get_seperation_vector(&other_point.data)
-                    } else if let Some(other_line) = line_b {
-                        point.data.try_get_seperation_vector(&other_line.data)
-                    } else if let Some(other_bbox) = bbox_b {
-                        point.data.try_get_seperation_vector(&other_bbox.data)
-                    } else if let Some(other_circle) = circle_b {
-                        point.data.try_get_seperation_vector(&other_circle.data)
-                    } else if let Some(other_polygon) = polygon_b {
-                        point.data.try_get_seperation_vector(&other_polygon.data)
-                    } else {
-                        None
-                    }
-                } else if let (Some(line), _) = (line_a, line_b) {
-                    if let Some(other_point) = point_b {
-                        line.data.try_get_seperation_vector(&other_point.data)
-                    } else if let Some(other_line) = line_b {
-                        line.data.try_get_seperation_vector(&other_line.data)
-                    } else if let Some(other_bbox) = bbox_b {
-                        line.data.try_get_seperation_vector(&other_bbox.data)
-                    } else if let Some(other_circle) = circle_b {
-                        line.data.try_get_seperation_vector(&other_circle.data)
-                    } else if let Some(other_polygon) = polygon_b {
-                        line.data.try_get_seperation_vector(&other_polygon.data)
-                    } else {
-                        None
-                    }
-                } else if let (Some(bbox), _) = (bbox_a, bbox_b) {
-                    if let Some(other_point) = point_b {
-                        bbox.data.try_get_seperation_vector(&other_point.data)
-                    } else if let Some(other_line) = line_b {
-                        bbox.data.try_get_seperation_vector(&other_line.data)
-                    } else if let Some(other_bbox) = bbox_b {
-                        bbox.data.try_get_seperation_vector(&other_bbox.data)
-                    } else if let Some(other_circle) = circle_b {
-                        bbox.data.try_get_seperation_vector(&other_circle.data)
-                    } else if let Some(other_polygon) = polygon_b {
-                        bbox.data.try_get_seperation_vector(&other_polygon.data)
-                    } else {
-                        None
-                    }
-                } else if let (Some(circle), _) = (circle_a, circle_b) {
-                    if let Some(other_point) = point_b {
-                        circle.data.try_get_seperation_vector(&other_point.data)
-                    } else if let Some(other_line) = line_b {
-                        circle.data.try_get_seperation_vector(&other_line.data)
-                    } else if let Some(other_bbox) = bbox_b {
-                        circle.data.try_get_seperation_vector(&other_bbox.data)
-                    } else if let Some(other_circle) = circle_b {
-                        circle.data.try_get_seperation_vector(&other_circle.data)
-                    } else if let Some(other_polygon) = polygon_b {
-                        circle.data.try_get_seperation_vector(&other_polygon.data)
-                    } else {
-                        None
-                    }
-                } else if let (Some(polygon), _) = (polygon_a, polygon_b) {
-                    if let Some(other_point) = point_b {
-                        polygon.data.try_get_seperation_vector(&other_point.data)
-                    } else if let Some(other_line) = line_b {
-                        polygon.data.try_get_seperation_vector(&other_line.data)
-                    } else if let Some(other_bbox) = bbox_b {
-                        polygon.data.try_get_seperation_vector(&other_bbox.data)
-                    } else if let Some(other_circle) = circle_b {
-                        polygon.data.try_get_seperation_vector(&other_circle.data)
-                    } else if let Some(other_polygon) = polygon_b {
-                        polygon.data.try_get_seperation_vector(&other_polygon.data)
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                };
-
-                // Visualize bbox for first shape
-                if let (Some(point), _) = (point_a, point_b) {
-                    let data = point.data.get_bbox();
-                    commands.spawn((
-                        EditorShape {
-                            layer: ShapeLayer::Generated,
-                            shape_type: data.get_shape_type(),
-                            color: collision_detection_settings.shape_color_bbox,
-                            ..default()
-                        },
-                        QBboxData { data },
-                        CollisionVisualization,
-                        Transform::default(),
-                        Visibility::default(),
-                    ));
-                } else if let (Some(line), _) = (line_a, line_b) {
-                    let data = line.data.get_bbox();
-                    commands.spawn((
-                        EditorShape {
-                            layer: ShapeLayer::Generated,
-                            shape_type: data.get_shape_type(),
-                            color: collision_detection_settings.shape_color_bbox,
-                            ..default()
-                        },
-                        QBboxData { data },
-                        CollisionVisualization,
-                        Transform::default(),
-                        Visibility::default(),
-                    ));
-                } else if let (Some(bbox), _) = (bbox_a, bbox_b) {
-                    let data = bbox.data.get_bbox(); // Already a bbox, but call get_bbox for consistency
-                    commands.spawn((
-                        EditorShape {
-                            layer: ShapeLayer::Generated,
-                            shape_type: data.get_shape_type(),
-                            color: collision_detection_settings.shape_color_bbox,
-                            ..default()
-                        },
-                        QBboxData { data },
-                        CollisionVisualization,
-                        Transform::default(),
-                        Visibility::default(),
-                    ));
-                } else if let (Some(circle), _) = (circle_a, circle_b) {
-                    let data = circle.data.get_bbox();
-                    commands.spawn((
-                        EditorShape {
-                            layer: ShapeLayer::Generated,
-                            shape_type: data.get_shape_type(),
-                            color: collision_detection_settings.shape_color_bbox,
-                            ..default()
-                        },
-                        QBboxData { data },
-                        CollisionVisualization,
-                        Transform::default(),
-                        Visibility::default(),
-                    ));
-                } else if let (Some(polygon), _) = (polygon_a, polygon_b) {
-                    let data = polygon.data.get_bbox();
-                    commands.spawn((
+            }
+            None => {
+                let entity = commands
+                    .spawn((
                         EditorShape {
                             layer: ShapeLayer::Generated,
                             shape_type: data.get_shape_type(),
@@ -276,89 +124,33 @@ pub fn detect_collisions(
                         CollisionVisualization,
                         Transform::default(),
                         Visibility::default(),
-                    ));
-                }
+                    ))
+                    .id();
+                visuals.bboxes.insert(shape, entity);
+            }
+        }
+    }
 
-                // Visualize bbox for second shape
-                if let (_, Some(other_point)) = (point_a, point_b) {
-                    let data = other_point.data.get_bbox();
-                    commands.spawn((
-                        EditorShape {
-                            layer: ShapeLayer::Generated,
-                            shape_type: data.get_shape_type(),
-                            color: collision_detection_settings.shape_color_bbox,
-                            ..default()
-                        },
-                        QBboxData { data },
-                        CollisionVisualization,
-                        Transform::default(),
-                        Visibility::default(),
-                    ));
-                } else if let (_, Some(other_line)) = (line_a, line_b) {
-                    let data = other_line.data.get_bbox();
-                    commands.spawn((
-                        EditorShape {
-                            layer: ShapeLayer::Generated,
-                            shape_type: data.get_shape_type(),
-                            color: collision_detection_settings.shape_color_bbox,
-                            ..default()
-                        },
-                        QBboxData { data },
-                        CollisionVisualization,
-                        Transform::default(),
-                        Visibility::default(),
-                    ));
-                } else if let (_, Some(other_bbox)) = (bbox_a, bbox_b) {
-                    let data = other_bbox.data.get_bbox(); // Already a bbox, but call get_bbox for consistency
-                    commands.spawn((
-                        EditorShape {
-                            layer: ShapeLayer::Generated,
-                            shape_type: data.get_shape_type(),
-                            color: collision_detection_settings.shape_color_bbox,
-                            ..default()
-                        },
-                        QBboxData { data },
-                        CollisionVisualization,
-                        Transform::default(),
-                        Visibility::default(),
-                    ));
-                } else if let (_, Some(other_circle)) = (circle_a, circle_b) {
-                    let data = other_circle.data.get_bbox();
-                    commands.spawn((
-                        EditorShape {
-                            layer: ShapeLayer::Generated,
-                            shape_type: data.get_shape_type(),
-                            color: collision_detection_settings.shape_color_bbox,
-                            ..default()
-                        },
-                        QBboxData { data },
-                        CollisionVisualization,
-                        Transform::default(),
-                        Visibility::default(),
-                    ));
-                } else if let (_, Some(other_polygon)) = (polygon_a, polygon_b) {
-                    let data = other_polygon.data.get_bbox();
-                    commands.spawn((
-                        EditorShape {
-                            layer: ShapeLayer::Generated,
-                            shape_type: data.get_shape_type(),
-                            color: collision_detection_settings.shape_color_bbox,
-                            ..default()
-                        },
-                        QBboxData { data },
-                        CollisionVisualization,
-                        Transform::default(),
-                        Visibility::default(),
-                    ));
+    // Separation vectors of the colliding pairs, kept the same way
+    visuals.separations.retain(|pair, visualization| {
+        let keep = separation_lines.contains_key(pair);
+        if !keep {
+            commands.entity(*visualization).despawn();
+        }
+        keep
+    });
+    for (pair, data) in separation_lines {
+        let existing = visuals.separations.get(&pair).copied();
+        match existing.and_then(|entity| separation_visualizations.get_mut(entity).ok()) {
+            Some(mut line) => {
+                let moved = line.data.start().pos() != data.start().pos() || line.data.end().pos() != data.end().pos();
+                if moved {
+                    line.data = data;
                 }
-
-                // Spawn separation vector visualization if available
-                if let Some(vector) = separation_vector
-                    && vector != QVec2::ZERO
-                {
-                    let start = get_shape_center(point_b, line_b, bbox_b, circle_b, polygon_b);
-                    let data = QLine::new_from_parts(start.pos(), start.pos().saturating_add(vector));
-                    commands.spawn((
+            }
+            None => {
+                let entity = commands
+                    .spawn((
                         EditorShape {
                             layer: ShapeLayer::Generated,
                             shape_type: data.get_shape_type(),
@@ -370,33 +162,14 @@ pub fn detect_collisions(
                         SeparationVectorVisualization,
                         Transform::default(),
                         Visibility::default(),
-                    ));
-                }
+                    ))
+                    .id();
+                visuals.separations.insert(pair, entity);
             }
         }
     }
 }
 
-// Helper function to get the center of a shape
-fn get_shape_center(
-    point: Option<&QPointData>, line: Option<&QLineData>, bbox: Option<&QBboxData>, circle: Option<&QCircleData>,
-    polygon: Option<&QPolygonData>,
-) -> QPoint {
-    if let Some(point) = point {
-        point.data.get_centroid()
-    } else if let Some(line) = line {
-        line.data.get_centroid()
-    } else if let Some(bbox) = bbox {
-        bbox.data.get_centroid()
-    } else if let Some(circle) = circle {
-        circle.data.get_centroid()
-    } else if let Some(polygon) = polygon {
-        polygon.data.get_centroid()
-    } else {
-        QPoint::ZERO
-    }
-}
-
 /// System to compute and visualize Minkowski difference of two selected polygons
 pub fn compute_minkowski_difference(
     // Query all shapes with their components
@@ -411,6 +184,7 @@ pub fn compute_minkowski_difference(
     )>,
     // Query existing Minkowski difference visualizations to clean them up
     mut minkowski_query: Query<Entity, With<MinkowskiDifferenceVisualization>>,
+    collision_detection_settings: Res<CollisionDetectionSettings>,
     // Add commands to spawn/despawn entities for visualization
     mut commands: Commands,
 ) {
@@ -418,6 +192,9 @@ pub fn compute_minkowski_difference(
     for entity in minkowski_query.iter_mut() {
         commands.entity(entity).despawn();
     }
+    if !collision_detection_settings.show_minkowski_difference {
+        return;
+    }
 
     // Find exactly two selected polygons
     let mut selected_polygons: Vec<(Entity, &QPolygonData)> = Vec::new();
@@ -455,6 +232,82 @@ pub fn compute_minkowski_difference(
     ));
 }
 
+/// System to compute and visualize the Minkowski sum of two selected polygons
+///
+/// The sum is the convex hull of the sums of all vertex pairs, which is exact for convex polygons and the
+/// hull of the sum otherwise.
+pub fn compute_minkowski_sum(
+    polygons: Query<(&EditorShape, &QPolygonData)>,
+    // Query existing Minkowski sum visualizations to clean them up
+    minkowski_query: Query<Entity, With<MinkowskiSumVisualization>>,
+    collision_detection_settings: Res<CollisionDetectionSettings>,
+    mut commands: Commands,
+) {
+    for entity in minkowski_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    if !collision_detection_settings.show_minkowski_sum {
+        return;
+    }
+
+    // Only proceed if exactly two polygons are selected
+    let selected_polygons: Vec<&QPolygonData> = polygons
+        .iter()
+        .filter(|(shape, _)| shape.selected)
+        .map(|(_, polygon)| polygon)
+        .collect();
+    let [polygon_a, polygon_b] = selected_polygons[..] else {
+        return;
+    };
+
+    let sums = polygon_a
+        .data
+        .points()
+        .iter()
+        .flat_map(|a| {
+            polygon_b
+                .data
+                .points()
+                .iter()
+                .map(move |b| a.pos().saturating_add(b.pos()))
+        })
+        .collect();
+    let Some(hull) = convex_hull(sums) else {
+        return;
+    };
+    let minkowski_sum = QPolygon::new(hull.into_iter().map(QPoint::new).collect());
+
+    // Visualize the Minkowski sum as a polygon
+    commands.spawn((
+        EditorShape {
+            layer: ShapeLayer::Generated,
+            shape_type: minkowski_sum.get_shape_type(),
+            ..default()
+        },
+        QPolygonData { data: minkowski_sum },
+        MinkowskiSumVisualization,
+        Transform::default(),
+        Visibility::default(),
+    ));
+}
+
+/// System to draw the Minkowski sum visualizations in their own color
+pub fn visualize_minkowski_sum(
+    mut gizmos: Gizmos, minkowski_shapes: Query<&QPolygonData, With<MinkowskiSumVisualization>>,
+    collision_detection_settings: Res<CollisionDetectionSettings>,
+) {
+    for polygon_shape in minkowski_shapes.iter() {
+        let points = polygon_shape.data.points();
+        for i in 0..points.len() {
+            gizmos.line_2d(
+                util::qvec2vec(points[i].pos()),
+                util::qvec2vec(points[(i + 1) % points.len()].pos()),
+                collision_detection_settings.shape_color_minkowski_sum,
+            );
+        }
+    }
+}
+
 pub fn visualize_minkowski_difference(
     mut gizmos: Gizmos,
     // Query for Minkowski difference visualizations with specific coloring
@@ -482,3 +335,178 @@ pub fn visualize_minkowski_difference(
         }
     }
 }
+
+/// Size of the GJK debugger's markers and search direction arrow, in pixels
+const GJK_MARKER_PIXELS: f32 = 6.0;
+const GJK_DIRECTION_PIXELS: f32 = 60.0;
+
+/// System tracing GJK and EPA for two selected shapes and stepping through the trace with `.` and `,`
+pub fn update_gjk_debugger(
+    keyboard_input: Res<ButtonInput<KeyCode>>, mut egui_contexts: EguiContexts,
+    shapes: Query<(
+        Entity,
+        &EditorShape,
+        Option<&QPointData>,
+        Option<&QLineData>,
+        Option<&QBboxData>,
+        Option<&QCircleData>,
+        Option<&QPolygonData>,
+    )>,
+    collision_detection_settings: Res<CollisionDetectionSettings>, mut debugger: ResMut<GjkDebugger>,
+) {
+    if !collision_detection_settings.show_gjk_steps {
+        *debugger = GjkDebugger::default();
+        return;
+    }
+
+    // Only proceed if exactly two shapes are selected
+    let selected: Vec<(Entity, AnyQShape)> = shapes
+        .iter()
+        .filter(|(_, shape, ..)| shape.selected && shape.layer != ShapeLayer::Generated)
+        .filter_map(|(entity, _, point, line, bbox, circle, polygon)| {
+            Some((entity, AnyQShape::from_components(point, line, bbox, circle, polygon)?))
+        })
+        .collect();
+    let [(entity_a, shape_a), (entity_b, shape_b)] = selected[..] else {
+        *debugger = GjkDebugger::default();
+        return;
+    };
+
+    // Retrace every frame so moving the shapes updates the steps
+    if debugger.pair != Some((entity_a, entity_b)) {
+        debugger.pair = Some((entity_a, entity_b));
+        debugger.current = 0;
+    }
+    debugger.steps = trace_gjk_epa(&shape_a.vertices(), &shape_b.vertices());
+
+    if !egui_contexts.ctx_mut().is_ok_and(|ctx| ctx.wants_keyboard_input()) {
+        if keyboard_input.just_pressed(KeyCode::Period) {
+            debugger.current += 1;
+        }
+        if keyboard_input.just_pressed(KeyCode::Comma) {
+            debugger.current = debugger.current.saturating_sub(1);
+        }
+    }
+    debugger.current = debugger.current.min(debugger.steps.len().saturating_sub(1));
+}
+
+/// System to draw the current GJK or EPA step: the simplex or polytope, the support point and the search
+/// direction from the origin of the Minkowski difference
+pub fn draw_gjk_debugger(
+    mut gizmos: Gizmos, debugger: Res<GjkDebugger>, collision_detection_settings: Res<CollisionDetectionSettings>,
+    camera_query: Query<&GlobalTransform, With<CameraMovement>>,
+) {
+    let Some(step) = debugger.steps.get(debugger.current) else {
+        return;
+    };
+    let camera_scale = camera_query
+        .single()
+        .map_or(1.0, |transform| transform.compute_transform().scale.x);
+    let marker = GJK_MARKER_PIXELS * camera_scale;
+    let color = collision_detection_settings.shape_color_gjk;
+
+    // Origin
+    gizmos.line_2d(Vec2::new(-marker, 0.0), Vec2::new(marker, 0.0), color);
+    gizmos.line_2d(Vec2::new(0.0, -marker), Vec2::new(0.0, marker), color);
+
+    let points: Vec<Vec2> = step.simplex.iter().map(|point| util::qvec2vec(*point)).collect();
+    // A two point simplex is a segment, the polytope and triangles are closed
+    let edges = match points.len() {
+        0 | 1 => 0,
+        2 => 1,
+        count => count,
+    };
+    for i in 0..edges {
+        gizmos.line_2d(points[i], points[(i + 1) % points.len()], color);
+    }
+    for point in &points {
+        gizmos.circle_2d(*point, marker * 0.5, color);
+    }
+    if let Some(support) = step.support {
+        gizmos.circle_2d(util::qvec2vec(support), marker, color);
+    }
+
+    let direction = util::qvec2vec(step.direction).normalize_or_zero();
+    if direction != Vec2::ZERO {
+        let length = match step.phase {
+            GjkPhase::Gjk => GJK_DIRECTION_PIXELS,
+            // EPA directions are edge normals, drawn shorter to tell them apart
+            GjkPhase::Epa => GJK_DIRECTION_PIXELS * 0.5,
+        };
+        gizmos.arrow_2d(Vec2::ZERO, direction * length * camera_scale, color);
+    }
+}
+
+/// System to find and draw the shortest segment between two selected shapes
+pub fn update_closest_points(
+    mut gizmos: Gizmos,
+    shapes: Query<(
+        &EditorShape,
+        Option<&QPointData>,
+        Option<&QLineData>,
+        Option<&QBboxData>,
+        Option<&QCircleData>,
+        Option<&QPolygonData>,
+    )>,
+    collision_detection_settings: Res<CollisionDetectionSettings>, mut closest: ResMut<ClosestPoints>,
+) {
+    closest.points = None;
+    if !collision_detection_settings.show_closest_points {
+        return;
+    }
+
+    // Only proceed if exactly two shapes are selected
+    let selected: Vec<AnyQShape> = shapes
+        .iter()
+        .filter(|(shape, ..)| shape.selected && shape.layer != ShapeLayer::Generated)
+        .filter_map(|(_, point, line, bbox, circle, polygon)| {
+            AnyQShape::from_components(point, line, bbox, circle, polygon)
+        })
+        .collect();
+    let [shape_a, shape_b] = selected[..] else {
+        return;
+    };
+
+    closest.points = closest_points(shape_a, shape_b);
+    if let Some((from, to)) = closest.points {
+        let color = collision_detection_settings.shape_color_closest_points;
+        gizmos.line_2d(util::qvec2vec(from), util::qvec2vec(to), color);
+        gizmos.circle_2d(util::qvec2vec(from), 0.1, color);
+        gizmos.circle_2d(util::qvec2vec(to), 0.1, color);
+    }
+}
+
+/// System to label the shortest segment between two selected shapes with its length
+pub fn draw_closest_points_label(
+    mut contexts: EguiContexts, closest: Res<ClosestPoints>, coordinate_settings: Res<CoordinateSettings>,
+    collision_detection_settings: Res<CollisionDetectionSettings>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<CameraMovement>>,
+) {
+    let Some((from, to)) = closest.points else {
+        return;
+    };
+    let (Ok(ctx), Ok((camera, camera_transform))) = (contexts.ctx_mut(), camera_query.single()) else {
+        return;
+    };
+    let Some(viewport) = camera.logical_viewport_rect() else {
+        return;
+    };
+    let middle = (util::qvec2vec(from) + util::qvec2vec(to)) / 2.0;
+    let Ok(position) = camera.world_to_viewport(camera_transform, middle.extend(0.0)) else {
+        return;
+    };
+    let position = position + viewport.min;
+
+    let length = to.saturating_sub(from).length().to_num::<f32>();
+    let [r, g, b, _] = collision_detection_settings
+        .shape_color_closest_points
+        .to_srgba()
+        .to_u8_array();
+    ctx.layer_painter(egui::LayerId::background()).text(
+        egui::pos2(position.x, position.y),
+        egui::Align2::LEFT_BOTTOM,
+        coordinate_settings.unit.format(length, 3),
+        egui::FontId::monospace(12.0),
+        egui::Color32::from_rgb(r, g, b),
+    );
+}
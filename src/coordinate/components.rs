@@ -0,0 +1,18 @@
+//! Coordinate components
+//!
+//! This module defines the components and messages used by the coordinate system.
+
+use bevy::prelude::*;
+
+/// Marker for the sprite showing the reference image
+#[derive(Component, Default)]
+pub struct ReferenceImageSprite;
+
+/// Events to load or remove the reference image
+#[derive(Message, Clone, Debug, PartialEq, Eq)]
+pub enum ReferenceImageEvent {
+    /// Load a PNG or JPG file as the reference image, replacing the current one
+    Load { file_path: String },
+    /// Remove the reference image
+    Clear,
+}
@@ -3,6 +3,8 @@
 //! This module provides functionality for rendering and interacting with a 2D coordinate system
 //! including axes and grid functionality.
 
+pub mod components;
+pub mod object_snap;
 pub mod plugin;
 pub mod resources;
 pub mod systems;
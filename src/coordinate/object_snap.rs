@@ -0,0 +1,135 @@
+//! Object snapping
+//!
+//! Finds the shape feature nearest to the cursor: vertices, edge midpoints, centers and the crossings of
+//! edges. Only edges passing near the cursor are intersected with each other, so the cost doesn't grow with
+//! the square of the scene size.
+
+use super::resources::{ObjectSnap, ObjectSnapKind};
+use crate::shapes::components::AnyQShape;
+use crate::util::{cross, qvec2vec};
+use bevy::prelude::*;
+use qmath::prelude::*;
+use qmath::vec2::QVec2;
+
+/// Features of one shape that can be snapped to, kept exact so a snapped point lands on the feature itself
+#[derive(Default)]
+struct Features {
+    vertices: Vec<QVec2>,
+    centers: Vec<QVec2>,
+    edges: Vec<(QVec2, QVec2)>,
+}
+
+fn features(shape: AnyQShape) -> Features {
+    let closed = |points: Vec<QVec2>| Features {
+        edges: (0..points.len())
+            .map(|i| (points[i], points[(i + 1) % points.len()]))
+            .collect(),
+        vertices: points,
+        centers: Vec::new(),
+    };
+    match shape {
+        AnyQShape::Point(point) => Features {
+            vertices: vec![point.pos()],
+            ..default()
+        },
+        AnyQShape::Line(line) => {
+            let (start, end) = (line.start().pos(), line.end().pos());
+            Features {
+                vertices: vec![start, end],
+                centers: Vec::new(),
+                edges: vec![(start, end)],
+            }
+        }
+        AnyQShape::Bbox(bbox) => {
+            let (min, max) = (bbox.left_bottom().pos(), bbox.right_top().pos());
+            let mut features = closed(vec![min, QVec2::new(max.x, min.y), max, QVec2::new(min.x, max.y)]);
+            features.centers.push(midpoint((min, max)));
+            features
+        }
+        AnyQShape::Circle(circle) => Features {
+            centers: vec![circle.center().pos()],
+            ..default()
+        },
+        AnyQShape::Polygon(polygon) => closed(polygon.points().iter().map(|point| point.pos()).collect()),
+    }
+}
+
+fn midpoint((a, b): (QVec2, QVec2)) -> QVec2 {
+    QVec2::new(a.x.saturating_add(b.x).half(), a.y.saturating_add(b.y).half())
+}
+
+/// Distance from `point` to the segment from `a` to `b`, in world units for comparing with the snap radius
+fn segment_distance(point: Vec2, (a, b): (QVec2, QVec2)) -> f32 {
+    let (a, b) = (qvec2vec(a), qvec2vec(b));
+    let ab = b - a;
+    let t = if ab.length_squared() > 0.0 {
+        ((point - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    point.distance(a + ab * t)
+}
+
+/// Crossing point of two segments, `None` when they don't cross or are parallel
+fn segment_intersection((a, b): (QVec2, QVec2), (c, d): (QVec2, QVec2)) -> Option<QVec2> {
+    let (ab, cd) = (b.saturating_sub(a), d.saturating_sub(c));
+    // Both segments have to reach the other's line, a segment ending on the other one still crosses it
+    let (c_side, d_side) = (cross(ab, c.saturating_sub(a)), cross(ab, d.saturating_sub(a)));
+    let (a_side, b_side) = (cross(cd, a.saturating_sub(c)), cross(cd, b.saturating_sub(c)));
+    let reaches = |u: Q64, v: Q64| u != v && !(u > Q64::ZERO && v > Q64::ZERO) && !(u < Q64::ZERO && v < Q64::ZERO);
+    if !reaches(c_side, d_side) || !reaches(a_side, b_side) {
+        return None;
+    }
+    let t = a_side.saturating_div(a_side.saturating_sub(b_side));
+    Some(a.saturating_add(QVec2::new(ab.x.saturating_mul(t), ab.y.saturating_mul(t))))
+}
+
+/// Nearest enabled snap target of `shapes` within `radius` of `cursor`
+pub fn object_snap<'a>(
+    shapes: impl Iterator<Item = AnyQShape<'a>>, cursor: Vec2, radius: f32, targets: &ObjectSnap,
+) -> Option<(ObjectSnapKind, QVec2)> {
+    let mut candidates: Vec<(ObjectSnapKind, QVec2)> = Vec::new();
+    let mut near_edges: Vec<(QVec2, QVec2)> = Vec::new();
+    for shape in shapes {
+        let features = features(shape);
+        if targets.vertices {
+            candidates.extend(features.vertices.iter().map(|vertex| (ObjectSnapKind::Vertex, *vertex)));
+        }
+        if targets.centers {
+            candidates.extend(features.centers.iter().map(|center| (ObjectSnapKind::Center, *center)));
+        }
+        if targets.midpoints {
+            let midpoints = features
+                .edges
+                .iter()
+                .map(|edge| (ObjectSnapKind::Midpoint, midpoint(*edge)));
+            candidates.extend(midpoints);
+        }
+        near_edges.extend(
+            features
+                .edges
+                .into_iter()
+                .filter(|edge| segment_distance(cursor, *edge) <= radius),
+        );
+    }
+    if targets.intersections {
+        for (i, a) in near_edges.iter().enumerate() {
+            for b in &near_edges[i + 1..] {
+                // Edges of the same outline meet at their shared vertex, which is already a vertex target
+                if a.0 == b.0 || a.0 == b.1 || a.1 == b.0 || a.1 == b.1 {
+                    continue;
+                }
+                if let Some(crossing) = segment_intersection(*a, *b) {
+                    candidates.push((ObjectSnapKind::Intersection, crossing));
+                }
+            }
+        }
+    }
+
+    candidates
+        .into_iter()
+        .map(|(kind, position)| (qvec2vec(position).distance(cursor), kind, position))
+        .filter(|(distance, ..)| *distance <= radius)
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, kind, position)| (kind, position))
+}
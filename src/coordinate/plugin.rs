@@ -4,8 +4,18 @@
 //! initialization of resources and registration of systems for rendering the grid
 //! and axes.
 
-use super::{resources::CoordinateSettings, systems::draw_coordinate_system};
+use super::{
+    components::ReferenceImageEvent,
+    resources::{ChunkGizmos, CoordinateSettings, Guides, MeasureTool, ReferenceImage, SnapTarget},
+    systems::{
+        draw_axis_labels, draw_chunk_labels, draw_coordinate_system, draw_dimension_labels, draw_guides,
+        draw_measure_preview, draw_origin_and_crosshair, draw_rulers, draw_snap_preview, handle_reference_image_events,
+        measure_tool, set_origin_here, sync_chunk_gizmo_config, sync_reference_image, toggle_coordinate_visibility,
+        update_snap_target,
+    },
+};
 use bevy::prelude::*;
+use bevy_egui::EguiPrimaryContextPass;
 
 /// `CoordinatePlugin` registers the coordinate system resource and its rendering systems.
 pub struct CoordinatePlugin;
@@ -14,7 +24,42 @@ impl Plugin for CoordinatePlugin {
     fn build(&self, app: &mut App) {
         // Initialize coordinate settings using `init_resource` for consistency.
         app.init_resource::<CoordinateSettings>()
+            .init_resource::<Guides>()
+            .init_resource::<SnapTarget>()
+            .init_resource::<MeasureTool>()
+            .init_resource::<ReferenceImage>()
+            .init_gizmo_group::<ChunkGizmos>()
+            .add_message::<ReferenceImageEvent>()
+            .add_systems(
+                Update,
+                (
+                    toggle_coordinate_visibility,
+                    set_origin_here,
+                    (measure_tool, draw_measure_preview).chain(),
+                    (
+                        handle_reference_image_events,
+                        sync_reference_image.run_if(resource_changed::<ReferenceImage>),
+                    )
+                        .chain(),
+                ),
+            )
+            .add_systems(
+                PreUpdate,
+                sync_chunk_gizmo_config.run_if(resource_changed::<CoordinateSettings>),
+            )
             // Register the drawing system at the Update stage.
-            .add_systems(PreUpdate, draw_coordinate_system);
+            .add_systems(
+                PreUpdate,
+                (
+                    draw_coordinate_system,
+                    draw_guides,
+                    (update_snap_target, draw_origin_and_crosshair, draw_snap_preview).chain(),
+                ),
+            )
+            // Labels are painted with egui so they keep a readable size at every zoom
+            .add_systems(
+                EguiPrimaryContextPass,
+                (draw_axis_labels, draw_chunk_labels, draw_rulers, draw_dimension_labels),
+            );
     }
 }
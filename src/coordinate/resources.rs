@@ -3,22 +3,205 @@
 //! This module defines the resources used for the coordinate system.
 
 use bevy::prelude::*;
+use qmath::prelude::*;
+use qmath::vec2::QVec2;
+use serde::{Deserialize, Serialize};
+
+/// Layout of the grid lines and the points shapes snap to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum GridMode {
+    /// Horizontal and vertical lines every `grid_spacing`
+    #[default]
+    Cartesian,
+    /// Rings every `grid_spacing` and evenly spaced spokes around `polar_origin`
+    Polar,
+    /// 2:1 diamonds `grid_spacing` high and twice as wide
+    Isometric,
+    /// Pointy-top hexagons with corners `grid_spacing` from their centers
+    Hex,
+}
+
+/// How the grid is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum GridStyle {
+    /// Full grid lines
+    #[default]
+    Lines,
+    /// Dots where the grid lines would cross, quieter for dense grids and large scenes
+    Dots,
+}
+
+/// Kind of shape feature the cursor snapped to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectSnapKind {
+    Vertex,
+    Midpoint,
+    Center,
+    Intersection,
+}
+
+/// Shape features the cursor snaps to when one is near, ahead of the grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ObjectSnap {
+    pub enabled: bool,
+    /// Points, line ends, box corners and polygon vertices
+    pub vertices: bool,
+    /// Middles of lines, box sides and polygon edges
+    pub midpoints: bool,
+    /// Circle and box centers
+    pub centers: bool,
+    /// Crossings of lines, box sides and polygon edges
+    pub intersections: bool,
+}
+
+impl Default for ObjectSnap {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            vertices: true,
+            midpoints: true,
+            centers: true,
+            intersections: true,
+        }
+    }
+}
+
+/// Unit coordinates and measurements are shown in, relative to world units
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DisplayUnit {
+    /// Text shown after values, empty for plain world units
+    pub suffix: String,
+    /// How many display units make up one world unit
+    pub per_world_unit: f32,
+}
+
+impl DisplayUnit {
+    /// Plain world units
+    pub fn world() -> Self {
+        Self {
+            suffix: String::new(),
+            per_world_unit: 1.0,
+        }
+    }
+
+    /// One meter per world unit
+    pub fn meters() -> Self {
+        Self {
+            suffix: " m".to_string(),
+            per_world_unit: 1.0,
+        }
+    }
+
+    /// Sixteen pixels per world unit, a common tile size
+    pub fn pixels() -> Self {
+        Self {
+            suffix: " px".to_string(),
+            per_world_unit: 16.0,
+        }
+    }
+
+    /// Convert a world length to display units
+    pub fn to_display(&self, world: f32) -> f32 {
+        world * self.per_world_unit
+    }
+
+    /// Convert a length in display units back to world units
+    pub fn to_world(&self, display: f32) -> f32 {
+        if self.per_world_unit == 0.0 {
+            display
+        } else {
+            display / self.per_world_unit
+        }
+    }
+
+    /// Format a world length in display units with its suffix
+    pub fn format(&self, world: f32, decimals: usize) -> String {
+        format!("{:.*}{}", decimals, self.to_display(world), self.suffix)
+    }
+}
+
+impl Default for DisplayUnit {
+    fn default() -> Self {
+        Self::world()
+    }
+}
+
+/// Center of the pointy-top hex at axial coordinates `hex`, for hexes with corners `size` from their centers
+pub fn hex_center(size: f32, hex: IVec2) -> Vec2 {
+    let sqrt3 = 3f32.sqrt();
+    Vec2::new(sqrt3 * hex.x as f32 + sqrt3 / 2.0 * hex.y as f32, 1.5 * hex.y as f32) * size
+}
+
+/// Axial coordinates of the pointy-top hex containing `position`
+pub fn hex_at(size: f32, position: Vec2) -> IVec2 {
+    let q = (3f32.sqrt() / 3.0 * position.x - position.y / 3.0) / size;
+    let r = (2.0 / 3.0 * position.y) / size;
+    // Round in cube coordinates, then fix the component that moved the most so they still sum to zero
+    let s = -q - r;
+    let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+    let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+    if dq > dr && dq > ds {
+        rq = -rr - rs;
+    } else if dr > ds {
+        rr = -rq - rs;
+    }
+    IVec2::new(rq as i32, rr as i32)
+}
 
 /// Resource containing coordinate system settings
-#[derive(Resource, Debug, Clone)]
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CoordinateSettings {
     /// Color of the X axis
     pub x_axis_color: Color,
     /// Color of the Y axis
     pub y_axis_color: Color,
+    /// Whether to draw the grid and chunk lines
+    pub show_grid: bool,
+    /// Whether to draw the axes
+    pub show_axes: bool,
     /// Spacing between grid lines
     pub grid_spacing: f32,
     /// Color of the grid lines
     pub grid_color: Color,
-    /// Spacing between chunks
+    /// Spacing between chunks, drawn as major grid lines
     pub chunk_spacing: f32,
     /// Color of the chunks
     pub chunk_color: Color,
+    /// Width of the chunk lines in pixels
+    pub chunk_line_width: f32,
+    /// Whether to draw the chunk boundaries in every grid mode, labelled with the chunk indices
+    pub show_chunk_overlay: bool,
+    /// Whether to label the axes with their coordinates
+    pub show_axis_labels: bool,
+    /// Whether to label grid intersections with their coordinates
+    pub show_intersection_labels: bool,
+    /// Whether to show rulers along the top and left edges of the viewport
+    pub show_rulers: bool,
+    /// Whether to mark the origin
+    pub show_origin_marker: bool,
+    /// Whether to draw lines across the view through the snapped cursor position
+    pub show_crosshair: bool,
+    /// Color of the cursor crosshair
+    pub crosshair_color: Color,
+    /// Layout the grid is drawn and snapped in
+    pub grid_mode: GridMode,
+    /// Whether the grid is drawn as lines or as dots at their intersections
+    pub grid_style: GridStyle,
+    /// Center of the rings and spokes of the polar grid
+    pub polar_origin: Vec2,
+    /// Number of spokes of the polar grid, evenly spaced around the full circle
+    pub polar_spokes: u32,
+    /// Unit coordinates, measurements and physics values are shown in
+    pub unit: DisplayUnit,
+    /// World position shown as the origin. Displayed coordinates and the grid are relative to it, while
+    /// shapes are still stored in world coordinates.
+    pub origin_offset: Vec2,
+    /// Shape features the cursor snaps to
+    pub object_snap: ObjectSnap,
+    /// Distance in pixels within which the cursor snaps to a shape feature
+    pub object_snap_pixels: f32,
 }
 
 impl Default for CoordinateSettings {
@@ -26,10 +209,138 @@ impl Default for CoordinateSettings {
         Self {
             x_axis_color: Color::srgba(1.0, 0.0, 0.0, 0.5), // Red for X axis
             y_axis_color: Color::srgba(0.0, 0.0, 1.0, 0.5), // Blue for Y axis
+            show_grid: true,
+            show_axes: true,
             grid_spacing: 1.0,
             grid_color: Color::srgba(0.5, 0.5, 0.5, 0.3),
             chunk_spacing: 100.0,
             chunk_color: Color::srgba(0.5, 0.5, 0.5, 0.5),
+            chunk_line_width: 2.0,
+            show_chunk_overlay: false,
+            show_axis_labels: true,
+            show_intersection_labels: false,
+            show_rulers: true,
+            show_origin_marker: true,
+            show_crosshair: false,
+            crosshair_color: Color::srgba(1.0, 1.0, 1.0, 0.4),
+            grid_mode: GridMode::Cartesian,
+            grid_style: GridStyle::Lines,
+            polar_origin: Vec2::ZERO,
+            polar_spokes: 24,
+            unit: DisplayUnit::world(),
+            origin_offset: Vec2::ZERO,
+            object_snap: ObjectSnap::default(),
+            object_snap_pixels: 10.0,
+        }
+    }
+}
+
+impl CoordinateSettings {
+    /// Position relative to the origin offset, as coordinates are displayed
+    pub fn to_local(&self, world: Vec2) -> Vec2 {
+        world - self.origin_offset
+    }
+
+    /// Snap a world position to the nearest grid point of the current grid mode
+    pub fn snap(&self, position: Vec2) -> Vec2 {
+        if self.grid_spacing <= 0.0 {
+            return position;
+        }
+        // All grids but the polar one are laid out from the origin offset
+        let origin = self.origin_offset;
+        match self.grid_mode {
+            GridMode::Cartesian => origin + (self.to_local(position) / self.grid_spacing).round() * self.grid_spacing,
+            GridMode::Polar => {
+                let offset = position - self.polar_origin;
+                let radius = (offset.length() / self.grid_spacing).round() * self.grid_spacing;
+                let step = std::f32::consts::TAU / self.polar_spokes.max(1) as f32;
+                let angle = (offset.to_angle() / step).round() * step;
+                self.polar_origin + Vec2::from_angle(angle) * radius
+            }
+            GridMode::Isometric => {
+                // Corners are at `i * (s, s / 2) + j * (s, -s / 2)`
+                let local = self.to_local(position) / self.grid_spacing;
+                let (i, j) = (
+                    ((local.x + 2.0 * local.y) / 2.0).round(),
+                    ((local.x - 2.0 * local.y) / 2.0).round(),
+                );
+                origin + Vec2::new(i + j, (i - j) / 2.0) * self.grid_spacing
+            }
+            GridMode::Hex => origin + hex_center(self.grid_spacing, hex_at(self.grid_spacing, self.to_local(position))),
+        }
+    }
+}
+
+/// Resource holding the world position the next click places at, updated every frame
+#[derive(Resource, Debug, Clone, Default)]
+pub struct SnapTarget {
+    /// Cursor position, snapped when snapping is enabled. `None` while the cursor is over the UI or
+    /// outside the main viewport.
+    pub position: Option<Vec2>,
+    /// Cursor position before snapping, for picking existing geometry
+    pub cursor: Option<Vec2>,
+    /// Shape feature snapped to and its exact position, `None` when `position` is on the grid or unsnapped
+    pub object: Option<(ObjectSnapKind, QVec2)>,
+}
+
+impl SnapTarget {
+    /// Exact position the next click places at, the snapped shape feature itself when there is one
+    pub fn qposition(&self) -> Option<QVec2> {
+        self.object.map(|(_, position)| position).or_else(|| {
+            self.position
+                .map(|position| QVec2::new(Q64::from_num(position.x), Q64::from_num(position.y)))
+        })
+    }
+}
+
+/// Resource holding the image shown behind the scene to trace over
+#[derive(Resource, Debug, Clone)]
+pub struct ReferenceImage {
+    /// File the image was loaded from, `None` while no image is loaded
+    pub file_path: Option<String>,
+    /// World position of the image center
+    pub position: Vec2,
+    /// World units per image pixel
+    pub scale: f32,
+    /// Opacity of the image, from 0 to 1
+    pub opacity: f32,
+    /// Whether the image is shown
+    pub visible: bool,
+    /// Whether the position and scale are locked against edits
+    pub locked: bool,
+}
+
+impl Default for ReferenceImage {
+    fn default() -> Self {
+        Self {
+            file_path: None,
+            position: Vec2::ZERO,
+            scale: 0.05,
+            opacity: 0.5,
+            visible: true,
+            locked: false,
         }
     }
 }
+
+/// Resource holding the guide lines dragged out of the rulers
+#[derive(Resource, Debug, Clone, Default)]
+pub struct Guides {
+    /// World Y of the horizontal guides, dragged from the top ruler
+    pub horizontal: Vec<f32>,
+    /// World X of the vertical guides, dragged from the left ruler
+    pub vertical: Vec<f32>,
+}
+
+/// State of the measure tool, which places dimension lines between two clicked points
+#[derive(Resource, Debug, Clone, Default)]
+pub struct MeasureTool {
+    /// Whether clicks in the viewport measure instead of drawing
+    pub active: bool,
+    /// First point of the measurement, waiting for the second click
+    pub start: Option<Vec2>,
+}
+
+/// Gizmo group for the chunk lines, so they can be drawn thicker than the minor grid
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct ChunkGizmos;
@@ -3,23 +3,130 @@
 //! This module defines the systems used for the coordinate system functionality,
 //! including rendering axes and grid lines.
 
-use crate::coordinate::resources::CoordinateSettings;
+use crate::camera::components::CameraMovement;
+use crate::camera::resources::CameraSettings;
+use crate::camera::systems::cursor_in_viewport;
+use crate::coordinate::components::{ReferenceImageEvent, ReferenceImageSprite};
+use crate::coordinate::object_snap::object_snap;
+use crate::coordinate::resources::{
+    ChunkGizmos, CoordinateSettings, DisplayUnit, GridMode, GridStyle, Guides, MeasureTool, ObjectSnapKind,
+    ReferenceImage, SnapTarget, hex_at, hex_center,
+};
+use crate::history::resources::EditHistory;
+use crate::save_load::components::{SerializableQPhysics, SerializableQShape, SerializableQShapeData};
+use crate::save_load::systems::{default_editor_shape, spawn_scene_shape};
+use crate::shapes::components::{
+    AnyQShape, EditorShape, LineAppearance, QBboxData, QCircleData, QLineData, QPointData, QPolygonData, ShapeLayer,
+};
+use crate::shapes::resources::{ShapeDrawingState, TransformGizmoState, VertexEditState};
+use crate::ui::resources::{Notifications, UiState};
+use crate::util::qvec2vec;
+use bevy::asset::RenderAssetUsages;
+use bevy::image::{CompressedImageFormats, ImageSampler, ImageType};
 use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{EguiContexts, egui};
+use qgeometry::shape::{QLine, QPoint};
+use qmath::prelude::*;
+use qmath::vec2::QVec2;
 
-fn draw_grids(gizmos: &mut Gizmos, spacing: f32, color: Color, camera_transform: &GlobalTransform) {
-    // Get the camera viewport to determine the visible area
-    let camera_position = camera_transform.translation();
-    let camera_scale = camera_transform.compute_transform().scale;
+/// Grid lines closer than this many pixels are hidden
+const MIN_GRID_PIXELS: f32 = 8.0;
+/// Grid lines fade in until they are this many pixels apart
+const FULL_GRID_PIXELS: f32 = 32.0;
+/// Grid dots closer than this many pixels are hidden, each dot takes two lines to draw
+const MIN_DOT_PIXELS: f32 = 16.0;
+/// Half the size of a grid dot in pixels
+const DOT_PIXELS: f32 = 1.5;
+/// Hexes smaller than this many pixels are hidden, since each one takes several lines to draw
+const MIN_HEX_PIXELS: f32 = 16.0;
+/// Axis labels are at least this many pixels apart
+const MIN_LABEL_PIXELS: f32 = 80.0;
+/// Distance in pixels between an axis and its labels, and kept from the viewport edges
+const LABEL_MARGIN: f32 = 4.0;
+/// Room a label needs past its anchor when pinned to the viewport edge, wide enough for a few digits
+const EDGE_LABEL_ROOM: Vec2 = Vec2::new(40.0, 12.0);
+/// Thickness of the rulers in pixels
+const RULER_SIZE: f32 = 20.0;
+/// Depth of the reference image, behind the shapes
+const REFERENCE_IMAGE_DEPTH: f32 = -100.0;
+/// Color of the guide lines
+const GUIDE_COLOR: Color = Color::srgba(0.0, 0.8, 1.0, 0.8);
+/// Radius of the origin marker in pixels
+const ORIGIN_MARKER_PIXELS: f32 = 8.0;
+/// Color of the origin marker
+const ORIGIN_MARKER_COLOR: Color = Color::srgb(1.0, 1.0, 1.0);
+/// Radius of the snap preview in pixels
+const SNAP_PREVIEW_PIXELS: f32 = 5.0;
+/// Color of the snap preview
+const SNAP_PREVIEW_COLOR: Color = Color::srgb(1.0, 0.8, 0.0);
+/// Color of the object snap markers
+const OBJECT_SNAP_COLOR: Color = Color::srgb(0.2, 1.0, 0.4);
+/// Most grid intersection labels drawn at once, more would cover the view
+const MAX_INTERSECTION_LABELS: usize = 400;
+/// Color of the measurement preview and dimension labels
+const MEASURE_COLOR: Color = Color::srgb(1.0, 0.5, 0.9);
 
-    // Calculate the visible area based on camera position and scale
-    // This creates an "infinite" feel by dynamically generating lines in the visible area
-    let visible_width = 2000.0 * camera_scale.x;
-    let visible_height = 2000.0 * camera_scale.y;
+/// Spacing of a grid level: even levels are `base_spacing` times a power of ten, odd levels five times
+/// that, so the levels run ..., 0.5, 1, 5, 10, 50, ... times `base_spacing`
+fn grid_level_spacing(base_spacing: f32, level: i32) -> f32 {
+    let multiplier = if level.rem_euclid(2) == 1 { 5.0 } else { 1.0 };
+    base_spacing * 10f32.powi(level.div_euclid(2)) * multiplier
+}
 
-    let left = camera_position.x - visible_width / 2.0;
-    let right = camera_position.x + visible_width / 2.0;
-    let bottom = camera_position.y - visible_height / 2.0;
-    let top = camera_position.y + visible_height / 2.0;
+/// Finest grid level whose lines are at least `min_pixels` apart at the camera scale
+fn grid_level(base_spacing: f32, camera_scale: f32, min_pixels: f32) -> i32 {
+    let mut level = 0;
+    while grid_level_spacing(base_spacing, level) / camera_scale < min_pixels {
+        level += 1;
+    }
+    while grid_level_spacing(base_spacing, level - 1) / camera_scale >= min_pixels {
+        level -= 1;
+    }
+    level
+}
+
+/// Pick the finest grid level at least [`MIN_GRID_PIXELS`] apart for the camera scale. Returns its
+/// spacing, its opacity, and how many of its cells make up one cell of the next level, which is always
+/// drawn fully opaque.
+fn adaptive_grid_level(base_spacing: f32, camera_scale: f32) -> (f32, f32, i32) {
+    let level = grid_level(base_spacing, camera_scale, MIN_GRID_PIXELS);
+    let spacing = grid_level_spacing(base_spacing, level);
+    let ratio = if level.rem_euclid(2) == 1 { 2 } else { 5 };
+    let fade = ((spacing / camera_scale - MIN_GRID_PIXELS) / (FULL_GRID_PIXELS - MIN_GRID_PIXELS)).clamp(0.0, 1.0);
+    (spacing, fade, ratio)
+}
+
+/// World area seen through the camera viewport, as the bounds of its corners so it also covers a
+/// rotated view. This creates an "infinite" feel by only generating the lines that can be seen.
+pub fn visible_area(camera: &Camera, camera_transform: &GlobalTransform) -> Option<Rect> {
+    let size = camera.logical_viewport_size()?;
+    let corners = [Vec2::ZERO, Vec2::new(size.x, 0.0), Vec2::new(0.0, size.y), size];
+    corners.into_iter().try_fold(Rect::EMPTY, |area, corner| {
+        let world = camera.viewport_to_world_2d(camera_transform, corner).ok()?;
+        Some(area.union_point(world))
+    })
+}
+
+/// Draw the chunk lines on top of the grid, skipped once they get as dense as the grid hides its lines
+fn draw_chunks(
+    gizmos: &mut Gizmos<ChunkGizmos>, spacing: f32, color: Color, area: Rect, camera_scale: f32, skip_axes: bool,
+) {
+    if spacing <= 0.0 || spacing / camera_scale < MIN_GRID_PIXELS {
+        return;
+    }
+    draw_grid_lines(gizmos, spacing, 1, color, color, area, Vec2::ZERO, skip_axes);
+}
+
+/// Draw grid lines every `spacing` units from `origin`, using `major_color` for every `ratio`th line and
+/// `color` for the others. The lines through `origin` are left out when `skip_axes` is set, for the axes
+/// drawn over them.
+fn draw_grid_lines<Config: GizmoConfigGroup>(
+    gizmos: &mut Gizmos<Config>, spacing: f32, ratio: i32, color: Color, major_color: Color, area: Rect, origin: Vec2,
+    skip_axes: bool,
+) {
+    let (left, right) = (area.min.x - origin.x, area.max.x - origin.x);
+    let (bottom, top) = (area.min.y - origin.y, area.max.y - origin.y);
 
     // Calculate grid lines within visible area
     let start_x = (left / spacing).floor() as i32;
@@ -30,69 +137,1104 @@ fn draw_grids(gizmos: &mut Gizmos, spacing: f32, color: Color, camera_transform:
     // Draw vertical grid lines
     for x in start_x..=end_x {
         let x_pos = x as f32 * spacing;
-        if x_pos != 0.0 {
-            // Skip the axis line
-            gizmos.line_2d(Vec2::new(x_pos, bottom), Vec2::new(x_pos, top), color);
+        if x != 0 || !skip_axes {
+            let color = if x % ratio == 0 { major_color } else { color };
+            gizmos.line_2d(origin + Vec2::new(x_pos, bottom), origin + Vec2::new(x_pos, top), color);
         }
     }
 
     // Draw horizontal grid lines
     for y in start_y..=end_y {
         let y_pos = y as f32 * spacing;
-        if y_pos != 0.0 {
-            // Skip the axis line
-            gizmos.line_2d(Vec2::new(left, y_pos), Vec2::new(right, y_pos), color);
+        if y != 0 || !skip_axes {
+            let color = if y % ratio == 0 { major_color } else { color };
+            gizmos.line_2d(origin + Vec2::new(left, y_pos), origin + Vec2::new(right, y_pos), color);
+        }
+    }
+}
+
+/// Draw the grid at the density fitting the zoom, fading the finest lines in as they spread apart
+fn draw_adaptive_grid(
+    gizmos: &mut Gizmos, base_spacing: f32, color: Color, area: Rect, camera_scale: f32, origin: Vec2, skip_axes: bool,
+) {
+    if base_spacing <= 0.0 {
+        return;
+    }
+    let (spacing, fade, ratio) = adaptive_grid_level(base_spacing, camera_scale);
+    let minor_color = color.with_alpha(color.alpha() * fade);
+    draw_grid_lines(gizmos, spacing, ratio, minor_color, color, area, origin, skip_axes);
+}
+
+/// Draw rings around `origin` at the density fitting the zoom, and `spokes` evenly spaced rays
+fn draw_polar_grid(
+    gizmos: &mut Gizmos, origin: Vec2, base_spacing: f32, spokes: u32, color: Color, area: Rect, camera_scale: f32,
+) {
+    if base_spacing <= 0.0 {
+        return;
+    }
+    let (spacing, fade, ratio) = adaptive_grid_level(base_spacing, camera_scale);
+    let minor_color = color.with_alpha(color.alpha() * fade);
+
+    // Only rings passing through the visible area are drawn
+    let nearest = origin.clamp(area.min, area.max).distance(origin);
+    let corners = [
+        area.min,
+        area.max,
+        Vec2::new(area.min.x, area.max.y),
+        Vec2::new(area.max.x, area.min.y),
+    ];
+    let farthest = corners.iter().map(|corner| corner.distance(origin)).fold(0.0, f32::max);
+    let first = ((nearest / spacing).floor() as i32).max(1);
+    let last = (farthest / spacing).ceil() as i32;
+    for ring in first..=last {
+        let color = if ring % ratio == 0 { color } else { minor_color };
+        gizmos.circle_2d(origin, ring as f32 * spacing, color).resolution(128);
+    }
+
+    let step = std::f32::consts::TAU / spokes.max(1) as f32;
+    for spoke in 0..spokes {
+        let direction = Vec2::from_angle(spoke as f32 * step);
+        gizmos.line_2d(origin + direction * nearest, origin + direction * farthest, color);
+    }
+}
+
+/// Draw the 2:1 diamond grid around `origin` at the density fitting the zoom, fading the finest lines in as
+/// they spread apart
+fn draw_isometric_grid(
+    gizmos: &mut Gizmos, base_spacing: f32, color: Color, area: Rect, camera_scale: f32, origin: Vec2,
+) {
+    if base_spacing <= 0.0 {
+        return;
+    }
+    let (spacing, fade, ratio) = adaptive_grid_level(base_spacing, camera_scale);
+    let minor_color = color.with_alpha(color.alpha() * fade);
+
+    let (left, right) = (area.min.x - origin.x, area.max.x - origin.x);
+    let (bottom, top) = (area.min.y - origin.y, area.max.y - origin.y);
+    let line_color = |index: i32| if index % ratio == 0 { color } else { minor_color };
+
+    // Falling lines are `x + 2y = 2 * spacing * i`
+    let start_i = ((left + 2.0 * bottom) / (2.0 * spacing)).floor() as i32;
+    let end_i = ((right + 2.0 * top) / (2.0 * spacing)).ceil() as i32;
+    for i in start_i..=end_i {
+        let offset = i as f32 * spacing;
+        let (start, end) = (
+            Vec2::new(left, offset - left / 2.0),
+            Vec2::new(right, offset - right / 2.0),
+        );
+        gizmos.line_2d(origin + start, origin + end, line_color(i));
+    }
+
+    // Rising lines are `x - 2y = 2 * spacing * j`
+    let start_j = ((left - 2.0 * top) / (2.0 * spacing)).floor() as i32;
+    let end_j = ((right - 2.0 * bottom) / (2.0 * spacing)).ceil() as i32;
+    for j in start_j..=end_j {
+        let offset = j as f32 * spacing;
+        let (start, end) = (
+            Vec2::new(left, left / 2.0 - offset),
+            Vec2::new(right, right / 2.0 - offset),
+        );
+        gizmos.line_2d(origin + start, origin + end, line_color(j));
+    }
+}
+
+/// Draw the pointy-top hex grid, fading it in as the hexes grow. Hexes don't nest into larger hexes, so
+/// unlike the other grids it is hidden rather than coarsened when zoomed out.
+fn draw_hex_grid(gizmos: &mut Gizmos, size: f32, color: Color, area: Rect, camera_scale: f32, origin: Vec2) {
+    if size <= 0.0 || size / camera_scale < MIN_HEX_PIXELS {
+        return;
+    }
+    let fade = ((size / camera_scale - MIN_HEX_PIXELS) / (FULL_GRID_PIXELS - MIN_HEX_PIXELS)).clamp(0.0, 1.0);
+    let color = color.with_alpha(color.alpha() * fade);
+
+    let corners: [Vec2; 6] = std::array::from_fn(|k| Vec2::from_angle((30.0 + 60.0 * k as f32).to_radians()) * size);
+    let area = Rect::from_corners(area.min - origin, area.max - origin);
+    let bottom_row = hex_at(size, area.min).y - 1;
+    let top_row = hex_at(size, area.max).y + 1;
+    for r in bottom_row..=top_row {
+        // Rows are shifted half a hex to the right per row up
+        let first = hex_at(size, Vec2::new(area.min.x, hex_center(size, IVec2::new(0, r)).y)).x - 1;
+        let last = hex_at(size, Vec2::new(area.max.x, hex_center(size, IVec2::new(0, r)).y)).x + 1;
+        for q in first..=last {
+            let center = origin + hex_center(size, IVec2::new(q, r));
+            // The other three edges are drawn by the neighbours to the right and below
+            for k in 0..3 {
+                gizmos.line_2d(center + corners[k], center + corners[k + 1], color);
+            }
+        }
+    }
+}
+
+/// Draw a grid dot `size` world units across
+fn draw_dot(gizmos: &mut Gizmos, position: Vec2, size: f32, color: Color) {
+    gizmos.line_2d(position - Vec2::new(size, 0.0), position + Vec2::new(size, 0.0), color);
+    gizmos.line_2d(position - Vec2::new(0.0, size), position + Vec2::new(0.0, size), color);
+}
+
+/// Draw dots where the lines of the grid would cross, at the density fitting the zoom. Dots of the
+/// finest level fade in like its lines do.
+fn draw_dot_grid(gizmos: &mut Gizmos, coordinate_settings: &CoordinateSettings, area: Rect, camera_scale: f32) {
+    let base_spacing = coordinate_settings.grid_spacing;
+    if base_spacing <= 0.0 {
+        return;
+    }
+    let color = coordinate_settings.grid_color;
+    let size = DOT_PIXELS * camera_scale;
+
+    let level = grid_level(base_spacing, camera_scale, MIN_DOT_PIXELS);
+    let spacing = grid_level_spacing(base_spacing, level);
+    let ratio = if level.rem_euclid(2) == 1 { 2 } else { 5 };
+    let fade = ((spacing / camera_scale - MIN_DOT_PIXELS) / (FULL_GRID_PIXELS - MIN_DOT_PIXELS)).clamp(0.0, 1.0);
+    let minor_color = color.with_alpha(color.alpha() * fade);
+    let dot_color = |a: i32, b: i32| {
+        if a % ratio == 0 && b % ratio == 0 {
+            color
+        } else {
+            minor_color
+        }
+    };
+    // Area relative to the origin offset, which all grids but the polar one are laid out from
+    let origin = coordinate_settings.origin_offset;
+    let local = Rect::from_corners(area.min - origin, area.max - origin);
+
+    match coordinate_settings.grid_mode {
+        GridMode::Cartesian => {
+            for x in (local.min.x / spacing).floor() as i32..=(local.max.x / spacing).ceil() as i32 {
+                for y in (local.min.y / spacing).floor() as i32..=(local.max.y / spacing).ceil() as i32 {
+                    draw_dot(
+                        gizmos,
+                        origin + Vec2::new(x as f32, y as f32) * spacing,
+                        size,
+                        dot_color(x, y),
+                    );
+                }
+            }
+        }
+        GridMode::Isometric => {
+            // Corners are at `x = spacing * (i + j)` and `y = spacing * (i - j) / 2`, so `i + j` and
+            // `i - j` always have the same parity
+            let (start_u, end_u) = (
+                (local.min.x / spacing).floor() as i32,
+                (local.max.x / spacing).ceil() as i32,
+            );
+            let start_v = (2.0 * local.min.y / spacing).floor() as i32;
+            let end_v = (2.0 * local.max.y / spacing).ceil() as i32;
+            for u in start_u..=end_u {
+                for v in (start_v..=end_v).filter(|v| (u - v).rem_euclid(2) == 0) {
+                    let position = origin + Vec2::new(u as f32, v as f32 / 2.0) * spacing;
+                    draw_dot(gizmos, position, size, dot_color((u + v) / 2, (u - v) / 2));
+                }
+            }
+        }
+        GridMode::Polar => {
+            let center = coordinate_settings.polar_origin;
+            let nearest = center.clamp(area.min, area.max).distance(center);
+            let corners = [
+                area.min,
+                area.max,
+                Vec2::new(area.min.x, area.max.y),
+                Vec2::new(area.max.x, area.min.y),
+            ];
+            let farthest = corners.iter().map(|corner| corner.distance(center)).fold(0.0, f32::max);
+            let spokes = coordinate_settings.polar_spokes.max(1);
+            let step = std::f32::consts::TAU / spokes as f32;
+            draw_dot(gizmos, center, size, color);
+            for ring in ((nearest / spacing).floor() as i32).max(1)..=(farthest / spacing).ceil() as i32 {
+                let color = if ring % ratio == 0 { color } else { minor_color };
+                for spoke in 0..spokes {
+                    let position = center + Vec2::from_angle(spoke as f32 * step) * ring as f32 * spacing;
+                    if area.contains(position) {
+                        draw_dot(gizmos, position, size, color);
+                    }
+                }
+            }
+        }
+        GridMode::Hex => draw_hex_dots(gizmos, base_spacing, size, color, local, camera_scale, origin),
+    }
+}
+
+/// Draw dots at the corners of hexes laid out from `origin`, over `area` relative to it. Hexes don't nest,
+/// so the corners are only drawn at the base size.
+fn draw_hex_dots(
+    gizmos: &mut Gizmos, hex_size: f32, size: f32, color: Color, area: Rect, camera_scale: f32, origin: Vec2,
+) {
+    if hex_size / camera_scale < MIN_DOT_PIXELS {
+        return;
+    }
+    let top = Vec2::new(0.0, hex_size);
+    let right = Vec2::from_angle(30f32.to_radians()) * hex_size;
+    for r in hex_at(hex_size, area.min).y - 1..=hex_at(hex_size, area.max).y + 1 {
+        let row_y = hex_center(hex_size, IVec2::new(0, r)).y;
+        let first = hex_at(hex_size, Vec2::new(area.min.x, row_y)).x - 1;
+        let last = hex_at(hex_size, Vec2::new(area.max.x, row_y)).x + 1;
+        for q in first..=last {
+            // Every corner is shared by three hexes, so each hex draws two of them
+            let center = origin + hex_center(hex_size, IVec2::new(q, r));
+            draw_dot(gizmos, center + top, size, color);
+            draw_dot(gizmos, center + right, size, color);
         }
     }
 }
 
 /// System to draw the coordinate axes and grid using gizmos
 pub fn draw_coordinate_system(
-    coordinate_settings: Res<CoordinateSettings>, camera_query: Query<(&Camera, &GlobalTransform)>, mut gizmos: Gizmos,
+    coordinate_settings: Res<CoordinateSettings>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<CameraMovement>>, mut gizmos: Gizmos,
+    mut chunk_gizmos: Gizmos<ChunkGizmos>,
 ) {
     // Get the camera transform to determine the visible area
-    let Ok((_camera, camera_transform)) = camera_query.single() else {
+    let Ok((camera, camera_transform)) = camera_query.single() else {
+        return;
+    };
+    let Some(area) = visible_area(camera, camera_transform) else {
         return;
     };
+    let camera_scale = camera_transform.compute_transform().scale.x;
+    let (left, right, bottom, top) = (area.min.x, area.max.x, area.min.y, area.max.y);
+    let origin = coordinate_settings.origin_offset;
 
-    // Get the camera viewport to determine the visible area
-    let camera_position = camera_transform.translation();
-    let camera_scale = camera_transform.compute_transform().scale;
+    if coordinate_settings.show_axes {
+        // Draw X axis (red)
+        gizmos.line_2d(
+            Vec2::new(left, origin.y),
+            Vec2::new(right, origin.y),
+            coordinate_settings.x_axis_color,
+        );
 
-    // Calculate the visible area based on camera position and scale
-    // This creates an "infinite" feel by dynamically generating lines in the visible area
-    let visible_width = 2000.0 * camera_scale.x;
-    let visible_height = 2000.0 * camera_scale.y;
+        // Draw Y axis (green)
+        gizmos.line_2d(
+            Vec2::new(origin.x, bottom),
+            Vec2::new(origin.x, top),
+            coordinate_settings.y_axis_color,
+        );
+    }
+    // Chunks are square regions, so they only line up with the Cartesian grid unless the chunk overlay
+    // asks for them. Like the stored geometry they ignore the origin offset.
+    let grid_chunks = coordinate_settings.show_grid && coordinate_settings.grid_mode == GridMode::Cartesian;
+    if grid_chunks || coordinate_settings.show_chunk_overlay {
+        draw_chunks(
+            &mut chunk_gizmos,
+            coordinate_settings.chunk_spacing,
+            coordinate_settings.chunk_color,
+            area,
+            camera_scale,
+            // Chunk lines only run along the axes when the origin isn't offset
+            coordinate_settings.show_axes && origin == Vec2::ZERO,
+        );
+    }
+    if !coordinate_settings.show_grid {
+        return;
+    }
 
-    let left = camera_position.x - visible_width / 2.0;
-    let right = camera_position.x + visible_width / 2.0;
-    let bottom = camera_position.y - visible_height / 2.0;
-    let top = camera_position.y + visible_height / 2.0;
+    match (coordinate_settings.grid_style, coordinate_settings.grid_mode) {
+        (GridStyle::Dots, _) => draw_dot_grid(&mut gizmos, &coordinate_settings, area, camera_scale),
+        (GridStyle::Lines, GridMode::Cartesian) => draw_adaptive_grid(
+            &mut gizmos,
+            coordinate_settings.grid_spacing,
+            coordinate_settings.grid_color,
+            area,
+            camera_scale,
+            origin,
+            coordinate_settings.show_axes,
+        ),
+        (GridStyle::Lines, GridMode::Polar) => draw_polar_grid(
+            &mut gizmos,
+            coordinate_settings.polar_origin,
+            coordinate_settings.grid_spacing,
+            coordinate_settings.polar_spokes,
+            coordinate_settings.grid_color,
+            area,
+            camera_scale,
+        ),
+        (GridStyle::Lines, GridMode::Isometric) => draw_isometric_grid(
+            &mut gizmos,
+            coordinate_settings.grid_spacing,
+            coordinate_settings.grid_color,
+            area,
+            camera_scale,
+            origin,
+        ),
+        (GridStyle::Lines, GridMode::Hex) => draw_hex_grid(
+            &mut gizmos,
+            coordinate_settings.grid_spacing,
+            coordinate_settings.grid_color,
+            area,
+            camera_scale,
+            origin,
+        ),
+    }
+}
 
-    // Draw X axis (red)
+/// System to toggle the grid with G, the axes with X and the axis labels with L, to hide the scaffolding
+/// for screenshots and presentations
+pub fn toggle_coordinate_visibility(
+    keyboard_input: Res<ButtonInput<KeyCode>>, mut coordinate_settings: ResMut<CoordinateSettings>,
+    mut egui_contexts: EguiContexts,
+) {
+    if !keyboard_input.any_just_pressed([KeyCode::KeyG, KeyCode::KeyX, KeyCode::KeyL]) {
+        return;
+    }
+    if egui_contexts.ctx_mut().is_ok_and(|ctx| ctx.wants_keyboard_input()) {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyG) {
+        coordinate_settings.show_grid = !coordinate_settings.show_grid;
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyX) {
+        coordinate_settings.show_axes = !coordinate_settings.show_axes;
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyL) {
+        coordinate_settings.show_axis_labels = !coordinate_settings.show_axis_labels;
+    }
+}
+
+/// System to move the origin offset to the snapped cursor position with O
+pub fn set_origin_here(
+    keyboard_input: Res<ButtonInput<KeyCode>>, snap_target: Res<SnapTarget>,
+    mut coordinate_settings: ResMut<CoordinateSettings>, mut egui_contexts: EguiContexts,
+) {
+    if !keyboard_input.just_pressed(KeyCode::KeyO) {
+        return;
+    }
+    if egui_contexts.ctx_mut().is_ok_and(|ctx| ctx.wants_keyboard_input()) {
+        return;
+    }
+    if let Some(position) = snap_target.position {
+        coordinate_settings.origin_offset = position;
+    }
+}
+
+/// Shapes the cursor can snap to, see [`update_snap_target`]
+type SnapShapeData = (
+    Entity,
+    &'static EditorShape,
+    Option<&'static QPointData>,
+    Option<&'static QLineData>,
+    Option<&'static QBboxData>,
+    Option<&'static QCircleData>,
+    Option<&'static QPolygonData>,
+);
+
+/// System to find the point the next click places at, so drawing, placing and the snap preview agree
+pub fn update_snap_target(
+    mut snap_target: ResMut<SnapTarget>, coordinate_settings: Res<CoordinateSettings>, ui_state: Res<UiState>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<CameraMovement>>,
+    windows: Query<&Window, With<PrimaryWindow>>, mut egui_contexts: EguiContexts, shapes: Query<SnapShapeData>,
+    drawing_state: Res<ShapeDrawingState>, vertex_edit: Res<VertexEditState>,
+    transform_gizmo: Res<TransformGizmoState>,
+) {
+    snap_target.position = None;
+    snap_target.cursor = None;
+    snap_target.object = None;
+    if egui_contexts.ctx_mut().is_ok_and(|ctx| ctx.wants_pointer_input()) {
+        return;
+    }
+    let (Ok((camera, camera_transform)), Ok(window)) = (camera_query.single(), windows.single()) else {
+        return;
+    };
+    // The second viewport of a split view doesn't edit through the main camera
+    let Some(cursor) = window
+        .cursor_position()
+        .filter(|cursor| cursor_in_viewport(camera, Some(*cursor)))
+    else {
+        return;
+    };
+    let Ok(world_pos) = camera.viewport_to_world_2d(camera_transform, cursor) else {
+        return;
+    };
+    snap_target.cursor = Some(world_pos);
+
+    // Shapes being drawn or dragged follow the cursor, so they can't be snapped to
+    let moving_selection = transform_gizmo.drag.is_some();
+    let snappable = shapes.iter().filter(|(entity, shape, ..)| {
+        Some(*entity) != drawing_state.current_shape
+            && vertex_edit.dragging.is_none_or(|(dragged, _)| dragged != *entity)
+            && !(moving_selection && shape.selected)
+            && (!ui_state.only_show_select_layer || shape.layer == ui_state.selected_layer)
+            // Generated shapes are rebuilt from other shapes, points placed on them wouldn't stay there
+            && shape.layer != ShapeLayer::Generated
+    });
+    let object = coordinate_settings.object_snap.enabled.then(|| {
+        let radius = coordinate_settings.object_snap_pixels * camera_transform.compute_transform().scale.x;
+        let shapes = snappable.filter_map(|(_, _, point, line, bbox, circle, polygon)| {
+            AnyQShape::from_components(point, line, bbox, circle, polygon)
+        });
+        object_snap(shapes, world_pos, radius, &coordinate_settings.object_snap)
+    });
+    if let Some((kind, position)) = object.flatten() {
+        snap_target.position = Some(qvec2vec(position));
+        snap_target.object = Some((kind, position));
+        return;
+    }
+    snap_target.position = Some(if ui_state.enable_snap {
+        coordinate_settings.snap(world_pos)
+    } else {
+        world_pos
+    });
+}
+
+/// System to mark the origin, and optionally draw a crosshair through the point a click would place at
+pub fn draw_origin_and_crosshair(
+    coordinate_settings: Res<CoordinateSettings>, snap_target: Res<SnapTarget>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<CameraMovement>>, mut gizmos: Gizmos,
+) {
+    let Ok((camera, camera_transform)) = camera_query.single() else {
+        return;
+    };
+    let camera_scale = camera_transform.compute_transform().scale.x;
+
+    // The marker keeps its size on screen at every zoom
+    if coordinate_settings.show_origin_marker {
+        let origin = coordinate_settings.origin_offset;
+        let radius = ORIGIN_MARKER_PIXELS * camera_scale;
+        let (arm_x, arm_y) = (Vec2::new(radius * 1.5, 0.0), Vec2::new(0.0, radius * 1.5));
+        gizmos.circle_2d(origin, radius, ORIGIN_MARKER_COLOR);
+        gizmos.line_2d(origin - arm_x, origin + arm_x, ORIGIN_MARKER_COLOR);
+        gizmos.line_2d(origin - arm_y, origin + arm_y, ORIGIN_MARKER_COLOR);
+    }
+
+    let Some(position) = snap_target.position.filter(|_| coordinate_settings.show_crosshair) else {
+        return;
+    };
+    let Some(area) = visible_area(camera, camera_transform) else {
+        return;
+    };
+    let color = coordinate_settings.crosshair_color;
     gizmos.line_2d(
-        Vec2::new(left, 0.0),
-        Vec2::new(right, 0.0),
-        coordinate_settings.x_axis_color,
+        Vec2::new(area.min.x, position.y),
+        Vec2::new(area.max.x, position.y),
+        color,
     );
-
-    // Draw Y axis (green)
     gizmos.line_2d(
-        Vec2::new(0.0, bottom),
-        Vec2::new(0.0, top),
-        coordinate_settings.y_axis_color,
+        Vec2::new(position.x, area.min.y),
+        Vec2::new(position.x, area.max.y),
+        color,
+    );
+}
+
+/// System to highlight the snapped point the next click places at
+pub fn draw_snap_preview(
+    snap_target: Res<SnapTarget>, ui_state: Res<UiState>, camera_query: Query<&GlobalTransform, With<CameraMovement>>,
+    mut gizmos: Gizmos,
+) {
+    let (Some(position), Ok(camera_transform)) = (snap_target.position, camera_query.single()) else {
+        return;
+    };
+    let radius = SNAP_PREVIEW_PIXELS * camera_transform.compute_transform().scale.x;
+    // Each kind of shape feature has its own marker, like in CAD tools
+    match snap_target.object.map(|(kind, _)| kind) {
+        Some(ObjectSnapKind::Vertex) => {
+            gizmos.rect_2d(position, Vec2::splat(radius * 2.0), OBJECT_SNAP_COLOR);
+        }
+        Some(ObjectSnapKind::Midpoint) => {
+            let corners = [Vec2::new(0.0, 1.0), Vec2::new(-0.87, -0.5), Vec2::new(0.87, -0.5)];
+            let corners = corners.map(|corner| position + corner * radius * 1.2);
+            gizmos.linestrip_2d([corners[0], corners[1], corners[2], corners[0]], OBJECT_SNAP_COLOR);
+        }
+        Some(ObjectSnapKind::Center) => {
+            gizmos.circle_2d(position, radius, OBJECT_SNAP_COLOR);
+            gizmos.line_2d(
+                position - Vec2::X * radius,
+                position + Vec2::X * radius,
+                OBJECT_SNAP_COLOR,
+            );
+            gizmos.line_2d(
+                position - Vec2::Y * radius,
+                position + Vec2::Y * radius,
+                OBJECT_SNAP_COLOR,
+            );
+        }
+        Some(ObjectSnapKind::Intersection) => {
+            gizmos.line_2d(
+                position - Vec2::ONE * radius,
+                position + Vec2::ONE * radius,
+                OBJECT_SNAP_COLOR,
+            );
+            let flipped = Vec2::new(radius, -radius);
+            gizmos.line_2d(position - flipped, position + flipped, OBJECT_SNAP_COLOR);
+        }
+        None if ui_state.enable_snap => {
+            gizmos.circle_2d(position, radius, SNAP_PREVIEW_COLOR);
+            gizmos.circle_2d(position, radius * 0.3, SNAP_PREVIEW_COLOR);
+        }
+        None => {}
+    }
+}
+
+/// Format a tick value in display units, with as many decimals as the tick spacing needs
+fn format_tick(value: f32, spacing: f32, unit: &DisplayUnit) -> String {
+    let (value, spacing) = (unit.to_display(value), unit.to_display(spacing).abs());
+    let decimals = (-spacing.log10()).ceil().max(0.0) as usize;
+    // Avoid printing "-0"
+    let value = if value.abs() < spacing * 0.5 { 0.0 } else { value };
+    format!("{:.*}", decimals, value)
+}
+
+fn color32(color: Color) -> egui::Color32 {
+    let [r, g, b, _] = color.to_srgba().to_u8_array();
+    egui::Color32::from_rgb(r, g, b)
+}
+
+/// System to label the axes with their coordinates, and optionally the grid intersections, at a
+/// constant screen size
+pub fn draw_axis_labels(
+    mut contexts: EguiContexts, coordinate_settings: Res<CoordinateSettings>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<CameraMovement>>,
+) {
+    if !coordinate_settings.show_axis_labels && !coordinate_settings.show_intersection_labels {
+        return;
+    }
+    let (Ok(ctx), Ok((camera, camera_transform))) = (contexts.ctx_mut(), camera_query.single()) else {
+        return;
+    };
+    let Some(viewport) = camera.logical_viewport_rect() else {
+        return;
+    };
+    if coordinate_settings.grid_spacing <= 0.0 {
+        return;
+    }
+
+    let camera_scale = camera_transform.compute_transform().scale.x;
+    let level = grid_level(coordinate_settings.grid_spacing, camera_scale, MIN_LABEL_PIXELS);
+    let spacing = grid_level_spacing(coordinate_settings.grid_spacing, level);
+    let Some(area) = visible_area(camera, camera_transform) else {
+        return;
+    };
+    // Labels show coordinates relative to the origin offset
+    let origin = coordinate_settings.origin_offset;
+    let area = Rect::from_corners(area.min - origin, area.max - origin);
+    let (start_x, end_x) = (
+        (area.min.x / spacing).floor() as i32,
+        (area.max.x / spacing).ceil() as i32,
+    );
+    let (start_y, end_y) = (
+        (area.min.y / spacing).floor() as i32,
+        (area.max.y / spacing).ceil() as i32,
     );
 
-    draw_grids(
-        &mut gizmos,
-        coordinate_settings.grid_spacing,
-        coordinate_settings.grid_color,
-        camera_transform,
+    let unit = &coordinate_settings.unit;
+    // Screen position of a point relative to the origin offset, in egui coordinates
+    let to_screen = |local: Vec2| {
+        camera
+            .world_to_viewport(camera_transform, (origin + local).extend(0.0))
+            .ok()
+            .map(|position| position + viewport.min)
+    };
+    let painter = ctx.layer_painter(egui::LayerId::background());
+    let font = egui::FontId::monospace(11.0);
+    let inner = viewport.inflate(-LABEL_MARGIN);
+    let draw_label = |position: Vec2, anchor: egui::Align2, text: String, color: egui::Color32| {
+        painter.text(egui::pos2(position.x, position.y), anchor, text, font.clone(), color);
+    };
+
+    // Labels of an off-screen axis stay along the viewport edge so they can still be read
+    if coordinate_settings.show_axis_labels {
+        let x_color = color32(coordinate_settings.x_axis_color);
+        for x in start_x..=end_x {
+            let Some(position) = to_screen(Vec2::new(x as f32 * spacing, 0.0)) else {
+                continue;
+            };
+            let position = position + Vec2::new(LABEL_MARGIN, LABEL_MARGIN);
+            if position.x < inner.min.x || position.x > inner.max.x {
+                continue;
+            }
+            let position = Vec2::new(
+                position.x,
+                position.y.clamp(inner.min.y, inner.max.y - EDGE_LABEL_ROOM.y),
+            );
+            draw_label(
+                position,
+                egui::Align2::LEFT_TOP,
+                format_tick(x as f32 * spacing, spacing, unit),
+                x_color,
+            );
+        }
+        let y_color = color32(coordinate_settings.y_axis_color);
+        for y in start_y..=end_y {
+            // The origin is labelled once, on the X axis
+            if y == 0 {
+                continue;
+            }
+            let Some(position) = to_screen(Vec2::new(0.0, y as f32 * spacing)) else {
+                continue;
+            };
+            let position = position - Vec2::new(LABEL_MARGIN, 0.0);
+            if position.y < inner.min.y || position.y > inner.max.y {
+                continue;
+            }
+            let position = Vec2::new(
+                position.x.clamp(inner.min.x + EDGE_LABEL_ROOM.x, inner.max.x),
+                position.y,
+            );
+            draw_label(
+                position,
+                egui::Align2::RIGHT_CENTER,
+                format_tick(y as f32 * spacing, spacing, unit),
+                y_color,
+            );
+        }
+    }
+
+    let intersections = (end_x - start_x + 1).max(0) as usize * (end_y - start_y + 1).max(0) as usize;
+    let cartesian = coordinate_settings.grid_mode == GridMode::Cartesian;
+    if coordinate_settings.show_intersection_labels && cartesian && intersections <= MAX_INTERSECTION_LABELS {
+        let color = color32(coordinate_settings.grid_color);
+        for x in start_x..=end_x {
+            for y in start_y..=end_y {
+                if x == 0 || y == 0 {
+                    continue;
+                }
+                let local = Vec2::new(x as f32, y as f32) * spacing;
+                let Some(position) = to_screen(local).filter(|position| viewport.contains(*position)) else {
+                    continue;
+                };
+                let text = format!(
+                    "{}, {}",
+                    format_tick(local.x, spacing, unit),
+                    format_tick(local.y, spacing, unit)
+                );
+                draw_label(
+                    position + Vec2::new(LABEL_MARGIN, LABEL_MARGIN),
+                    egui::Align2::LEFT_TOP,
+                    text,
+                    color,
+                );
+            }
+        }
+    }
+}
+
+/// System to label each chunk with its index while the chunk overlay is on. Labels sit in the top left
+/// of the visible part of their chunk, so a chunk larger than the view is still labelled.
+pub fn draw_chunk_labels(
+    mut contexts: EguiContexts, coordinate_settings: Res<CoordinateSettings>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<CameraMovement>>,
+) {
+    let spacing = coordinate_settings.chunk_spacing;
+    if !coordinate_settings.show_chunk_overlay || spacing <= 0.0 {
+        return;
+    }
+    let (Ok(ctx), Ok((camera, camera_transform))) = (contexts.ctx_mut(), camera_query.single()) else {
+        return;
+    };
+    let (Some(viewport), Some(area)) = (camera.logical_viewport_rect(), visible_area(camera, camera_transform)) else {
+        return;
+    };
+    // Small chunks have no room for their labels
+    if spacing / camera_transform.compute_transform().scale.x < MIN_LABEL_PIXELS {
+        return;
+    }
+
+    let painter = ctx.layer_painter(egui::LayerId::background());
+    let font = egui::FontId::monospace(11.0);
+    let color = color32(coordinate_settings.chunk_color);
+    let (start_x, end_x) = (
+        (area.min.x / spacing).floor() as i32,
+        (area.max.x / spacing).floor() as i32,
     );
-    draw_grids(
-        &mut gizmos,
-        coordinate_settings.chunk_spacing,
-        coordinate_settings.chunk_color,
-        camera_transform,
+    let (start_y, end_y) = (
+        (area.min.y / spacing).floor() as i32,
+        (area.max.y / spacing).floor() as i32,
     );
+    for x in start_x..=end_x {
+        for y in start_y..=end_y {
+            // Screen bounds of the chunk corners, in egui coordinates
+            let (min, max) = (Vec2::new(x as f32, y as f32), Vec2::new(x as f32 + 1.0, y as f32 + 1.0));
+            let corners = [min, max, Vec2::new(min.x, max.y), Vec2::new(max.x, min.y)];
+            let Some(bounds) = corners.into_iter().try_fold(Rect::EMPTY, |bounds, corner| {
+                let screen = camera
+                    .world_to_viewport(camera_transform, (corner * spacing).extend(0.0))
+                    .ok()?;
+                Some(bounds.union_point(screen + viewport.min))
+            }) else {
+                continue;
+            };
+            let visible = bounds.intersect(viewport);
+            if visible.is_empty() {
+                continue;
+            }
+            let position = visible.min + Vec2::splat(LABEL_MARGIN);
+            let text = format!("chunk {}, {}", x, y);
+            painter.text(
+                egui::pos2(position.x, position.y),
+                egui::Align2::LEFT_TOP,
+                text,
+                font.clone(),
+                color,
+            );
+        }
+    }
+}
+
+/// System to apply the chunk line width to the chunk gizmo group
+pub fn sync_chunk_gizmo_config(
+    coordinate_settings: Res<CoordinateSettings>, mut config_store: ResMut<GizmoConfigStore>,
+) {
+    let (config, _) = config_store.config_mut::<ChunkGizmos>();
+    config.line.width = coordinate_settings.chunk_line_width;
+}
+
+/// System to draw the guide lines across the scene
+pub fn draw_guides(
+    guides: Res<Guides>, camera_query: Query<(&Camera, &GlobalTransform), With<CameraMovement>>, mut gizmos: Gizmos,
+) {
+    let Ok((camera, camera_transform)) = camera_query.single() else {
+        return;
+    };
+    let Some(area) = visible_area(camera, camera_transform) else {
+        return;
+    };
+    for y in guides.horizontal.iter() {
+        gizmos.line_2d(Vec2::new(area.min.x, *y), Vec2::new(area.max.x, *y), GUIDE_COLOR);
+    }
+    for x in guides.vertical.iter() {
+        gizmos.line_2d(Vec2::new(*x, area.min.y), Vec2::new(*x, area.max.y), GUIDE_COLOR);
+    }
+}
+
+/// Paint one ruler: ticks every `minor` world units, labelled in display units every `major`. `to_screen`
+/// maps a world coordinate along the ruler to a screen coordinate along it.
+fn paint_ruler(
+    painter: &egui::Painter, rect: egui::Rect, horizontal: bool, world_range: (f32, f32), spacing: (f32, f32),
+    to_screen: impl Fn(f32) -> f32, unit: &DisplayUnit,
+) {
+    let visuals = painter.ctx().style().visuals.clone();
+    painter.rect_filled(rect, 0.0, visuals.extreme_bg_color);
+    let stroke = egui::Stroke::new(1.0, visuals.text_color());
+    let font = egui::FontId::monospace(9.0);
+    let (minor, major) = spacing;
+    let (start, end) = (
+        (world_range.0 / minor).floor() as i64,
+        (world_range.1 / minor).ceil() as i64,
+    );
+    let ratio = (major / minor).round().max(1.0) as i64;
+    for i in start..=end {
+        let screen = to_screen(i as f32 * minor);
+        let is_major = i % ratio == 0;
+        let length = if is_major { RULER_SIZE * 0.6 } else { RULER_SIZE * 0.25 };
+        if horizontal {
+            if screen < rect.min.x || screen > rect.max.x {
+                continue;
+            }
+            painter.line_segment(
+                [egui::pos2(screen, rect.max.y - length), egui::pos2(screen, rect.max.y)],
+                stroke,
+            );
+            if is_major {
+                let text = format_tick(i as f32 * minor, major, unit);
+                let position = egui::pos2(screen + 2.0, rect.min.y);
+                painter.text(position, egui::Align2::LEFT_TOP, text, font.clone(), stroke.color);
+            }
+        } else {
+            if screen < rect.min.y || screen > rect.max.y {
+                continue;
+            }
+            painter.line_segment(
+                [egui::pos2(rect.max.x - length, screen), egui::pos2(rect.max.x, screen)],
+                stroke,
+            );
+            if is_major {
+                let text = format_tick(i as f32 * minor, major, unit);
+                let position = egui::pos2(rect.min.x + 1.0, screen + 2.0);
+                painter.text(position, egui::Align2::LEFT_TOP, text, font.clone(), stroke.color);
+            }
+        }
+    }
+}
+
+/// Paint the cursor marker across a ruler at the screen coordinate `cursor` along it
+fn paint_ruler_cursor(painter: &egui::Painter, rect: egui::Rect, horizontal: bool, cursor: Option<f32>) {
+    if let Some(cursor) = cursor {
+        let marker = egui::Stroke::new(1.0, egui::Color32::from_rgb(255, 80, 80));
+        if horizontal && rect.x_range().contains(cursor) {
+            painter.line_segment([egui::pos2(cursor, rect.min.y), egui::pos2(cursor, rect.max.y)], marker);
+        } else if !horizontal && rect.y_range().contains(cursor) {
+            painter.line_segment([egui::pos2(rect.min.x, cursor), egui::pos2(rect.max.x, cursor)], marker);
+        }
+    }
+}
+
+/// System to draw rulers along the top and left viewport edges, with a marker at the cursor. Dragging out
+/// of a ruler into the scene adds a guide line. The rulers are hidden while the view is rotated, where
+/// screen edges don't follow the world axes.
+pub fn draw_rulers(
+    mut contexts: EguiContexts, coordinate_settings: Res<CoordinateSettings>, mut guides: ResMut<Guides>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<CameraMovement>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+) {
+    if !coordinate_settings.show_rulers || coordinate_settings.grid_spacing <= 0.0 {
+        return;
+    }
+    let (Ok(ctx), Ok((camera, camera_transform)), Ok(window)) =
+        (contexts.ctx_mut(), camera_query.single(), windows.single())
+    else {
+        return;
+    };
+    if camera_transform.rotation().to_euler(EulerRot::ZYX).0.abs() > 1e-4 {
+        return;
+    }
+    let Some(viewport) = camera.logical_viewport_rect() else {
+        return;
+    };
+
+    // Without rotation, screen and world axes map linearly onto each other
+    let camera_position = camera_transform.translation().truncate();
+    let camera_scale = camera_transform.compute_transform().scale.x;
+    let center = viewport.center();
+    let world_x = |screen_x: f32| camera_position.x + (screen_x - center.x) * camera_scale;
+    let world_y = |screen_y: f32| camera_position.y - (screen_y - center.y) * camera_scale;
+    // Ruler ticks are laid out and labelled relative to the origin offset
+    let origin = coordinate_settings.origin_offset;
+    let screen_x = |local_x: f32| center.x + (origin.x + local_x - camera_position.x) / camera_scale;
+    let screen_y = |local_y: f32| center.y - (origin.y + local_y - camera_position.y) / camera_scale;
+
+    let base_spacing = coordinate_settings.grid_spacing;
+    let minor = grid_level_spacing(base_spacing, grid_level(base_spacing, camera_scale, MIN_GRID_PIXELS));
+    let major = grid_level_spacing(base_spacing, grid_level(base_spacing, camera_scale, MIN_LABEL_PIXELS));
+    let cursor = window.cursor_position();
+
+    let top = egui::Rect::from_min_max(
+        egui::pos2(viewport.min.x + RULER_SIZE, viewport.min.y),
+        egui::pos2(viewport.max.x, viewport.min.y + RULER_SIZE),
+    );
+    let left = egui::Rect::from_min_max(
+        egui::pos2(viewport.min.x, viewport.min.y + RULER_SIZE),
+        egui::pos2(viewport.min.x + RULER_SIZE, viewport.max.y),
+    );
+    let scene = egui::Rect::from_min_max(
+        egui::pos2(viewport.min.x + RULER_SIZE, viewport.min.y + RULER_SIZE),
+        egui::pos2(viewport.max.x, viewport.max.y),
+    );
+
+    for (id, rect, horizontal) in [("ruler_top", top, true), ("ruler_left", left, false)] {
+        egui::Area::new(egui::Id::new(id))
+            .fixed_pos(rect.min)
+            .order(egui::Order::Background)
+            .show(ctx, |ui| {
+                let (response, painter) = ui.allocate_painter(rect.size(), egui::Sense::drag());
+                let (local_range, to_screen, cursor): (_, &dyn Fn(f32) -> f32, _) = if horizontal {
+                    let range = (world_x(rect.min.x) - origin.x, world_x(rect.max.x) - origin.x);
+                    (range, &screen_x, cursor.map(|cursor| cursor.x))
+                } else {
+                    let range = (world_y(rect.max.y) - origin.y, world_y(rect.min.y) - origin.y);
+                    (range, &screen_y, cursor.map(|cursor| cursor.y))
+                };
+                let unit = &coordinate_settings.unit;
+                paint_ruler(&painter, rect, horizontal, local_range, (minor, major), to_screen, unit);
+                paint_ruler_cursor(&painter, rect, horizontal, cursor);
+
+                // The top ruler makes horizontal guides, the left one vertical guides
+                let Some(pointer) = response.interact_pointer_pos() else {
+                    return;
+                };
+                let preview = egui::Stroke::new(1.0, color32(GUIDE_COLOR));
+                let guide_painter = ui.ctx().layer_painter(egui::LayerId::background());
+                if response.dragged() && scene.contains(pointer) {
+                    if horizontal {
+                        guide_painter.hline(scene.x_range(), pointer.y, preview);
+                    } else {
+                        guide_painter.vline(pointer.x, scene.y_range(), preview);
+                    }
+                }
+                if response.drag_stopped() && scene.contains(pointer) {
+                    if horizontal {
+                        guides.horizontal.push(world_y(pointer.y));
+                    } else {
+                        guides.vertical.push(world_x(pointer.x));
+                    }
+                }
+            });
+    }
+}
+
+/// System to toggle the measure tool with M. While it is active, two clicks place a dimension line on the
+/// AuxiliaryLine layer. Escape or picking a shape tool ends measuring.
+pub fn measure_tool(
+    mut commands: Commands, mut measure: ResMut<MeasureTool>, mut ui_state: ResMut<UiState>,
+    snap_target: Res<SnapTarget>, mouse_button_input: Res<ButtonInput<MouseButton>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>, camera_settings: Res<CameraSettings>, mut history: ResMut<EditHistory>,
+    mut egui_contexts: EguiContexts,
+) {
+    let typing = egui_contexts.ctx_mut().is_ok_and(|ctx| ctx.wants_keyboard_input());
+    if keyboard_input.just_pressed(KeyCode::KeyM) && !typing {
+        measure.active = !measure.active;
+        measure.start = None;
+        if measure.active {
+            ui_state.selected_shape = None;
+            ui_state.edit_vertices = false;
+        }
+    }
+    if !measure.active {
+        return;
+    }
+    if ui_state.selected_shape.is_some() || ui_state.edit_vertices || keyboard_input.just_pressed(KeyCode::Escape) {
+        *measure = MeasureTool::default();
+        return;
+    }
+    // The snap target is missing while the cursor is over the UI
+    if !mouse_button_input.just_pressed(MouseButton::Left) || camera_settings.space_pan_active(&keyboard_input) {
+        return;
+    }
+    let Some(position) = snap_target.position else {
+        return;
+    };
+    let start = match measure.start {
+        Some(start) if start != position => start,
+        _ => {
+            measure.start = Some(position);
+            return;
+        }
+    };
+    measure.start = None;
+
+    let qpoint = |pos: Vec2| QPoint::new(QVec2::new(Q64::from_num(pos.x), Q64::from_num(pos.y)));
+    let data = SerializableQShapeData::Line(QLineData {
+        data: QLine::new(qpoint(start), qpoint(position)),
+    });
+    let editor_shape = EditorShape {
+        name: "Dimension".to_string(),
+        layer: ShapeLayer::AuxiliaryLine,
+        line_appearance: LineAppearance::Dimension,
+        ..default_editor_shape(&data)
+    };
+    let entity = spawn_scene_shape(
+        &mut commands,
+        SerializableQShape {
+            editor_shape,
+            data,
+            physics: SerializableQPhysics::default(),
+        },
+    );
+    history.record_created("Measure", vec![entity]);
+}
+
+/// System to draw the line from the first measured point to the cursor
+pub fn draw_measure_preview(measure: Res<MeasureTool>, snap_target: Res<SnapTarget>, mut gizmos: Gizmos) {
+    if let (true, Some(start), Some(position)) = (measure.active, measure.start, snap_target.position) {
+        gizmos.line_2d(start, position, MEASURE_COLOR);
+        gizmos.circle_2d(start, 0.1, MEASURE_COLOR);
+    }
+}
+
+/// System to label the dimension lines, and the measurement in progress, with their length. Lengths are
+/// computed in fixed-point like the geometry itself.
+pub fn draw_dimension_labels(
+    mut contexts: EguiContexts, coordinate_settings: Res<CoordinateSettings>, ui_state: Res<UiState>,
+    measure: Res<MeasureTool>, snap_target: Res<SnapTarget>, lines: Query<(&EditorShape, &QLineData)>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<CameraMovement>>,
+) {
+    let (Ok(ctx), Ok((camera, camera_transform))) = (contexts.ctx_mut(), camera_query.single()) else {
+        return;
+    };
+    let Some(viewport) = camera.logical_viewport_rect() else {
+        return;
+    };
+
+    let mut dimensions: Vec<(QVec2, QVec2)> = lines
+        .iter()
+        .filter(|(shape, _)| shape.line_appearance == LineAppearance::Dimension)
+        .filter(|(shape, _)| !ui_state.only_show_select_layer || shape.layer == ui_state.selected_layer)
+        .map(|(_, line)| (line.data.start().pos(), line.data.end().pos()))
+        .collect();
+    if let (true, Some(start), Some(position)) = (measure.active, measure.start, snap_target.position) {
+        let qvec = |pos: Vec2| QVec2::new(Q64::from_num(pos.x), Q64::from_num(pos.y));
+        dimensions.push((qvec(start), qvec(position)));
+    }
+    if dimensions.is_empty() {
+        return;
+    }
+
+    let painter = ctx.layer_painter(egui::LayerId::background());
+    let font = egui::FontId::monospace(12.0);
+    for (start, end) in dimensions {
+        let length = end.saturating_sub(start).length();
+        let middle = (qvec2vec(start) + qvec2vec(end)) / 2.0;
+        let Ok(screen) = camera.world_to_viewport(camera_transform, middle.extend(0.0)) else {
+            continue;
+        };
+        let screen = screen + viewport.min;
+        if !viewport.contains(screen) {
+            continue;
+        }
+        painter.text(
+            egui::pos2(screen.x, screen.y - LABEL_MARGIN),
+            egui::Align2::CENTER_BOTTOM,
+            coordinate_settings.unit.format(length.to_num(), 4),
+            font.clone(),
+            color32(MEASURE_COLOR),
+        );
+    }
+}
+
+/// Read a PNG or JPG file into an image
+fn read_reference_image(file_path: &str) -> Result<Image, String> {
+    let bytes = std::fs::read(file_path).map_err(|e| e.to_string())?;
+    let extension = std::path::Path::new(file_path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("png");
+    Image::from_buffer(
+        &bytes,
+        ImageType::Extension(extension),
+        CompressedImageFormats::NONE,
+        true,
+        ImageSampler::Default,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// System to load or remove the reference image shown behind the scene
+pub fn handle_reference_image_events(
+    mut commands: Commands, mut events: MessageReader<ReferenceImageEvent>, mut images: ResMut<Assets<Image>>,
+    mut reference_image: ResMut<ReferenceImage>, sprite_query: Query<Entity, With<ReferenceImageSprite>>,
+    mut notifications: ResMut<Notifications>,
+) {
+    for event in events.read() {
+        let file_path = match event {
+            ReferenceImageEvent::Load { file_path } => file_path,
+            ReferenceImageEvent::Clear => {
+                for entity in sprite_query.iter() {
+                    commands.entity(entity).despawn();
+                }
+                reference_image.file_path = None;
+                continue;
+            }
+        };
+        let image = match read_reference_image(file_path) {
+            Ok(image) => image,
+            Err(error) => {
+                notifications.push(format!("Failed to load reference image {}: {}", file_path, error), true);
+                continue;
+            }
+        };
+        for entity in sprite_query.iter() {
+            commands.entity(entity).despawn();
+        }
+        commands.spawn((
+            ReferenceImageSprite,
+            Sprite::from_image(images.add(image)),
+            Transform::from_translation(reference_image.position.extend(REFERENCE_IMAGE_DEPTH))
+                .with_scale(Vec3::splat(reference_image.scale)),
+        ));
+        // Marks the resource changed so the new sprite picks up the opacity and visibility
+        reference_image.file_path = Some(file_path.clone());
+    }
+}
+
+/// System to apply the reference image placement, opacity and visibility to its sprite
+pub fn sync_reference_image(
+    reference_image: Res<ReferenceImage>,
+    mut sprite_query: Query<(&mut Transform, &mut Sprite, &mut Visibility), With<ReferenceImageSprite>>,
+) {
+    for (mut transform, mut sprite, mut visibility) in sprite_query.iter_mut() {
+        transform.translation = reference_image.position.extend(REFERENCE_IMAGE_DEPTH);
+        transform.scale = Vec3::splat(reference_image.scale);
+        sprite.color = Color::WHITE.with_alpha(reference_image.opacity);
+        *visibility = if reference_image.visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
 }
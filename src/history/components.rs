@@ -0,0 +1,14 @@
+//! History components
+//!
+//! This module defines the messages used to undo and redo edits.
+
+use bevy::prelude::*;
+
+/// Events to step through the edit history
+#[derive(Message, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistoryEvent {
+    /// Revert the last edit
+    Undo,
+    /// Apply the last undone edit again
+    Redo,
+}
@@ -0,0 +1,11 @@
+//! History module for the 2D geometry editor
+//!
+//! This module provides undo and redo of shape edits. Systems that change shapes record the edit in
+//! the [`EditHistory`](resources::EditHistory) resource, which stores the action that reverts it.
+
+pub mod components;
+pub mod plugin;
+pub mod resources;
+pub mod systems;
+
+pub use plugin::HistoryPlugin;
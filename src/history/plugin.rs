@@ -0,0 +1,19 @@
+//! History plugin implementation
+//!
+//! Registers the edit history and the systems that undo and redo edits.
+
+use super::components::HistoryEvent;
+use super::resources::EditHistory;
+use super::systems::{handle_history_events, history_shortcuts};
+use bevy::prelude::*;
+
+/// `HistoryPlugin` registers the edit history and the undo/redo systems.
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EditHistory>()
+            .add_message::<HistoryEvent>()
+            .add_systems(Update, (history_shortcuts, handle_history_events).chain());
+    }
+}
@@ -0,0 +1,147 @@
+//! History resources
+
+use crate::save_load::components::SerializableQShape;
+use bevy::prelude::*;
+
+/// Change to the scene that reverts an edit, or reapplies an undone one
+#[derive(Clone)]
+pub enum EditAction {
+    /// Spawn the shapes again, with the entities they were recorded from
+    Spawn(Vec<(Entity, SerializableQShape)>),
+    /// Despawn the shapes
+    Despawn(Vec<Entity>),
+    /// Put the shapes back into the recorded state, keeping their entities
+    Restore(Vec<(Entity, SerializableQShape)>),
+    /// Despawn some shapes and spawn others again, for edits that rebuild a large part of the scene at once
+    Replace {
+        spawn: Vec<(Entity, SerializableQShape)>,
+        despawn: Vec<Entity>,
+    },
+}
+
+impl EditAction {
+    /// Replace `old` with `new` wherever the action refers to it
+    fn remap(&mut self, old: Entity, new: Entity) {
+        match self {
+            EditAction::Spawn(shapes) | EditAction::Restore(shapes) => {
+                shapes
+                    .iter_mut()
+                    .filter(|(entity, _)| *entity == old)
+                    .for_each(|(entity, _)| *entity = new);
+            }
+            EditAction::Despawn(entities) => {
+                entities
+                    .iter_mut()
+                    .filter(|entity| **entity == old)
+                    .for_each(|entity| *entity = new);
+            }
+            EditAction::Replace { spawn, despawn } => {
+                spawn
+                    .iter_mut()
+                    .filter(|(entity, _)| *entity == old)
+                    .for_each(|(entity, _)| *entity = new);
+                despawn
+                    .iter_mut()
+                    .filter(|entity| **entity == old)
+                    .for_each(|entity| *entity = new);
+            }
+        }
+    }
+}
+
+/// A recorded edit
+#[derive(Clone)]
+pub struct EditEntry {
+    /// Description shown next to the undo and redo buttons, e.g. "Add Circle"
+    pub label: String,
+    /// Action that takes the scene to the other side of the edit
+    pub action: EditAction,
+    /// Revision of the scene with this edit applied, see [`EditHistory::revision`]
+    pub revision: u64,
+}
+
+/// Undo and redo stacks of shape edits
+///
+/// Every entry holds the action that reverts it. Undoing an entry applies its action and moves the
+/// inverse action to the redo stack, and redoing does the opposite.
+#[derive(Resource, Default)]
+pub struct EditHistory {
+    pub undo_stack: Vec<EditEntry>,
+    pub redo_stack: Vec<EditEntry>,
+    /// Revision of the scene with every edit of the undo stack undone
+    base_revision: u64,
+    /// Revisions given out so far, each edit gets a new one
+    last_revision: u64,
+}
+
+impl EditHistory {
+    /// Maximum number of edits that can be undone, older edits are forgotten
+    pub const MAX_ENTRIES: usize = 100;
+
+    /// Record shapes that were added to the scene
+    pub fn record_created(&mut self, label: impl Into<String>, entities: Vec<Entity>) {
+        self.push(label, EditAction::Despawn(entities));
+    }
+
+    /// Record shapes that are about to be removed, with their current state
+    pub fn record_deleted(&mut self, label: impl Into<String>, shapes: Vec<(Entity, SerializableQShape)>) {
+        self.push(label, EditAction::Spawn(shapes));
+    }
+
+    /// Record shapes that are about to change, with their state before the change
+    pub fn record_modified(&mut self, label: impl Into<String>, shapes: Vec<(Entity, SerializableQShape)>) {
+        self.push(label, EditAction::Restore(shapes));
+    }
+
+    /// Record shapes that are about to be removed together with the shapes that replace them, as one edit
+    pub fn record_replaced(
+        &mut self, label: impl Into<String>, removed: Vec<(Entity, SerializableQShape)>, created: Vec<Entity>,
+    ) {
+        self.push(
+            label,
+            EditAction::Replace {
+                spawn: removed,
+                despawn: created,
+            },
+        );
+    }
+
+    /// Identifies the state of the scene, to tell whether it changed since it was saved. Undoing an edit
+    /// and redoing it returns to the same revision, and every new edit leads to a revision not seen before.
+    pub fn revision(&self) -> u64 {
+        self.undo_stack
+            .last()
+            .map_or(self.base_revision, |entry| entry.revision)
+    }
+
+    /// Push a new edit, which makes the undone edits unreachable
+    fn push(&mut self, label: impl Into<String>, action: EditAction) {
+        self.last_revision += 1;
+        self.redo_stack.clear();
+        self.undo_stack.push(EditEntry {
+            label: label.into(),
+            action,
+            revision: self.last_revision,
+        });
+        let excess = self.undo_stack.len().saturating_sub(Self::MAX_ENTRIES);
+        // Undoing everything left now stops at the state after the forgotten edits
+        if let Some(forgotten) = self.undo_stack.drain(..excess).last() {
+            self.base_revision = forgotten.revision;
+        }
+    }
+
+    /// Point every entry referring to `old` at `new`, after a shape was spawned again
+    pub fn remap(&mut self, old: Entity, new: Entity) {
+        for entry in self.undo_stack.iter_mut().chain(self.redo_stack.iter_mut()) {
+            entry.action.remap(old, new);
+        }
+    }
+
+    pub fn undo_label(&self) -> Option<&str> {
+        self.undo_stack.last().map(|entry| entry.label.as_str())
+    }
+
+    pub fn redo_label(&self) -> Option<&str> {
+        self.redo_stack.last().map(|entry| entry.label.as_str())
+    }
+}
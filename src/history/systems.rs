@@ -0,0 +1,122 @@
+//! History systems
+//!
+//! This module defines the systems that undo and redo recorded edits.
+
+use super::components::HistoryEvent;
+use super::resources::{EditAction, EditEntry, EditHistory};
+use crate::save_load::components::SerializableQShape;
+use crate::save_load::systems::{ShapeSaveData, insert_scene_shape, shape_record, spawn_scene_shape};
+use bevy::prelude::*;
+use bevy_egui::EguiContexts;
+
+/// System to undo with Ctrl+Z and redo with Ctrl+Y or Ctrl+Shift+Z
+pub fn history_shortcuts(
+    mut commands: Commands, keyboard_input: Res<ButtonInput<KeyCode>>, mut egui_contexts: EguiContexts,
+) {
+    if egui_contexts.ctx_mut().is_ok_and(|ctx| ctx.wants_keyboard_input()) {
+        return;
+    }
+    let modifiers = [
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+    ];
+    if !keyboard_input.any_pressed(modifiers) {
+        return;
+    }
+
+    let shift = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if keyboard_input.just_pressed(KeyCode::KeyY) || (shift && keyboard_input.just_pressed(KeyCode::KeyZ)) {
+        commands.write_message(HistoryEvent::Redo);
+    } else if keyboard_input.just_pressed(KeyCode::KeyZ) {
+        commands.write_message(HistoryEvent::Undo);
+    }
+}
+
+/// System to undo or redo an edit, moving its inverse to the other stack
+pub fn handle_history_events(
+    mut commands: Commands, mut events: MessageReader<HistoryEvent>, mut history: ResMut<EditHistory>,
+    shapes_query: Query<ShapeSaveData>,
+) {
+    // Shapes are read from the query, so a second event this frame would see the scene from before the
+    // first one was applied
+    let Some(&event) = events.read().next() else {
+        return;
+    };
+    events.clear();
+
+    let entry = match event {
+        HistoryEvent::Undo => history.undo_stack.pop(),
+        HistoryEvent::Redo => history.redo_stack.pop(),
+    };
+    let Some(entry) = entry else {
+        return;
+    };
+    // The entry keeps its revision, so undoing and redoing it comes back to the same one
+    let inverse = EditEntry {
+        label: entry.label,
+        action: apply_action(entry.action, &mut commands, &shapes_query, &mut history),
+        revision: entry.revision,
+    };
+    match event {
+        HistoryEvent::Undo => history.redo_stack.push(inverse),
+        HistoryEvent::Redo => history.undo_stack.push(inverse),
+    }
+}
+
+/// Apply an action to the scene, returning the action that reverts it. Shapes that no longer exist
+/// are skipped.
+fn apply_action(
+    action: EditAction, commands: &mut Commands, shapes_query: &Query<ShapeSaveData>, history: &mut EditHistory,
+) -> EditAction {
+    match action {
+        EditAction::Spawn(shapes) => EditAction::Despawn(spawn_shapes(shapes, commands, history)),
+        EditAction::Despawn(entities) => EditAction::Spawn(despawn_shapes(entities, commands, shapes_query)),
+        EditAction::Restore(shapes) => {
+            let mut previous = Vec::with_capacity(shapes.len());
+            for (entity, shape) in shapes {
+                if let Some(current) = shape_record(shapes_query, entity) {
+                    insert_scene_shape(&mut commands.entity(entity), shape);
+                    previous.push((entity, current));
+                }
+            }
+            EditAction::Restore(previous)
+        }
+        EditAction::Replace { spawn, despawn } => {
+            let despawned = despawn_shapes(despawn, commands, shapes_query);
+            EditAction::Replace {
+                spawn: despawned,
+                despawn: spawn_shapes(spawn, commands, history),
+            }
+        }
+    }
+}
+
+/// Spawn recorded shapes again, returning their new entities
+fn spawn_shapes(
+    shapes: Vec<(Entity, SerializableQShape)>, commands: &mut Commands, history: &mut EditHistory,
+) -> Vec<Entity> {
+    let mut spawned = Vec::with_capacity(shapes.len());
+    for (old_entity, shape) in shapes {
+        let entity = spawn_scene_shape(commands, shape);
+        // Older entries still refer to the entity the shape had before it was removed
+        history.remap(old_entity, entity);
+        spawned.push(entity);
+    }
+    spawned
+}
+
+/// Despawn shapes, returning their records
+fn despawn_shapes(
+    entities: Vec<Entity>, commands: &mut Commands, shapes_query: &Query<ShapeSaveData>,
+) -> Vec<(Entity, SerializableQShape)> {
+    let mut despawned = Vec::with_capacity(entities.len());
+    for entity in entities {
+        if let Some(shape) = shape_record(shapes_query, entity) {
+            commands.entity(entity).despawn();
+            despawned.push((entity, shape));
+        }
+    }
+    despawned
+}
@@ -0,0 +1,57 @@
+//! QEditor library
+//!
+//! Exposes the editor plugins so other Bevy apps can embed the full editor with
+//! [`QEditorPlugins`], or pick individual pieces such as the [`QPhysicsPlugin`] runtime. Custom
+//! tools and panels are added with [`EditorToolsAppExt`].
+//!
+//! The editor plugins share resources (camera settings, snap target, UI state) and are
+//! meant to be added together; [`QPhysicsPlugin`] only depends on itself.
+
+pub mod batch;
+pub mod camera;
+pub mod collision_detection;
+pub mod coordinate;
+pub mod history;
+pub mod qphysics;
+pub mod save_load;
+pub mod scenes;
+pub mod scripting;
+pub mod shapes;
+pub mod tools;
+pub mod ui;
+pub mod util;
+
+use bevy::app::PluginGroupBuilder;
+use bevy::prelude::*;
+
+pub use camera::CameraControlPlugin;
+pub use collision_detection::CollisionDetectionPlugin;
+pub use coordinate::CoordinatePlugin;
+pub use history::HistoryPlugin;
+pub use qphysics::QPhysicsPlugin;
+pub use save_load::SaveLoadPlugin;
+pub use scenes::ScenesPlugin;
+pub use scripting::ScriptingPlugin;
+pub use shapes::ShapesPlugin;
+pub use tools::{EditorPanel, EditorTool, EditorToolsAppExt, ToolsPlugin};
+pub use ui::UiPlugin;
+
+/// All editor plugins, without `DefaultPlugins` or `EguiPlugin`
+pub struct QEditorPlugins;
+
+impl PluginGroup for QEditorPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(CoordinatePlugin)
+            .add(CameraControlPlugin)
+            .add(CollisionDetectionPlugin)
+            .add(SaveLoadPlugin)
+            .add(ShapesPlugin)
+            .add(HistoryPlugin)
+            .add(ScenesPlugin)
+            .add(UiPlugin)
+            .add(QPhysicsPlugin)
+            .add(ScriptingPlugin)
+            .add(ToolsPlugin)
+    }
+}
@@ -1,34 +1,39 @@
 //! Main application entry point
 
-mod util;
-
 use bevy::prelude::*;
 use bevy_egui::EguiPlugin;
 
-mod coordinate;
-use coordinate::CoordinatePlugin;
-
-mod camera;
-use camera::CameraControlPlugin;
-
-mod ui;
-use ui::UiPlugin;
-
-mod shapes;
-use shapes::ShapesPlugin;
-
-mod collision_detection;
-use collision_detection::CollisionDetectionPlugin;
-
-mod save_load;
-use save_load::SaveLoadPlugin;
-
-mod qphysics;
-use qphysics::QPhysicsPlugin;
+use qeditor::save_load::resources::{LaunchOptions, RecentFiles};
+use qeditor::{QEditorPlugins, batch};
 
 fn main() {
+    let launch_options = match LaunchOptions::from_env() {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("qeditor: {}", e);
+            eprintln!("usage: qeditor [FILE] [--layer <LAYER>] [--readonly] [--list-recent]");
+            eprintln!("       qeditor FILE --headless [--steps <N>] [--collisions] [--output <FILE>]");
+            eprintln!("       qeditor RECORDING --headless --verify [--output <FILE>]");
+            std::process::exit(2);
+        }
+    };
+    if launch_options.list_recent {
+        for path in RecentFiles::load().paths {
+            println!("{}", path);
+        }
+        return;
+    }
+    if launch_options.headless {
+        if let Err(e) = batch::run(&launch_options) {
+            eprintln!("qeditor: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     App::new()
         .insert_resource(ClearColor(Color::WHITE))
+        .insert_resource(launch_options)
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
                 title: "QEditor".into(),
@@ -37,12 +42,6 @@ fn main() {
             ..default()
         }))
         .add_plugins(EguiPlugin::default())
-        .add_plugins(CoordinatePlugin)
-        .add_plugins(CameraControlPlugin)
-        .add_plugins(CollisionDetectionPlugin)
-        .add_plugins(SaveLoadPlugin)
-        .add_plugins(ShapesPlugin)
-        .add_plugins(UiPlugin)
-        .add_plugins(QPhysicsPlugin)
+        .add_plugins(QEditorPlugins)
         .run();
 }
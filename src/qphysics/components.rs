@@ -1,11 +1,15 @@
+use crate::util::{closest_on_segment, dot};
 use bevy::prelude::*;
 use qgeometry::prelude::*;
 use qmath::{dir::QDir, prelude::*, vec2::QVec2};
+use serde::{Deserialize, Serialize};
 use std::hash::{Hash, Hasher};
 
-#[derive(Default, Component, Debug, Clone, Copy)]
+#[derive(Default, Component, Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct QObject {
     pub uuid: u64,
+    /// Entity owning this object, refreshed every step so it is never serialized
+    #[serde(skip)]
     pub entity: Option<Entity>,
 }
 
@@ -24,7 +28,7 @@ impl PartialEq for QObject {
 impl Eq for QObject {}
 
 /// Basic physics properties of a body
-#[derive(Component, Debug, Clone)]
+#[derive(Component, Debug, Clone, Serialize, Deserialize)]
 pub struct QPhysicsBody {
     /// Mass of the body in kg
     pub mass: Q64,
@@ -32,6 +36,10 @@ pub struct QPhysicsBody {
     pub restitution: Q64,
     /// Coefficient of friction, range [0, 1]
     pub friction: Q64,
+    /// Whether continuous collision detection is enabled, for fast bodies that could tunnel
+    /// through thin geometry in a single step
+    #[serde(default)]
+    pub ccd_enabled: bool,
 }
 
 impl QPhysicsBody {
@@ -41,6 +49,7 @@ impl QPhysicsBody {
             mass,
             restitution,
             friction,
+            ccd_enabled: false,
         }
     }
 
@@ -50,6 +59,7 @@ impl QPhysicsBody {
             mass: Q64::ZERO, // 0 mass indicates infinite mass (static)
             restitution,
             friction,
+            ccd_enabled: false,
         }
     }
 
@@ -59,9 +69,16 @@ impl QPhysicsBody {
             mass,
             restitution,
             friction,
+            ccd_enabled: false,
         }
     }
 
+    /// Enable or disable continuous collision detection
+    pub fn with_ccd(mut self, ccd_enabled: bool) -> Self {
+        self.ccd_enabled = ccd_enabled;
+        self
+    }
+
     /// Check if the body has infinite mass (is static)
     pub fn is_static(&self) -> bool {
         self.mass <= 0.0
@@ -75,16 +92,106 @@ impl QPhysicsBody {
             self.mass.saturating_recip()
         }
     }
+
+    /// Get the inverse moment of inertia about the centroid of the given shape, or 0 for static bodies
+    /// and shapes that cannot spin (points)
+    pub fn inverse_inertia(&self, shape: &QCollisionShape) -> Q64 {
+        if self.is_static() {
+            return Q64::ZERO;
+        }
+        let inertia = shape.moment_of_inertia(self.mass);
+        if inertia <= Q64::EPS {
+            Q64::ZERO
+        } else {
+            inertia.saturating_recip()
+        }
+    }
+}
+
+/// Open polyline collider meant for static terrain.
+///
+/// Only the left side of each segment, walking from the first point to the last, is solid and the
+/// chain has no interior. Shapes are always pushed out along a segment normal, so bodies sliding
+/// across a joint don't catch on it the way they do on separate thin polygons.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QEdgeChain {
+    pub points: Vec<QPoint>,
+}
+
+impl QEdgeChain {
+    pub fn new(points: Vec<QPoint>) -> Self {
+        Self { points }
+    }
+
+    /// Iterate over the segments as (start, end) pairs
+    pub fn segments(&self) -> impl Iterator<Item = (QVec2, QVec2)> + '_ {
+        self.points.windows(2).map(|pair| (pair[0].pos(), pair[1].pos()))
+    }
+
+    /// Average of the chain's points
+    pub fn get_centroid(&self) -> QPoint {
+        if self.points.is_empty() {
+            return QPoint::new(QVec2::ZERO);
+        }
+        let sum = self
+            .points
+            .iter()
+            .fold(QVec2::ZERO, |sum, p| sum.saturating_add(p.pos()));
+        let count = Q64::from_num(self.points.len());
+        QPoint::new(QVec2::new(sum.x.saturating_div(count), sum.y.saturating_div(count)))
+    }
+
+    /// Get the vector that pushes a shape out of the chain, or `None` if it doesn't penetrate the
+    /// solid side of any segment.
+    ///
+    /// Each segment only considers the shape when its centroid is in front of the segment, and the
+    /// deepest penetration along a segment normal wins.
+    pub fn try_get_separation_vector(&self, shape: &QCollisionShape) -> Option<QVec2> {
+        let polygon = shape.to_polygon();
+        let centroid = shape.get_centroid().pos();
+        let mut best: Option<(Q64, QVec2)> = None;
+        for (start, end) in self.segments() {
+            let direction = end.saturating_sub(start);
+            let length = direction.length();
+            if length == Q64::ZERO {
+                continue;
+            }
+            let tangent = QVec2::new(direction.x.saturating_div(length), direction.y.saturating_div(length));
+            let normal = QVec2::new(-tangent.y, tangent.x);
+            if dot(centroid.saturating_sub(start), normal) < Q64::ZERO {
+                continue;
+            }
+
+            // Deepest vertex behind the segment within its extent
+            let depth = polygon
+                .points()
+                .iter()
+                .map(|p| p.pos().saturating_sub(start))
+                .filter(|offset| {
+                    let along = dot(*offset, tangent);
+                    along >= Q64::ZERO && along <= length
+                })
+                .map(|offset| -dot(offset, normal))
+                .max();
+            if let Some(depth) = depth.filter(|depth| *depth > Q64::ZERO) {
+                if best.is_none_or(|(best_depth, _)| depth > best_depth) {
+                    best = Some((depth, normal));
+                }
+            }
+        }
+        best.map(|(depth, normal)| normal.saturating_mul_num(depth))
+    }
 }
 
 /// Shape component for collision detection
-#[derive(Component, Debug, Clone)]
+#[derive(Component, Debug, Clone, Serialize, Deserialize)]
 pub enum QCollisionShape {
     Point(QPoint),
     Line(QLine),
     Circle(QCircle),
     Rectangle(QBbox),
     Polygon(QPolygon),
+    EdgeChain(QEdgeChain),
 }
 
 impl QCollisionShape {
@@ -96,6 +203,7 @@ impl QCollisionShape {
             QCollisionShape::Circle(circle) => circle.get_polygon(),
             QCollisionShape::Rectangle(rect) => rect.get_polygon(),
             QCollisionShape::Polygon(polygon) => polygon.clone(),
+            QCollisionShape::EdgeChain(chain) => QPolygon::new(chain.points.clone()),
         }
     }
 
@@ -107,6 +215,7 @@ impl QCollisionShape {
             QCollisionShape::Circle(circle) => circle.get_bbox(),
             QCollisionShape::Rectangle(rect) => rect.get_bbox(),
             QCollisionShape::Polygon(polygon) => polygon.get_bbox(),
+            QCollisionShape::EdgeChain(_) => self.to_polygon().get_bbox(),
         }
     }
 
@@ -118,6 +227,37 @@ impl QCollisionShape {
             QCollisionShape::Circle(circle) => circle.get_centroid(),
             QCollisionShape::Rectangle(rect) => rect.get_centroid(),
             QCollisionShape::Polygon(polygon) => polygon.get_centroid(),
+            QCollisionShape::EdgeChain(chain) => chain.get_centroid(),
+        }
+    }
+
+    /// Get the moment of inertia about the centroid for the given mass. Rectangles and polygons are
+    /// approximated by their bounding box.
+    pub fn moment_of_inertia(&self, mass: Q64) -> Q64 {
+        let twelve = Q64::from_num(12);
+        match self {
+            QCollisionShape::Point(_) => Q64::ZERO,
+            QCollisionShape::Line(line) => {
+                // I = m * L^2 / 12
+                let length = line.end().pos().saturating_sub(line.start().pos()).length();
+                mass.saturating_mul(length.saturating_mul(length))
+                    .saturating_div(twelve)
+            }
+            QCollisionShape::Circle(circle) => {
+                // I = m * r^2 / 2
+                let radius = circle.radius();
+                mass.saturating_mul(radius.saturating_mul(radius)).half()
+            }
+            QCollisionShape::Rectangle(_) | QCollisionShape::Polygon(_) | QCollisionShape::EdgeChain(_) => {
+                // I = m * (w^2 + h^2) / 12
+                let bbox = self.get_bbox();
+                let size = bbox.right_top().pos().saturating_sub(bbox.left_bottom().pos());
+                let size_sq = size
+                    .x
+                    .saturating_mul(size.x)
+                    .saturating_add(size.y.saturating_mul(size.y));
+                mass.saturating_mul(size_sq).saturating_div(twelve)
+            }
         }
     }
 
@@ -129,11 +269,25 @@ impl QCollisionShape {
             QCollisionShape::Circle(c) => c.is_point_inside(point),
             QCollisionShape::Rectangle(r) => r.is_point_inside(point),
             QCollisionShape::Polygon(poly) => poly.is_point_inside(point),
+            // Edge chains have no interior
+            QCollisionShape::EdgeChain(_) => false,
         }
     }
 
+    /// Whether the shape is a closed outline, false for open edge chains
+    pub fn is_closed(&self) -> bool {
+        !matches!(self, QCollisionShape::EdgeChain(_))
+    }
+
     /// Check if this shape collides with another shape
     pub fn is_collide(&self, other: &QCollisionShape) -> bool {
+        match (self, other) {
+            (QCollisionShape::EdgeChain(_), QCollisionShape::EdgeChain(_)) => return false,
+            (QCollisionShape::EdgeChain(chain), shape) | (shape, QCollisionShape::EdgeChain(chain)) => {
+                return chain.try_get_separation_vector(shape).is_some();
+            }
+            _ => {}
+        }
         let self_polygon = self.to_polygon();
         let other_polygon = other.to_polygon();
         self_polygon.is_collide(&other_polygon)
@@ -141,14 +295,61 @@ impl QCollisionShape {
 
     /// Try to get separation vector between this shape and another shape
     pub fn try_get_separation_vector(&self, other: &QCollisionShape) -> Option<QVec2> {
+        match (self, other) {
+            (QCollisionShape::EdgeChain(_), QCollisionShape::EdgeChain(_)) => return None,
+            (QCollisionShape::EdgeChain(chain), shape) => return chain.try_get_separation_vector(shape),
+            (shape, QCollisionShape::EdgeChain(chain)) => return chain.try_get_separation_vector(shape).map(|v| -v),
+            _ => {}
+        }
         let self_polygon = self.to_polygon();
         let other_polygon = other.to_polygon();
         self_polygon.try_get_seperation_vector(&other_polygon)
     }
+
+    /// Segments of the outline collisions are tested against, a point being a single segment of zero length.
+    /// Edge chains only keep the segments with `facing` in front of their solid side.
+    fn outline_segments(&self, facing: QVec2) -> Vec<(QVec2, QVec2)> {
+        if let QCollisionShape::EdgeChain(chain) = self {
+            return chain
+                .segments()
+                .filter(|(start, end)| {
+                    let (direction, offset) = (end.saturating_sub(*start), facing.saturating_sub(*start));
+                    direction.x.saturating_mul(offset.y) >= direction.y.saturating_mul(offset.x)
+                })
+                .collect();
+        }
+        let points: Vec<QVec2> = self.to_polygon().points().iter().map(|point| point.pos()).collect();
+        match points.len() {
+            0 => Vec::new(),
+            1 => vec![(points[0], points[0])],
+            2 => vec![(points[0], points[1])],
+            count => (0..count).map(|i| (points[i], points[(i + 1) % count])).collect(),
+        }
+    }
+
+    /// Closest points of the outlines of two shapes that don't collide, the first on this shape and the
+    /// second on `other`. `None` when one of them has no outline, or `other` is behind an edge chain.
+    pub fn closest_points(&self, other: &QCollisionShape) -> Option<(QVec2, QVec2)> {
+        let other_segments = other.outline_segments(self.get_centroid().pos());
+        self.outline_segments(other.get_centroid().pos())
+            .into_iter()
+            .flat_map(|(a, b)| {
+                other_segments.iter().flat_map(move |&(c, d)| {
+                    // Segments that don't cross are closest at an endpoint of one of them
+                    [
+                        (a, closest_on_segment(a, (c, d))),
+                        (b, closest_on_segment(b, (c, d))),
+                        (closest_on_segment(c, (a, b)), c),
+                        (closest_on_segment(d, (a, b)), d),
+                    ]
+                })
+            })
+            .min_by_key(|(from, to)| to.saturating_sub(*from).length())
+    }
 }
 
 /// Motion state of a body
-#[derive(Default, Component, Debug, Clone)]
+#[derive(Default, Component, Debug, Clone, Serialize, Deserialize)]
 pub struct QMotion {
     /// Linear velocity in units per second
     pub velocity: QVec2,
@@ -186,7 +387,7 @@ impl QMotion {
 }
 
 /// Collision flag for specifying collision behavior
-#[derive(Component, Debug, Clone)]
+#[derive(Component, Debug, Clone, Serialize, Deserialize)]
 pub struct QCollisionFlag {
     /// Whether this is a trigger (detects collisions but doesn't resolve them)
     pub is_trigger: bool,
@@ -244,7 +445,7 @@ impl QCollisionFlag {
 
 /// Describe the position of an 2d entity. If the entity has a parent, the position is relative
 /// to its parent position.
-#[derive(Clone, Copy, Component)]
+#[derive(Clone, Copy, Component, Serialize, Deserialize)]
 pub struct QTransform {
     /// Position of the entity.
     pub position: QVec2,
@@ -324,6 +525,115 @@ impl QTransform {
                     .collect();
                 QCollisionShape::Polygon(QPolygon::new(new_points))
             }
+            QCollisionShape::EdgeChain(chain) => {
+                let new_points: Vec<QPoint> = chain
+                    .points
+                    .iter()
+                    .map(|p| {
+                        QPoint::new(
+                            self.rotation
+                                .rotate_vec(p.pos().saturating_mul(self.scale))
+                                .saturating_add(self.position),
+                        )
+                    })
+                    .collect();
+                QCollisionShape::EdgeChain(QEdgeChain::new(new_points))
+            }
+        }
+    }
+}
+
+/// Marker for bodies that came to rest and are skipped by integration until woken
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct QSleeping;
+
+/// Time a body has been moving slower than the sleep threshold
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct QSleepTimer(pub Q64);
+
+/// Marks a body on which a saturating Q64 operation clamped, recorded by the overflow diagnostics mode
+#[derive(Component, Debug, Clone, Copy)]
+pub struct QOverflowDiagnostic {
+    /// Physics stage in which the first saturation happened
+    pub context: &'static str,
+}
+
+/// State of a body when the simulation started, captured on its first simulated step and
+/// restored (then removed) by a reset.
+#[derive(Clone, Component)]
+pub struct QInitialState {
+    pub transform: QTransform,
+    pub motion: QMotion,
+}
+
+/// Opt-in marker that writes the body's QTransform into its Bevy `Transform` every frame, so
+/// sprites and meshes follow the physics body.
+#[derive(Clone, Copy, Debug, Component)]
+pub struct QTransformSync {
+    /// Bevy units per physics unit, applied to the position
+    pub unit_scale: f32,
+    /// Whether to also write the QTransform scale into the Bevy scale
+    pub sync_scale: bool,
+}
+
+impl Default for QTransformSync {
+    fn default() -> Self {
+        Self {
+            unit_scale: 1.0,
+            sync_scale: false,
+        }
+    }
+}
+
+impl QTransformSync {
+    /// Create a sync with the given unit scale
+    pub fn with_unit_scale(unit_scale: f32) -> Self {
+        Self {
+            unit_scale,
+            ..Default::default()
+        }
+    }
+}
+
+/// Transform of a body at the previous and current fixed steps, used to interpolate rendering
+/// between steps.
+#[derive(Clone, Copy, Component)]
+pub struct QTransformHistory {
+    /// Transform at the end of the previous fixed step
+    pub previous: QTransform,
+    /// Transform at the end of the current fixed step
+    pub current: QTransform,
+}
+
+impl QTransformHistory {
+    /// Create a history where the previous and current transforms are the same
+    pub fn new(transform: QTransform) -> Self {
+        Self {
+            previous: transform,
+            current: transform,
+        }
+    }
+
+    /// Push the transform of a new fixed step
+    pub fn push(&mut self, transform: QTransform) {
+        self.previous = self.current;
+        self.current = transform;
+    }
+
+    /// Interpolate between the previous and current transforms, `alpha` in range [0, 1]
+    pub fn interpolate(&self, alpha: Q64) -> QTransform {
+        let lerp = |a: QVec2, b: QVec2| a.saturating_add(b.saturating_sub(a).saturating_mul_num(alpha));
+        let direction = lerp(self.previous.rotation.to_vec(), self.current.rotation.to_vec());
+        // Opposite directions lerp through zero, fall back to the current rotation in that case
+        let rotation = if direction == QVec2::ZERO {
+            self.current.rotation
+        } else {
+            QDir::new_from_vec(direction)
+        };
+        QTransform {
+            position: lerp(self.previous.position, self.current.position),
+            rotation,
+            scale: lerp(self.previous.scale, self.current.scale),
         }
     }
 }
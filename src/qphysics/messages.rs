@@ -1,5 +1,32 @@
 use super::components::QObject;
 use bevy::prelude::*;
+use qmath::prelude::*;
+use qmath::vec2::QVec2;
+
+/// Lifecycle events of physics bodies
+#[derive(Message, Debug, Clone, Copy)]
+pub enum QBodyEvent {
+    /// Fired when a physics body is added
+    Added(Entity),
+    /// Fired when a physics body is removed or despawned
+    Removed(Entity),
+    /// Fired when a body comes to rest and is put to sleep
+    Slept(Entity),
+    /// Fired when a sleeping body is woken by a collision
+    Woken(Entity),
+}
+
+impl QBodyEvent {
+    /// Get the entity this event is about
+    pub fn entity(&self) -> Entity {
+        match self {
+            QBodyEvent::Added(e) => *e,
+            QBodyEvent::Removed(e) => *e,
+            QBodyEvent::Slept(e) => *e,
+            QBodyEvent::Woken(e) => *e,
+        }
+    }
+}
 
 /// Trigger events for detecting when objects enter/exit trigger areas
 #[derive(Message, Debug, Clone)]
@@ -74,3 +101,29 @@ impl QCollisionEvent {
         matches!(self, QCollisionEvent::Ended(_, _))
     }
 }
+
+/// Fired for every contact impulse applied while resolving collisions
+#[derive(Message, Debug, Clone, Copy)]
+pub struct QImpactEvent {
+    pub object_a: QObject,
+    pub object_b: QObject,
+    /// Magnitude of the impulse along the contact normal
+    pub impulse: Q64,
+    /// Estimated contact point in world space
+    pub contact_point: QVec2,
+}
+
+/// Commands for the determinism recorder
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QRecorderCommand {
+    /// Capture the current world as the initial state and start recording inputs and state hashes
+    StartRecording,
+    /// Stop recording, keeping the captured data for verification
+    StopRecording,
+    /// Restore the initial state and replay the recorded inputs, comparing state hashes each step
+    Verify,
+}
+
+/// Restore every body to its state when the simulation started and pause the simulation
+#[derive(Message, Debug, Clone, Copy)]
+pub struct QResetSimulation;
@@ -2,6 +2,7 @@ use crate::qphysics::messages::*;
 use crate::qphysics::resources::*;
 use crate::qphysics::systems::*;
 use bevy::prelude::*;
+use bevy::transform::TransformSystems;
 
 pub struct QPhysicsPlugin;
 
@@ -13,9 +14,17 @@ impl Plugin for QPhysicsPlugin {
             .init_resource::<QPhysicsDebugConfig>()
             .init_resource::<QCollisionPairs>()
             .init_resource::<QCollisionPairsSetLastFrame>()
+            .init_resource::<QSpeculativePairs>()
+            .init_resource::<QPhysicsRecorder>()
+            .init_resource::<QSimulationControl>()
+            .init_resource::<QPhysicsStats>()
             // Add messages
             .add_message::<QCollisionEvent>()
+            .add_message::<QImpactEvent>()
             .add_message::<QTriggerEvent>()
+            .add_message::<QRecorderCommand>()
+            .add_message::<QResetSimulation>()
+            .add_message::<QBodyEvent>()
             // Configure system sets
             .configure_sets(
                 FixedUpdate,
@@ -30,17 +39,59 @@ impl Plugin for QPhysicsPlugin {
                 )
                     .chain(),
             )
+            // Gate the simulation sets on the playback controls. PostUpdate keeps running so the
+            // transform history settles while paused.
+            .configure_sets(
+                FixedUpdate,
+                (
+                    QPhysicsUpdateSet::PreUpdate,
+                    QPhysicsUpdateSet::VelocityIntegration,
+                    QPhysicsUpdateSet::BroadPhase,
+                    QPhysicsUpdateSet::NarrowPhase,
+                    QPhysicsUpdateSet::CollisionResolution,
+                    QPhysicsUpdateSet::PositionIntegration,
+                )
+                    .run_if(simulation_running),
+            )
             // Add systems
+            .add_systems(
+                Update,
+                (
+                    sync_fixed_timestep_qsystem,
+                    reset_simulation_qsystem,
+                    body_lifecycle_qsystem,
+                ),
+            )
+            // Render with interpolated transforms every frame instead of once per fixed step
+            .add_systems(
+                PostUpdate,
+                (sync_transforms_qsystem, debug_render_qsystem).before(TransformSystems::Propagate),
+            )
             .add_systems(
                 FixedUpdate,
                 (
-                    (update_qobject_qsysytem, apply_forces_qsystem).in_set(QPhysicsUpdateSet::PreUpdate),
+                    simulation_control_qsystem.before(QPhysicsUpdateSet::PreUpdate),
+                    (
+                        update_qobject_qsysytem,
+                        capture_initial_state_qsystem,
+                        recorder_input_qsystem.before(apply_forces_qsystem),
+                        apply_forces_qsystem,
+                    )
+                        .in_set(QPhysicsUpdateSet::PreUpdate),
                     integrate_velocities_qsystem.in_set(QPhysicsUpdateSet::VelocityIntegration),
                     broad_phase_qsystem.in_set(QPhysicsUpdateSet::BroadPhase),
-                    narrow_phase_qsystem.in_set(QPhysicsUpdateSet::NarrowPhase),
+                    (narrow_phase_qsystem, wake_colliding_bodies_qsystem)
+                        .chain()
+                        .in_set(QPhysicsUpdateSet::NarrowPhase),
                     collision_resolution_qsystem.in_set(QPhysicsUpdateSet::CollisionResolution),
-                    integrate_positions_qsystem.in_set(QPhysicsUpdateSet::PositionIntegration),
-                    debug_render_qsystem.in_set(QPhysicsUpdateSet::PostUpdate),
+                    (integrate_positions_qsystem, update_sleep_qsystem)
+                        .chain()
+                        .in_set(QPhysicsUpdateSet::PositionIntegration),
+                    (
+                        recorder_hash_qsystem.run_if(simulation_running),
+                        store_transform_history_qsystem,
+                    )
+                        .in_set(QPhysicsUpdateSet::PostUpdate),
                 ),
             );
     }
@@ -2,28 +2,105 @@
 
 use bevy::prelude::*;
 use qmath::{prelude::*, vec2::QVec2};
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use std::time::Duration;
 
-use crate::qphysics::components::QObject;
+use crate::qphysics::components::{QCollisionFlag, QMotion, QObject, QTransform};
 
-/// Physics world configuration
-#[derive(Resource, Debug, Clone)]
+/// Rule for combining a material property of two colliding bodies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum QCombineRule {
+    /// Arithmetic mean of both values
+    #[default]
+    Average,
+    /// Smaller of both values
+    Min,
+    /// Larger of both values
+    Max,
+    /// Product of both values
+    Multiply,
+}
+
+impl QCombineRule {
+    /// Combine the two values with this rule
+    pub fn combine(&self, a: Q64, b: Q64) -> Q64 {
+        match self {
+            QCombineRule::Average => a.saturating_add(b).half(),
+            QCombineRule::Min => {
+                if a < b {
+                    a
+                } else {
+                    b
+                }
+            }
+            QCombineRule::Max => {
+                if a > b {
+                    a
+                } else {
+                    b
+                }
+            }
+            QCombineRule::Multiply => a.saturating_mul(b),
+        }
+    }
+}
+
+/// Physics world configuration, saved with determinism recordings as the input of each step
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
 pub struct QPhysicsConfig {
     /// Gravity vector in units per second squared
     pub gravity: QVec2,
+    /// Gravity overrides keyed by collision layer, used instead of `gravity` for bodies on that layer
+    pub layer_gravity: HashMap<u32, QVec2>,
+    /// Constant wind force applied to every dynamic body, scaled by its inverse mass
+    pub wind_force: QVec2,
     /// Fixed time step for physics simulation
     pub time_step: Q64,
+    /// How the restitution of two colliding bodies is combined
+    pub restitution_combine: QCombineRule,
+    /// How the friction of two colliding bodies is combined
+    pub friction_combine: QCombineRule,
+    /// Penetration depth allowed without positional correction, avoids jitter of resting contacts
+    pub position_correction_slop: Q64,
+    /// Fraction of the remaining penetration resolved per step, range [0, 1]
+    pub position_correction_percent: Q64,
+    /// Whether bodies at rest are put to sleep
+    pub allow_sleeping: bool,
+    /// Linear and angular speed below which a body counts as resting
+    pub sleep_velocity_threshold: Q64,
+    /// Time in seconds a body has to rest before it is put to sleep
+    pub sleep_time: Q64,
     /// Number of velocity iterations for constraint solving
     pub velocity_iterations: i32,
     /// Number of position iterations for constraint solving
     pub position_iterations: i32,
 }
 
+impl QPhysicsConfig {
+    /// Get the gravity acting on a body in the given collision layer
+    pub fn gravity_for_layer(&self, collision_layer: u32) -> QVec2 {
+        self.layer_gravity
+            .get(&collision_layer)
+            .copied()
+            .unwrap_or(self.gravity)
+    }
+}
+
 impl Default for QPhysicsConfig {
     fn default() -> Self {
         Self {
             gravity: QVec2::new(Q64::ZERO, q64!(-10)), // Standard Earth gravity
+            layer_gravity: HashMap::new(),
+            wind_force: QVec2::ZERO,
             time_step: Q64::ONE / 10,
+            restitution_combine: QCombineRule::Average,
+            friction_combine: QCombineRule::Average,
+            position_correction_slop: Q64::ONE / 100,
+            position_correction_percent: Q64::from_num(0.8),
+            allow_sleeping: true,
+            sleep_velocity_threshold: Q64::ONE / 20,
+            sleep_time: Q64::ONE,
             velocity_iterations: 8,
             position_iterations: 3,
         }
@@ -31,12 +108,52 @@ impl Default for QPhysicsConfig {
 }
 
 /// Collision matrix for defining which layers can collide with each other
+///
+/// The matrix is a project-wide filter applied on top of the per-body `QCollisionFlag` masks: a pair
+/// of bodies is only tested when the matrix allows their layers to collide *and* both bodies' masks
+/// include the other's layer. Layers without an entry in the matrix are unrestricted.
 #[derive(Resource, Debug, Clone)]
 pub struct QCollisionMatrix {
-    /// Map of layer masks defining collision relationships
+    /// Map of layer masks defining collision relationships, keyed by single-bit layer
     pub layer_masks: HashMap<u32, u32>,
 }
 
+impl QCollisionMatrix {
+    /// Get the combined mask of all layer bits set in `collision_layer`
+    pub fn mask_for_layer(&self, collision_layer: u32) -> u32 {
+        let mut mask = 0;
+        for bit in 0..u32::BITS {
+            let layer = 1 << bit;
+            if collision_layer & layer != 0 {
+                mask |= self.layer_masks.get(&layer).copied().unwrap_or(u32::MAX);
+            }
+        }
+        mask
+    }
+
+    /// Check if the matrix allows two layers to collide
+    pub fn layers_can_collide(&self, layer_a: u32, layer_b: u32) -> bool {
+        (self.mask_for_layer(layer_a) & layer_b) != 0 && (self.mask_for_layer(layer_b) & layer_a) != 0
+    }
+
+    /// Check if two bodies can collide, combining the matrix with both bodies' own masks
+    pub fn can_collide(&self, flag_a: &QCollisionFlag, flag_b: &QCollisionFlag) -> bool {
+        flag_a.can_collide_with(flag_b) && self.layers_can_collide(flag_a.collision_layer, flag_b.collision_layer)
+    }
+
+    /// Allow or disallow two single-bit layers to collide with each other
+    pub fn set_layers_collide(&mut self, layer_a: u32, layer_b: u32, collide: bool) {
+        for (layer, other) in [(layer_a, layer_b), (layer_b, layer_a)] {
+            let mask = self.layer_masks.entry(layer).or_insert(u32::MAX);
+            if collide {
+                *mask |= other;
+            } else {
+                *mask &= !other;
+            }
+        }
+    }
+}
+
 impl Default for QCollisionMatrix {
     fn default() -> Self {
         let mut layer_masks = HashMap::new();
@@ -49,6 +166,11 @@ impl Default for QCollisionMatrix {
 #[derive(Resource, Debug, Clone, Default)]
 pub struct QCollisionPairs(pub Vec<(QObject, QObject)>);
 
+/// Pairs that don't overlap yet but close the gap between them within this step, found for CCD bodies in
+/// the narrow phase. They are resolved without firing collision events.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct QSpeculativePairs(pub Vec<(QObject, QObject)>);
+
 #[derive(Resource, Debug, Clone, Default)]
 pub struct QCollisionPairsSetLastFrame(pub HashSet<(QObject, QObject)>);
 
@@ -61,6 +183,10 @@ pub struct QPhysicsDebugConfig {
     pub show_velocity: bool,
     /// Whether to show contact points
     pub show_contacts: bool,
+    /// Whether rendering interpolates bodies between fixed steps
+    pub interpolate_rendering: bool,
+    /// Whether to check saturating Q64 operations against checked ones and mark bodies where they clamp
+    pub detect_overflow: bool,
 }
 
 impl Default for QPhysicsDebugConfig {
@@ -69,6 +195,120 @@ impl Default for QPhysicsDebugConfig {
             show_colliders: true,
             show_velocity: true,
             show_contacts: false,
+            interpolate_rendering: true,
+            detect_overflow: false,
         }
     }
 }
+
+/// Per-phase timings and counts of the last physics step
+#[derive(Resource, Debug, Clone, Default)]
+pub struct QPhysicsStats {
+    /// Number of steps simulated so far
+    pub steps: u64,
+    /// Time spent applying forces to the velocities
+    pub velocity_integration_time: Duration,
+    /// Time spent finding the pairs whose bounding boxes overlap
+    pub broad_phase_time: Duration,
+    /// Time spent testing the broad phase pairs for contact
+    pub narrow_phase_time: Duration,
+    /// Time spent applying contact impulses and position corrections
+    pub collision_resolution_time: Duration,
+    /// Time spent moving and rotating the bodies by their velocities
+    pub position_integration_time: Duration,
+    /// Bounding box tests done in the broad phase, for the pairs the collision matrix lets collide
+    pub pairs_tested: usize,
+    /// Pairs kept by the broad phase for the narrow phase
+    pub pairs_kept: usize,
+    /// Pairs found overlapping or speculatively colliding in the narrow phase
+    pub contacts_found: usize,
+    /// Contacts the resolution phase applied impulses to
+    pub contacts_solved: usize,
+    /// Bodies integrated this step, i.e. not sleeping
+    pub bodies_awake: usize,
+}
+
+impl QPhysicsStats {
+    /// Total time spent in the measured phases
+    pub fn total_time(&self) -> Duration {
+        self.velocity_integration_time
+            + self.broad_phase_time
+            + self.narrow_phase_time
+            + self.collision_resolution_time
+            + self.position_integration_time
+    }
+}
+
+/// Simulation playback controls
+#[derive(Resource, Debug, Clone)]
+pub struct QSimulationControl {
+    /// Whether the simulation is paused
+    pub paused: bool,
+    /// Number of steps still to run while paused
+    pub pending_steps: u32,
+    /// Whether the physics sets run during the current fixed step, updated at the start of each step
+    pub running_this_step: bool,
+    /// Playback speed multiplier. The physics time step is unchanged, only the fixed update rate is
+    /// scaled, so the simulation result stays deterministic.
+    pub time_scale: f32,
+}
+
+impl Default for QSimulationControl {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            pending_steps: 0,
+            running_this_step: false,
+            time_scale: 1.0,
+        }
+    }
+}
+
+impl QSimulationControl {
+    /// Queue the given number of steps to run while paused
+    pub fn step(&mut self, steps: u32) {
+        self.paused = true;
+        self.pending_steps = self.pending_steps.saturating_add(steps);
+    }
+}
+
+/// Mode of the determinism recorder
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QRecorderMode {
+    /// Not recording or replaying
+    #[default]
+    Idle,
+    /// Recording inputs and state hashes each step
+    Recording,
+    /// Replaying recorded inputs and verifying state hashes each step
+    Replaying,
+}
+
+/// State of a body captured when recording starts
+#[derive(Clone)]
+pub struct QBodySnapshot {
+    pub entity: Entity,
+    pub transform: QTransform,
+    pub motion: QMotion,
+}
+
+/// Records the initial world state, per-step inputs and per-step world state hashes, so that a
+/// replay can prove the simulation is bit-exact deterministic. Recordings are saved to files with the
+/// world they started from, so another run or machine can replay and verify them.
+#[derive(Resource, Default)]
+pub struct QPhysicsRecorder {
+    /// Current recorder mode
+    pub mode: QRecorderMode,
+    /// Body states captured when recording started
+    pub initial_state: Vec<QBodySnapshot>,
+    /// Physics configuration used as input for each recorded step
+    pub inputs: Vec<QPhysicsConfig>,
+    /// State hash of the `initial_state` bodies after each recorded step, see [`hash_body_states`]
+    ///
+    /// [`hash_body_states`]: crate::qphysics::systems::hash_body_states
+    pub hashes: Vec<u64>,
+    /// Index of the next step to verify while replaying
+    pub replay_step: usize,
+    /// Result of the last verification: `Ok(steps)` if all steps matched, `Err(step)` at the first mismatch
+    pub verify_result: Option<Result<usize, usize>>,
+}
@@ -1,13 +1,22 @@
-use super::components::{QCollisionFlag, QCollisionShape, QMotion, QObject, QPhysicsBody, QTransform};
-use super::messages::QCollisionEvent;
-use super::resources::{QCollisionPairs, QCollisionPairsSetLastFrame, QPhysicsConfig, QPhysicsDebugConfig};
+use super::components::{
+    QCollisionFlag, QCollisionShape, QInitialState, QMotion, QObject, QOverflowDiagnostic, QPhysicsBody, QSleepTimer,
+    QSleeping, QTransform, QTransformHistory, QTransformSync,
+};
+use super::messages::{QBodyEvent, QCollisionEvent, QImpactEvent, QRecorderCommand, QResetSimulation};
+use super::resources::{
+    QBodySnapshot, QCollisionMatrix, QCollisionPairs, QCollisionPairsSetLastFrame, QPhysicsConfig, QPhysicsDebugConfig,
+    QPhysicsRecorder, QPhysicsStats, QRecorderMode, QSimulationControl, QSpeculativePairs,
+};
 use crate::qphysics::messages::QTriggerEvent;
-use crate::util;
+use crate::util::{self, cross, dot};
 use bevy::prelude::*;
+use bevy::tasks::ComputeTaskPool;
 use qgeometry::prelude::*;
 use qmath::dir::QDir;
 use qmath::prelude::*;
+use qmath::vec2::QVec2;
 use std::collections::HashSet;
+use std::time::Instant;
 
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
 pub enum QPhysicsUpdateSet {
@@ -20,38 +29,266 @@ pub enum QPhysicsUpdateSet {
     PostUpdate,
 }
 
+/// Decide whether the physics sets run this step, consuming a pending step when paused.
+pub fn simulation_control_qsystem(mut control: ResMut<QSimulationControl>) {
+    if !control.paused {
+        control.running_this_step = true;
+    } else if control.pending_steps > 0 {
+        control.pending_steps -= 1;
+        control.running_this_step = true;
+    } else {
+        control.running_this_step = false;
+    }
+}
+
+/// Keep the fixed update rate in sync with the physics time step and the playback time scale.
+pub fn sync_fixed_timestep_qsystem(
+    mut fixed_time: ResMut<Time<Fixed>>, physics_config: Res<QPhysicsConfig>, control: Res<QSimulationControl>,
+) {
+    if !physics_config.is_changed() && !control.is_changed() {
+        return;
+    }
+
+    let time_scale = control.time_scale.max(0.01) as f64;
+    let time_step = physics_config.time_step.to_num::<f64>().max(0.001);
+    fixed_time.set_timestep_seconds(time_step / time_scale);
+}
+
+/// Run condition for systems that advance the simulation.
+pub fn simulation_running(control: Res<QSimulationControl>) -> bool {
+    control.running_this_step
+}
+
+/// Capture the state of bodies that are simulated for the first time since the last reset.
+pub fn capture_initial_state_qsystem(
+    mut commands: Commands, query: Query<(Entity, &QTransform, &QMotion), Without<QInitialState>>,
+) {
+    for (entity, transform, motion) in query.iter() {
+        commands.entity(entity).insert(QInitialState {
+            transform: *transform,
+            motion: motion.clone(),
+        });
+    }
+}
+
+/// Restore captured initial states, clear collision pair caches and pause the simulation.
+pub fn reset_simulation_qsystem(
+    mut commands: Commands, mut reset_events: MessageReader<QResetSimulation>,
+    mut query: Query<(Entity, &QInitialState, &mut QTransform, &mut QMotion, Option<&mut QTransformHistory>)>,
+    mut collision_pairs: ResMut<QCollisionPairs>,
+    mut collision_pairs_set_last_frame: ResMut<QCollisionPairsSetLastFrame>, mut control: ResMut<QSimulationControl>,
+) {
+    if reset_events.read().count() == 0 {
+        return;
+    }
+
+    for (entity, initial_state, mut transform, mut motion, history) in query.iter_mut() {
+        *transform = initial_state.transform;
+        *motion = initial_state.motion.clone();
+        if let Some(mut history) = history {
+            *history = QTransformHistory::new(initial_state.transform);
+        }
+        commands.entity(entity).remove::<(QInitialState, QSleeping, QSleepTimer)>();
+    }
+    collision_pairs.0.clear();
+    collision_pairs_set_last_frame.0.clear();
+    control.paused = true;
+    control.pending_steps = 0;
+}
+
 pub fn update_qobject_qsysytem(mut query: Query<(Entity, &mut QObject)>) {
     for (entity, mut qobject) in query.iter_mut() {
         qobject.entity = Some(entity);
     }
 }
 
+/// Handle recorder commands and record or replay the inputs of the current step.
+pub fn recorder_input_qsystem(
+    mut recorder: ResMut<QPhysicsRecorder>, mut recorder_commands: MessageReader<QRecorderCommand>,
+    mut physics_config: ResMut<QPhysicsConfig>, mut collision_pairs: ResMut<QCollisionPairs>,
+    mut collision_pairs_set_last_frame: ResMut<QCollisionPairsSetLastFrame>,
+    mut bodies: Query<(Entity, &mut QTransform, &mut QMotion)>,
+) {
+    for command in recorder_commands.read() {
+        match command {
+            QRecorderCommand::StartRecording => {
+                let mut initial_state: Vec<QBodySnapshot> = bodies
+                    .iter()
+                    .map(|(entity, transform, motion)| QBodySnapshot {
+                        entity,
+                        transform: *transform,
+                        motion: motion.clone(),
+                    })
+                    .collect();
+                initial_state.sort_by_key(|snapshot| snapshot.entity);
+                recorder.initial_state = initial_state;
+                recorder.inputs.clear();
+                recorder.hashes.clear();
+                recorder.verify_result = None;
+                recorder.mode = QRecorderMode::Recording;
+            }
+            QRecorderCommand::StopRecording => {
+                if recorder.mode == QRecorderMode::Recording {
+                    recorder.mode = QRecorderMode::Idle;
+                }
+            }
+            QRecorderCommand::Verify => {
+                if recorder.hashes.is_empty() {
+                    continue;
+                }
+                for snapshot in recorder.initial_state.iter() {
+                    if let Ok((_, mut transform, mut motion)) = bodies.get_mut(snapshot.entity) {
+                        *transform = snapshot.transform;
+                        *motion = snapshot.motion.clone();
+                    }
+                }
+                collision_pairs.0.clear();
+                collision_pairs_set_last_frame.0.clear();
+                recorder.replay_step = 0;
+                recorder.verify_result = None;
+                recorder.mode = QRecorderMode::Replaying;
+            }
+        }
+    }
+
+    match recorder.mode {
+        QRecorderMode::Idle => {}
+        QRecorderMode::Recording => {
+            recorder.inputs.push(physics_config.clone());
+        }
+        QRecorderMode::Replaying => {
+            if let Some(input) = recorder.inputs.get(recorder.replay_step) {
+                *physics_config = input.clone();
+            }
+        }
+    }
+}
+
 pub fn apply_forces_qsystem(
-    mut motion_query: Query<(&QPhysicsBody, &mut QMotion)>, physics_config: Res<QPhysicsConfig>,
+    mut motion_query: Query<(&QPhysicsBody, &mut QMotion, Option<&QCollisionFlag>), Without<QSleeping>>,
+    physics_config: Res<QPhysicsConfig>,
 ) {
-    for (body, mut motion) in motion_query.iter_mut() {
+    for (body, mut motion, flag) in motion_query.iter_mut() {
         if !body.is_static() {
-            // F = ma, a = F/m = g
-            motion.acceleration = physics_config.gravity;
+            // F = ma, a = g + F_wind / m
+            let gravity = match flag {
+                Some(flag) => physics_config.gravity_for_layer(flag.collision_layer),
+                None => physics_config.gravity,
+            };
+            let wind = physics_config.wind_force.saturating_mul_num(body.inverse_mass());
+            motion.acceleration = gravity.saturating_add(wind);
         }
     }
 }
 
-pub fn integrate_velocities_qsystem(mut motion_query: Query<&mut QMotion>, physics_config: Res<QPhysicsConfig>) {
+pub fn integrate_velocities_qsystem(
+    mut commands: Commands, mut motion_query: Query<(Entity, &mut QMotion), Without<QSleeping>>,
+    physics_config: Res<QPhysicsConfig>, debug_config: Res<QPhysicsDebugConfig>,
+    diagnostics: Query<(), With<QOverflowDiagnostic>>, mut stats: ResMut<QPhysicsStats>,
+) {
+    let start = Instant::now();
     let delta_time = physics_config.time_step;
 
-    for mut motion in motion_query.iter_mut() {
+    for (entity, mut motion) in motion_query.iter_mut() {
+        if debug_config.detect_overflow && mul_add_overflows(motion.velocity, motion.acceleration, delta_time) {
+            report_overflow(&mut commands, &diagnostics, entity, "velocity integration");
+        }
+
         // v = v0 + a * dt
         let delta_v = motion.acceleration.saturating_mul_num(delta_time);
         motion.velocity = motion.velocity.saturating_add(delta_v);
     }
+
+    stats.steps += 1;
+    stats.velocity_integration_time = start.elapsed();
+}
+
+/// Check whether `a + b * s` overflows in either component, i.e. whether the saturating version clamps
+fn mul_add_overflows(a: QVec2, b: QVec2, s: Q64) -> bool {
+    let overflows = |a: Q64, b: Q64| b.checked_mul(s).and_then(|bs| a.checked_add(bs)).is_none();
+    overflows(a.x, b.x) || overflows(a.y, b.y)
+}
+
+/// Log a Q64 saturation and mark the body, once per body until the marker is removed
+fn report_overflow(
+    commands: &mut Commands, diagnostics: &Query<(), With<QOverflowDiagnostic>>, entity: Entity,
+    context: &'static str,
+) {
+    if diagnostics.contains(entity) {
+        return;
+    }
+    warn!("Q64 saturation in {} on body {:?}", context, entity);
+    commands.entity(entity).insert(QOverflowDiagnostic { context });
+}
+
+/// Map every item to a list of results on the compute task pool and concatenate them in input order.
+///
+/// Chunks are spawned in order and `scope` returns their results in spawn order, so the output is
+/// identical to a sequential loop, keeping the simulation deterministic.
+fn par_flat_map_ordered<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send + 'static,
+    F: Fn(usize, &T) -> Vec<R> + Sync,
+{
+    if items.is_empty() {
+        return Vec::new();
+    }
+
+    let task_pool = ComputeTaskPool::get();
+    // Use several chunks per thread to balance the triangular pair loop of the broad phase.
+    let chunk_count = task_pool.thread_num().max(1) * 4;
+    let chunk_size = items.len().div_ceil(chunk_count).max(1);
+    let chunk_results = task_pool.scope(|scope| {
+        for (chunk_index, chunk) in items.chunks(chunk_size).enumerate() {
+            let f = &f;
+            scope.spawn(async move {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(i, item)| f(chunk_index * chunk_size + i, item))
+                    .collect::<Vec<R>>()
+            });
+        }
+    });
+    chunk_results.into_iter().flatten().collect()
+}
+
+/// Expand a bbox to cover its sweep along the given displacement
+fn swept_bbox(bbox: &QBbox, displacement: QVec2) -> QBbox {
+    let min = bbox.left_bottom().pos();
+    let max = bbox.right_top().pos();
+    let moved_min = min.saturating_add(displacement);
+    let moved_max = max.saturating_add(displacement);
+    let pick = |a: Q64, b: Q64, smaller: bool| if (a < b) == smaller { a } else { b };
+    QBbox::new_from_parts(
+        QVec2::new(pick(min.x, moved_min.x, true), pick(min.y, moved_min.y, true)),
+        QVec2::new(pick(max.x, moved_max.x, false), pick(max.y, moved_max.y, false)),
+    )
+}
+
+/// Gap between two shapes that don't collide and the unit direction across it from `shape_a` to `shape_b`,
+/// or `None` when there is no gap to close, e.g. for a shape behind an edge chain
+fn contact_gap(shape_a: &QCollisionShape, shape_b: &QCollisionShape) -> Option<(Q64, QVec2)> {
+    let (on_a, on_b) = shape_a.closest_points(shape_b)?;
+    let offset = on_b.saturating_sub(on_a);
+    let gap = offset.length();
+    if gap == Q64::ZERO {
+        return None;
+    }
+    Some((
+        gap,
+        QVec2::new(offset.x.saturating_div(gap), offset.y.saturating_div(gap)),
+    ))
 }
 
 pub fn broad_phase_qsystem(
     mut collision_pairs: ResMut<QCollisionPairs>,
     mut collision_pairs_set_last_frame: ResMut<QCollisionPairsSetLastFrame>,
-    query: Query<(&QObject, &QCollisionShape, &QCollisionFlag, &QTransform)>,
+    query: Query<(&QObject, &QCollisionShape, &QCollisionFlag, &QTransform, Option<&QPhysicsBody>, Option<&QMotion>)>,
+    collision_matrix: Res<QCollisionMatrix>, physics_config: Res<QPhysicsConfig>, mut stats: ResMut<QPhysicsStats>,
 ) {
+    let start = Instant::now();
     // Reset collision pairs.
     let collision_pairs = &mut collision_pairs.0;
     collision_pairs.to_owned().into_iter().for_each(|pair| {
@@ -59,42 +296,92 @@ pub fn broad_phase_qsystem(
     });
     collision_pairs.clear();
 
-    let shapes: Vec<_> = query.iter().collect();
-
-    for i in 0..shapes.len() {
-        for j in (i + 1)..shapes.len() {
-            let (qobject_a, shape_a, flag_a, transform_a) = shapes[i];
-            let (qobject_b, shape_b, flag_b, transform_b) = shapes[j];
-
-            if !flag_a.can_collide_with(flag_b) {
-                continue;
-            }
+    let shapes: Vec<_> = query
+        .iter()
+        .map(|(qobject, shape, flag, transform, body, motion)| {
+            let bbox = transform.apply_to(shape).get_bbox();
+            // CCD bodies cover the whole sweep of this step
+            let bbox = match (body, motion) {
+                (Some(body), Some(motion)) if body.ccd_enabled => {
+                    swept_bbox(&bbox, motion.velocity.saturating_mul_num(physics_config.time_step))
+                }
+                _ => bbox,
+            };
+            (*qobject, flag, bbox)
+        })
+        .collect();
 
-            let bbox_a = transform_a.apply_to(shape_a).get_bbox();
-            let bbox_b = transform_b.apply_to(shape_b).get_bbox();
+    // Test every shape against the shapes after it, in parallel, counting the bounding box tests.
+    let collision_matrix = &*collision_matrix;
+    let results = par_flat_map_ordered(&shapes, |i, (qobject_a, flag_a, bbox_a)| {
+        let mut tested = 0;
+        let pairs: Vec<_> = shapes[(i + 1)..]
+            .iter()
+            .filter(|(_, flag_b, _)| collision_matrix.can_collide(flag_a, flag_b))
+            .inspect(|_| tested += 1)
+            .filter(|(_, _, bbox_b)| bbox_a.is_collide(bbox_b))
+            .map(|(qobject_b, _, _)| (*qobject_a, *qobject_b))
+            .collect();
+        vec![(tested, pairs)]
+    });
+    stats.pairs_tested = results.iter().map(|(tested, _)| tested).sum();
+    collision_pairs.extend(results.into_iter().flat_map(|(_, pairs)| pairs));
 
-            if bbox_a.is_collide(&bbox_b) {
-                collision_pairs.push((*qobject_a, *qobject_b));
-            }
-        }
-    }
+    stats.pairs_kept = collision_pairs.len();
+    stats.broad_phase_time = start.elapsed();
 }
 
 pub fn narrow_phase_qsystem(
     mut collision_pairs: ResMut<QCollisionPairs>, collision_pairs_set_last_frame: ResMut<QCollisionPairsSetLastFrame>,
-    shapes: Query<(&QCollisionShape, &QCollisionFlag, &QTransform)>,
+    mut speculative_pairs: ResMut<QSpeculativePairs>, shapes: Query<(&QCollisionShape, &QCollisionFlag, &QTransform)>,
+    bodies: Query<(&QPhysicsBody, &QMotion)>, physics_config: Res<QPhysicsConfig>,
     mut collision_events: MessageWriter<QCollisionEvent>, mut trigger_events: MessageWriter<QTriggerEvent>,
+    mut stats: ResMut<QPhysicsStats>,
 ) {
+    let start = Instant::now();
     let collision_pairs = &mut collision_pairs.0;
-    collision_pairs.retain(|(qobject_a, qobject_b)| {
-        if let (Ok((shape_a, _, transform_a)), Ok((shape_b, _, transform_b))) =
-            (shapes.get(qobject_a.entity.unwrap()), shapes.get(qobject_b.entity.unwrap()))
+    let time_step = physics_config.time_step;
+    // (colliding now, colliding by the end of the step)
+    let results = par_flat_map_ordered(collision_pairs, |_, (qobject_a, qobject_b)| {
+        let (entity_a, entity_b) = (qobject_a.entity.unwrap(), qobject_b.entity.unwrap());
+        if let (Ok((shape_a, flag_a, transform_a)), Ok((shape_b, flag_b, transform_b))) =
+            (shapes.get(entity_a), shapes.get(entity_b))
         {
-            return transform_a.apply_to(shape_a).is_collide(&transform_b.apply_to(shape_b));
+            if transform_a.apply_to(shape_a).is_collide(&transform_b.apply_to(shape_b)) {
+                return vec![(true, false)];
+            }
+
+            // Speculative contact when either body uses CCD: the pair closes its gap within the step, however
+            // far past each other the bodies would end up
+            if flag_a.is_trigger || flag_b.is_trigger {
+                return vec![(false, false)];
+            }
+            let (body_a, body_b) = (bodies.get(entity_a).ok(), bodies.get(entity_b).ok());
+            let ccd = |body: Option<(&QPhysicsBody, &QMotion)>| body.is_some_and(|(body, _)| body.ccd_enabled);
+            if !ccd(body_a) && !ccd(body_b) {
+                return vec![(false, false)];
+            }
+            let Some((gap, normal)) = contact_gap(&transform_a.apply_to(shape_a), &transform_b.apply_to(shape_b))
+            else {
+                return vec![(false, false)];
+            };
+            let velocity =
+                |body: Option<(&QPhysicsBody, &QMotion)>| body.map_or(QVec2::ZERO, |(_, motion)| motion.velocity);
+            let closing_speed = dot(velocity(body_a).saturating_sub(velocity(body_b)), normal);
+            return vec![(false, closing_speed.saturating_mul(time_step) >= gap)];
         }
-        return false;
+        return vec![(false, false)];
     });
 
+    speculative_pairs.0 = collision_pairs
+        .iter()
+        .zip(results.iter())
+        .filter(|(_, (_, speculative))| *speculative)
+        .map(|(pair, _)| *pair)
+        .collect();
+    let mut results = results.into_iter();
+    collision_pairs.retain(|_| results.next().map(|(colliding, _)| colliding).unwrap_or(false));
+
     // Fire colliding messages.
     for collision_pair in collision_pairs.iter() {
         if let (Ok((_, flag_a, _)), Ok((_, flag_b, _))) =
@@ -131,12 +418,24 @@ pub fn narrow_phase_qsystem(
             }
         }
     });
+
+    stats.contacts_found = collision_pairs.len() + speculative_pairs.0.len();
+    stats.narrow_phase_time = start.elapsed();
 }
 
 pub fn collision_resolution_qsystem(
     mut collision_pairs: ResMut<QCollisionPairs>, mut motions: Query<(&QPhysicsBody, &mut QMotion)>,
-    mut shapes: Query<(&QCollisionShape, &mut QTransform)>,
+    mut shapes: Query<(&QCollisionShape, &mut QTransform)>, physics_config: Res<QPhysicsConfig>,
+    speculative_pairs: Res<QSpeculativePairs>, mut commands: Commands, debug_config: Res<QPhysicsDebugConfig>,
+    diagnostics: Query<(), With<QOverflowDiagnostic>>, mut stats: ResMut<QPhysicsStats>,
+    mut impacts: MessageWriter<QImpactEvent>,
 ) {
+    let start = Instant::now();
+    let mut contacts_solved = speculative_pairs.0.len();
+    resolve_speculative_contacts(&speculative_pairs, &mut motions, &shapes, physics_config.time_step);
+
+    // Bodies whose impulses saturated, reported after the loop
+    let mut overflowed: Vec<Entity> = Vec::new();
     let collision_pairs = &mut collision_pairs.0;
     for (qobject_a, qobject_b) in collision_pairs.iter() {
         if let Ok([(body_a, mut motion_a), (body_b, mut motion_b)]) =
@@ -144,25 +443,40 @@ pub fn collision_resolution_qsystem(
         {
             if let Ok([(shape_a, mut transform_a), (shape_b, mut transform_b)]) = shapes.get_many_mut([qobject_a.entity.unwrap(), qobject_b.entity.unwrap()])
             {
-                if let Some(separation_vector_b) = transform_a
-                    .apply_to(shape_a)
-                    .try_get_separation_vector(&transform_b.apply_to(shape_b))
-                {
+                let world_shape_a = transform_a.apply_to(shape_a);
+                let world_shape_b = transform_b.apply_to(shape_b);
+                if let Some(separation_vector_b) = world_shape_a.try_get_separation_vector(&world_shape_b) {
+                    contacts_solved += 1;
+
+                    // Contact geometry is taken before the bodies are separated.
+                    let contact_point = estimate_contact_point(&world_shape_a, &world_shape_b);
+                    let r_a = contact_point.saturating_sub(world_shape_a.get_centroid().pos());
+                    let r_b = contact_point.saturating_sub(world_shape_b.get_centroid().pos());
+
                     /*
-                     * Apply separation vector.
+                     * Apply positional correction, pushing the bodies apart in proportion to their
+                     * inverse masses so static bodies never move.
                      */
-                    let mass_sum = body_a.mass + body_b.mass;
-                    if mass_sum != Q64::ZERO {
-                        let separation_part_vector_a = -separation_vector_b.saturating_mul_num(body_a.mass.saturating_div(mass_sum));
-                        let separation_part_vector_b = separation_vector_b.saturating_mul_num(body_b.mass.saturating_div(mass_sum));
-                        transform_a.position = transform_a.position.saturating_add(separation_part_vector_a);
-                        transform_b.position = transform_b.position.saturating_add(separation_part_vector_b);
+                    let inv_mass_a = body_a.inverse_mass();
+                    let inv_mass_b = body_b.inverse_mass();
+                    let inv_mass_sum = inv_mass_a.saturating_add(inv_mass_b);
+                    let depth = separation_vector_b.length();
+                    let corrected_depth = depth.saturating_sub(physics_config.position_correction_slop);
+                    if inv_mass_sum != Q64::ZERO && corrected_depth > Q64::ZERO && depth > Q64::ZERO {
+                        let correction_scale = corrected_depth
+                            .saturating_mul(physics_config.position_correction_percent)
+                            .saturating_div(depth.saturating_mul(inv_mass_sum));
+                        let correction = separation_vector_b.saturating_mul_num(correction_scale);
+                        transform_a.position = transform_a.position.saturating_sub(correction.saturating_mul_num(inv_mass_a));
+                        transform_b.position = transform_b.position.saturating_add(correction.saturating_mul_num(inv_mass_b));
                     }
 
                     /*
-                     * Apply impluse.
+                     * Apply impluse at the contact point.
                      */
-                    let relative_velocity = motion_a.velocity.saturating_sub(motion_b.velocity);
+                    let contact_velocity_a = motion_a.velocity.saturating_add(cross_scalar_vec(motion_a.angular_velocity, r_a));
+                    let contact_velocity_b = motion_b.velocity.saturating_add(cross_scalar_vec(motion_b.angular_velocity, r_b));
+                    let relative_velocity = contact_velocity_a.saturating_sub(contact_velocity_b);
 
                     let magnitude = separation_vector_b.length();
                     if magnitude == Q64::ZERO {
@@ -170,60 +484,450 @@ pub fn collision_resolution_qsystem(
                     }
 
                     let separation_dir_b = QDir::new_from_vec(separation_vector_b);
+                    let normal = separation_dir_b.to_vec();
                     let vel_along_normal = separation_dir_b.projection_of(relative_velocity);
                     if vel_along_normal < Q64::ZERO {
                         continue;
                     }
 
-                    let restitution = (body_a.restitution.saturating_add(body_b.restitution)).half();
-                    let inv_mass_a = body_a.inverse_mass();
-                    let inv_mass_b = body_b.inverse_mass();
+                    let restitution = physics_config.restitution_combine.combine(body_a.restitution, body_b.restitution);
+                    let friction = physics_config.friction_combine.combine(body_a.friction, body_b.friction);
+                    let inv_inertia_a = body_a.inverse_inertia(&world_shape_a);
+                    let inv_inertia_b = body_b.inverse_inertia(&world_shape_b);
                     let separate_vel = -(restitution.saturating_add(Q64::ONE)).saturating_mul(vel_along_normal);
-                    let inv_mass_sum = inv_mass_a + inv_mass_b;
+                    let inv_mass_sum = effective_inverse_mass(normal, r_a, r_b, inv_mass_a, inv_mass_b, inv_inertia_a, inv_inertia_b);
                     if inv_mass_sum == Q64::ZERO {
                         continue;
                     }
 
                     let impulse_scalar = separate_vel.saturating_div(inv_mass_sum);
-                    let impulse = separation_dir_b.to_vec().saturating_mul_num(impulse_scalar);
-                    motion_a.velocity = motion_a.velocity.saturating_add(impulse.saturating_mul_num(inv_mass_a));
-                    motion_b.velocity = motion_b.velocity.saturating_sub(impulse.saturating_mul_num(inv_mass_b));
+                    let impulse = normal.saturating_mul_num(impulse_scalar);
+                    if apply_contact_impulse(&mut motion_a, impulse, r_a, inv_mass_a, inv_inertia_a) {
+                        overflowed.push(qobject_a.entity.unwrap());
+                    }
+                    if apply_contact_impulse(&mut motion_b, -impulse, r_b, inv_mass_b, inv_inertia_b) {
+                        overflowed.push(qobject_b.entity.unwrap());
+                    }
+                    impacts.write(QImpactEvent {
+                        object_a: *qobject_a,
+                        object_b: *qobject_b,
+                        impulse: impulse_scalar.abs(),
+                        contact_point,
+                    });
+
+                    /*
+                     * Apply friction impulse along the contact tangent, clamped by Coulomb's law.
+                     */
+                    let tangent_velocity = relative_velocity.saturating_sub(normal.saturating_mul_num(vel_along_normal));
+                    let tangent_speed = tangent_velocity.length();
+                    if tangent_speed <= Q64::EPS {
+                        continue;
+                    }
+                    let tangent = QDir::new_from_vec(tangent_velocity).to_vec();
+                    let inv_mass_sum_tangent = effective_inverse_mass(tangent, r_a, r_b, inv_mass_a, inv_mass_b, inv_inertia_a, inv_inertia_b);
+                    if inv_mass_sum_tangent == Q64::ZERO {
+                        continue;
+                    }
+                    let max_friction_scalar = friction.saturating_mul(impulse_scalar.abs());
+                    let mut friction_scalar = -tangent_speed.saturating_div(inv_mass_sum_tangent);
+                    if friction_scalar < -max_friction_scalar {
+                        friction_scalar = -max_friction_scalar;
+                    }
+                    let friction_impulse = tangent.saturating_mul_num(friction_scalar);
+                    if apply_contact_impulse(&mut motion_a, friction_impulse, r_a, inv_mass_a, inv_inertia_a) {
+                        overflowed.push(qobject_a.entity.unwrap());
+                    }
+                    if apply_contact_impulse(&mut motion_b, -friction_impulse, r_b, inv_mass_b, inv_inertia_b) {
+                        overflowed.push(qobject_b.entity.unwrap());
+                    }
                 }
             }
         }
     }
+
+    if debug_config.detect_overflow {
+        for entity in overflowed {
+            report_overflow(&mut commands, &diagnostics, entity, "collision resolution");
+        }
+    }
+
+    stats.contacts_solved = contacts_solved;
+    stats.collision_resolution_time = start.elapsed();
+}
+
+/// Remove just enough approaching velocity from speculative pairs that they touch, instead of
+/// passing into or through each other, at the end of the step.
+fn resolve_speculative_contacts(
+    speculative_pairs: &QSpeculativePairs, motions: &mut Query<(&QPhysicsBody, &mut QMotion)>,
+    shapes: &Query<(&QCollisionShape, &mut QTransform)>, time_step: Q64,
+) {
+    if time_step == Q64::ZERO {
+        return;
+    }
+
+    for (qobject_a, qobject_b) in speculative_pairs.0.iter() {
+        let (entity_a, entity_b) = (qobject_a.entity.unwrap(), qobject_b.entity.unwrap());
+        let (Ok((shape_a, transform_a)), Ok((shape_b, transform_b))) = (shapes.get(entity_a), shapes.get(entity_b))
+        else {
+            continue;
+        };
+        let Ok([(body_a, mut motion_a), (body_b, mut motion_b)]) = motions.get_many_mut([entity_a, entity_b]) else {
+            continue;
+        };
+
+        let Some((gap, normal)) = contact_gap(&transform_a.apply_to(shape_a), &transform_b.apply_to(shape_b))
+        else {
+            continue;
+        };
+        let inv_mass_a = body_a.inverse_mass();
+        let inv_mass_b = body_b.inverse_mass();
+        let inv_mass_sum = inv_mass_a.saturating_add(inv_mass_b);
+        if inv_mass_sum == Q64::ZERO {
+            continue;
+        }
+
+        // The relative normal velocity may close at most the gap within the step
+        let closing_speed = dot(motion_a.velocity.saturating_sub(motion_b.velocity), normal);
+        let excess = closing_speed.saturating_sub(gap.saturating_div(time_step));
+        if excess <= Q64::ZERO {
+            continue;
+        }
+        let impulse = normal.saturating_mul_num(excess.saturating_div(inv_mass_sum));
+        motion_a.velocity = motion_a.velocity.saturating_sub(impulse.saturating_mul_num(inv_mass_a));
+        motion_b.velocity = motion_b.velocity.saturating_add(impulse.saturating_mul_num(inv_mass_b));
+    }
+}
+
+/// Effective inverse mass of a contact along `direction`, including the rotational terms
+fn effective_inverse_mass(
+    direction: QVec2, r_a: QVec2, r_b: QVec2, inv_mass_a: Q64, inv_mass_b: Q64, inv_inertia_a: Q64,
+    inv_inertia_b: Q64,
+) -> Q64 {
+    let r_a_cross_d = cross(r_a, direction);
+    let r_b_cross_d = cross(r_b, direction);
+    inv_mass_a
+        .saturating_add(inv_mass_b)
+        .saturating_add(r_a_cross_d.saturating_mul(r_a_cross_d).saturating_mul(inv_inertia_a))
+        .saturating_add(r_b_cross_d.saturating_mul(r_b_cross_d).saturating_mul(inv_inertia_b))
+}
+
+/// Apply an impulse at offset `r` from the centroid, changing both linear and angular velocity.
+/// Returns whether the velocity update saturated.
+fn apply_contact_impulse(motion: &mut QMotion, impulse: QVec2, r: QVec2, inv_mass: Q64, inv_inertia: Q64) -> bool {
+    let angular_impulse = cross(r, impulse);
+    let overflowed = mul_add_overflows(motion.velocity, impulse, inv_mass)
+        || angular_impulse
+            .checked_mul(inv_inertia)
+            .and_then(|delta| motion.angular_velocity.checked_add(delta))
+            .is_none();
+    motion.velocity = motion.velocity.saturating_add(impulse.saturating_mul_num(inv_mass));
+    motion.angular_velocity = motion.angular_velocity.saturating_add(angular_impulse.saturating_mul(inv_inertia));
+    overflowed
+}
+
+/// 2D cross product of a scalar (z axis) and a vector, e.g. the velocity `ω × r` of a point at `r`
+fn cross_scalar_vec(s: Q64, v: QVec2) -> QVec2 {
+    QVec2::new(-s.saturating_mul(v.y), s.saturating_mul(v.x))
+}
+
+/// Estimate the contact point of two overlapping shapes as the average of the vertices of each shape
+/// that lie inside the other, falling back to the midpoint of their centroids.
+fn estimate_contact_point(shape_a: &QCollisionShape, shape_b: &QCollisionShape) -> QVec2 {
+    let mut sum = QVec2::ZERO;
+    let mut count = 0;
+    for (shape, other) in [(shape_a, shape_b), (shape_b, shape_a)] {
+        for point in shape.to_polygon().points().iter() {
+            if other.is_point_inside(point) {
+                sum = sum.saturating_add(point.pos());
+                count += 1;
+            }
+        }
+    }
+
+    if count == 0 {
+        let centroid_a = shape_a.get_centroid().pos();
+        let centroid_b = shape_b.get_centroid().pos();
+        return QVec2::new(
+            centroid_a.x.saturating_add(centroid_b.x).half(),
+            centroid_a.y.saturating_add(centroid_b.y).half(),
+        );
+    }
+    let count = Q64::from_num(count);
+    QVec2::new(sum.x.saturating_div(count), sum.y.saturating_div(count))
 }
 
-pub fn integrate_positions_qsystem(mut transform_query: Query<(&mut QTransform, &QMotion)>, physics_config: Res<QPhysicsConfig>) {
+pub fn integrate_positions_qsystem(
+    mut commands: Commands,
+    mut transform_query: Query<(Entity, &mut QTransform, &QMotion, Option<&QCollisionShape>), Without<QSleeping>>,
+    physics_config: Res<QPhysicsConfig>, debug_config: Res<QPhysicsDebugConfig>,
+    diagnostics: Query<(), With<QOverflowDiagnostic>>, mut stats: ResMut<QPhysicsStats>,
+) {
+    let start = Instant::now();
     let delta_time = physics_config.time_step;
+    stats.bodies_awake = 0;
+
+    for (entity, mut transform, motion, shape) in transform_query.iter_mut() {
+        stats.bodies_awake += 1;
+
+        if debug_config.detect_overflow
+            && (mul_add_overflows(transform.position, motion.velocity, delta_time)
+                || motion.angular_velocity.checked_mul(delta_time).is_none())
+        {
+            report_overflow(&mut commands, &diagnostics, entity, "position integration");
+        }
 
-    for (mut transform, motion) in transform_query.iter_mut() {
         // x = x0 + v * dt
         let displacement = motion.velocity.saturating_mul_num(delta_time);
         transform.position = transform.position.saturating_add(displacement);
 
         // θ = θ0 + ω * dt
         let angle_displacement = motion.angular_velocity.saturating_mul(delta_time);
+        if angle_displacement == Q64::ZERO {
+            continue;
+        }
+        // Rotate about the shape's centroid rather than the local origin, so the world centroid c taken with
+        // the old rotation stays in place: p' = c - R(θ')·c_local
+        let centroid = shape.map(|shape| transform.apply_to(shape).get_centroid().pos());
         transform.rotation.rotate(angle_displacement);
+        if let (Some(shape), Some(centroid)) = (shape, centroid) {
+            let rotated = QTransform { position: QVec2::ZERO, ..*transform };
+            let local_centroid = rotated.apply_to(shape).get_centroid().pos();
+            transform.position = centroid.saturating_sub(local_centroid);
+        }
+    }
+
+    stats.position_integration_time = start.elapsed();
+}
+
+/// Emit lifecycle events for physics bodies that were added or removed.
+///
+/// Runs in `Update` because removal detection only keeps removals for a couple of frames, which
+/// a slow fixed step could miss.
+pub fn body_lifecycle_qsystem(
+    added: Query<Entity, Added<QPhysicsBody>>, mut removed: RemovedComponents<QPhysicsBody>,
+    mut body_events: MessageWriter<QBodyEvent>,
+) {
+    for entity in added.iter() {
+        body_events.write(QBodyEvent::Added(entity));
+    }
+    for entity in removed.read() {
+        body_events.write(QBodyEvent::Removed(entity));
+    }
+}
+
+/// Put dynamic bodies to sleep after they rested for `sleep_time`.
+pub fn update_sleep_qsystem(
+    mut commands: Commands,
+    mut query: Query<(Entity, &QPhysicsBody, &mut QMotion, Option<&mut QSleepTimer>), Without<QSleeping>>,
+    physics_config: Res<QPhysicsConfig>, mut body_events: MessageWriter<QBodyEvent>,
+) {
+    if !physics_config.allow_sleeping {
+        return;
+    }
+
+    let threshold = physics_config.sleep_velocity_threshold;
+    for (entity, body, mut motion, timer) in query.iter_mut() {
+        if body.is_static() {
+            continue;
+        }
+        let Some(mut timer) = timer else {
+            commands.entity(entity).insert(QSleepTimer::default());
+            continue;
+        };
+
+        let resting = motion.velocity.length() < threshold && motion.angular_velocity.abs() < threshold;
+        if !resting {
+            timer.0 = Q64::ZERO;
+            continue;
+        }
+        timer.0 = timer.0.saturating_add(physics_config.time_step);
+        if timer.0 >= physics_config.sleep_time {
+            motion.velocity = QVec2::ZERO;
+            motion.angular_velocity = Q64::ZERO;
+            commands.entity(entity).insert(QSleeping);
+            body_events.write(QBodyEvent::Slept(entity));
+        }
+    }
+}
+
+/// Wake sleeping bodies that are hit by an awake dynamic body.
+pub fn wake_colliding_bodies_qsystem(
+    mut commands: Commands, collision_pairs: Res<QCollisionPairs>, speculative_pairs: Res<QSpeculativePairs>,
+    mut bodies: Query<(&QPhysicsBody, Has<QSleeping>, Option<&mut QSleepTimer>)>,
+    mut body_events: MessageWriter<QBodyEvent>,
+) {
+    for (qobject_a, qobject_b) in collision_pairs.0.iter().chain(speculative_pairs.0.iter()) {
+        let (entity_a, entity_b) = (qobject_a.entity.unwrap(), qobject_b.entity.unwrap());
+        let (Ok((body_a, sleeping_a, _)), Ok((body_b, sleeping_b, _))) = (bodies.get(entity_a), bodies.get(entity_b))
+        else {
+            continue;
+        };
+
+        let wake = if sleeping_a && !sleeping_b && !body_b.is_static() {
+            Some(entity_a)
+        } else if sleeping_b && !sleeping_a && !body_a.is_static() {
+            Some(entity_b)
+        } else {
+            None
+        };
+        if let Some(entity) = wake {
+            if let Ok((_, _, Some(mut timer))) = bodies.get_mut(entity) {
+                timer.0 = Q64::ZERO;
+            }
+            commands.entity(entity).remove::<QSleeping>();
+            body_events.write(QBodyEvent::Woken(entity));
+        }
+    }
+}
+
+/// Hash the world state after a step, recording it or verifying it against the recording.
+///
+/// Only the bodies captured when recording started are hashed, in the order they were captured, so a
+/// recording loaded into a world whose entities were allocated differently still verifies.
+pub fn recorder_hash_qsystem(mut recorder: ResMut<QPhysicsRecorder>, bodies: Query<(Entity, &QTransform, &QMotion)>) {
+    if recorder.mode == QRecorderMode::Idle {
+        return;
+    }
+
+    let hash = hash_body_states(recorder.initial_state.iter().filter_map(|snapshot| {
+        bodies
+            .get(snapshot.entity)
+            .ok()
+            .map(|(_, transform, motion)| (transform, motion))
+    }));
+    match recorder.mode {
+        QRecorderMode::Idle => {}
+        QRecorderMode::Recording => recorder.hashes.push(hash),
+        QRecorderMode::Replaying => {
+            let step = recorder.replay_step;
+            if recorder.hashes[step] != hash {
+                recorder.verify_result = Some(Err(step));
+                recorder.mode = QRecorderMode::Idle;
+                return;
+            }
+            recorder.replay_step += 1;
+            if recorder.replay_step == recorder.hashes.len() {
+                recorder.verify_result = Some(Ok(recorder.replay_step));
+                recorder.mode = QRecorderMode::Idle;
+            }
+        }
+    }
+}
+
+/// Hash the bit patterns of every body's transform and motion, in entity order.
+pub fn hash_world_state(bodies: &Query<(Entity, &QTransform, &QMotion)>) -> u64 {
+    let mut sorted: Vec<_> = bodies.iter().collect();
+    sorted.sort_by_key(|(entity, _, _)| *entity);
+    hash_body_states(sorted.into_iter().map(|(_, transform, motion)| (transform, motion)))
+}
+
+/// Hash the bit patterns of the given bodies' transforms and motions, in the given order.
+///
+/// FNV-1a is used instead of `DefaultHasher` so the hash is stable across Rust versions and platforms.
+pub fn hash_body_states<'a>(bodies: impl IntoIterator<Item = (&'a QTransform, &'a QMotion)>) -> u64 {
+    fn write(hash: &mut u64, value: Q64) {
+        for byte in value.to_bits().to_le_bytes() {
+            *hash ^= byte as u64;
+            *hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for (transform, motion) in bodies {
+        let rotation = transform.rotation.to_vec();
+        for value in [
+            transform.position.x,
+            transform.position.y,
+            rotation.x,
+            rotation.y,
+            transform.scale.x,
+            transform.scale.y,
+            motion.velocity.x,
+            motion.velocity.y,
+            motion.angular_velocity,
+        ] {
+            write(&mut hash, value);
+        }
+    }
+    hash
+}
+
+/// Push the transform of this fixed step into each body's history.
+///
+/// Runs even while paused, so the history settles and rendering stops interpolating.
+pub fn store_transform_history_qsystem(
+    mut commands: Commands, mut query: Query<(Entity, &QTransform, Option<&mut QTransformHistory>)>,
+) {
+    for (entity, transform, history) in query.iter_mut() {
+        match history {
+            Some(mut history) => history.push(*transform),
+            None => {
+                commands.entity(entity).insert(QTransformHistory::new(*transform));
+            }
+        }
+    }
+}
+
+/// Get the fraction of a fixed step that has elapsed since the last one, for render interpolation.
+fn interpolation_alpha(fixed_time: &Time<Fixed>, debug_config: &QPhysicsDebugConfig) -> Q64 {
+    if debug_config.interpolate_rendering {
+        Q64::from_num(fixed_time.overstep_fraction().clamp(0.0, 1.0))
+    } else {
+        Q64::ONE
+    }
+}
+
+/// Get the transform a body should be rendered with this frame.
+fn render_transform(transform: &QTransform, history: Option<&QTransformHistory>, alpha: Q64) -> QTransform {
+    match history {
+        Some(history) => history.interpolate(alpha),
+        None => *transform,
+    }
+}
+
+/// Write the (interpolated) QTransform of each opted-in body into its Bevy `Transform`.
+pub fn sync_transforms_qsystem(
+    mut query: Query<(&QTransform, Option<&QTransformHistory>, &QTransformSync, &mut Transform)>,
+    fixed_time: Res<Time<Fixed>>, debug_config: Res<QPhysicsDebugConfig>,
+) {
+    let alpha = interpolation_alpha(&fixed_time, &debug_config);
+    for (qtransform, history, sync, mut transform) in query.iter_mut() {
+        let rendered = render_transform(qtransform, history, alpha);
+        let position = util::qvec2vec(rendered.position) * sync.unit_scale;
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+        transform.rotation = Quat::from_rotation_z(util::qdir2angle(rendered.rotation));
+        if sync.sync_scale {
+            let scale = util::qvec2vec(rendered.scale);
+            transform.scale.x = scale.x;
+            transform.scale.y = scale.y;
+        }
     }
 }
 
 pub fn debug_render_qsystem(
-    query: Query<(&QTransform, &QMotion, &QCollisionShape)>, debug_config: Res<QPhysicsDebugConfig>, mut gizmos: Gizmos,
+    query: Query<(&QTransform, Option<&QTransformHistory>, &QMotion, &QCollisionShape, Has<QOverflowDiagnostic>)>,
+    debug_config: Res<QPhysicsDebugConfig>, fixed_time: Res<Time<Fixed>>, mut gizmos: Gizmos,
 ) {
     if !debug_config.show_colliders && !debug_config.show_velocity {
         return;
     }
 
-    for (transform, motion, shape) in query.iter() {
+    let alpha = interpolation_alpha(&fixed_time, &debug_config);
+    for (transform, history, motion, shape, overflowed) in query.iter() {
+        let transform = render_transform(transform, history, alpha);
+        // Bodies that hit Q64 saturation are drawn red
+        let collider_color = if overflowed { Color::srgb(1.0, 0.0, 0.0) } else { Color::BLACK };
         if debug_config.show_colliders {
             let polygon = transform.apply_to(shape).to_polygon();
             let points = polygon.points();
             if points.len() > 1 {
-                for i in 0..points.len() {
+                // Open edge chains don't draw the closing edge
+                let edge_count = if shape.is_closed() { points.len() } else { points.len() - 1 };
+                for i in 0..edge_count {
                     let current = points[i].pos();
                     let next = points[(i + 1) % points.len()].pos();
-                    gizmos.line_2d(util::qvec2vec(current), util::qvec2vec(next), Color::BLACK);
+                    gizmos.line_2d(util::qvec2vec(current), util::qvec2vec(next), collider_color);
                 }
             }
         }
@@ -1,24 +1,334 @@
 //! Components for the save/load functionality
 //!
-//! This module defines the components used for the save/load functionality.
+//! This module defines the save file formats and the serializable shape, project and snapshot records, the
+//! events requesting file operations, and the components tracking them while they run.
 
-// Currently no specific components are needed for save/load functionality
-// All functionality is handled through events and systems
-
-use crate::shapes::components::{QBboxData, QCircleData, QLineData, QPointData, QPolygonData};
+use crate::camera::resources::CameraBookmarks;
+use crate::coordinate::resources::CoordinateSettings;
+use crate::qphysics::components::{QCollisionFlag, QCollisionShape, QMotion, QObject, QPhysicsBody, QTransform};
+use crate::qphysics::resources::QPhysicsConfig;
+use crate::shapes::components::{
+    AnyQShape, EditorShape, QBboxData, QCircleData, QLineData, QPointData, QPolygonData, ShapeLayer,
+};
+use crate::shapes::resources::ShapesSettings;
 use bevy::prelude::*;
+use bevy::tasks::Task;
+use qmath::vec2::QVec2;
 use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Header written at the start of binary save files
+pub const BINARY_MAGIC: &[u8] = b"QEDB";
+
+/// Byte following [`BINARY_MAGIC`] in versioned binary files, before the format version as a little-endian
+/// `u32`. Binary scene files of format v1 start their bare shape list right after the magic instead, and a
+/// list only starts with a zero byte when it is empty and the file ends there.
+pub const BINARY_VERSION_MARKER: u8 = 0;
+
+/// File format of a save file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SaveFormat {
+    #[default]
+    Json,
+    /// Rusty Object Notation, Bevy's usual asset format
+    Ron,
+    /// Compact postcard encoding behind [`BINARY_MAGIC`] and the format version, for large scenes
+    Binary,
+}
+
+impl SaveFormat {
+    /// Pick the format from the file extension, falling back to JSON
+    pub fn from_path(file_path: &str) -> Self {
+        match Path::new(file_path).extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("ron") => SaveFormat::Ron,
+            Some(ext) if ext.eq_ignore_ascii_case("bin") => SaveFormat::Binary,
+            _ => SaveFormat::Json,
+        }
+    }
+
+    /// Detect the format of a file's contents, recognizing binary files by their magic bytes and
+    /// text files by extension
+    pub fn detect(file_path: &str, bytes: &[u8]) -> Self {
+        if bytes.starts_with(BINARY_MAGIC) {
+            SaveFormat::Binary
+        } else {
+            Self::from_path(file_path)
+        }
+    }
+
+    /// File extension written for this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SaveFormat::Json => "json",
+            SaveFormat::Ron => "ron",
+            SaveFormat::Binary => "bin",
+        }
+    }
+}
+
+/// Which shapes a save request writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SaveScope {
+    /// Only the selected shapes
+    #[default]
+    Selection,
+    /// Every shape in one layer
+    Layer(ShapeLayer),
+    /// Every shape in all layers
+    EntireScene,
+}
+
+impl SaveScope {
+    /// Check whether a shape falls into this scope. Generated visualizations are never saved, they are rebuilt
+    /// from the shapes they visualize.
+    pub fn contains(&self, shape: &EditorShape) -> bool {
+        if shape.layer == ShapeLayer::Generated {
+            return false;
+        }
+        match self {
+            SaveScope::Selection => shape.selected,
+            SaveScope::Layer(layer) => shape.layer == *layer,
+            SaveScope::EntireScene => true,
+        }
+    }
+}
 
 /// Events to trigger save operations
 #[derive(Message, Clone)]
 pub struct SaveSelectedShapesEvent {
     pub file_path: String,
+    pub scope: SaveScope,
+}
+
+/// How loaded shapes are combined with the shapes already in the scene
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LoadMode {
+    /// Despawn the existing MainScene shapes, and shapes in any other layer the file contains, before loading
+    #[default]
+    Replace,
+    /// Add the loaded shapes next to the existing ones
+    Merge,
+    /// Add the loaded shapes moved by a world offset, so loading a file twice doesn't stack identical copies
+    MergeWithOffset(Vec2),
 }
 
 /// Events to trigger load operations
 #[derive(Message, Clone)]
 pub struct LoadShapesFromFileEvent {
     pub file_path: String,
+    pub mode: LoadMode,
+}
+
+/// Events to trigger exporting the MainScene shapes to a DXF drawing
+#[derive(Message, Clone)]
+pub struct ExportDxfEvent {
+    pub file_path: String,
+}
+
+/// Events to trigger exporting the MainScene shapes as bevy_rapier2d collider descriptions, written as JSON
+/// or RON by the file extension
+#[derive(Message, Clone)]
+pub struct ExportRapierEvent {
+    pub file_path: String,
+}
+
+/// Events to trigger exporting a PNG image of the scene, including grid and shapes
+#[derive(Message, Clone)]
+pub struct ExportPngEvent {
+    pub file_path: String,
+    /// World rectangle to export, or `None` for the current viewport
+    pub region: Option<Rect>,
+    /// Image size in pixels, or `None` to use the window size
+    pub resolution: Option<UVec2>,
+}
+
+/// Events to trigger a screenshot of the viewport at [`UiState::screenshot_scale`] times the window
+/// resolution
+///
+/// [`UiState::screenshot_scale`]: crate::ui::resources::UiState::screenshot_scale
+#[derive(Message, Clone, Copy)]
+pub struct TakeScreenshotEvent;
+
+/// Offscreen camera rendering a PNG export. It is despawned once the image is written.
+#[derive(Component, Debug, Clone)]
+pub struct ImageExportCamera {
+    pub file_path: String,
+    /// Image the camera renders into
+    pub image: Handle<Image>,
+    /// Frames rendered so far, the screenshot is taken once the target holds a full frame
+    pub frames_rendered: u32,
+}
+
+/// Events to trigger saving the whole working context to a project file
+#[derive(Message, Clone)]
+pub struct SaveProjectEvent {
+    pub file_path: String,
+}
+
+/// Events to trigger reopening a project file, replacing the scene and settings
+#[derive(Message, Clone)]
+pub struct LoadProjectEvent {
+    pub file_path: String,
+}
+
+/// Events to trigger exporting a collision pair as a qgeometry test asserting its collision result and
+/// separation vector
+#[derive(Message, Clone)]
+pub struct ExportCollisionFixtureEvent {
+    pub file_path: String,
+}
+
+/// Events to trigger saving the selected shapes as a prefab. A prefab is a scene file with the shapes
+/// stored relative to their collective centroid.
+#[derive(Message, Clone)]
+pub struct SavePrefabEvent {
+    pub file_path: String,
+}
+
+/// Events to trigger inserting a prefab with its centroid at `position`
+#[derive(Message, Clone)]
+pub struct InsertPrefabEvent {
+    pub file_path: String,
+    pub position: QVec2,
+    /// Layer the inserted shapes are put on
+    pub layer: ShapeLayer,
+}
+
+/// Events to trigger exporting shapes as Rust source built from qgeometry constructors
+#[derive(Message, Clone)]
+pub struct ExportRustEvent {
+    pub file_path: String,
+    pub scope: SaveScope,
+}
+
+/// What a CSV import creates from its rows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CsvImportTarget {
+    /// One point shape per row
+    #[default]
+    Points,
+    /// A single closed polygon through all rows
+    Polygon,
+    /// Line shapes connecting consecutive rows
+    Polyline,
+}
+
+/// Column mapping and scaling used when importing CSV/TSV data
+#[derive(Debug, Clone)]
+pub struct CsvImportOptions {
+    /// Zero-based column holding x values
+    pub x_column: usize,
+    /// Zero-based column holding y values
+    pub y_column: usize,
+    /// Whether the first non-empty row is a header
+    pub has_header: bool,
+    /// Factor applied to both coordinates
+    pub scale: f64,
+    pub target: CsvImportTarget,
+    /// Layer the imported shapes are created in
+    pub layer: ShapeLayer,
+}
+
+impl Default for CsvImportOptions {
+    fn default() -> Self {
+        Self {
+            x_column: 0,
+            y_column: 1,
+            has_header: false,
+            scale: 1.0,
+            target: CsvImportTarget::default(),
+            layer: ShapeLayer::MainScene,
+        }
+    }
+}
+
+/// Events to trigger importing points from a CSV or TSV file
+#[derive(Message, Clone)]
+pub struct ImportCsvEvent {
+    pub file_path: String,
+    pub options: CsvImportOptions,
+}
+
+/// Events to trigger importing the basic shapes of an SVG drawing
+#[derive(Message, Clone)]
+pub struct ImportSvgEvent {
+    pub file_path: String,
+    /// Editor units per SVG user unit
+    pub scale: f64,
+    /// Layer the imported shapes are created in
+    pub layer: ShapeLayer,
+}
+
+/// Events to trigger saving a snapshot of the whole physics world
+#[derive(Message, Clone)]
+pub struct SavePhysicsSnapshotEvent {
+    pub file_path: String,
+}
+
+/// Events to trigger restoring the physics world from a snapshot
+#[derive(Message, Clone)]
+pub struct LoadPhysicsSnapshotEvent {
+    pub file_path: String,
+}
+
+/// Events to trigger saving the determinism recording together with the world it started from
+#[derive(Message, Clone)]
+pub struct SaveRecordingEvent {
+    pub file_path: String,
+}
+
+/// Events to trigger loading a determinism recording, restoring its world and replaying it to verify the
+/// recorded state hashes
+#[derive(Message, Clone)]
+pub struct VerifyRecordingEvent {
+    pub file_path: String,
+}
+
+/// Result of a save, load or export request, reported to the user as a notification
+#[derive(Message, Clone, Debug)]
+pub enum SaveLoadResultEvent {
+    SaveCompleted {
+        file_path: String,
+    },
+    SaveFailed {
+        file_path: String,
+        error: String,
+    },
+    LoadCompleted {
+        file_path: String,
+        count: usize,
+        /// Degenerate shapes left out of the load
+        skipped: usize,
+    },
+    LoadFailed {
+        file_path: String,
+        error: String,
+    },
+}
+
+/// File write running on the IO task pool, polled until it finishes
+#[derive(Component)]
+pub struct SaveTask {
+    pub file_path: String,
+    pub task: Task<Result<(), String>>,
+}
+
+/// File read and parse running on the IO task pool, applied to the world once it finishes
+#[derive(Component)]
+pub struct LoadTask {
+    pub file_path: String,
+    pub task: Task<Result<LoadedFile, String>>,
+}
+
+/// Contents of a file read by a [`LoadTask`]
+pub enum LoadedFile {
+    Shapes {
+        shapes: Vec<SerializableQShape>,
+        mode: LoadMode,
+    },
+    Project(ProjectFile),
+    Snapshot(QPhysicsSnapshot),
+    Recording(QPhysicsRecording),
 }
 
 /// Serializable representation of a shape
@@ -30,3 +340,262 @@ pub enum SerializableQShapeData {
     Circle(QCircleData),
     Polygon(QPolygonData),
 }
+
+impl<'a> From<&'a SerializableQShapeData> for AnyQShape<'a> {
+    fn from(data: &'a SerializableQShapeData) -> Self {
+        match data {
+            SerializableQShapeData::Point(point) => AnyQShape::Point(&point.data),
+            SerializableQShapeData::Line(line) => AnyQShape::Line(&line.data),
+            SerializableQShapeData::Bbox(bbox) => AnyQShape::Bbox(&bbox.data),
+            SerializableQShapeData::Circle(circle) => AnyQShape::Circle(&circle.data),
+            SerializableQShapeData::Polygon(polygon) => AnyQShape::Polygon(&polygon.data),
+        }
+    }
+}
+
+/// Serializable scene record of a shape, keeping the full editor metadata next to its geometry
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SerializableQShape {
+    pub editor_shape: EditorShape,
+    pub data: SerializableQShapeData,
+    /// Physics components attached to the shape, none for plain drawings
+    #[serde(default)]
+    pub physics: SerializableQPhysics,
+}
+
+/// Physics components of a shape, saved with the scene so physics test scenes survive a restart
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct SerializableQPhysics {
+    pub object: Option<QObject>,
+    pub body: Option<QPhysicsBody>,
+    pub collision_shape: Option<QCollisionShape>,
+    pub collision_flag: Option<QCollisionFlag>,
+    pub transform: Option<QTransform>,
+    pub motion: Option<QMotion>,
+}
+
+/// Shape record of scene format v1 and v2, written before physics components were saved. Binary files
+/// can't skip missing fields, so they are read with this layout.
+#[derive(Deserialize)]
+pub struct LegacySerializableQShape {
+    pub editor_shape: EditorShape,
+    pub data: SerializableQShapeData,
+}
+
+impl From<LegacySerializableQShape> for SerializableQShape {
+    fn from(shape: LegacySerializableQShape) -> Self {
+        Self {
+            editor_shape: shape.editor_shape,
+            data: shape.data,
+            physics: SerializableQPhysics::default(),
+        }
+    }
+}
+
+/// Scene file of format v2 with [`LegacySerializableQShape`] records
+#[derive(Deserialize)]
+pub struct LegacySceneFile {
+    pub version: u32,
+    pub shapes: Vec<LegacySerializableQShape>,
+}
+
+/// Scene file of format v3, written before the checksum was added
+#[derive(Deserialize)]
+pub struct SceneFileV3 {
+    pub version: u32,
+    pub shapes: Vec<SerializableQShape>,
+}
+
+/// Version of the scene file format written by this editor
+///
+/// - 0: bare list of shape geometry
+/// - 1: list of shapes with their editor metadata
+/// - 2: versioned [`SceneFile`]
+/// - 3: shapes keep their physics components
+/// - 4: optional checksum of the shapes
+pub const SCENE_FORMAT_VERSION: u32 = 4;
+
+/// Scene file written by the save commands
+///
+/// Each shape keeps its whole [`EditorShape`], so name, layer, color, line appearance and selection
+/// round-trip with the geometry. Bare lists of shapes from older versions are migrated on load.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SceneFile {
+    /// Format version the file was written with, see [`SCENE_FORMAT_VERSION`]
+    pub version: u32,
+    pub shapes: Vec<SerializableQShape>,
+    /// Checksum of the shapes, see [`SceneFile::compute_checksum`]. Files without one aren't checked.
+    #[serde(default)]
+    pub checksum: Option<u64>,
+}
+
+impl SceneFile {
+    pub fn new(shapes: Vec<SerializableQShape>) -> Self {
+        let mut scene = Self {
+            version: SCENE_FORMAT_VERSION,
+            shapes,
+            checksum: None,
+        };
+        scene.checksum = Some(scene.compute_checksum());
+        scene
+    }
+
+    /// FNV-1a hash of the binary encoding of the shapes, so it is the same whatever format the file is in
+    pub fn compute_checksum(&self) -> u64 {
+        let bytes = postcard::to_stdvec(&self.shapes).unwrap_or_default();
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for byte in bytes {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        hash
+    }
+}
+
+/// Leading part of a [`SceneFile`], read to check the version before parsing the rest
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct SceneFileHeader {
+    pub version: u32,
+}
+
+/// Version of the project file format written by this editor
+///
+/// - v2: the camera stores the view bookmarks
+///
+/// Binary project files are read with the layout of the version they record, except for the settings, which
+/// are stored self-described.
+pub const PROJECT_FORMAT_VERSION: u32 = 2;
+
+/// Stores a value as JSON text in binary files and as itself in the text formats
+///
+/// Settings gain fields with most features. The text formats fill in fields missing from older files with
+/// their serde defaults, but postcard isn't self-describing and can't tell a field is missing, so a binary
+/// file would have to be read with the exact layout it was written with.
+mod self_described {
+    use serde::de::{DeserializeOwned, Error as _};
+    use serde::ser::Error as _;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<T: Serialize, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            value.serialize(serializer)
+        } else {
+            serde_json::to_string(value)
+                .map_err(S::Error::custom)?
+                .serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, T: DeserializeOwned, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+        if deserializer.is_human_readable() {
+            T::deserialize(deserializer)
+        } else {
+            serde_json::from_str(&String::deserialize(deserializer)?).map_err(D::Error::custom)
+        }
+    }
+}
+
+/// Camera placement stored in a project file
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct ProjectCamera {
+    pub position: Vec2,
+    /// Camera transform scale, smaller is zoomed in
+    pub zoom: f32,
+    /// Numbered view bookmarks, empty in files written before v2
+    #[serde(default)]
+    pub bookmarks: CameraBookmarks,
+}
+
+/// Layer state stored in a project file. Layers are the fixed [`ShapeLayer`] set, so this records
+/// which one is active and whether the others are hidden.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct ProjectLayers {
+    pub selected_layer: ShapeLayer,
+    pub only_show_select_layer: bool,
+}
+
+/// UI preferences stored in a project file
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProjectUiPreferences {
+    pub panel_visible: bool,
+    pub enable_snap: bool,
+    pub file_path: String,
+}
+
+/// Project file bundling the scene with the camera, settings and UI state it was edited with
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProjectFile {
+    /// Format version the file was written with, see [`PROJECT_FORMAT_VERSION`]
+    pub version: u32,
+    pub scene: SceneFile,
+    #[serde(with = "self_described")]
+    pub camera: ProjectCamera,
+    #[serde(with = "self_described")]
+    pub coordinate_settings: CoordinateSettings,
+    #[serde(with = "self_described")]
+    pub shapes_settings: ShapesSettings,
+    #[serde(with = "self_described")]
+    pub layers: ProjectLayers,
+    #[serde(with = "self_described")]
+    pub ui: ProjectUiPreferences,
+}
+
+/// Serializable representation of a physics body and the editor shape it belongs to
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SerializableQBody {
+    pub object: QObject,
+    pub body: Option<QPhysicsBody>,
+    pub collision_shape: Option<QCollisionShape>,
+    pub collision_flag: Option<QCollisionFlag>,
+    pub transform: Option<QTransform>,
+    pub motion: Option<QMotion>,
+    pub editor_shape: Option<EditorShape>,
+    pub shape_data: Option<SerializableQShapeData>,
+}
+
+/// Version of the physics snapshot format written by this editor
+///
+/// - 1: list of bodies
+/// - 2: the snapshot records its version
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 2;
+
+/// Serializable snapshot of the entire physics world, stored in entity order
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct QPhysicsSnapshot {
+    /// Format version the file was written with, see [`SNAPSHOT_FORMAT_VERSION`]. Text files written before
+    /// v2 don't have it and read as 0.
+    #[serde(default)]
+    pub version: u32,
+    pub bodies: Vec<SerializableQBody>,
+}
+
+impl QPhysicsSnapshot {
+    pub fn new(bodies: Vec<SerializableQBody>) -> Self {
+        Self {
+            version: SNAPSHOT_FORMAT_VERSION,
+            bodies,
+        }
+    }
+}
+
+/// Physics snapshot of format v1, written before the snapshot recorded its version
+#[derive(Deserialize)]
+pub struct QPhysicsSnapshotV1 {
+    pub bodies: Vec<SerializableQBody>,
+}
+
+/// Version of the determinism recording format written by this editor
+pub const RECORDING_FORMAT_VERSION: u32 = 1;
+
+/// Determinism recording saved to a file, replayed by another run or machine to check that it computes the
+/// same states
+#[derive(Serialize, Deserialize, Clone)]
+pub struct QPhysicsRecording {
+    pub version: u32,
+    /// Bodies when recording started, in the order their states are hashed
+    pub world: QPhysicsSnapshot,
+    /// Physics configuration of each recorded step
+    pub inputs: Vec<QPhysicsConfig>,
+    /// State hash of the bodies after each recorded step
+    pub hashes: Vec<u64>,
+}
@@ -1,22 +1,83 @@
 //! Save/Load plugin implementation
 //!
-//! Registers systems for saving and loading selected shapes from the MainScene layer.
+//! Registers systems for saving and loading shapes.
 
 use super::components::*;
+use super::resources::*;
 use super::systems::*;
 use bevy::prelude::*;
 
-/// `SaveLoadPlugin` handles saving and loading of selected shapes.
+/// `SaveLoadPlugin` handles saving and loading of shapes.
 pub struct SaveLoadPlugin;
 
 impl Plugin for SaveLoadPlugin {
     fn build(&self, app: &mut App) {
-        app
+        app.init_resource::<SceneFileWatcher>()
+            .init_resource::<LaunchOptions>()
+            .insert_resource(RecentFiles::load())
+            .init_resource::<PrefabPlacement>()
+            .init_resource::<PendingSaves>()
             // Register events
             .add_message::<SaveSelectedShapesEvent>()
             .add_message::<LoadShapesFromFileEvent>()
+            .add_message::<SavePhysicsSnapshotEvent>()
+            .add_message::<LoadPhysicsSnapshotEvent>()
+            .add_message::<ExportDxfEvent>()
+            .add_message::<ExportRapierEvent>()
+            .add_message::<ExportRustEvent>()
+            .add_message::<ImportCsvEvent>()
+            .add_message::<ImportSvgEvent>()
+            .add_message::<ExportPngEvent>()
+            .add_message::<TakeScreenshotEvent>()
+            .add_message::<SaveRecordingEvent>()
+            .add_message::<VerifyRecordingEvent>()
+            .add_message::<SaveLoadResultEvent>()
+            .add_message::<SaveProjectEvent>()
+            .add_message::<LoadProjectEvent>()
+            .add_message::<SavePrefabEvent>()
+            .add_message::<InsertPrefabEvent>()
+            .add_message::<ExportCollisionFixtureEvent>()
             // Register systems for save/load functionality
-            .add_systems(Update, handle_save_request)
-            .add_systems(Update, handle_load_request);
+            .add_systems(Startup, open_launch_file)
+            .add_systems(
+                Update,
+                (
+                    handle_load_request,
+                    handle_load_snapshot_request,
+                    handle_load_project_request,
+                    handle_verify_recording_request,
+                ),
+            )
+            .add_systems(Update, handle_import_csv_request)
+            .add_systems(Update, handle_import_svg_request)
+            .add_systems(Update, load_dropped_files)
+            .add_systems(Update, (place_prefab_on_click, handle_insert_prefab_request).chain())
+            // Everything writing files is disabled in read-only sessions
+            .add_systems(
+                Update,
+                (
+                    handle_save_request,
+                    handle_save_snapshot_request,
+                    handle_save_recording_request,
+                    handle_save_project_request,
+                    handle_save_prefab_request,
+                    handle_export_dxf_request,
+                    handle_export_rapier_request,
+                    handle_export_rust_request,
+                    handle_export_collision_fixture_request,
+                    handle_export_png_request,
+                )
+                    .run_if(writes_allowed),
+            )
+            .add_systems(Update, (take_screenshot, capture_image_export))
+            // Apply file operations finished on the IO task pool
+            .add_systems(Update, (poll_save_tasks, poll_load_tasks))
+            .add_systems(
+                Update,
+                (track_watched_file, watch_loaded_file, record_recent_files)
+                    .chain()
+                    .after(poll_save_tasks)
+                    .after(poll_load_tasks),
+            );
     }
 }
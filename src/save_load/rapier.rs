@@ -0,0 +1,148 @@
+//! Rapier2D collider export
+//!
+//! Describes shapes as the colliders bevy_rapier2d builds them from: boxes become `Collider::cuboid`,
+//! circles `Collider::ball`, lines `Collider::segment` and polygons `Collider::convex_polygon`, or
+//! `Collider::convex_decomposition` of their outline when they are concave. Every collider is placed at
+//! its shape's centroid, with the geometry relative to it. Points have no area and are skipped.
+
+use super::components::{SerializableQShape, SerializableQShapeData};
+use super::systems::shape_data_centroid;
+use crate::shapes::hull::convex_hull;
+use qgeometry::shape::QShapeCommon;
+use qmath::prelude::*;
+use qmath::vec2::QVec2;
+use serde::Serialize;
+
+/// Version of the collider export format, recorded in binary exports
+pub const RAPIER_FORMAT_VERSION: u32 = 1;
+
+/// Colliders of a scene, in the order of its shapes
+#[derive(Serialize)]
+pub struct RapierScene {
+    pub colliders: Vec<RapierCollider>,
+}
+
+/// One collider, with the components bevy_rapier2d attaches next to it
+#[derive(Serialize)]
+pub struct RapierCollider {
+    /// Name of the shape, empty for unnamed shapes
+    pub name: String,
+    /// Position of the collider's `Transform`
+    pub translation: [f32; 2],
+    pub shape: RapierShape,
+    /// Whether the collider is a `Sensor`, from the shape's trigger flag
+    pub sensor: bool,
+    /// `Friction` and `Restitution` coefficients, for shapes with a physics body
+    pub friction: Option<f32>,
+    pub restitution: Option<f32>,
+    /// `CollisionGroups` memberships and filters, from the shape's collision layer and mask
+    pub collision_groups: Option<[u32; 2]>,
+}
+
+/// Collider shape, relative to the collider's translation
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RapierShape {
+    Cuboid {
+        half_extents: [f32; 2],
+    },
+    Ball {
+        radius: f32,
+    },
+    Segment {
+        a: [f32; 2],
+        b: [f32; 2],
+    },
+    ConvexPolygon {
+        points: Vec<[f32; 2]>,
+    },
+    /// Closed outline of a concave polygon, split into convex parts by Rapier
+    ConvexDecomposition {
+        vertices: Vec<[f32; 2]>,
+        indices: Vec<[u32; 2]>,
+    },
+}
+
+/// Colliders of `shapes`, and the number of shapes that have no collider
+pub fn rapier_scene(shapes: &[SerializableQShape]) -> (RapierScene, usize) {
+    let colliders: Vec<RapierCollider> = shapes.iter().filter_map(rapier_collider).collect();
+    let skipped = shapes.len() - colliders.len();
+    (RapierScene { colliders }, skipped)
+}
+
+fn rapier_collider(shape: &SerializableQShape) -> Option<RapierCollider> {
+    let center = shape_data_centroid(&shape.data);
+    let local = |pos: QVec2| {
+        let offset = pos.saturating_sub(center);
+        [offset.x.to_num::<f32>(), offset.y.to_num::<f32>()]
+    };
+    let collider_shape = match &shape.data {
+        SerializableQShapeData::Point(_) => return None,
+        SerializableQShapeData::Line(line) => RapierShape::Segment {
+            a: local(line.data.start().pos()),
+            b: local(line.data.end().pos()),
+        },
+        SerializableQShapeData::Bbox(bbox) => {
+            let size = bbox
+                .data
+                .right_top()
+                .pos()
+                .saturating_sub(bbox.data.left_bottom().pos());
+            RapierShape::Cuboid {
+                half_extents: [size.x.to_num::<f32>() / 2.0, size.y.to_num::<f32>() / 2.0],
+            }
+        }
+        SerializableQShapeData::Circle(circle) => RapierShape::Ball {
+            radius: circle.data.radius().to_num(),
+        },
+        SerializableQShapeData::Polygon(polygon) => {
+            let points: Vec<QVec2> = polygon.data.points().iter().map(|point| point.pos()).collect();
+            // Polygons without area have no collider
+            let hull = convex_hull(points.clone())?;
+            if is_convex(&points) {
+                RapierShape::ConvexPolygon {
+                    points: hull.into_iter().map(local).collect(),
+                }
+            } else {
+                let count = points.len() as u32;
+                RapierShape::ConvexDecomposition {
+                    vertices: points.into_iter().map(local).collect(),
+                    indices: (0..count).map(|i| [i, (i + 1) % count]).collect(),
+                }
+            }
+        }
+    };
+
+    let physics = &shape.physics;
+    Some(RapierCollider {
+        name: shape.editor_shape.name.clone(),
+        translation: [center.x.to_num(), center.y.to_num()],
+        shape: collider_shape,
+        sensor: physics.collision_flag.as_ref().is_some_and(|flag| flag.is_trigger),
+        friction: physics.body.as_ref().map(|body| body.friction.to_num()),
+        restitution: physics.body.as_ref().map(|body| body.restitution.to_num()),
+        collision_groups: physics
+            .collision_flag
+            .as_ref()
+            .map(|flag| [flag.collision_layer, flag.collision_mask]),
+    })
+}
+
+/// Whether the outline turns the same way at every vertex, ignoring collinear ones
+fn is_convex(points: &[QVec2]) -> bool {
+    let count = points.len();
+    let mut sign = Q64::ZERO;
+    for i in 0..count {
+        let (a, b, c) = (points[i], points[(i + 1) % count], points[(i + 2) % count]);
+        let (ab, bc) = (b.saturating_sub(a), c.saturating_sub(b));
+        let turn = ab.x * bc.y - ab.y * bc.x;
+        if turn == Q64::ZERO {
+            continue;
+        }
+        if sign != Q64::ZERO && (turn > Q64::ZERO) != (sign > Q64::ZERO) {
+            return false;
+        }
+        sign = turn;
+    }
+    true
+}
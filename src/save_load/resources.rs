@@ -0,0 +1,323 @@
+use crate::shapes::components::ShapeLayer;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Watches the last loaded scene file and reloads it when it changes on disk
+#[derive(Resource, Debug)]
+pub struct SceneFileWatcher {
+    /// Whether the loaded file is watched at all
+    pub enabled: bool,
+    /// Whether changes are reloaded right away instead of waiting for the user to confirm
+    pub auto_reload: bool,
+    /// Path of the last loaded scene file
+    pub file_path: Option<String>,
+    /// Modification time of the file when it was last loaded or saved by the editor
+    pub modified: Option<SystemTime>,
+    /// Whether the file changed on disk and waits for the user to reload it
+    pub change_pending: bool,
+    /// Interval between modification time checks
+    pub poll_timer: Timer,
+}
+
+impl SceneFileWatcher {
+    /// Start watching a file, taking its current contents as already loaded
+    pub fn watch(&mut self, file_path: &str) {
+        self.file_path = Some(file_path.to_string());
+        self.modified = modified_time(file_path);
+        self.change_pending = false;
+    }
+}
+
+impl Default for SceneFileWatcher {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            auto_reload: false,
+            file_path: None,
+            modified: None,
+            change_pending: false,
+            poll_timer: Timer::from_seconds(1.0, TimerMode::Repeating),
+        }
+    }
+}
+
+/// Modification time of a file, `None` if it can't be read
+pub fn modified_time(file_path: &str) -> Option<SystemTime> {
+    std::fs::metadata(file_path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Options the editor was launched with, from the command line or the environment
+///
+/// `qeditor [FILE] [--layer <LAYER>] [--readonly] [--list-recent]`, with `QEDITOR_FILE` used when no
+/// file is given, `qeditor FILE --headless [--steps <N>] [--collisions] [--output <FILE>]` for a batch run, or
+/// `qeditor RECORDING --headless --verify [--output <FILE>]` to replay a determinism recording.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct LaunchOptions {
+    /// Scene or project file opened at startup
+    pub file_path: Option<String>,
+    /// Layer selected at startup, the other layers are hidden
+    pub layer: Option<ShapeLayer>,
+    /// Whether saving and exporting are disabled
+    pub readonly: bool,
+    /// Print the recently used paths and exit instead of starting the editor, for shell completion
+    pub list_recent: bool,
+    /// Run the batch mode on the file without opening a window, see [`crate::batch`]
+    pub headless: bool,
+    /// Number of physics steps simulated by the batch run
+    pub steps: u32,
+    /// Whether the batch run reports the colliding shape pairs
+    pub collisions: bool,
+    /// Whether the batch run replays the file as a determinism recording and checks its state hashes
+    pub verify: bool,
+    /// File the batch report is written to, standard output if unset
+    pub output_path: Option<String>,
+}
+
+impl LaunchOptions {
+    /// Environment variable naming the file to open when none is given on the command line
+    pub const FILE_ENV_VAR: &'static str = "QEDITOR_FILE";
+
+    /// Read the options from the process arguments and environment
+    pub fn from_env() -> Result<Self, String> {
+        Self::parse(std::env::args().skip(1), std::env::var(Self::FILE_ENV_VAR).ok())
+    }
+
+    /// Parse the arguments following the program name
+    pub fn parse(args: impl IntoIterator<Item = String>, env_file: Option<String>) -> Result<Self, String> {
+        let mut options = LaunchOptions::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if arg.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+                _ => (arg.clone(), None),
+            };
+            match flag.as_str() {
+                "--readonly" => options.readonly = true,
+                "--list-recent" => options.list_recent = true,
+                "--headless" => options.headless = true,
+                "--collisions" => options.collisions = true,
+                "--verify" => options.verify = true,
+                "--steps" => {
+                    let value = inline_value
+                        .or_else(|| args.next())
+                        .ok_or("--steps needs a step count")?;
+                    options.steps = value.parse().map_err(|_| format!("invalid step count '{}'", value))?;
+                }
+                "--output" => {
+                    let path = inline_value
+                        .or_else(|| args.next())
+                        .ok_or("--output needs a file path")?;
+                    options.output_path = Some(path);
+                }
+                "--layer" => {
+                    let name = inline_value
+                        .or_else(|| args.next())
+                        .ok_or("--layer needs a layer name")?;
+                    let layer = ShapeLayer::from_name(&name).ok_or_else(|| {
+                        format!(
+                            "unknown layer '{}', expected MainScene, AuxiliaryLine or Generated",
+                            name
+                        )
+                    })?;
+                    options.layer = Some(layer);
+                }
+                _ if flag.starts_with("--") => return Err(format!("unknown option '{}'", flag)),
+                _ if options.file_path.is_none() => options.file_path = Some(arg),
+                _ => return Err(format!("unexpected argument '{}', only one file can be opened", arg)),
+            }
+        }
+        if options.file_path.is_none() {
+            options.file_path = env_file.filter(|path| !path.is_empty());
+        }
+        let batch_flags = options.steps > 0 || options.collisions || options.verify || options.output_path.is_some();
+        if batch_flags && !options.headless {
+            return Err("--steps, --collisions, --verify and --output need --headless".to_string());
+        }
+        if options.verify && (options.steps > 0 || options.collisions) {
+            return Err(
+                "--verify replays the recorded steps, it can't be combined with --steps or --collisions".to_string(),
+            );
+        }
+        if options.headless && options.file_path.is_none() {
+            return Err("--headless needs a scene or project file".to_string());
+        }
+        Ok(options)
+    }
+
+    /// Whether the file to open is a project file rather than a scene file
+    pub fn opens_project(&self) -> bool {
+        self.file_path.as_deref().is_some_and(is_project_path)
+    }
+}
+
+/// Whether a path names a project file, such as `level.project.json`, rather than a scene file
+pub fn is_project_path(path: &str) -> bool {
+    Path::new(path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .is_some_and(|stem| stem.ends_with(".project"))
+}
+
+/// Run condition allowing systems that write files unless the editor was launched read-only
+pub fn writes_allowed(options: Res<LaunchOptions>) -> bool {
+    !options.readonly
+}
+
+/// Recently saved and loaded paths, most recent first, persisted in the user config directory
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecentFiles {
+    pub paths: Vec<String>,
+}
+
+impl RecentFiles {
+    /// Maximum number of remembered paths
+    pub const MAX_ENTRIES: usize = 10;
+
+    /// Read the list from the config directory, empty if it doesn't exist or can't be read
+    pub fn load() -> Self {
+        Self::file_path()
+            .and_then(|path| std::fs::read(path).ok())
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write the list to the config directory
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = Self::file_path().ok_or("no user config directory")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Move a path to the front of the list, dropping the oldest entries beyond the cap
+    pub fn push(&mut self, file_path: &str) {
+        let path = std::fs::canonicalize(file_path)
+            .map(|path| path.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| file_path.to_string());
+        self.paths.retain(|existing| *existing != path);
+        self.paths.insert(0, path);
+        self.paths.truncate(Self::MAX_ENTRIES);
+    }
+
+    /// Location of the list, `<config dir>/qeditor/recent_files.json`
+    pub fn file_path() -> Option<PathBuf> {
+        Some(user_config_dir()?.join("qeditor").join("recent_files.json"))
+    }
+}
+
+/// Per-user configuration directory of the platform
+fn user_config_dir() -> Option<PathBuf> {
+    let env_dir = |name: &str| std::env::var_os(name).filter(|dir| !dir.is_empty()).map(PathBuf::from);
+    if cfg!(target_os = "windows") {
+        env_dir("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        env_dir("HOME").map(|home| home.join("Library").join("Application Support"))
+    } else {
+        env_dir("XDG_CONFIG_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".config")))
+    }
+}
+
+/// Prefab waiting to be placed by the next click in the viewport
+#[derive(Resource, Debug, Default)]
+pub struct PrefabPlacement {
+    pub file_path: Option<String>,
+}
+
+/// Write of a save file, run on the IO task pool with the path to write to
+pub type SaveFn = Box<dyn FnOnce(&str) -> Result<(), Box<dyn std::error::Error>> + Send + Sync>;
+
+/// Saves waiting to start, keyed by file path. A file is written by one save at a time, and a newer save of
+/// a file replaces the one still waiting for it.
+#[derive(Resource, Default)]
+pub struct PendingSaves(pub HashMap<String, SaveFn>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str], env_file: Option<&str>) -> Result<LaunchOptions, String> {
+        LaunchOptions::parse(args.iter().map(|arg| arg.to_string()), env_file.map(String::from))
+    }
+
+    #[test]
+    fn parses_file_and_editor_flags() {
+        let options = parse(&["scene.json", "--layer", "AuxiliaryLine", "--readonly"], None).unwrap();
+        assert_eq!(options.file_path.as_deref(), Some("scene.json"));
+        assert_eq!(options.layer, Some(ShapeLayer::AuxiliaryLine));
+        assert!(options.readonly);
+        assert!(!options.headless);
+    }
+
+    #[test]
+    fn parses_inline_values() {
+        let options = parse(
+            &["--headless", "--steps=30", "--output=report.txt", "level.project.json"],
+            None,
+        )
+        .unwrap();
+        assert_eq!(options.steps, 30);
+        assert_eq!(options.output_path.as_deref(), Some("report.txt"));
+        assert!(options.opens_project());
+    }
+
+    #[test]
+    fn falls_back_to_the_environment_file() {
+        let options = parse(&[], Some("env.json")).unwrap();
+        assert_eq!(options.file_path.as_deref(), Some("env.json"));
+        let options = parse(&["arg.json"], Some("env.json")).unwrap();
+        assert_eq!(options.file_path.as_deref(), Some("arg.json"));
+        assert_eq!(parse(&[], Some("")).unwrap().file_path, None);
+    }
+
+    #[test]
+    fn rejects_invalid_arguments() {
+        assert!(parse(&["--verbose"], None).unwrap_err().contains("unknown option"));
+        assert!(
+            parse(&["a.json", "b.json"], None)
+                .unwrap_err()
+                .contains("only one file")
+        );
+        assert!(
+            parse(&["--layer", "Missing"], None)
+                .unwrap_err()
+                .contains("unknown layer")
+        );
+        assert!(
+            parse(&["--headless", "a.json", "--steps"], None)
+                .unwrap_err()
+                .contains("needs a step count")
+        );
+        assert!(
+            parse(&["--headless", "a.json", "--steps", "ten"], None)
+                .unwrap_err()
+                .contains("invalid step count")
+        );
+        assert!(
+            parse(&["a.json", "--collisions"], None)
+                .unwrap_err()
+                .contains("need --headless")
+        );
+        assert!(
+            parse(&["--headless"], None)
+                .unwrap_err()
+                .contains("needs a scene or project file")
+        );
+        assert!(
+            parse(&["a.json", "--verify"], None)
+                .unwrap_err()
+                .contains("need --headless")
+        );
+        assert!(
+            parse(&["--headless", "a.json", "--verify", "--steps", "5"], None)
+                .unwrap_err()
+                .contains("can't be combined")
+        );
+    }
+}
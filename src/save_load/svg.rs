@@ -0,0 +1,323 @@
+//! SVG import
+//!
+//! Reads the basic SVG elements into editor shapes: `line`, `rect`, `circle`, `polygon`, `polyline`, and
+//! `path` with straight segments only. Transforms and styles are ignored, and paths with curves or arcs
+//! are skipped. SVG's y axis points down, so y is flipped to keep the drawing upright.
+
+use super::components::SerializableQShapeData;
+use crate::shapes::components::{QBboxData, QCircleData, QLineData, QPointData, QPolygonData};
+use crate::util::checked_q64;
+use qgeometry::shape::{QBbox, QCircle, QLine, QPoint, QPolygon};
+use qmath::prelude::*;
+use qmath::vec2::QVec2;
+
+/// Shapes read from an SVG file
+pub struct SvgImport {
+    pub shapes: Vec<SerializableQShapeData>,
+    /// Number of shape elements that couldn't be converted
+    pub skipped: usize,
+}
+
+/// Value of the attribute `name` in the attribute text of a tag
+fn attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = attributes;
+    while let Some(equals) = rest.find('=') {
+        let key = rest[..equals].trim();
+        let value = rest[equals + 1..].trim_start();
+        let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let end = value[1..].find(quote)? + 1;
+        // The key is the last word before the equals sign
+        if key.rsplit(char::is_whitespace).next() == Some(name) {
+            return Some(&value[1..end]);
+        }
+        rest = &value[end + 1..];
+    }
+    None
+}
+
+/// Numbers of an attribute such as `points` or a path's `d`, separated by commas or whitespace
+fn numbers(text: &str) -> Result<Vec<f64>, String> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|number| !number.is_empty())
+        .map(|number| number.parse().map_err(|_| format!("'{}' is not a number", number)))
+        .collect()
+}
+
+/// Converts SVG user units to editor coordinates
+struct Converter {
+    scale: f64,
+}
+
+impl Converter {
+    fn length(&self, value: f64) -> Result<Q64, String> {
+        checked_q64(value * self.scale)
+    }
+
+    fn point(&self, x: f64, y: f64) -> Result<QVec2, String> {
+        Ok(QVec2::new(self.length(x)?, self.length(-y)?))
+    }
+
+    fn number(&self, attributes: &str, name: &str) -> Result<f64, String> {
+        let value = attribute(attributes, name).unwrap_or("0");
+        // Plain user units, with an optional px suffix
+        value
+            .trim()
+            .trim_end_matches("px")
+            .parse()
+            .map_err(|_| format!("{}: '{}' is not a number", name, value))
+    }
+
+    fn points(&self, text: &str) -> Result<Vec<QVec2>, String> {
+        numbers(text)?
+            .chunks_exact(2)
+            .map(|pair| self.point(pair[0], pair[1]))
+            .collect()
+    }
+}
+
+/// Lines through consecutive points
+fn polyline(points: &[QVec2]) -> Vec<SerializableQShapeData> {
+    points
+        .windows(2)
+        .map(|pair| {
+            SerializableQShapeData::Line(QLineData {
+                data: QLine::new(QPoint::new(pair[0]), QPoint::new(pair[1])),
+            })
+        })
+        .collect()
+}
+
+fn polygon(points: &[QVec2]) -> SerializableQShapeData {
+    SerializableQShapeData::Polygon(QPolygonData {
+        data: QPolygon::new(points.iter().map(|pos| QPoint::new(*pos)).collect()),
+    })
+}
+
+/// Shapes of a path's subpaths, `None` if it uses curves or arcs. Closed subpaths become polygons and
+/// open ones lines.
+fn path_shapes(d: &str, converter: &Converter) -> Result<Option<Vec<SerializableQShapeData>>, String> {
+    // Split the commands from their arguments, "M10-5" is "M 10 -5"
+    let mut tokens: Vec<String> = Vec::new();
+    for c in d.chars() {
+        if c.is_ascii_alphabetic() && c != 'e' && c != 'E' {
+            tokens.push(c.to_string());
+            tokens.push(String::new());
+        } else if c == '-'
+            && tokens
+                .last()
+                .is_some_and(|token| !token.is_empty() && !token.ends_with(['e', 'E']))
+        {
+            tokens.push(c.to_string());
+        } else if c == ',' || c.is_whitespace() {
+            tokens.push(String::new());
+        } else if let Some(token) = tokens.last_mut() {
+            token.push(c);
+        } else {
+            tokens.push(c.to_string());
+        }
+    }
+    tokens.retain(|token| !token.is_empty());
+
+    let mut shapes = Vec::new();
+    let mut subpath: Vec<(f64, f64)> = Vec::new();
+    let mut finish = |subpath: &mut Vec<(f64, f64)>, closed: bool| -> Result<(), String> {
+        let points: Vec<QVec2> = subpath
+            .drain(..)
+            .map(|(x, y)| converter.point(x, y))
+            .collect::<Result<_, _>>()?;
+        if closed && points.len() >= 3 {
+            shapes.push(polygon(&points));
+        } else {
+            shapes.extend(polyline(&points));
+        }
+        Ok(())
+    };
+
+    let (mut command, mut current, mut start) = ('M', (0.0, 0.0), (0.0, 0.0));
+    let mut index = 0;
+    while index < tokens.len() {
+        if let Some(letter) = tokens[index].chars().next().filter(|c| c.is_ascii_alphabetic()) {
+            command = letter;
+            index += 1;
+            if command.eq_ignore_ascii_case(&'z') {
+                finish(&mut subpath, true)?;
+                // A path continuing without a move starts from the closed subpath's start
+                current = start;
+                subpath.push(current);
+                continue;
+            }
+        }
+        let relative = command.is_ascii_lowercase();
+        let argument_count = match command.to_ascii_uppercase() {
+            'M' | 'L' => 2,
+            'H' | 'V' => 1,
+            _ => return Ok(None),
+        };
+        let Some(arguments) = tokens.get(index..index + argument_count) else {
+            return Err(format!("path command '{}' is missing arguments", command));
+        };
+        let arguments = numbers(&arguments.join(" "))?;
+        index += argument_count;
+
+        let offset = if relative { current } else { (0.0, 0.0) };
+        current = match command.to_ascii_uppercase() {
+            'H' => (arguments[0] + offset.0, current.1),
+            'V' => (current.0, arguments[0] + offset.1),
+            _ => (arguments[0] + offset.0, arguments[1] + offset.1),
+        };
+        if command.eq_ignore_ascii_case(&'m') {
+            finish(&mut subpath, false)?;
+            start = current;
+            // Further coordinate pairs after a move are lines
+            command = if relative { 'l' } else { 'L' };
+        }
+        subpath.push(current);
+    }
+    finish(&mut subpath, false)?;
+    Ok(Some(shapes))
+}
+
+/// Elements describing shapes, the ones not converted are counted as skipped
+const SHAPE_ELEMENTS: [&str; 7] = ["line", "rect", "circle", "ellipse", "polygon", "polyline", "path"];
+
+/// Shapes of a shape element, `None` if it can't be converted
+fn element_shapes(
+    name: &str, attributes: &str, converter: &Converter,
+) -> Result<Option<Vec<SerializableQShapeData>>, String> {
+    let number = |name: &str| converter.number(attributes, name);
+    match name {
+        "line" => {
+            let start = converter.point(number("x1")?, number("y1")?)?;
+            let end = converter.point(number("x2")?, number("y2")?)?;
+            Ok(Some(polyline(&[start, end])))
+        }
+        "rect" => {
+            let (x, y, width, height) = (number("x")?, number("y")?, number("width")?, number("height")?);
+            // The top left corner in SVG is the bottom left once y is flipped
+            let min = converter.point(x, y + height)?;
+            let max = converter.point(x + width, y)?;
+            Ok(Some(vec![SerializableQShapeData::Bbox(QBboxData {
+                data: QBbox::new_from_parts(min, max),
+            })]))
+        }
+        "circle" => {
+            let center = converter.point(number("cx")?, number("cy")?)?;
+            let radius = converter.length(number("r")?)?;
+            Ok(Some(vec![SerializableQShapeData::Circle(QCircleData {
+                data: QCircle::new(QPoint::new(center), radius),
+            })]))
+        }
+        "polygon" | "polyline" => {
+            let points = converter.points(attribute(attributes, "points").unwrap_or(""))?;
+            Ok(match (name, points.len()) {
+                ("polygon", 3..) => Some(vec![polygon(&points)]),
+                (_, 2..) => Some(polyline(&points)),
+                (_, 1) => Some(vec![SerializableQShapeData::Point(QPointData {
+                    data: QPoint::new(points[0]),
+                })]),
+                _ => None,
+            })
+        }
+        "path" => path_shapes(attribute(attributes, "d").unwrap_or(""), converter),
+        _ => Ok(None),
+    }
+}
+
+/// Parse the shape elements of an SVG document, scaling its user units by `scale`
+pub fn parse_svg_shapes(text: &str, scale: f64) -> Result<SvgImport, String> {
+    let converter = Converter { scale };
+    let mut import = SvgImport {
+        shapes: Vec::new(),
+        skipped: 0,
+    };
+
+    let mut rest = text;
+    while let Some(open) = rest.find('<') {
+        rest = &rest[open + 1..];
+        // Comments can contain anything, including tags
+        if let Some(comment) = rest.strip_prefix("!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let end = rest.find('>').ok_or("unterminated tag")?;
+        let tag = rest[..end].trim_end_matches('/');
+        rest = &rest[end + 1..];
+        let (name, attributes) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+        if !SHAPE_ELEMENTS.contains(&name) {
+            continue;
+        }
+
+        match element_shapes(name, attributes, &converter).map_err(|e| format!("<{}>: {}", name, e))? {
+            Some(shapes) if !shapes.is_empty() => import.shapes.extend(shapes),
+            _ => import.skipped += 1,
+        }
+    }
+    Ok(import)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::qvec;
+
+    /// Vertices of each polygon and line, in order
+    fn vertices(shapes: &[SerializableQShapeData]) -> Vec<Vec<QVec2>> {
+        shapes
+            .iter()
+            .map(|shape| match shape {
+                SerializableQShapeData::Polygon(polygon) => {
+                    polygon.data.points().iter().map(|point| point.pos()).collect()
+                }
+                SerializableQShapeData::Line(line) => vec![line.data.start().pos(), line.data.end().pos()],
+                _ => panic!("expected a polygon or a line"),
+            })
+            .collect()
+    }
+
+    fn path(d: &str) -> SvgImport {
+        parse_svg_shapes(&format!("<svg><path d=\"{}\"/></svg>", d), 1.0).unwrap()
+    }
+
+    #[test]
+    fn tokenizes_commands_glued_to_numbers() {
+        let import = path("M10-5L20,-5l0-10z");
+        assert_eq!(
+            vertices(&import.shapes),
+            vec![vec![qvec(10.0, 5.0), qvec(20.0, 5.0), qvec(20.0, 15.0)]]
+        );
+    }
+
+    #[test]
+    fn tokenizes_exponents_and_implicit_lines() {
+        let import = path("M 1e1,0 2E1 0 h-1.5e1 v5");
+        assert_eq!(
+            vertices(&import.shapes),
+            vec![
+                vec![qvec(10.0, 0.0), qvec(20.0, 0.0)],
+                vec![qvec(20.0, 0.0), qvec(5.0, 0.0)],
+                vec![qvec(5.0, 0.0), qvec(5.0, -5.0)],
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_paths_with_curves() {
+        let import = path("M0 0 C 1 1 2 2 3 3");
+        assert!(import.shapes.is_empty());
+        assert_eq!(import.skipped, 1);
+    }
+
+    #[test]
+    fn rejects_paths_missing_arguments() {
+        let error = parse_svg_shapes("<path d=\"M0 0 L 5\"/>", 1.0).err().unwrap();
+        assert!(error.contains("missing arguments"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_coordinates_outside_the_q64_range() {
+        let error = parse_svg_shapes("<circle cx=\"2147483648\" cy=\"0\" r=\"1\"/>", 1.0)
+            .err()
+            .unwrap();
+        assert!(error.contains("out of the Q64 range"), "{}", error);
+    }
+}
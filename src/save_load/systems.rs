@@ -1,102 +1,608 @@
 //! Save/Load systems
 //!
-//! This module defines the systems used for saving and loading selected shapes
-//! from the MainScene layer to and from files.
+//! This module defines the systems used for saving and loading shapes to and from files.
 
-use super::components::{LoadShapesFromFileEvent, SaveSelectedShapesEvent, SerializableQShapeData};
-use crate::shapes::components::{EditorShape, QBboxData, QCircleData, QLineData, QPointData, QPolygonData, ShapeLayer};
+use super::components::{
+    BINARY_MAGIC, BINARY_VERSION_MARKER, CsvImportOptions, CsvImportTarget, ExportCollisionFixtureEvent,
+    ExportDxfEvent, ExportPngEvent, ExportRapierEvent, ExportRustEvent, ImageExportCamera, ImportCsvEvent,
+    ImportSvgEvent, InsertPrefabEvent, LegacySceneFile, LegacySerializableQShape, LoadMode, LoadPhysicsSnapshotEvent,
+    LoadProjectEvent, LoadShapesFromFileEvent, LoadTask, LoadedFile, PROJECT_FORMAT_VERSION, ProjectCamera,
+    ProjectFile, ProjectLayers, ProjectUiPreferences, QPhysicsRecording, QPhysicsSnapshot, QPhysicsSnapshotV1,
+    RECORDING_FORMAT_VERSION, SCENE_FORMAT_VERSION, SNAPSHOT_FORMAT_VERSION, SaveFormat, SaveLoadResultEvent,
+    SavePhysicsSnapshotEvent, SavePrefabEvent, SaveProjectEvent, SaveRecordingEvent, SaveSelectedShapesEvent, SaveTask,
+    SceneFile, SceneFileHeader, SceneFileV3, SerializableQBody, SerializableQPhysics, SerializableQShape,
+    SerializableQShapeData, TakeScreenshotEvent, VerifyRecordingEvent,
+};
+use super::rapier::{RAPIER_FORMAT_VERSION, rapier_scene};
+use super::resources::{
+    LaunchOptions, PendingSaves, PrefabPlacement, RecentFiles, SaveFn, SceneFileWatcher, is_project_path, modified_time,
+};
+use super::svg::parse_svg_shapes;
+use crate::camera::components::CameraMovement;
+use crate::camera::resources::{CameraBookmarks, CameraSettings};
+use crate::coordinate::resources::{CoordinateSettings, SnapTarget};
+use crate::history::resources::EditHistory;
+use crate::qphysics::components::{QCollisionFlag, QCollisionShape, QMotion, QObject, QPhysicsBody, QTransform};
+use crate::qphysics::messages::QRecorderCommand;
+use crate::qphysics::resources::{QBodySnapshot, QPhysicsRecorder, QRecorderMode, QSimulationControl};
+use crate::shapes::components::{
+    AnyQShape, EditorShape, QBboxData, QCircleData, QLineData, QPointData, QPolygonData, ShapeLayer, shape_type_name,
+};
+use crate::shapes::resources::ShapesSettings;
+use crate::ui::resources::{Notifications, UiState};
+use crate::util::checked_q64;
+use bevy::asset::RenderAssetUsages;
+use bevy::camera::{RenderTarget, ScalingMode};
 use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages};
+use bevy::render::view::screenshot::{Screenshot, ScreenshotCaptured, save_to_disk};
+use bevy::tasks::{IoTaskPool, block_on, futures_lite::future};
+use bevy::window::{FileDragAndDrop, PrimaryWindow};
+use bevy_egui::EguiContexts;
 use qgeometry;
+use qgeometry::shape::{QBbox, QCircle, QLine, QPoint, QPolygon, QShapeCommon};
+use qmath::dir::QDir;
+use qmath::prelude::*;
+use qmath::vec2::QVec2;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::HashSet;
 use std::fs::File;
-use std::io::{BufReader, BufWriter};
+use std::io::{BufWriter, Write};
+use std::path::Path;
 
-/// System to handle save requests for selected shapes in MainScene layer
+/// Components of a shape read when saving it: editor metadata, geometry and physics
+pub type ShapeSaveData = (
+    &'static EditorShape,
+    Option<&'static QPointData>,
+    Option<&'static QLineData>,
+    Option<&'static QBboxData>,
+    Option<&'static QCircleData>,
+    Option<&'static QPolygonData>,
+    ShapePhysicsData<'static>,
+);
+
+/// Physics components of a shape, see [`ShapeSaveData`]
+type ShapePhysicsData<'a> = (
+    Option<&'a QObject>,
+    Option<&'a QPhysicsBody>,
+    Option<&'a QCollisionShape>,
+    Option<&'a QCollisionFlag>,
+    Option<&'a QTransform>,
+    Option<&'a QMotion>,
+);
+
+/// System to handle save requests for the shapes in the requested scope
 pub fn handle_save_request(
-    mut events: MessageReader<SaveSelectedShapesEvent>,
-    shapes_query: Query<(
-        &EditorShape,
-        Option<&QPointData>,
-        Option<&QLineData>,
-        Option<&QBboxData>,
-        Option<&QCircleData>,
-        Option<&QPolygonData>,
-    )>,
+    mut commands: Commands, mut events: MessageReader<SaveSelectedShapesEvent>, shapes_query: Query<ShapeSaveData>,
 ) {
     for event in events.read() {
-        // Save to file
-        if let Err(e) = save_shapes_to_file(&event.file_path, shapes_query) {
-            eprintln!("Failed to save shapes to file: {}", e);
-        }
+        // Collect on the main thread, serialize and write on the IO task pool
+        let scene = SceneFile::new(collect_shapes(&shapes_query, |shape| event.scope.contains(shape)));
+        spawn_save_task(&mut commands, &event.file_path, move |file_path| {
+            write_to_file(file_path, SCENE_FORMAT_VERSION, &scene)
+        });
     }
 }
 
-/// Save shapes to a JSON file
-fn save_shapes_to_file(
-    file_path: &str,
-    shapes_query: Query<(
-        &EditorShape,
-        Option<&QPointData>,
-        Option<&QLineData>,
-        Option<&QBboxData>,
-        Option<&QCircleData>,
-        Option<&QPolygonData>,
-    )>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let mut data_list = Vec::new();
-    for (shape, point_opt, line_opt, bbox_opt, circle_opt, polygon_opt) in shapes_query.iter() {
-        if shape.layer != ShapeLayer::MainScene {
-            continue; // Skip shapes not in MainScene layer
-        }
+/// Queue a file write, started on the IO task pool by [`poll_save_tasks`] once no other save is writing
+/// the same file, and reported once it finishes
+fn spawn_save_task(
+    commands: &mut Commands, file_path: &str,
+    save: impl FnOnce(&str) -> Result<(), Box<dyn std::error::Error>> + Send + Sync + 'static,
+) {
+    let file_path = file_path.to_string();
+    commands.queue(move |world: &mut World| {
+        world.resource_mut::<PendingSaves>().0.insert(file_path, Box::new(save));
+    });
+}
 
-        if let Some(data) = point_opt {
-            data_list.push(SerializableQShapeData::Point(data.clone()));
-        }
-        if let Some(data) = line_opt {
-            data_list.push(SerializableQShapeData::Line(data.clone()));
-        }
-        if let Some(data) = bbox_opt {
-            data_list.push(SerializableQShapeData::Bbox(data.clone()));
-        }
-        if let Some(data) = circle_opt {
-            data_list.push(SerializableQShapeData::Circle(data.clone()));
+/// Write a file through a temporary file next to it, renamed over it once complete, so a failed or
+/// interrupted save leaves the previous file intact. The temporary file keeps the extension, which
+/// picks the format.
+fn write_atomically(file_path: &str, save: SaveFn) -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new(file_path);
+    let file_name = path.file_name().ok_or("save path has no file name")?;
+    let temp_path = path.with_file_name(format!(".{}", file_name.to_string_lossy()));
+    let temp_file_path = temp_path.to_string_lossy().into_owned();
+    let result = save(&temp_file_path).and_then(|()| Ok(std::fs::rename(&temp_path, path)?));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Run a file read on the IO task pool, applied to the world by [`poll_load_tasks`] once it finishes
+fn spawn_load_task(
+    commands: &mut Commands, file_path: &str,
+    load: impl FnOnce(&str) -> Result<LoadedFile, Box<dyn std::error::Error>> + Send + 'static,
+) {
+    let path = file_path.to_string();
+    let task = IoTaskPool::get().spawn(async move { load(&path).map_err(|e| e.to_string()) });
+    commands.spawn(LoadTask {
+        file_path: file_path.to_string(),
+        task,
+    });
+}
+
+/// System to report finished save tasks and start the saves waiting for their file
+pub fn poll_save_tasks(
+    mut commands: Commands, mut tasks: Query<(Entity, &mut SaveTask)>, mut results: MessageWriter<SaveLoadResultEvent>,
+    mut pending_saves: ResMut<PendingSaves>,
+) {
+    let mut writing = HashSet::new();
+    for (entity, mut save_task) in tasks.iter_mut() {
+        let Some(result) = block_on(future::poll_once(&mut save_task.task)) else {
+            writing.insert(save_task.file_path.clone());
+            continue;
+        };
+        let file_path = save_task.file_path.clone();
+        results.write(match result {
+            Ok(()) => SaveLoadResultEvent::SaveCompleted { file_path },
+            Err(error) => SaveLoadResultEvent::SaveFailed { file_path, error },
+        });
+        commands.entity(entity).despawn();
+    }
+
+    let ready: Vec<String> = pending_saves
+        .0
+        .keys()
+        .filter(|file_path| !writing.contains(*file_path))
+        .cloned()
+        .collect();
+    for file_path in ready {
+        let Some(save) = pending_saves.0.remove(&file_path) else {
+            continue;
+        };
+        let path = file_path.clone();
+        let task = IoTaskPool::get().spawn(async move { write_atomically(&path, save).map_err(|e| e.to_string()) });
+        commands.spawn(SaveTask { file_path, task });
+    }
+}
+
+/// System to apply finished load tasks to the world and report them
+pub fn poll_load_tasks(
+    mut commands: Commands, mut tasks: Query<(Entity, &mut LoadTask)>, mut results: MessageWriter<SaveLoadResultEvent>,
+    existing_shapes: Query<(Entity, &EditorShape)>, shapes_query: Query<ShapeSaveData>,
+    objects: Query<Entity, With<QObject>>, mut camera_query: Query<&mut Transform, With<CameraMovement>>,
+    mut coordinate_settings: ResMut<CoordinateSettings>, mut shapes_settings: ResMut<ShapesSettings>,
+    mut ui_state: ResMut<UiState>, mut camera_bookmarks: ResMut<CameraBookmarks>, mut history: ResMut<EditHistory>,
+) {
+    for (entity, mut load_task) in tasks.iter_mut() {
+        let Some(result) = block_on(future::poll_once(&mut load_task.task)) else {
+            continue;
+        };
+        commands.entity(entity).despawn();
+        let file_path = load_task.file_path.clone();
+        let loaded = match result {
+            Ok(loaded) => loaded,
+            Err(error) => {
+                results.write(SaveLoadResultEvent::LoadFailed { file_path, error });
+                continue;
+            }
+        };
+
+        // Degenerate shapes, e.g. polygons finished after one click by older versions, are left out rather than
+        // failing the whole file
+        let mut skipped = 0;
+        let count = match loaded {
+            LoadedFile::Shapes { mut shapes, mode } => {
+                skipped = remove_degenerate_shapes(&mut shapes);
+                let (removed, spawned) =
+                    apply_loaded_shapes(&mut commands, shapes, mode, &existing_shapes, &shapes_query);
+                let count = spawned.len();
+                match mode {
+                    LoadMode::Merge | LoadMode::MergeWithOffset(_) => {
+                        history.record_created(format!("Insert {}", file_path), spawned)
+                    }
+                    LoadMode::Replace => history.record_replaced(format!("Load {}", file_path), removed, spawned),
+                }
+                count
+            }
+            LoadedFile::Project(mut project) => {
+                skipped = remove_degenerate_shapes(&mut project.scene.shapes);
+                // Only the shapes can be undone, the camera and settings keep the project's
+                let (removed, spawned) = apply_loaded_project(
+                    &mut commands,
+                    project,
+                    &existing_shapes,
+                    &shapes_query,
+                    camera_query.single_mut().ok(),
+                    &mut camera_bookmarks,
+                    &mut coordinate_settings,
+                    &mut shapes_settings,
+                    &mut ui_state,
+                );
+                let count = spawned.len();
+                history.record_replaced(format!("Open {}", file_path), removed, spawned);
+                count
+            }
+            LoadedFile::Snapshot(mut snapshot) => {
+                let before = snapshot.bodies.len();
+                snapshot.bodies.retain(|body| {
+                    let reason = body.shape_data.as_ref().and_then(degenerate_reason);
+                    if let Some(reason) = &reason {
+                        warn!("Skipped body {}: {}", body.object.uuid, reason);
+                    }
+                    reason.is_none()
+                });
+                skipped = before - snapshot.bodies.len();
+                apply_loaded_snapshot(&mut commands, &snapshot, &objects).len()
+            }
+            LoadedFile::Recording(recording) => apply_loaded_recording(&mut commands, recording, &objects),
+        };
+        results.write(SaveLoadResultEvent::LoadCompleted {
+            file_path,
+            count,
+            skipped,
+        });
+    }
+}
+
+/// Write a value to a file in the format picked by the file extension. Binary files record `version`, the
+/// format version of the value, after their magic bytes since they can't be read without knowing the layout.
+fn write_to_file<T: Serialize>(file_path: &str, version: u32, value: &T) -> Result<(), Box<dyn std::error::Error>> {
+    let file = File::create(file_path)?;
+    let mut writer = BufWriter::new(file);
+    match SaveFormat::from_path(file_path) {
+        SaveFormat::Json => serde_json::to_writer_pretty(writer, value)?,
+        SaveFormat::Ron => {
+            let text = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())?;
+            writer.write_all(text.as_bytes())?;
         }
-        if let Some(data) = polygon_opt {
-            data_list.push(SerializableQShapeData::Polygon(data.clone()));
+        SaveFormat::Binary => {
+            writer.write_all(BINARY_MAGIC)?;
+            writer.write_all(&[BINARY_VERSION_MARKER])?;
+            writer.write_all(&version.to_le_bytes())?;
+            writer.write_all(&postcard::to_stdvec(value)?)?;
         }
     }
-    let file = File::create(file_path)?;
-    let writer = BufWriter::new(file);
-    serde_json::to_writer_pretty(writer, &data_list)?;
+    writer.flush()?;
     Ok(())
 }
 
+/// Deserialize the contents of a save file in its detected format. Binary files are decoded with `T`'s layout
+/// whatever version they were written with, see [`split_binary_header`] to pick the layout first.
+fn deserialize_bytes<T: DeserializeOwned>(file_path: &str, bytes: &[u8]) -> Result<T, Box<dyn std::error::Error>> {
+    let value = match SaveFormat::detect(file_path, bytes) {
+        SaveFormat::Json => serde_json::from_slice(bytes).map_err(|e| format!("invalid JSON: {}", e))?,
+        SaveFormat::Ron => ron::de::from_bytes(bytes).map_err(|e| format!("invalid RON: {}", e))?,
+        SaveFormat::Binary => decode_binary(split_binary_header(bytes)?.1)?,
+    };
+    Ok(value)
+}
+
+/// Format version and payload of a binary save file. The version is `None` for files written before binary
+/// files recorded it, which hold scene format v1, see [`BINARY_VERSION_MARKER`].
+fn split_binary_header(bytes: &[u8]) -> Result<(Option<u32>, &[u8]), String> {
+    let rest = bytes
+        .strip_prefix(BINARY_MAGIC)
+        .ok_or("binary save file is missing its header")?;
+    match rest {
+        [BINARY_VERSION_MARKER, a, b, c, d, payload @ ..] => Ok((Some(u32::from_le_bytes([*a, *b, *c, *d])), payload)),
+        _ => Ok((None, rest)),
+    }
+}
+
+/// Decode the postcard payload of a binary save file
+fn decode_binary<T: DeserializeOwned>(payload: &[u8]) -> Result<T, String> {
+    postcard::from_bytes(payload).map_err(|e| format!("invalid or truncated binary data: {}", e))
+}
+
+/// Collect the serializable records of every shape accepted by `filter`
+pub fn collect_shapes(
+    shapes_query: &Query<ShapeSaveData>, filter: impl Fn(&EditorShape) -> bool,
+) -> Vec<SerializableQShape> {
+    shapes_query
+        .iter()
+        .filter(|(shape, ..)| filter(shape))
+        .filter_map(
+            |(shape, point_opt, line_opt, bbox_opt, circle_opt, polygon_opt, physics)| {
+                let data = get_serializable_shape_data(point_opt, line_opt, bbox_opt, circle_opt, polygon_opt)?;
+                Some(serializable_shape(shape, data, physics))
+            },
+        )
+        .collect()
+}
+
+/// Serializable record of one shape entity, `None` if it isn't a shape
+pub fn shape_record(shapes_query: &Query<ShapeSaveData>, entity: Entity) -> Option<SerializableQShape> {
+    let (shape, point_opt, line_opt, bbox_opt, circle_opt, polygon_opt, physics) = shapes_query.get(entity).ok()?;
+    let data = get_serializable_shape_data(point_opt, line_opt, bbox_opt, circle_opt, polygon_opt)?;
+    Some(serializable_shape(shape, data, physics))
+}
+
+/// Build a shape record from its editor metadata, geometry and physics components
+fn serializable_shape(
+    shape: &EditorShape, data: SerializableQShapeData,
+    (object, body, collision_shape, collision_flag, transform, motion): ShapePhysicsData,
+) -> SerializableQShape {
+    SerializableQShape {
+        editor_shape: shape.clone(),
+        data,
+        physics: SerializableQPhysics {
+            object: object.copied(),
+            body: body.cloned(),
+            collision_shape: collision_shape.cloned(),
+            collision_flag: collision_flag.cloned(),
+            transform: transform.copied(),
+            motion: motion.cloned(),
+        },
+    }
+}
+
 /// System to handle load requests for shapes from a file
 pub fn handle_load_request(mut commands: Commands, mut events: MessageReader<LoadShapesFromFileEvent>) {
     for event in events.read() {
-        match load_shapes_from_file(&event.file_path) {
-            Ok(serialized_shapes) => {
-                // Spawn loaded shapes as entities
-                for serialized_shape in serialized_shapes {
-                    spawn_shape_from_serialized(&mut commands, &serialized_shape);
+        let mode = event.mode;
+        spawn_load_task(&mut commands, &event.file_path, move |file_path| {
+            Ok(LoadedFile::Shapes {
+                shapes: load_shapes_from_file(file_path)?,
+                mode,
+            })
+        });
+    }
+}
+
+/// Spawn loaded shapes, despawning the ones they replace first. Returns the records of the despawned shapes
+/// and the spawned entities.
+fn apply_loaded_shapes(
+    commands: &mut Commands, shapes: Vec<SerializableQShape>, mode: LoadMode,
+    existing_shapes: &Query<(Entity, &EditorShape)>, shapes_query: &Query<ShapeSaveData>,
+) -> (Vec<(Entity, SerializableQShape)>, Vec<Entity>) {
+    let mut removed = Vec::new();
+    if mode == LoadMode::Replace {
+        let mut replaced_layers = vec![ShapeLayer::MainScene];
+        replaced_layers.extend(shapes.iter().map(|shape| shape.editor_shape.layer));
+        for (entity, shape) in existing_shapes.iter() {
+            if replaced_layers.contains(&shape.layer) {
+                removed.extend(shape_record(shapes_query, entity).map(|record| (entity, record)));
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+
+    let offset = match mode {
+        LoadMode::MergeWithOffset(offset) => QVec2::new(Q64::from_num(offset.x), Q64::from_num(offset.y)),
+        LoadMode::Replace | LoadMode::Merge => QVec2::ZERO,
+    };
+    let spawned = shapes
+        .into_iter()
+        .map(|serialized_shape| {
+            let serialized_shape = if offset == QVec2::ZERO {
+                serialized_shape
+            } else {
+                translate_shape(serialized_shape, offset)
+            };
+            spawn_scene_shape(commands, serialized_shape)
+        })
+        .collect();
+    (removed, spawned)
+}
+
+/// Load shapes from a save file, upgrading files written by older versions of the editor
+pub fn load_shapes_from_file(file_path: &str) -> Result<Vec<SerializableQShape>, Box<dyn std::error::Error>> {
+    let bytes = std::fs::read(file_path)?;
+    let scene = parse_scene(file_path, &bytes)?;
+    validate_scene(&scene)?;
+    Ok(scene.shapes)
+}
+
+/// Parse the contents of a scene file of any version and format, upgrading it to the current format
+fn parse_scene(file_path: &str, bytes: &[u8]) -> Result<SceneFile, Box<dyn std::error::Error>> {
+    let scene = match SaveFormat::detect(file_path, bytes) {
+        SaveFormat::Json => migrate_json_scene(deserialize_bytes(file_path, bytes)?, bytes)?,
+        // Binary files can't skip missing fields, so each version is read with the layout it was written with
+        SaveFormat::Binary => {
+            let (version, payload) = split_binary_header(bytes)?;
+            let version = version.unwrap_or(1);
+            check_scene_version(version)?;
+            match version {
+                0 | 1 => {
+                    let legacy: Vec<LegacySerializableQShape> = decode_binary(payload)?;
+                    SceneFile::new(legacy.into_iter().map(SerializableQShape::from).collect())
                 }
+                2 => {
+                    let legacy: LegacySceneFile = decode_binary(payload)?;
+                    SceneFile::new(legacy.shapes.into_iter().map(SerializableQShape::from).collect())
+                }
+                3 => SceneFile::new(decode_binary::<SceneFileV3>(payload)?.shapes),
+                _ => decode_binary::<SceneFile>(payload)?,
+            }
+        }
+        // RON files were only ever written as lists of shapes (v1) or versioned scene files, and fill in the
+        // fields added since from their defaults
+        SaveFormat::Ron => match deserialize_bytes::<SceneFileHeader>(file_path, bytes) {
+            Ok(header) => {
+                check_scene_version(header.version)?;
+                let mut scene: SceneFile = deserialize_bytes(file_path, bytes)?;
+                scene.version = SCENE_FORMAT_VERSION;
+                scene
+            }
+            Err(_) => {
+                let legacy: Vec<LegacySerializableQShape> = deserialize_bytes(file_path, bytes)?;
+                SceneFile::new(legacy.into_iter().map(SerializableQShape::from).collect())
             }
-            Err(e) => {
-                eprintln!("Failed to load shapes from file: {}", e);
+        },
+    };
+    Ok(scene)
+}
+
+/// Largest number of shapes or polygon vertices accepted from a file, guarding against corrupt counts
+const MAX_LOADED_ITEMS: usize = 1_000_000;
+
+/// Check a loaded scene against its checksum and reject data that isn't a valid scene at all. Degenerate
+/// shapes are structurally fine and are left to [`remove_degenerate_shapes`].
+fn validate_scene(scene: &SceneFile) -> Result<(), Box<dyn std::error::Error>> {
+    if scene.shapes.len() > MAX_LOADED_ITEMS {
+        return Err(format!(
+            "shapes: {} shapes exceed the limit of {}",
+            scene.shapes.len(),
+            MAX_LOADED_ITEMS
+        )
+        .into());
+    }
+    if let Some(checksum) = scene.checksum {
+        if checksum != scene.compute_checksum() {
+            return Err(
+                "checksum: the shapes don't match the checksum, the file is truncated or corrupt. \
+                Remove the checksum field to load a hand-edited file."
+                    .into(),
+            );
+        }
+    }
+    for (index, shape) in scene.shapes.iter().enumerate() {
+        let color = shape.editor_shape.color.to_linear();
+        if ![color.red, color.green, color.blue, color.alpha]
+            .iter()
+            .all(|c| c.is_finite())
+        {
+            return Err(format!("shapes[{}].editor_shape.color: components must be finite", index).into());
+        }
+        if let SerializableQShapeData::Polygon(polygon) = &shape.data {
+            let vertices = polygon.data.points().len();
+            if vertices > MAX_LOADED_ITEMS {
+                return Err(format!(
+                    "shapes[{}].data: polygon has {} vertices, exceeding the limit of {}",
+                    index, vertices, MAX_LOADED_ITEMS
+                )
+                .into());
             }
         }
     }
+    Ok(())
 }
 
-/// Load shapes from a JSON file
-fn load_shapes_from_file(file_path: &str) -> Result<Vec<SerializableQShapeData>, Box<dyn std::error::Error>> {
-    let file = File::open(file_path)?;
-    let reader = BufReader::new(file);
-    let shapes: Vec<SerializableQShapeData> = serde_json::from_reader(reader)?;
-    Ok(shapes)
+/// Remove the shapes the editor can't work with from loaded shapes, warning about each. Returns how many
+/// were removed.
+pub fn remove_degenerate_shapes(shapes: &mut Vec<SerializableQShape>) -> usize {
+    let before = shapes.len();
+    shapes.retain(|shape| {
+        let reason = degenerate_reason(&shape.data).or_else(|| match &shape.physics.collision_shape {
+            Some(QCollisionShape::EdgeChain(chain)) if chain.points.len() < 2 => Some(format!(
+                "edge chain has {} points, needs at least 2",
+                chain.points.len()
+            )),
+            _ => None,
+        });
+        if let Some(reason) = &reason {
+            let name = if shape.editor_shape.name.is_empty() {
+                shape_type_name(shape.editor_shape.shape_type)
+            } else {
+                shape.editor_shape.name.as_str()
+            };
+            warn!("Skipped {}: {}", name, reason);
+        }
+        reason.is_none()
+    });
+    before - shapes.len()
 }
 
-/// Spawn a shape entity from serialized data
-fn spawn_shape_from_serialized(commands: &mut Commands, serialized: &SerializableQShapeData) {
+/// Why a shape's geometry is degenerate: zero length lines, empty bboxes and circles, and polygons without
+/// an area. `None` for usable shapes.
+pub fn degenerate_reason(data: &SerializableQShapeData) -> Option<String> {
+    match data {
+        SerializableQShapeData::Point(_) => None,
+        SerializableQShapeData::Line(line) => {
+            (line.data.start() == line.data.end()).then(|| "line has zero length".to_string())
+        }
+        SerializableQShapeData::Bbox(bbox) => {
+            let min = bbox.data.left_bottom().pos();
+            let max = bbox.data.right_top().pos();
+            (min.x >= max.x || min.y >= max.y).then(|| {
+                format!(
+                    "bbox is degenerate, left bottom ({}, {}) must be below and left of right top ({}, {})",
+                    min.x.to_num::<f64>(),
+                    min.y.to_num::<f64>(),
+                    max.x.to_num::<f64>(),
+                    max.y.to_num::<f64>()
+                )
+            })
+        }
+        SerializableQShapeData::Circle(circle) => (circle.data.radius() <= Q64::ZERO)
+            .then(|| format!("circle radius {} is not positive", circle.data.radius().to_num::<f64>())),
+        SerializableQShapeData::Polygon(polygon) => {
+            let mut vertices: Vec<QVec2> = polygon.data.points().iter().map(|point| point.pos()).collect();
+            vertices.dedup();
+            if vertices.len() > 1 && vertices.first() == vertices.last() {
+                vertices.pop();
+            }
+            (vertices.len() < 3).then(|| format!("polygon has {} distinct vertices, needs at least 3", vertices.len()))
+        }
+    }
+}
+
+/// Refuse scene files written by a newer, incompatible version of the editor
+fn check_scene_version(version: u32) -> Result<(), Box<dyn std::error::Error>> {
+    if version > SCENE_FORMAT_VERSION {
+        return Err(format!(
+            "scene file format v{} is newer than the supported v{}, update the editor to open it",
+            version, SCENE_FORMAT_VERSION
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Detect the version of a JSON scene file and upgrade it step by step to the current format. Files that
+/// need no structural upgrade are parsed from `bytes` so errors point at their line and column.
+fn migrate_json_scene(mut value: serde_json::Value, bytes: &[u8]) -> Result<SceneFile, Box<dyn std::error::Error>> {
+    let version = match &value {
+        serde_json::Value::Object(map) => {
+            let version = map
+                .get("version")
+                .and_then(|version| version.as_u64())
+                .ok_or("scene file has no version")?;
+            u32::try_from(version)?
+        }
+        serde_json::Value::Array(items) => {
+            if items.first().is_some_and(|item| item.get("editor_shape").is_some()) {
+                1
+            } else {
+                0
+            }
+        }
+        _ => return Err("unrecognized scene file".into()),
+    };
+    check_scene_version(version)?;
+
+    if version < 1 {
+        value = migrate_scene_v0_to_v1(value)?;
+    }
+    if version < 2 {
+        value = migrate_scene_v1_to_v2(value);
+    }
+    // v2 -> v3 only added the physics components and v3 -> v4 the checksum, which default to none when missing
+    let mut scene: SceneFile = if version >= 2 {
+        serde_json::from_slice(bytes).map_err(|e| format!("invalid scene file: {}", e))?
+    } else {
+        serde_json::from_value(value).map_err(|e| format!("invalid scene file: {}", e))?
+    };
+    scene.version = SCENE_FORMAT_VERSION;
+    Ok(scene)
+}
+
+/// v0 -> v1: give bare geometry default metadata in MainScene
+fn migrate_scene_v0_to_v1(value: serde_json::Value) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let legacy_shapes: Vec<SerializableQShapeData> = serde_json::from_value(value)?;
+    let shapes: Vec<SerializableQShape> = legacy_shapes
+        .into_iter()
+        .map(|data| SerializableQShape {
+            editor_shape: default_editor_shape(&data),
+            data,
+            physics: SerializableQPhysics::default(),
+        })
+        .collect();
+    Ok(serde_json::to_value(shapes)?)
+}
+
+/// v1 -> v2: wrap the list of shapes in a versioned scene file
+fn migrate_scene_v1_to_v2(value: serde_json::Value) -> serde_json::Value {
+    serde_json::json!({ "version": 2, "shapes": value })
+}
+
+/// Get the editor shape a shape without saved metadata is spawned with
+pub fn default_editor_shape(serialized: &SerializableQShapeData) -> EditorShape {
     let shape_type = match serialized {
         SerializableQShapeData::Point(_data) => qgeometry::shape::QShapeType::QPoint,
         SerializableQShapeData::Line(_data) => qgeometry::shape::QShapeType::QLine,
@@ -104,15 +610,17 @@ fn spawn_shape_from_serialized(commands: &mut Commands, serialized: &Serializabl
         SerializableQShapeData::Circle(_data) => qgeometry::shape::QShapeType::QCircle,
         SerializableQShapeData::Polygon(_data) => qgeometry::shape::QShapeType::QPolygon,
     };
+    EditorShape {
+        shape_type,
+        ..default()
+    }
+}
 
-    let mut entity_commands = commands.spawn((
-        EditorShape {
-            shape_type,
-            ..default()
-        },
-        Transform::default(),
-        Visibility::default(),
-    ));
+/// Spawn a shape entity from serialized data
+fn spawn_shape_from_serialized(
+    commands: &mut Commands, serialized: &SerializableQShapeData, editor_shape: EditorShape,
+) -> Entity {
+    let mut entity_commands = commands.spawn((editor_shape, Transform::default(), Visibility::default()));
 
     match serialized {
         SerializableQShapeData::Point(data) => {
@@ -131,4 +639,1700 @@ fn spawn_shape_from_serialized(commands: &mut Commands, serialized: &Serializabl
             entity_commands.insert(data.clone());
         }
     }
+    entity_commands.id()
+}
+
+/// Spawn a shape entity from its scene record, restoring its physics components
+pub fn spawn_scene_shape(commands: &mut Commands, shape: SerializableQShape) -> Entity {
+    let entity = spawn_shape_from_serialized(commands, &shape.data, shape.editor_shape);
+    insert_physics(&mut commands.entity(entity), shape.physics);
+    entity
+}
+
+/// Replace the components of an existing shape entity with a scene record, keeping the entity
+pub fn insert_scene_shape(entity_commands: &mut EntityCommands, shape: SerializableQShape) {
+    entity_commands.remove::<(QPointData, QLineData, QBboxData, QCircleData, QPolygonData)>();
+    entity_commands.remove::<(
+        QObject,
+        QPhysicsBody,
+        QCollisionShape,
+        QCollisionFlag,
+        QTransform,
+        QMotion,
+    )>();
+    entity_commands.insert(shape.editor_shape);
+    match shape.data {
+        SerializableQShapeData::Point(data) => entity_commands.insert(data),
+        SerializableQShapeData::Line(data) => entity_commands.insert(data),
+        SerializableQShapeData::Bbox(data) => entity_commands.insert(data),
+        SerializableQShapeData::Circle(data) => entity_commands.insert(data),
+        SerializableQShapeData::Polygon(data) => entity_commands.insert(data),
+    };
+    insert_physics(entity_commands, shape.physics);
+}
+
+/// Insert the physics components present in a shape record
+fn insert_physics(entity_commands: &mut EntityCommands, physics: SerializableQPhysics) {
+    if let Some(object) = physics.object {
+        entity_commands.insert(object);
+    }
+    if let Some(body) = physics.body {
+        entity_commands.insert(body);
+    }
+    if let Some(collision_shape) = physics.collision_shape {
+        entity_commands.insert(collision_shape);
+    }
+    if let Some(collision_flag) = physics.collision_flag {
+        entity_commands.insert(collision_flag);
+    }
+    if let Some(transform) = physics.transform {
+        entity_commands.insert(transform);
+    }
+    if let Some(motion) = physics.motion {
+        entity_commands.insert(motion);
+    }
+}
+
+/// Collision shape matching a shape's geometry, as the editor attaches it to drawn shapes
+pub fn collision_shape_for(data: &SerializableQShapeData) -> QCollisionShape {
+    match data {
+        SerializableQShapeData::Point(point) => QCollisionShape::Point(point.data.clone()),
+        SerializableQShapeData::Line(line) => QCollisionShape::Line(line.data.clone()),
+        SerializableQShapeData::Bbox(bbox) => QCollisionShape::Rectangle(bbox.data.clone()),
+        SerializableQShapeData::Circle(circle) => QCollisionShape::Circle(circle.data.clone()),
+        SerializableQShapeData::Polygon(polygon) => QCollisionShape::Polygon(polygon.data.clone()),
+    }
+}
+
+/// Get the serializable shape data of an entity, if it has any
+fn get_serializable_shape_data(
+    point_opt: Option<&QPointData>, line_opt: Option<&QLineData>, bbox_opt: Option<&QBboxData>,
+    circle_opt: Option<&QCircleData>, polygon_opt: Option<&QPolygonData>,
+) -> Option<SerializableQShapeData> {
+    if let Some(data) = point_opt {
+        Some(SerializableQShapeData::Point(data.clone()))
+    } else if let Some(data) = line_opt {
+        Some(SerializableQShapeData::Line(data.clone()))
+    } else if let Some(data) = bbox_opt {
+        Some(SerializableQShapeData::Bbox(data.clone()))
+    } else if let Some(data) = circle_opt {
+        Some(SerializableQShapeData::Circle(data.clone()))
+    } else if let Some(data) = polygon_opt {
+        Some(SerializableQShapeData::Polygon(data.clone()))
+    } else {
+        None
+    }
+}
+
+/// System to handle prefab save requests, storing the selected shapes relative to their collective centroid
+pub fn handle_save_prefab_request(
+    mut commands: Commands, mut events: MessageReader<SavePrefabEvent>, shapes_query: Query<ShapeSaveData>,
+) {
+    for event in events.read() {
+        let shapes = collect_shapes(&shapes_query, |shape| shape.selected);
+        if shapes.is_empty() {
+            commands.write_message(SaveLoadResultEvent::SaveFailed {
+                file_path: event.file_path.clone(),
+                error: "no shapes selected".to_string(),
+            });
+            continue;
+        }
+
+        let sum = shapes.iter().fold(QVec2::ZERO, |sum, shape| {
+            sum.saturating_add(shape_data_centroid(&shape.data))
+        });
+        let count = Q64::from_num(shapes.len());
+        let offset = QVec2::new(-sum.x.saturating_div(count), -sum.y.saturating_div(count));
+        let shapes = shapes
+            .into_iter()
+            .map(|shape| {
+                let mut shape = translate_shape(shape, offset);
+                shape.editor_shape.selected = false;
+                shape
+            })
+            .collect();
+        let prefab = SceneFile::new(shapes);
+        spawn_save_task(&mut commands, &event.file_path, move |file_path| {
+            write_to_file(file_path, SCENE_FORMAT_VERSION, &prefab)
+        });
+    }
+}
+
+/// System to handle prefab insert requests, merging the prefab's shapes into the scene
+pub fn handle_insert_prefab_request(mut commands: Commands, mut events: MessageReader<InsertPrefabEvent>) {
+    for event in events.read() {
+        let position = event.position;
+        let layer = event.layer;
+        spawn_load_task(&mut commands, &event.file_path, move |file_path| {
+            let shapes = load_shapes_from_file(file_path)?
+                .into_iter()
+                .map(|shape| {
+                    let mut shape = translate_shape(shape, position);
+                    shape.editor_shape.layer = layer;
+                    shape
+                })
+                .collect();
+            Ok(LoadedFile::Shapes {
+                shapes,
+                mode: LoadMode::Merge,
+            })
+        });
+    }
+}
+
+/// System to insert the armed prefab where the viewport is clicked, snapped like drawn shapes
+pub fn place_prefab_on_click(
+    mut commands: Commands, mut placement: ResMut<PrefabPlacement>, mouse_button_input: Res<ButtonInput<MouseButton>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>, ui_state: Res<UiState>, camera_settings: Res<CameraSettings>,
+    snap_target: Res<SnapTarget>, mut egui_contexts: EguiContexts,
+) {
+    if placement.file_path.is_none() {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        placement.file_path = None;
+        return;
+    }
+    // Space+left drag pans the view instead of placing
+    if !mouse_button_input.just_pressed(MouseButton::Left) || camera_settings.space_pan_active(&keyboard_input) {
+        return;
+    }
+    if egui_contexts.ctx_mut().is_ok_and(|ctx| ctx.wants_pointer_input()) {
+        return;
+    }
+    let Some(position) = snap_target.qposition() else {
+        return;
+    };
+    if let Some(file_path) = placement.file_path.take() {
+        commands.write_message(InsertPrefabEvent {
+            file_path,
+            position,
+            layer: ui_state.selected_layer,
+        });
+    }
+}
+
+/// Centroid of a shape's geometry
+pub fn shape_data_centroid(data: &SerializableQShapeData) -> QVec2 {
+    match data {
+        SerializableQShapeData::Point(point) => point.data.get_centroid().pos(),
+        SerializableQShapeData::Line(line) => line.data.get_centroid().pos(),
+        SerializableQShapeData::Bbox(bbox) => bbox.data.get_centroid().pos(),
+        SerializableQShapeData::Circle(circle) => circle.data.get_centroid().pos(),
+        SerializableQShapeData::Polygon(polygon) => polygon.data.get_centroid().pos(),
+    }
+}
+
+/// Move a shape by `offset`, rebuilding its collision shape from the moved geometry
+pub fn translate_shape(shape: SerializableQShape, offset: QVec2) -> SerializableQShape {
+    let data = translate_shape_data(&shape.data, offset);
+    let mut physics = shape.physics;
+    if physics.collision_shape.is_some() {
+        physics.collision_shape = Some(collision_shape_for(&data));
+    }
+    SerializableQShape {
+        editor_shape: shape.editor_shape,
+        data,
+        physics,
+    }
+}
+
+/// Move a shape's geometry by `offset`
+fn translate_shape_data(data: &SerializableQShapeData, offset: QVec2) -> SerializableQShapeData {
+    let moved = |pos: QVec2| pos.saturating_add(offset);
+    match data {
+        SerializableQShapeData::Point(point) => SerializableQShapeData::Point(QPointData {
+            data: QPoint::new(moved(point.data.pos())),
+        }),
+        SerializableQShapeData::Line(line) => SerializableQShapeData::Line(QLineData {
+            data: QLine::new(
+                QPoint::new(moved(line.data.start().pos())),
+                QPoint::new(moved(line.data.end().pos())),
+            ),
+        }),
+        SerializableQShapeData::Bbox(bbox) => SerializableQShapeData::Bbox(QBboxData {
+            data: QBbox::new_from_parts(moved(bbox.data.left_bottom().pos()), moved(bbox.data.right_top().pos())),
+        }),
+        SerializableQShapeData::Circle(circle) => SerializableQShapeData::Circle(QCircleData {
+            data: QCircle::new(QPoint::new(moved(circle.data.center().pos())), circle.data.radius()),
+        }),
+        SerializableQShapeData::Polygon(polygon) => SerializableQShapeData::Polygon(QPolygonData {
+            data: QPolygon::new(
+                polygon
+                    .data
+                    .points()
+                    .iter()
+                    .map(|p| QPoint::new(moved(p.pos())))
+                    .collect(),
+            ),
+        }),
+    }
+}
+
+/// Rotate a shape by `rotation` and scale it by `scale` around `pivot`, rebuilding its collision shape.
+/// Rectangles stay rectangles when `axis_aligned` is set, as for rotations by multiples of 90 degrees,
+/// and become polygons otherwise.
+pub fn transform_shape(
+    shape: SerializableQShape, pivot: QVec2, rotation: QDir, scale: Q64, axis_aligned: bool,
+) -> SerializableQShape {
+    let transformed = |pos: QVec2| {
+        let local = pos.saturating_sub(pivot).saturating_mul(QVec2::new(scale, scale));
+        QPoint::new(rotation.rotate_vec(local).saturating_add(pivot))
+    };
+    let mut editor_shape = shape.editor_shape;
+    let data = match &shape.data {
+        SerializableQShapeData::Point(point) => SerializableQShapeData::Point(QPointData {
+            data: transformed(point.data.pos()),
+        }),
+        SerializableQShapeData::Line(line) => SerializableQShapeData::Line(QLineData {
+            data: QLine::new(transformed(line.data.start().pos()), transformed(line.data.end().pos())),
+        }),
+        SerializableQShapeData::Bbox(bbox) => {
+            let min = bbox.data.left_bottom().pos();
+            let max = bbox.data.right_top().pos();
+            if axis_aligned {
+                let (a, b) = (transformed(min).pos(), transformed(max).pos());
+                let (min, max) = (
+                    QVec2::new(a.x.min(b.x), a.y.min(b.y)),
+                    QVec2::new(a.x.max(b.x), a.y.max(b.y)),
+                );
+                SerializableQShapeData::Bbox(QBboxData {
+                    data: QBbox::new_from_parts(min, max),
+                })
+            } else {
+                editor_shape.shape_type = qgeometry::shape::QShapeType::QPolygon;
+                let corners = [min, QVec2::new(max.x, min.y), max, QVec2::new(min.x, max.y)];
+                SerializableQShapeData::Polygon(QPolygonData {
+                    data: QPolygon::new(corners.into_iter().map(transformed).collect()),
+                })
+            }
+        }
+        SerializableQShapeData::Circle(circle) => SerializableQShapeData::Circle(QCircleData {
+            data: QCircle::new(
+                transformed(circle.data.center().pos()),
+                circle.data.radius().saturating_mul(scale),
+            ),
+        }),
+        SerializableQShapeData::Polygon(polygon) => SerializableQShapeData::Polygon(QPolygonData {
+            data: QPolygon::new(polygon.data.points().iter().map(|p| transformed(p.pos())).collect()),
+        }),
+    };
+    let mut physics = shape.physics;
+    if physics.collision_shape.is_some() {
+        physics.collision_shape = Some(collision_shape_for(&data));
+    }
+    SerializableQShape {
+        editor_shape,
+        data,
+        physics,
+    }
+}
+
+/// System to handle project save requests
+pub fn handle_save_project_request(
+    mut commands: Commands, mut events: MessageReader<SaveProjectEvent>, shapes_query: Query<ShapeSaveData>,
+    camera_query: Query<&Transform, With<CameraMovement>>, coordinate_settings: Res<CoordinateSettings>,
+    shapes_settings: Res<ShapesSettings>, ui_state: Res<UiState>, camera_bookmarks: Res<CameraBookmarks>,
+) {
+    for event in events.read() {
+        let camera = camera_query
+            .single()
+            .map(|transform| ProjectCamera {
+                position: transform.translation.truncate(),
+                zoom: transform.scale.x,
+                bookmarks: *camera_bookmarks,
+            })
+            .unwrap_or(ProjectCamera {
+                position: Vec2::ZERO,
+                zoom: 1.0,
+                bookmarks: *camera_bookmarks,
+            });
+        // Generated shapes are visualizations rebuilt by the editor, so they are left out
+        let shapes = collect_shapes(&shapes_query, |shape| shape.layer != ShapeLayer::Generated);
+        let project = ProjectFile {
+            version: PROJECT_FORMAT_VERSION,
+            scene: SceneFile::new(shapes),
+            camera,
+            coordinate_settings: coordinate_settings.clone(),
+            shapes_settings: shapes_settings.clone(),
+            layers: ProjectLayers {
+                selected_layer: ui_state.selected_layer,
+                only_show_select_layer: ui_state.only_show_select_layer,
+            },
+            ui: ProjectUiPreferences {
+                panel_visible: ui_state.panel_visible,
+                enable_snap: ui_state.enable_snap,
+                file_path: ui_state.file_path.clone(),
+            },
+        };
+        spawn_save_task(&mut commands, &event.file_path, move |file_path| {
+            write_to_file(file_path, PROJECT_FORMAT_VERSION, &project)
+        });
+    }
+}
+
+/// System to handle project load requests, replacing every shape and restoring the saved context
+pub fn handle_load_project_request(mut commands: Commands, mut events: MessageReader<LoadProjectEvent>) {
+    for event in events.read() {
+        spawn_load_task(&mut commands, &event.file_path, |file_path| {
+            Ok(LoadedFile::Project(load_project_from_file(file_path)?))
+        });
+    }
+}
+
+/// Replace every shape with the project's and restore the camera, settings and UI state it was saved
+/// with. Returns the records of the despawned shapes and the spawned entities.
+fn apply_loaded_project(
+    commands: &mut Commands, project: ProjectFile, existing_shapes: &Query<(Entity, &EditorShape)>,
+    shapes_query: &Query<ShapeSaveData>, camera_transform: Option<Mut<Transform>>,
+    camera_bookmarks: &mut CameraBookmarks, coordinate_settings: &mut CoordinateSettings,
+    shapes_settings: &mut ShapesSettings, ui_state: &mut UiState,
+) -> (Vec<(Entity, SerializableQShape)>, Vec<Entity>) {
+    // Generated visualizations are left to the systems that rebuild them, so undoing the open doesn't bring
+    // them back as plain shapes
+    let mut removed = Vec::new();
+    for (entity, shape) in existing_shapes.iter() {
+        if shape.layer != ShapeLayer::Generated {
+            removed.extend(shape_record(shapes_query, entity).map(|record| (entity, record)));
+            commands.entity(entity).despawn();
+        }
+    }
+    let spawned = project
+        .scene
+        .shapes
+        .into_iter()
+        .map(|serialized_shape| spawn_scene_shape(commands, serialized_shape))
+        .collect();
+
+    if let Some(mut transform) = camera_transform {
+        transform.translation.x = project.camera.position.x;
+        transform.translation.y = project.camera.position.y;
+        transform.scale = Vec3::new(project.camera.zoom, project.camera.zoom, transform.scale.z);
+    }
+    *camera_bookmarks = project.camera.bookmarks;
+    *coordinate_settings = project.coordinate_settings;
+    *shapes_settings = project.shapes_settings;
+    ui_state.selected_layer = project.layers.selected_layer;
+    ui_state.only_show_select_layer = project.layers.only_show_select_layer;
+    ui_state.panel_visible = project.ui.panel_visible;
+    ui_state.enable_snap = project.ui.enable_snap;
+    ui_state.file_path = project.ui.file_path;
+    (removed, spawned)
+}
+
+/// Load a project file, refusing versions newer than this editor understands
+pub fn load_project_from_file(file_path: &str) -> Result<ProjectFile, Box<dyn std::error::Error>> {
+    let bytes = std::fs::read(file_path)?;
+    let version = if SaveFormat::detect(file_path, &bytes) == SaveFormat::Binary {
+        split_binary_header(&bytes)?
+            .0
+            .ok_or("binary project file has no format version, it was written by an older editor")?
+    } else {
+        deserialize_bytes::<SceneFileHeader>(file_path, &bytes)?.version
+    };
+    if version > PROJECT_FORMAT_VERSION {
+        return Err(format!(
+            "project file format v{} is newer than the supported v{}, update the editor to open it",
+            version, PROJECT_FORMAT_VERSION
+        )
+        .into());
+    }
+    let project: ProjectFile = deserialize_bytes(file_path, &bytes)?;
+    check_scene_version(project.scene.version)?;
+    validate_scene(&project.scene).map_err(|e| format!("scene.{}", e))?;
+    Ok(project)
+}
+
+/// System to handle Rust source export requests
+pub fn handle_export_rust_request(
+    mut commands: Commands, mut events: MessageReader<ExportRustEvent>, shapes_query: Query<ShapeSaveData>,
+) {
+    for event in events.read() {
+        let shapes = collect_shapes(&shapes_query, |shape| event.scope.contains(shape));
+        let source = shapes_to_rust(&shapes);
+        spawn_save_task(&mut commands, &event.file_path, move |file_path| {
+            Ok(std::fs::write(file_path, source)?)
+        });
+    }
+}
+
+/// Emit `let` bindings constructing the shapes with exact Q64 bit patterns, ready to paste into a test
+fn shapes_to_rust(shapes: &[SerializableQShape]) -> String {
+    let mut output = String::new();
+    output.push_str("// Exported from QEditor\n");
+    output.push_str("use qgeometry::shape::{QBbox, QCircle, QLine, QPoint, QPolygon};\n");
+    output.push_str("use qmath::prelude::*;\n");
+    output.push_str("use qmath::vec2::QVec2;\n\n");
+
+    let mut used_names = HashSet::new();
+    for (index, shape) in shapes.iter().enumerate() {
+        let (kind, constructor) = rust_shape_constructor(&shape.data, "");
+
+        // Use the shape name when it makes a valid, unique identifier
+        let name = rust_identifier(&shape.editor_shape.name)
+            .filter(|name| !used_names.contains(name))
+            .unwrap_or_else(|| format!("{}_{}", kind, index));
+        output.push_str(&format!("let {} = {};\n", name, constructor));
+        used_names.insert(name);
+    }
+    output
+}
+
+/// Rust expression rebuilding a Q64 exactly from its bits
+fn rust_q64(value: Q64) -> String {
+    format!("Q64::from_bits({})", value.to_bits())
+}
+
+/// Rust expression constructing a QVec2 from exact components
+fn rust_qvec2(pos: QVec2) -> String {
+    format!("QVec2::new({}, {})", rust_q64(pos.x), rust_q64(pos.y))
+}
+
+/// Rust expression constructing a QPoint at a position
+fn rust_qpoint(pos: QVec2) -> String {
+    format!("QPoint::new({})", rust_qvec2(pos))
+}
+
+/// Rust expression constructing a shape, and the kind of shape used to name its binding. Multi-line
+/// expressions are indented by `indent`.
+fn rust_shape_constructor(data: &SerializableQShapeData, indent: &str) -> (&'static str, String) {
+    match data {
+        SerializableQShapeData::Point(point) => ("point", rust_qpoint(point.data.pos())),
+        SerializableQShapeData::Line(line) => (
+            "line",
+            format!(
+                "QLine::new({}, {})",
+                rust_qpoint(line.data.start().pos()),
+                rust_qpoint(line.data.end().pos())
+            ),
+        ),
+        SerializableQShapeData::Bbox(bbox) => (
+            "bbox",
+            format!(
+                "QBbox::new_from_parts({}, {})",
+                rust_qvec2(bbox.data.left_bottom().pos()),
+                rust_qvec2(bbox.data.right_top().pos())
+            ),
+        ),
+        SerializableQShapeData::Circle(circle) => (
+            "circle",
+            format!(
+                "QCircle::new({}, {})",
+                rust_qpoint(circle.data.center().pos()),
+                rust_q64(circle.data.radius())
+            ),
+        ),
+        SerializableQShapeData::Polygon(polygon) => {
+            let points: Vec<String> = polygon
+                .data
+                .points()
+                .iter()
+                .map(|p| format!("{}    {},\n", indent, rust_qpoint(p.pos())))
+                .collect();
+            (
+                "polygon",
+                format!("QPolygon::new(vec![\n{}{}])", points.concat(), indent),
+            )
+        }
+    }
+}
+
+/// System to handle collision fixture export requests. The fixture covers the two selected shapes, or
+/// the first colliding pair if not exactly two shapes are selected.
+pub fn handle_export_collision_fixture_request(
+    mut commands: Commands, mut events: MessageReader<ExportCollisionFixtureEvent>, shapes_query: Query<ShapeSaveData>,
+) {
+    for event in events.read() {
+        let shapes = collect_shapes(&shapes_query, |shape| shape.layer != ShapeLayer::Generated);
+        let selected: Vec<&SerializableQShape> = shapes.iter().filter(|shape| shape.editor_shape.selected).collect();
+        let pair = if selected.len() == 2 {
+            Some((selected[0], selected[1]))
+        } else {
+            shapes.iter().enumerate().find_map(|(i, a)| {
+                shapes[i + 1..]
+                    .iter()
+                    .find(|b| geometry_collision(&a.data, &b.data).0)
+                    .map(|b| (a, b))
+            })
+        };
+        let Some((a, b)) = pair else {
+            commands.write_message(SaveLoadResultEvent::SaveFailed {
+                file_path: event.file_path.clone(),
+                error: "select two shapes or make two shapes collide".to_string(),
+            });
+            continue;
+        };
+
+        let test_name = Path::new(&event.file_path)
+            .file_stem()
+            .and_then(|stem| rust_identifier(&stem.to_string_lossy()))
+            .unwrap_or_else(|| "collision_fixture".to_string());
+        let source = collision_fixture_to_rust(&test_name, &a.data, &b.data);
+        spawn_save_task(&mut commands, &event.file_path, move |file_path| {
+            Ok(std::fs::write(file_path, source)?)
+        });
+    }
+}
+
+/// Collision result and separation vector of two shapes, computed with qgeometry like the fixture test does
+pub fn geometry_collision(a: &SerializableQShapeData, b: &SerializableQShapeData) -> (bool, Option<QVec2>) {
+    let (a, b) = (AnyQShape::from(a), AnyQShape::from(b));
+    (a.is_collide(b), a.try_get_seperation_vector(b))
+}
+
+/// Emit a qgeometry test asserting the current collision result and separation vector of two shapes
+fn collision_fixture_to_rust(test_name: &str, a: &SerializableQShapeData, b: &SerializableQShapeData) -> String {
+    let (is_collide, separation) = geometry_collision(a, b);
+    let separation = match separation {
+        Some(vector) => format!("Some({})", rust_qvec2(vector)),
+        None => "None".to_string(),
+    };
+
+    let mut output = String::new();
+    output.push_str("// Collision fixture exported from QEditor\n");
+    output.push_str("use qgeometry::shape::{QBbox, QCircle, QLine, QPoint, QPolygon, QShapeCommon};\n");
+    output.push_str("use qmath::prelude::*;\n");
+    output.push_str("use qmath::vec2::QVec2;\n\n");
+    output.push_str("#[test]\n");
+    output.push_str(&format!("fn {}() {{\n", test_name));
+    output.push_str(&format!("    let a = {};\n", rust_shape_constructor(a, "    ").1));
+    output.push_str(&format!("    let b = {};\n", rust_shape_constructor(b, "    ").1));
+    output.push_str(&format!("    assert_eq!(a.is_collide(&b), {});\n", is_collide));
+    output.push_str(&format!(
+        "    assert_eq!(a.try_get_seperation_vector(&b), {});\n",
+        separation
+    ));
+    output.push_str("}\n");
+    output
+}
+
+/// Rust keywords, strict and reserved, that can't be used as plain identifiers
+const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate", "do", "dyn", "else",
+    "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "macro", "match", "mod",
+    "move", "mut", "override", "priv", "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true",
+    "try", "type", "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Turn a shape name into a snake case Rust identifier, or `None` if nothing usable is left. Keywords get a `_`
+/// suffix, since `self`, `super` and `crate` can't be written as raw identifiers
+fn rust_identifier(name: &str) -> Option<String> {
+    let mut identifier: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let starts_validly = identifier
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_');
+    if RUST_KEYWORDS.contains(&identifier.as_str()) {
+        identifier.push('_');
+    }
+    (starts_validly && identifier != "_").then_some(identifier)
+}
+
+/// System to handle CSV/TSV import requests, merging the imported shapes into the scene
+pub fn handle_import_csv_request(mut commands: Commands, mut events: MessageReader<ImportCsvEvent>) {
+    for event in events.read() {
+        let options = event.options.clone();
+        spawn_load_task(&mut commands, &event.file_path, move |file_path| {
+            let points = parse_csv_points(&std::fs::read_to_string(file_path)?, &options)?;
+            if options.target == CsvImportTarget::Polygon && points.len() < 3 {
+                return Err(format!("a polygon needs at least 3 points, the file has {}", points.len()).into());
+            }
+            Ok(LoadedFile::Shapes {
+                shapes: csv_points_to_shapes(&points, &options),
+                mode: LoadMode::Merge,
+            })
+        });
+    }
+}
+
+/// Build the shapes a CSV import creates from the parsed points
+fn csv_points_to_shapes(points: &[QVec2], options: &CsvImportOptions) -> Vec<SerializableQShape> {
+    let shapes: Vec<SerializableQShapeData> = match options.target {
+        CsvImportTarget::Points => points
+            .iter()
+            .map(|pos| {
+                SerializableQShapeData::Point(QPointData {
+                    data: QPoint::new(*pos),
+                })
+            })
+            .collect(),
+        CsvImportTarget::Polygon => {
+            let points = points.iter().map(|pos| QPoint::new(*pos)).collect();
+            vec![SerializableQShapeData::Polygon(QPolygonData {
+                data: QPolygon::new(points),
+            })]
+        }
+        CsvImportTarget::Polyline => points
+            .windows(2)
+            // Repeated rows would give zero length segments
+            .filter(|pair| pair[0] != pair[1])
+            .map(|pair| {
+                SerializableQShapeData::Line(QLineData {
+                    data: QLine::new(QPoint::new(pair[0]), QPoint::new(pair[1])),
+                })
+            })
+            .collect(),
+    };
+
+    shapes
+        .into_iter()
+        .map(|data| SerializableQShape {
+            editor_shape: EditorShape {
+                layer: options.layer,
+                ..default_editor_shape(&data)
+            },
+            data,
+            physics: SerializableQPhysics::default(),
+        })
+        .collect()
+}
+
+/// Parse x,y rows of CSV or TSV text. The delimiter is detected per row as tab, comma, semicolon
+/// or whitespace. Empty rows and rows starting with `#` are skipped.
+fn parse_csv_points(text: &str, options: &CsvImportOptions) -> Result<Vec<QVec2>, Box<dyn std::error::Error>> {
+    let mut points = Vec::new();
+    let mut header_skipped = !options.has_header;
+    for (line_index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if !header_skipped {
+            header_skipped = true;
+            continue;
+        }
+
+        let fields: Vec<&str> = if line.contains('\t') {
+            line.split('\t').collect()
+        } else if line.contains(',') {
+            line.split(',').collect()
+        } else if line.contains(';') {
+            line.split(';').collect()
+        } else {
+            line.split_whitespace().collect()
+        };
+        let field = |column: usize| -> Result<Q64, Box<dyn std::error::Error>> {
+            let value = fields
+                .get(column)
+                .ok_or_else(|| format!("line {}: missing column {}", line_index + 1, column + 1))?;
+            let value: f64 = value
+                .trim()
+                .parse()
+                .map_err(|_| format!("line {}: '{}' is not a number", line_index + 1, value.trim()))?;
+            Ok(checked_q64(value * options.scale).map_err(|e| format!("line {}: {}", line_index + 1, e))?)
+        };
+        points.push(QVec2::new(field(options.x_column)?, field(options.y_column)?));
+    }
+    Ok(points)
+}
+
+/// System to handle SVG import requests, merging the imported shapes into the scene
+pub fn handle_import_svg_request(mut commands: Commands, mut events: MessageReader<ImportSvgEvent>) {
+    for event in events.read() {
+        let (scale, layer) = (event.scale, event.layer);
+        spawn_load_task(&mut commands, &event.file_path, move |file_path| {
+            let import = parse_svg_shapes(&std::fs::read_to_string(file_path)?, scale)?;
+            if import.shapes.is_empty() {
+                return Err("no supported shapes in the drawing".into());
+            }
+            if import.skipped > 0 {
+                warn!(
+                    "Skipped {} SVG elements with curves or unsupported shapes",
+                    import.skipped
+                );
+            }
+            let shapes = import
+                .shapes
+                .into_iter()
+                .map(|data| SerializableQShape {
+                    editor_shape: EditorShape {
+                        layer,
+                        ..default_editor_shape(&data)
+                    },
+                    data,
+                    physics: SerializableQPhysics::default(),
+                })
+                .collect();
+            Ok(LoadedFile::Shapes {
+                shapes,
+                mode: LoadMode::Merge,
+            })
+        });
+    }
+}
+
+/// System to handle DXF export requests for shapes in MainScene layer
+pub fn handle_export_dxf_request(
+    mut commands: Commands, mut events: MessageReader<ExportDxfEvent>, shapes_query: Query<ShapeSaveData>,
+) {
+    for event in events.read() {
+        let mut dxf = DxfWriter::new();
+        for (shape, point_opt, line_opt, bbox_opt, circle_opt, polygon_opt, _) in shapes_query.iter() {
+            if shape.layer != ShapeLayer::MainScene {
+                continue;
+            }
+            let layer = if shape.name.is_empty() {
+                "MainScene"
+            } else {
+                shape.name.as_str()
+            };
+            if let Some(point) = point_opt {
+                dxf.point(layer, point.data.pos());
+            }
+            if let Some(line) = line_opt {
+                dxf.line(layer, line.data.start().pos(), line.data.end().pos());
+            }
+            if let Some(bbox) = bbox_opt {
+                let min = bbox.data.left_bottom().pos();
+                let max = bbox.data.right_top().pos();
+                let corners = [min, QVec2::new(max.x, min.y), max, QVec2::new(min.x, max.y)];
+                dxf.polyline(layer, &corners, true);
+            }
+            if let Some(circle) = circle_opt {
+                dxf.circle(layer, circle.data.center().pos(), circle.data.radius());
+            }
+            if let Some(polygon) = polygon_opt {
+                let points: Vec<QVec2> = polygon.data.points().iter().map(|p| p.pos()).collect();
+                dxf.polyline(layer, &points, true);
+            }
+        }
+
+        let contents = dxf.finish();
+        spawn_save_task(&mut commands, &event.file_path, move |file_path| {
+            Ok(std::fs::write(file_path, contents)?)
+        });
+    }
+}
+
+/// System to handle Rapier2D collider export requests for shapes in MainScene layer
+pub fn handle_export_rapier_request(
+    mut commands: Commands, mut events: MessageReader<ExportRapierEvent>, shapes_query: Query<ShapeSaveData>,
+) {
+    for event in events.read() {
+        let shapes = collect_shapes(&shapes_query, |shape| shape.layer == ShapeLayer::MainScene);
+        let (scene, skipped) = rapier_scene(&shapes);
+        if skipped > 0 {
+            warn!(
+                "Skipped {} points and shapes without area in the Rapier export",
+                skipped
+            );
+        }
+        spawn_save_task(&mut commands, &event.file_path, move |file_path| {
+            write_to_file(file_path, RAPIER_FORMAT_VERSION, &scene)
+        });
+    }
+}
+
+/// Minimal ASCII DXF (R12) writer holding a single ENTITIES section
+struct DxfWriter {
+    output: String,
+}
+
+impl DxfWriter {
+    fn new() -> Self {
+        let mut writer = Self { output: String::new() };
+        writer.group(0, "SECTION");
+        writer.group(2, "ENTITIES");
+        writer
+    }
+
+    /// Write a group code and its value
+    fn group(&mut self, code: u32, value: &str) {
+        self.output.push_str(&format!("{}\n{}\n", code, value));
+    }
+
+    /// Write a coordinate, using group codes `base`, `base + 10` and `base + 20` for x, y and z
+    fn coordinate(&mut self, base: u32, pos: QVec2) {
+        self.group(base, &pos.x.to_num::<f64>().to_string());
+        self.group(base + 10, &pos.y.to_num::<f64>().to_string());
+        self.group(base + 20, "0.0");
+    }
+
+    fn point(&mut self, layer: &str, pos: QVec2) {
+        self.group(0, "POINT");
+        self.group(8, layer);
+        self.coordinate(10, pos);
+    }
+
+    fn line(&mut self, layer: &str, start: QVec2, end: QVec2) {
+        self.group(0, "LINE");
+        self.group(8, layer);
+        self.coordinate(10, start);
+        self.coordinate(11, end);
+    }
+
+    fn circle(&mut self, layer: &str, center: QVec2, radius: Q64) {
+        self.group(0, "CIRCLE");
+        self.group(8, layer);
+        self.coordinate(10, center);
+        self.group(40, &radius.to_num::<f64>().to_string());
+    }
+
+    /// Write a 2D polyline as POLYLINE/VERTEX/SEQEND entities, which R12 readers understand
+    fn polyline(&mut self, layer: &str, points: &[QVec2], closed: bool) {
+        self.group(0, "POLYLINE");
+        self.group(8, layer);
+        self.group(66, "1");
+        self.group(70, if closed { "1" } else { "0" });
+        self.coordinate(10, QVec2::ZERO);
+        for point in points {
+            self.group(0, "VERTEX");
+            self.group(8, layer);
+            self.coordinate(10, *point);
+        }
+        self.group(0, "SEQEND");
+        self.group(8, layer);
+    }
+
+    fn finish(mut self) -> String {
+        self.group(0, "ENDSEC");
+        self.group(0, "EOF");
+        self.output
+    }
+}
+
+/// System to handle PNG export requests by spawning an offscreen camera over the exported region
+pub fn handle_export_png_request(
+    mut commands: Commands, mut events: MessageReader<ExportPngEvent>, mut results: MessageWriter<SaveLoadResultEvent>,
+    mut images: ResMut<Assets<Image>>, camera_query: Query<&GlobalTransform, With<CameraMovement>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+) {
+    for event in events.read() {
+        let Ok(window) = windows.single() else {
+            continue;
+        };
+        let Ok(camera_transform) = camera_query.single() else {
+            continue;
+        };
+        // Viewport exports keep the view's rotation, world rectangles are axis-aligned
+        let rotation = if event.region.is_none() {
+            camera_transform.rotation()
+        } else {
+            Quat::IDENTITY
+        };
+
+        // The viewport is the window size scaled by the camera zoom
+        let region = event.region.unwrap_or_else(|| {
+            let scale = camera_transform.compute_transform().scale.truncate();
+            Rect::from_center_size(camera_transform.translation().truncate(), window.size() * scale)
+        });
+        if region.width() <= 0.0 || region.height() <= 0.0 {
+            results.write(SaveLoadResultEvent::SaveFailed {
+                file_path: event.file_path.clone(),
+                error: "empty export region".to_string(),
+            });
+            continue;
+        }
+        let resolution = event.resolution.unwrap_or(window.physical_size()).max(UVec2::ONE);
+
+        let size = Extent3d {
+            width: resolution.x,
+            height: resolution.y,
+            depth_or_array_layers: 1,
+        };
+        let mut image = Image::new_fill(
+            size,
+            TextureDimension::D2,
+            &[255, 255, 255, 255],
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        );
+        image.texture_descriptor.usage = TextureUsages::TEXTURE_BINDING
+            | TextureUsages::COPY_SRC
+            | TextureUsages::COPY_DST
+            | TextureUsages::RENDER_ATTACHMENT;
+        let image = images.add(image);
+
+        commands.spawn((
+            Camera2d,
+            Camera {
+                target: RenderTarget::Image(image.clone().into()),
+                order: -1,
+                ..default()
+            },
+            Projection::Orthographic(OrthographicProjection {
+                scaling_mode: ScalingMode::Fixed {
+                    width: region.width(),
+                    height: region.height(),
+                },
+                ..OrthographicProjection::default_2d()
+            }),
+            Transform::from_translation(region.center().extend(0.0)).with_rotation(rotation),
+            ImageExportCamera {
+                file_path: event.file_path.clone(),
+                image,
+                frames_rendered: 0,
+            },
+        ));
+    }
+}
+
+/// System to render a screenshot of the viewport at a multiple of the window resolution with F12 or a
+/// [`TakeScreenshotEvent`]. The export camera doesn't draw the UI. Read-only sessions write no files, so the
+/// request is answered with a notification instead.
+pub fn take_screenshot(
+    keyboard_input: Res<ButtonInput<KeyCode>>, mut requests: MessageReader<TakeScreenshotEvent>,
+    windows: Query<&Window, With<PrimaryWindow>>, ui_state: Res<UiState>, launch_options: Res<LaunchOptions>,
+    mut notifications: ResMut<Notifications>, mut events: MessageWriter<ExportPngEvent>,
+) {
+    if requests.read().count() == 0 && !keyboard_input.just_pressed(KeyCode::F12) {
+        return;
+    }
+    if launch_options.readonly {
+        notifications.push("Screenshots are disabled in read-only sessions", true);
+        return;
+    }
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    events.write(ExportPngEvent {
+        file_path: screenshot_path(),
+        region: None,
+        resolution: Some(window.physical_size() * ui_state.screenshot_scale.max(1)),
+    });
+}
+
+/// Path of a new screenshot, named by the current time so earlier ones aren't overwritten
+pub fn screenshot_path() -> String {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default();
+    format!("assets/screenshots/screenshot-{}.png", timestamp)
+}
+
+/// System to capture export cameras once they rendered, writing the PNG and despawning the camera
+pub fn capture_image_export(mut commands: Commands, mut export_cameras: Query<(Entity, &mut ImageExportCamera)>) {
+    for (entity, mut export_camera) in export_cameras.iter_mut() {
+        export_camera.frames_rendered += 1;
+        // Wait a frame so the screenshot doesn't read the target before the camera drew into it
+        if export_camera.frames_rendered != 2 {
+            continue;
+        }
+        let file_path = export_camera.file_path.clone();
+        commands
+            .spawn(Screenshot::image(export_camera.image.clone()))
+            .observe(save_to_disk(export_camera.file_path.clone()))
+            .observe(
+                move |_: On<ScreenshotCaptured>,
+                      mut commands: Commands,
+                      mut results: MessageWriter<SaveLoadResultEvent>| {
+                    commands.entity(entity).despawn();
+                    results.write(SaveLoadResultEvent::SaveCompleted {
+                        file_path: file_path.clone(),
+                    });
+                },
+            );
+    }
+}
+
+/// Physics components of a body saved in snapshots
+pub type BodySaveData = (
+    Entity,
+    &'static QObject,
+    Option<&'static QPhysicsBody>,
+    Option<&'static QCollisionShape>,
+    Option<&'static QCollisionFlag>,
+    Option<&'static QTransform>,
+    Option<&'static QMotion>,
+    Option<&'static EditorShape>,
+);
+
+/// Geometry of a body's shape, see [`BodySaveData`]
+pub type BodyShapeData = (
+    Option<&'static QPointData>,
+    Option<&'static QLineData>,
+    Option<&'static QBboxData>,
+    Option<&'static QCircleData>,
+    Option<&'static QPolygonData>,
+);
+
+/// Build the snapshot record of a physics body, `None` if the entity has no [`QObject`]
+fn body_record(
+    entity: Entity, bodies_query: &Query<BodySaveData>, shape_data_query: &Query<BodyShapeData>,
+) -> Option<SerializableQBody> {
+    let (_, object, body, collision_shape, collision_flag, transform, motion, editor_shape) =
+        bodies_query.get(entity).ok()?;
+    let shape_data = shape_data_query
+        .get(entity)
+        .ok()
+        .and_then(|(point, line, bbox, circle, polygon)| {
+            get_serializable_shape_data(point, line, bbox, circle, polygon)
+        });
+    Some(SerializableQBody {
+        object: *object,
+        body: body.cloned(),
+        collision_shape: collision_shape.cloned(),
+        collision_flag: collision_flag.cloned(),
+        transform: transform.copied(),
+        motion: motion.cloned(),
+        editor_shape: editor_shape.cloned(),
+        shape_data,
+    })
+}
+
+/// System to handle physics snapshot save requests
+pub fn handle_save_snapshot_request(
+    mut commands: Commands, mut events: MessageReader<SavePhysicsSnapshotEvent>, bodies_query: Query<BodySaveData>,
+    shape_data_query: Query<BodyShapeData>,
+) {
+    for event in events.read() {
+        // Store bodies in entity order so a restored world steps in the same order
+        let mut entities: Vec<Entity> = bodies_query.iter().map(|(entity, ..)| entity).collect();
+        entities.sort();
+        let snapshot = QPhysicsSnapshot::new(
+            entities
+                .into_iter()
+                .filter_map(|entity| body_record(entity, &bodies_query, &shape_data_query))
+                .collect(),
+        );
+
+        spawn_save_task(&mut commands, &event.file_path, move |file_path| {
+            save_snapshot_to_file(file_path, &snapshot)
+        });
+    }
+}
+
+/// System to handle determinism recording save requests, storing the bodies with the states they had when
+/// recording started
+pub fn handle_save_recording_request(
+    mut commands: Commands, mut events: MessageReader<SaveRecordingEvent>, recorder: Res<QPhysicsRecorder>,
+    bodies_query: Query<BodySaveData>, shape_data_query: Query<BodyShapeData>,
+) {
+    for event in events.read() {
+        if recorder.mode != QRecorderMode::Idle || recorder.hashes.is_empty() {
+            commands.write_message(SaveLoadResultEvent::SaveFailed {
+                file_path: event.file_path.clone(),
+                error: "no finished recording to save".to_string(),
+            });
+            continue;
+        }
+
+        let mut bodies = Vec::with_capacity(recorder.initial_state.len());
+        for initial in recorder.initial_state.iter() {
+            let Some(mut body) = body_record(initial.entity, &bodies_query, &shape_data_query) else {
+                commands.write_message(SaveLoadResultEvent::SaveFailed {
+                    file_path: event.file_path.clone(),
+                    error: "a recorded body was removed since recording".to_string(),
+                });
+                break;
+            };
+            body.transform = Some(initial.transform);
+            body.motion = Some(initial.motion.clone());
+            bodies.push(body);
+        }
+        if bodies.len() != recorder.initial_state.len() {
+            continue;
+        }
+
+        let recording = QPhysicsRecording {
+            version: RECORDING_FORMAT_VERSION,
+            world: QPhysicsSnapshot::new(bodies),
+            inputs: recorder.inputs.clone(),
+            hashes: recorder.hashes.clone(),
+        };
+        spawn_save_task(&mut commands, &event.file_path, move |file_path| {
+            write_to_file(file_path, RECORDING_FORMAT_VERSION, &recording)
+        });
+    }
+}
+
+/// Save a physics snapshot to a file in the format picked by its extension
+fn save_snapshot_to_file(file_path: &str, snapshot: &QPhysicsSnapshot) -> Result<(), Box<dyn std::error::Error>> {
+    write_to_file(file_path, SNAPSHOT_FORMAT_VERSION, snapshot)
+}
+
+/// System to handle physics snapshot load requests, replacing every existing physics body
+pub fn handle_load_snapshot_request(mut commands: Commands, mut events: MessageReader<LoadPhysicsSnapshotEvent>) {
+    for event in events.read() {
+        spawn_load_task(&mut commands, &event.file_path, |file_path| {
+            Ok(LoadedFile::Snapshot(load_snapshot_from_file(file_path)?))
+        });
+    }
+}
+
+/// Replace every physics body with the snapshot's. Returns the spawned bodies in snapshot order.
+fn apply_loaded_snapshot(
+    commands: &mut Commands, snapshot: &QPhysicsSnapshot, objects: &Query<Entity, With<QObject>>,
+) -> Vec<Entity> {
+    for entity in objects.iter() {
+        commands.entity(entity).despawn();
+    }
+    snapshot
+        .bodies
+        .iter()
+        .map(|serialized| spawn_snapshot_body(commands, serialized))
+        .collect()
+}
+
+/// Spawn a physics body of a snapshot with its shape and components
+pub fn spawn_snapshot_body(commands: &mut Commands, serialized: &SerializableQBody) -> Entity {
+    let entity = match &serialized.shape_data {
+        Some(shape_data) => {
+            let editor_shape = serialized
+                .editor_shape
+                .clone()
+                .unwrap_or_else(|| default_editor_shape(shape_data));
+            spawn_shape_from_serialized(commands, shape_data, editor_shape)
+        }
+        None => commands.spawn((Transform::default(), Visibility::default())).id(),
+    };
+    let mut entity_commands = commands.entity(entity);
+    entity_commands.insert(serialized.object);
+    if let (None, Some(editor_shape)) = (&serialized.shape_data, &serialized.editor_shape) {
+        entity_commands.insert(editor_shape.clone());
+    }
+    if let Some(body) = &serialized.body {
+        entity_commands.insert(body.clone());
+    }
+    if let Some(collision_shape) = &serialized.collision_shape {
+        entity_commands.insert(collision_shape.clone());
+    }
+    if let Some(collision_flag) = &serialized.collision_flag {
+        entity_commands.insert(collision_flag.clone());
+    }
+    if let Some(transform) = serialized.transform {
+        entity_commands.insert(transform);
+    }
+    if let Some(motion) = &serialized.motion {
+        entity_commands.insert(motion.clone());
+    }
+    entity
+}
+
+/// System to handle determinism recording verify requests
+pub fn handle_verify_recording_request(mut commands: Commands, mut events: MessageReader<VerifyRecordingEvent>) {
+    for event in events.read() {
+        spawn_load_task(&mut commands, &event.file_path, |file_path| {
+            Ok(LoadedFile::Recording(load_recording_from_file(file_path)?))
+        });
+    }
+}
+
+/// Replace every physics body with the recording's world and replay the recorded steps from it, comparing
+/// the state hashes. Returns the number of spawned bodies.
+fn apply_loaded_recording(
+    commands: &mut Commands, recording: QPhysicsRecording, objects: &Query<Entity, With<QObject>>,
+) -> usize {
+    let entities = apply_loaded_snapshot(commands, &recording.world, objects);
+    // Bodies without a transform and motion aren't simulated, and weren't hashed when recording
+    let initial_state: Vec<QBodySnapshot> = entities
+        .iter()
+        .zip(recording.world.bodies.iter())
+        .filter_map(|(entity, body)| {
+            Some(QBodySnapshot {
+                entity: *entity,
+                transform: body.transform?,
+                motion: body.motion.clone()?,
+            })
+        })
+        .collect();
+    commands.queue(move |world: &mut World| {
+        let mut recorder = world.resource_mut::<QPhysicsRecorder>();
+        recorder.mode = QRecorderMode::Idle;
+        recorder.initial_state = initial_state;
+        recorder.inputs = recording.inputs;
+        recorder.hashes = recording.hashes;
+        recorder.verify_result = None;
+        world.write_message(QRecorderCommand::Verify);
+        world.resource_mut::<QSimulationControl>().paused = false;
+    });
+    entities.len()
+}
+
+/// Load a determinism recording from a save file of any format, refusing versions newer than this editor
+/// understands
+pub fn load_recording_from_file(file_path: &str) -> Result<QPhysicsRecording, Box<dyn std::error::Error>> {
+    let bytes = std::fs::read(file_path)?;
+    if SaveFormat::detect(file_path, &bytes) == SaveFormat::Binary {
+        let version = split_binary_header(&bytes)?
+            .0
+            .ok_or("binary recording has no format version")?;
+        check_recording_version(version)?;
+    }
+    let recording: QPhysicsRecording = deserialize_bytes(file_path, &bytes)?;
+    check_recording_version(recording.version)?;
+    if recording.inputs.len() != recording.hashes.len() {
+        return Err(format!(
+            "recording has {} inputs for {} steps",
+            recording.inputs.len(),
+            recording.hashes.len()
+        )
+        .into());
+    }
+    if recording.world.bodies.len() > MAX_LOADED_ITEMS {
+        return Err(format!(
+            "bodies: {} bodies exceed the limit of {}",
+            recording.world.bodies.len(),
+            MAX_LOADED_ITEMS
+        )
+        .into());
+    }
+    // Leaving out a body would change every hash, so degenerate shapes fail the whole recording
+    for body in recording.world.bodies.iter() {
+        if let Some(reason) = body.shape_data.as_ref().and_then(degenerate_reason) {
+            return Err(format!("recorded body {}: {}", body.object.uuid, reason).into());
+        }
+    }
+    Ok(recording)
+}
+
+/// Refuse recordings written by a newer, incompatible version of the editor
+fn check_recording_version(version: u32) -> Result<(), Box<dyn std::error::Error>> {
+    if version > RECORDING_FORMAT_VERSION {
+        return Err(format!(
+            "recording format v{} is newer than the supported v{}, update the editor to open it",
+            version, RECORDING_FORMAT_VERSION
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Refuse physics snapshots written by a newer, incompatible version of the editor
+fn check_snapshot_version(version: u32) -> Result<(), Box<dyn std::error::Error>> {
+    if version > SNAPSHOT_FORMAT_VERSION {
+        return Err(format!(
+            "snapshot format v{} is newer than the supported v{}, update the editor to open it",
+            version, SNAPSHOT_FORMAT_VERSION
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Load a physics snapshot from a save file of any format, refusing versions newer than this editor
+/// understands
+fn load_snapshot_from_file(file_path: &str) -> Result<QPhysicsSnapshot, Box<dyn std::error::Error>> {
+    let bytes = std::fs::read(file_path)?;
+    parse_snapshot(file_path, &bytes)
+}
+
+/// Parse the contents of a physics snapshot file, upgrading older formats to the current one
+fn parse_snapshot(file_path: &str, bytes: &[u8]) -> Result<QPhysicsSnapshot, Box<dyn std::error::Error>> {
+    let mut snapshot: QPhysicsSnapshot = match SaveFormat::detect(file_path, bytes) {
+        // Binary files can't skip missing fields, so v1 files are read with the layout they were written with
+        SaveFormat::Binary => {
+            let (version, payload) = split_binary_header(bytes)?;
+            // Snapshots written before binary files recorded their version have the v1 layout
+            let version = version.unwrap_or(1);
+            check_snapshot_version(version)?;
+            match version {
+                0 | 1 => QPhysicsSnapshot::new(decode_binary::<QPhysicsSnapshotV1>(payload)?.bodies),
+                _ => decode_binary(payload)?,
+            }
+        }
+        _ => deserialize_bytes(file_path, bytes)?,
+    };
+    check_snapshot_version(snapshot.version)?;
+    snapshot.version = SNAPSHOT_FORMAT_VERSION;
+    if snapshot.bodies.len() > MAX_LOADED_ITEMS {
+        return Err(format!(
+            "bodies: {} bodies exceed the limit of {}",
+            snapshot.bodies.len(),
+            MAX_LOADED_ITEMS
+        )
+        .into());
+    }
+    Ok(snapshot)
+}
+
+/// System to start watching scene files once they loaded, and to take the editor's own saves to the
+/// watched file as already loaded
+pub fn track_watched_file(
+    mut load_requests: MessageReader<LoadShapesFromFileEvent>, mut results: MessageReader<SaveLoadResultEvent>,
+    mut watcher: ResMut<SceneFileWatcher>, mut requested_paths: Local<Vec<String>>,
+) {
+    for event in load_requests.read() {
+        requested_paths.push(event.file_path.clone());
+    }
+    for result in results.read() {
+        match result {
+            SaveLoadResultEvent::LoadCompleted { file_path, .. } => {
+                if let Some(index) = requested_paths.iter().position(|path| path == file_path) {
+                    requested_paths.remove(index);
+                    watcher.watch(file_path);
+                }
+            }
+            SaveLoadResultEvent::LoadFailed { file_path, .. } => {
+                requested_paths.retain(|path| path != file_path);
+            }
+            SaveLoadResultEvent::SaveCompleted { file_path } => {
+                if watcher.file_path.as_ref() == Some(file_path) {
+                    watcher.modified = modified_time(file_path);
+                }
+            }
+            SaveLoadResultEvent::SaveFailed { .. } => {}
+        }
+    }
+}
+
+/// System to check the watched scene file for changes, reloading it or flagging it for the user
+pub fn watch_loaded_file(
+    mut commands: Commands, mut watcher: ResMut<SceneFileWatcher>, save_tasks: Query<&SaveTask>,
+    pending_saves: Res<PendingSaves>, time: Res<Time>,
+) {
+    if !watcher.enabled {
+        return;
+    }
+    if !watcher.poll_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let Some(file_path) = watcher.file_path.clone() else {
+        return;
+    };
+    // The editor is writing the file itself
+    if save_tasks.iter().any(|task| task.file_path == file_path) || pending_saves.0.contains_key(&file_path) {
+        return;
+    }
+
+    // A missing file is likely being replaced, keep waiting for it to reappear
+    let modified = modified_time(&file_path);
+    if modified.is_none() || modified == watcher.modified {
+        return;
+    }
+    watcher.modified = modified;
+    if watcher.auto_reload {
+        commands.write_message(LoadShapesFromFileEvent {
+            file_path,
+            mode: LoadMode::Replace,
+        });
+    } else {
+        watcher.change_pending = true;
+    }
+}
+
+/// System to open the file and select the layer given on the command line
+pub fn open_launch_file(mut commands: Commands, options: Res<LaunchOptions>, mut ui_state: ResMut<UiState>) {
+    if let Some(layer) = options.layer {
+        ui_state.selected_layer = layer;
+        ui_state.only_show_select_layer = true;
+    }
+
+    let Some(file_path) = options.file_path.clone() else {
+        return;
+    };
+    if options.opens_project() {
+        ui_state.project_path = file_path.clone();
+        commands.write_message(LoadProjectEvent { file_path });
+    } else {
+        ui_state.file_path = file_path.clone();
+        commands.write_message(LoadShapesFromFileEvent {
+            file_path,
+            mode: LoadMode::Replace,
+        });
+    }
+}
+
+/// System to load files dropped onto the window. Scenes are merged into the current one, projects replace
+/// it, and SVG and CSV files are imported with the settings of their import sections.
+pub fn load_dropped_files(
+    mut commands: Commands, mut drops: MessageReader<FileDragAndDrop>, mut ui_state: ResMut<UiState>,
+) {
+    for drop in drops.read() {
+        let FileDragAndDrop::DroppedFile { path_buf, .. } = drop else {
+            continue;
+        };
+        let file_path = path_buf.to_string_lossy().into_owned();
+        let extension = path_buf
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("svg") => {
+                commands.write_message(ImportSvgEvent {
+                    scale: ui_state.svg_scale,
+                    layer: ui_state.selected_layer,
+                    file_path,
+                });
+            }
+            Some("csv" | "tsv") => {
+                let mut options = ui_state.csv_options.clone();
+                options.layer = ui_state.selected_layer;
+                commands.write_message(ImportCsvEvent { file_path, options });
+            }
+            _ if is_project_path(&file_path) => {
+                ui_state.project_path = file_path.clone();
+                commands.write_message(LoadProjectEvent { file_path });
+            }
+            _ => {
+                commands.write_message(LoadShapesFromFileEvent {
+                    file_path,
+                    mode: LoadMode::Merge,
+                });
+            }
+        }
+    }
+}
+
+/// System to remember the paths of completed saves and loads, writing the list to the config directory
+pub fn record_recent_files(mut results: MessageReader<SaveLoadResultEvent>, mut recent_files: ResMut<RecentFiles>) {
+    let mut changed = false;
+    for result in results.read() {
+        if let SaveLoadResultEvent::SaveCompleted { file_path } | SaveLoadResultEvent::LoadCompleted { file_path, .. } =
+            result
+        {
+            recent_files.push(file_path);
+            changed = true;
+        }
+    }
+    if changed {
+        let recent_files = recent_files.clone();
+        IoTaskPool::get()
+            .spawn(async move {
+                if let Err(e) = recent_files.save() {
+                    warn!("Failed to store recent files: {}", e);
+                }
+            })
+            .detach();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::qvec;
+
+    fn shape(data: SerializableQShapeData) -> SerializableQShape {
+        SerializableQShape {
+            editor_shape: default_editor_shape(&data),
+            data,
+            physics: SerializableQPhysics::default(),
+        }
+    }
+
+    fn point(x: i32, y: i32) -> QPoint {
+        QPoint::new(QVec2::new(Q64::from_num(x), Q64::from_num(y)))
+    }
+
+    /// One shape of each kind. More than two, since the count a v1 list starts with used to be read as the
+    /// version of the file.
+    fn sample_shapes() -> Vec<SerializableQShape> {
+        vec![
+            shape(SerializableQShapeData::Point(QPointData { data: point(1, 2) })),
+            shape(SerializableQShapeData::Line(QLineData {
+                data: QLine::new(point(0, 0), point(3, 4)),
+            })),
+            shape(SerializableQShapeData::Bbox(QBboxData {
+                data: QBbox::new_from_parts(point(0, 0).pos(), point(2, 1).pos()),
+            })),
+            shape(SerializableQShapeData::Circle(QCircleData {
+                data: QCircle::new(point(-1, 5), Q64::from_num(2)),
+            })),
+            shape(SerializableQShapeData::Polygon(QPolygonData {
+                data: QPolygon::new(vec![point(0, 0), point(4, 0), point(0, 3)]),
+            })),
+        ]
+    }
+
+    fn encoded(shapes: &[SerializableQShape]) -> Vec<Vec<u8>> {
+        shapes
+            .iter()
+            .map(|shape| postcard::to_stdvec(&(&shape.editor_shape, &shape.data)).unwrap())
+            .collect()
+    }
+
+    /// Binary file of scene format v1: the bare list of shapes with their metadata right after the magic
+    fn v1_binary(shapes: &[SerializableQShape]) -> Vec<u8> {
+        let list: Vec<_> = shapes.iter().map(|shape| (&shape.editor_shape, &shape.data)).collect();
+        let mut bytes = BINARY_MAGIC.to_vec();
+        bytes.extend(postcard::to_stdvec(&list).unwrap());
+        bytes
+    }
+
+    #[test]
+    fn loads_v1_binary_scene() {
+        let shapes = sample_shapes();
+        let scene = parse_scene("scene.bin", &v1_binary(&shapes)).unwrap();
+        assert_eq!(scene.version, SCENE_FORMAT_VERSION);
+        assert_eq!(encoded(&scene.shapes), encoded(&shapes));
+    }
+
+    #[test]
+    fn loads_empty_v1_binary_scene() {
+        let scene = parse_scene("scene.bin", &v1_binary(&[])).unwrap();
+        assert!(scene.shapes.is_empty());
+    }
+
+    #[test]
+    fn binary_scene_round_trips_with_its_version() {
+        let shapes = sample_shapes();
+        let path = std::env::temp_dir().join(format!("qeditor-round-trip-{}.bin", std::process::id()));
+        let path = path.to_str().unwrap();
+        write_to_file(path, SCENE_FORMAT_VERSION, &SceneFile::new(shapes.clone())).unwrap();
+        let bytes = std::fs::read(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(split_binary_header(&bytes).unwrap().0, Some(SCENE_FORMAT_VERSION));
+        let scene = parse_scene(path, &bytes).unwrap();
+        assert_eq!(encoded(&scene.shapes), encoded(&shapes));
+        assert!(validate_scene(&scene).is_ok());
+    }
+
+    #[test]
+    fn rejects_newer_binary_scene() {
+        let mut bytes = BINARY_MAGIC.to_vec();
+        bytes.push(BINARY_VERSION_MARKER);
+        bytes.extend((SCENE_FORMAT_VERSION + 1).to_le_bytes());
+        bytes.extend(postcard::to_stdvec(&SceneFile::new(Vec::new())).unwrap());
+        let error = parse_scene("scene.bin", &bytes).unwrap_err().to_string();
+        assert!(error.contains("newer"), "{}", error);
+    }
+
+    #[test]
+    fn migrates_v0_json_scene() {
+        let shapes = sample_shapes();
+        let data: Vec<_> = shapes.iter().map(|shape| &shape.data).collect();
+        let scene = parse_scene("scene.json", &serde_json::to_vec(&data).unwrap()).unwrap();
+        assert_eq!(scene.version, SCENE_FORMAT_VERSION);
+        assert_eq!(encoded(&scene.shapes), encoded(&shapes));
+    }
+
+    #[test]
+    fn migrates_v1_json_scene() {
+        let shapes = sample_shapes();
+        let scene = parse_scene("scene.json", &serde_json::to_vec(&shapes).unwrap()).unwrap();
+        assert_eq!(scene.version, SCENE_FORMAT_VERSION);
+        assert_eq!(encoded(&scene.shapes), encoded(&shapes));
+    }
+
+    #[test]
+    fn migrates_empty_v0_json_scene() {
+        let scene = parse_scene("scene.json", b"[]").unwrap();
+        assert!(scene.shapes.is_empty());
+    }
+
+    #[test]
+    fn rejects_newer_json_scene() {
+        let bytes = serde_json::to_vec(&serde_json::json!({ "version": SCENE_FORMAT_VERSION + 1, "shapes": [] }));
+        let error = parse_scene("scene.json", &bytes.unwrap()).unwrap_err().to_string();
+        assert!(error.contains("newer"), "{}", error);
+    }
+
+    #[test]
+    fn loads_v1_binary_snapshot() {
+        let mut bytes = BINARY_MAGIC.to_vec();
+        bytes.push(BINARY_VERSION_MARKER);
+        bytes.extend(1u32.to_le_bytes());
+        bytes.extend(postcard::to_stdvec(&Vec::<SerializableQBody>::new()).unwrap());
+        let snapshot = parse_snapshot("snapshot.bin", &bytes).unwrap();
+        assert_eq!(snapshot.version, SNAPSHOT_FORMAT_VERSION);
+        assert!(snapshot.bodies.is_empty());
+    }
+
+    #[test]
+    fn binary_snapshot_round_trips_with_its_version() {
+        let path = std::env::temp_dir().join(format!("qeditor-snapshot-{}.bin", std::process::id()));
+        let path = path.to_str().unwrap();
+        save_snapshot_to_file(path, &QPhysicsSnapshot::new(Vec::new())).unwrap();
+        let bytes = std::fs::read(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(split_binary_header(&bytes).unwrap().0, Some(SNAPSHOT_FORMAT_VERSION));
+        assert_eq!(parse_snapshot(path, &bytes).unwrap().version, SNAPSHOT_FORMAT_VERSION);
+    }
+
+    #[test]
+    fn loads_text_snapshots_without_a_version() {
+        let snapshot = parse_snapshot("snapshot.json", br#"{ "bodies": [] }"#).unwrap();
+        assert_eq!(snapshot.version, SNAPSHOT_FORMAT_VERSION);
+        let snapshot = parse_snapshot("snapshot.ron", b"(bodies: [])").unwrap();
+        assert_eq!(snapshot.version, SNAPSHOT_FORMAT_VERSION);
+    }
+
+    #[test]
+    fn rejects_newer_snapshots_in_every_format() {
+        let newer = serde_json::json!({ "version": SNAPSHOT_FORMAT_VERSION + 1, "bodies": [] });
+        let error = parse_snapshot("snapshot.json", &serde_json::to_vec(&newer).unwrap()).unwrap_err();
+        assert!(error.to_string().contains("newer"), "{}", error);
+
+        let text = format!("(version: {}, bodies: [])", SNAPSHOT_FORMAT_VERSION + 1);
+        let error = parse_snapshot("snapshot.ron", text.as_bytes()).unwrap_err();
+        assert!(error.to_string().contains("newer"), "{}", error);
+
+        let mut bytes = BINARY_MAGIC.to_vec();
+        bytes.push(BINARY_VERSION_MARKER);
+        bytes.extend((SNAPSHOT_FORMAT_VERSION + 1).to_le_bytes());
+        bytes.extend(postcard::to_stdvec(&QPhysicsSnapshot::new(Vec::new())).unwrap());
+        let error = parse_snapshot("snapshot.bin", &bytes).unwrap_err();
+        assert!(error.to_string().contains("newer"), "{}", error);
+    }
+
+    #[test]
+    fn parses_csv_points_with_any_delimiter() {
+        let text = "# exported points\n1,2\n\n3\t4\n5;6\n7 8\n";
+        let points = parse_csv_points(text, &CsvImportOptions::default()).unwrap();
+        assert_eq!(
+            points,
+            vec![qvec(1.0, 2.0), qvec(3.0, 4.0), qvec(5.0, 6.0), qvec(7.0, 8.0)]
+        );
+    }
+
+    #[test]
+    fn parses_csv_points_with_header_columns_and_scale() {
+        let options = CsvImportOptions {
+            x_column: 2,
+            y_column: 0,
+            has_header: true,
+            scale: 2.0,
+            ..default()
+        };
+        let points = parse_csv_points("y,name,x\n1.5,a,-2\n", &options).unwrap();
+        assert_eq!(points, vec![qvec(-4.0, 3.0)]);
+    }
+
+    #[test]
+    fn rejects_bad_csv_rows() {
+        let options = CsvImportOptions::default();
+        let error = parse_csv_points("1,2\n3,x\n", &options).unwrap_err().to_string();
+        assert!(error.contains("line 2"), "{}", error);
+        let error = parse_csv_points("1\n", &options).unwrap_err().to_string();
+        assert!(error.contains("missing column 2"), "{}", error);
+        let error = parse_csv_points("1e300,0\n", &options).unwrap_err().to_string();
+        assert!(error.contains("out of the Q64 range"), "{}", error);
+        let error = parse_csv_points("2147483648,0\n", &options).unwrap_err().to_string();
+        assert!(error.contains("out of the Q64 range"), "{}", error);
+    }
+
+    #[test]
+    fn failed_save_keeps_the_previous_file() {
+        let path = std::env::temp_dir().join(format!("qeditor-atomic-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        std::fs::write(path, "previous").unwrap();
+
+        let result = write_atomically(
+            path,
+            Box::new(|file_path: &str| {
+                std::fs::write(file_path, "partial")?;
+                Err("disk full".into())
+            }),
+        );
+        let contents = std::fs::read_to_string(path).unwrap();
+        write_atomically(path, Box::new(|file_path: &str| Ok(std::fs::write(file_path, "next")?))).unwrap();
+        let next = std::fs::read_to_string(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert!(result.is_err());
+        assert_eq!(contents, "previous");
+        assert_eq!(next, "next");
+    }
+
+    #[test]
+    fn rust_identifier_avoids_keywords() {
+        assert_eq!(rust_identifier("Type").as_deref(), Some("type_"));
+        assert_eq!(rust_identifier("fn").as_deref(), Some("fn_"));
+        assert_eq!(rust_identifier("Self").as_deref(), Some("self_"));
+        assert_eq!(rust_identifier("Big Box").as_deref(), Some("big_box"));
+        assert_eq!(rust_identifier("1st"), None);
+    }
 }
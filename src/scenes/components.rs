@@ -0,0 +1,20 @@
+//! Scenes components
+//!
+//! This module defines the scene tag of shapes and the messages that manage the open scenes.
+
+use bevy::prelude::*;
+
+/// Component tagging a shape with the scene it belongs to
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SceneId(pub u32);
+
+/// Events to open, switch and close scene tabs
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SceneTabEvent {
+    /// Open an empty scene and switch to it
+    New,
+    /// Switch to the scene with this id
+    Switch(u32),
+    /// Close the scene with this id, removing its shapes
+    Close(u32),
+}
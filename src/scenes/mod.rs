@@ -0,0 +1,12 @@
+//! Scenes module for the 2D geometry editor
+//!
+//! This module lets several scenes be open at once, shown as tabs. Shapes are tagged with the
+//! [`SceneId`](components::SceneId) of their scene, and the shapes of the scenes in the background are
+//! disabled so the rest of the editor only ever sees the active scene.
+
+pub mod components;
+pub mod plugin;
+pub mod resources;
+pub mod systems;
+
+pub use plugin::ScenesPlugin;
@@ -0,0 +1,29 @@
+//! Scenes plugin implementation
+//!
+//! Registers the open scenes and the systems that manage them.
+
+use super::components::SceneTabEvent;
+use super::resources::SceneTabs;
+use super::systems::{
+    draw_scene_tabs, handle_scene_tab_events, scene_tab_shortcuts, tag_scene_shapes, track_saved_scenes,
+};
+use bevy::prelude::*;
+use bevy_egui::EguiPrimaryContextPass;
+
+/// `ScenesPlugin` registers the scene tabs and the systems that switch between scenes.
+pub struct ScenesPlugin;
+
+impl Plugin for ScenesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SceneTabs>()
+            .add_message::<SceneTabEvent>()
+            .add_systems(
+                Update,
+                (
+                    (tag_scene_shapes, scene_tab_shortcuts, handle_scene_tab_events).chain(),
+                    track_saved_scenes,
+                ),
+            )
+            .add_systems(EguiPrimaryContextPass, draw_scene_tabs);
+    }
+}
@@ -0,0 +1,76 @@
+//! Scenes resources
+
+use crate::history::resources::EditHistory;
+use crate::ui::resources::UiState;
+use bevy::prelude::*;
+
+/// State of an open scene. While the scene is active its save path and history live in [`UiState`] and
+/// [`EditHistory`], and are stored here when switching away.
+#[derive(Default)]
+pub struct SceneTab {
+    pub id: u32,
+    /// Save path of the scene
+    pub file_path: String,
+    /// Undo and redo stacks of the scene
+    pub history: EditHistory,
+    /// History revision the scene was last saved or loaded at, see [`EditHistory::revision`]
+    pub saved_revision: u64,
+}
+
+impl SceneTab {
+    /// Name shown on the tab, the file name of the save path
+    pub fn name(file_path: &str) -> &str {
+        let name = file_path.rsplit(['/', '\\']).next().unwrap_or(file_path);
+        if name.is_empty() { "Untitled" } else { name }
+    }
+}
+
+/// Resource holding the open scenes, in tab order
+#[derive(Resource)]
+pub struct SceneTabs {
+    pub tabs: Vec<SceneTab>,
+    /// Id of the active scene
+    pub active: u32,
+    /// Scene with unsaved edits waiting for the user to confirm closing it
+    pub confirm_close: Option<u32>,
+    next_id: u32,
+}
+
+impl Default for SceneTabs {
+    fn default() -> Self {
+        Self {
+            tabs: vec![SceneTab::default()],
+            active: 0,
+            confirm_close: None,
+            next_id: 1,
+        }
+    }
+}
+
+impl SceneTabs {
+    /// Add a tab for an empty scene, returning its id
+    pub fn add(&mut self) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.tabs.push(SceneTab {
+            id,
+            file_path: format!("assets/saves/untitled-{}.json", id),
+            ..default()
+        });
+        id
+    }
+
+    pub fn get_mut(&mut self, id: u32) -> Option<&mut SceneTab> {
+        self.tabs.iter_mut().find(|tab| tab.id == id)
+    }
+
+    /// Save path of a scene and whether it has edits since it was last saved or loaded. The active scene's
+    /// path and history are the editor's current ones.
+    pub fn status<'a>(&'a self, tab: &'a SceneTab, history: &EditHistory, ui_state: &'a UiState) -> (&'a str, bool) {
+        if tab.id == self.active {
+            (ui_state.file_path.as_str(), history.revision() != tab.saved_revision)
+        } else {
+            (tab.file_path.as_str(), tab.history.revision() != tab.saved_revision)
+        }
+    }
+}
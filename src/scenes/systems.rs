@@ -0,0 +1,209 @@
+//! Scenes systems
+//!
+//! This module defines the systems that tag shapes with their scene, switch between scenes and draw the
+//! scene tabs.
+
+use super::components::{SceneId, SceneTabEvent};
+use super::resources::{SceneTab, SceneTabs};
+use crate::collision_detection::components::{
+    CollisionVisualization, MinkowskiDifferenceVisualization, MinkowskiSumVisualization, SeparationVectorVisualization,
+};
+use crate::history::resources::EditHistory;
+use crate::save_load::components::SaveLoadResultEvent;
+use crate::shapes::components::EditorShape;
+use crate::ui::resources::UiState;
+use bevy::ecs::entity_disabling::Disabled;
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+/// Shapes not tagged with a scene yet. Collision visualizations are recomputed for whichever scene is
+/// active, so they don't belong to one.
+type UntaggedShapeFilter = (
+    With<EditorShape>,
+    Without<SceneId>,
+    Without<CollisionVisualization>,
+    Without<SeparationVectorVisualization>,
+    Without<MinkowskiDifferenceVisualization>,
+    Without<MinkowskiSumVisualization>,
+);
+
+/// System to tag new shapes with the active scene
+pub fn tag_scene_shapes(mut commands: Commands, tabs: Res<SceneTabs>, shapes: Query<Entity, UntaggedShapeFilter>) {
+    for entity in shapes.iter() {
+        commands.entity(entity).try_insert(SceneId(tabs.active));
+    }
+}
+
+/// Make `id` the active scene, storing the save path and history of the previous one in its tab
+fn switch_scene(tabs: &mut SceneTabs, id: u32, history: &mut EditHistory, ui_state: &mut UiState) {
+    if id == tabs.active || !tabs.tabs.iter().any(|tab| tab.id == id) {
+        return;
+    }
+    let active = tabs.active;
+    if let Some(tab) = tabs.get_mut(active) {
+        tab.history = std::mem::take(history);
+        tab.file_path = std::mem::take(&mut ui_state.file_path);
+    }
+    if let Some(tab) = tabs.get_mut(id) {
+        *history = std::mem::take(&mut tab.history);
+        ui_state.file_path = std::mem::take(&mut tab.file_path);
+    }
+    tabs.active = id;
+    ui_state.selected_shape = None;
+}
+
+/// System to open, switch and close scenes. Shapes of the background scenes are disabled, which hides
+/// them from every other system.
+pub fn handle_scene_tab_events(
+    mut commands: Commands, mut events: MessageReader<SceneTabEvent>, mut tabs: ResMut<SceneTabs>,
+    mut history: ResMut<EditHistory>, mut ui_state: ResMut<UiState>, shapes: Query<(Entity, &SceneId, Has<Disabled>)>,
+) {
+    if events.is_empty() {
+        return;
+    }
+    for event in events.read() {
+        match *event {
+            SceneTabEvent::New => {
+                let id = tabs.add();
+                switch_scene(&mut tabs, id, &mut history, &mut ui_state);
+            }
+            SceneTabEvent::Switch(id) => switch_scene(&mut tabs, id, &mut history, &mut ui_state),
+            SceneTabEvent::Close(id) => {
+                // The last scene stays open
+                let Some(index) = tabs.tabs.iter().position(|tab| tab.id == id) else {
+                    continue;
+                };
+                if tabs.tabs.len() <= 1 {
+                    continue;
+                }
+                if id == tabs.active {
+                    let neighbour = if index + 1 < tabs.tabs.len() {
+                        index + 1
+                    } else {
+                        index - 1
+                    };
+                    let neighbour = tabs.tabs[neighbour].id;
+                    switch_scene(&mut tabs, neighbour, &mut history, &mut ui_state);
+                }
+                tabs.tabs.retain(|tab| tab.id != id);
+            }
+        }
+    }
+
+    for (entity, scene, disabled) in shapes.iter() {
+        if !tabs.tabs.iter().any(|tab| tab.id == scene.0) {
+            commands.entity(entity).despawn();
+        } else if scene.0 != tabs.active && !disabled {
+            commands.entity(entity).insert(Disabled);
+        } else if scene.0 == tabs.active && disabled {
+            commands.entity(entity).remove::<Disabled>();
+        }
+    }
+}
+
+/// System to mark scenes as saved when their file is written or loaded
+pub fn track_saved_scenes(
+    mut results: MessageReader<SaveLoadResultEvent>, mut tabs: ResMut<SceneTabs>, history: Res<EditHistory>,
+    ui_state: Res<UiState>,
+) {
+    for result in results.read() {
+        let (SaveLoadResultEvent::SaveCompleted { file_path } | SaveLoadResultEvent::LoadCompleted { file_path, .. }) =
+            result
+        else {
+            continue;
+        };
+        let active = tabs.active;
+        for tab in tabs.tabs.iter_mut() {
+            if tab.id == active && *file_path == ui_state.file_path {
+                tab.saved_revision = history.revision();
+            } else if tab.id != active && *file_path == tab.file_path {
+                tab.saved_revision = tab.history.revision();
+            }
+        }
+    }
+}
+
+/// System to open a new scene with Ctrl+T
+pub fn scene_tab_shortcuts(
+    mut commands: Commands, keyboard_input: Res<ButtonInput<KeyCode>>, mut egui_contexts: EguiContexts,
+) {
+    if egui_contexts.ctx_mut().is_ok_and(|ctx| ctx.wants_keyboard_input()) {
+        return;
+    }
+    let modifiers = [
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+    ];
+    if keyboard_input.any_pressed(modifiers) && keyboard_input.just_pressed(KeyCode::KeyT) {
+        commands.write_message(SceneTabEvent::New);
+    }
+}
+
+/// System to draw the scene tabs along the top of the window, marking scenes with unsaved edits. Closing
+/// such a scene asks for confirmation first.
+pub fn draw_scene_tabs(
+    mut contexts: EguiContexts, mut commands: Commands, mut tabs: ResMut<SceneTabs>, history: Res<EditHistory>,
+    ui_state: Res<UiState>,
+) {
+    if !ui_state.panel_visible {
+        return;
+    }
+    let Ok(ctx) = contexts.ctx_mut() else {
+        return;
+    };
+
+    let mut confirm_close = tabs.confirm_close;
+    egui::TopBottomPanel::top("scene_tabs").show(ctx, |ui| {
+        ui.horizontal(|ui| {
+            for tab in &tabs.tabs {
+                let active = tab.id == tabs.active;
+                let (file_path, dirty) = tabs.status(tab, &history, &ui_state);
+                let label = format!("{}{}", SceneTab::name(file_path), if dirty { " *" } else { "" });
+                if ui.selectable_label(active, label).on_hover_text(file_path).clicked() {
+                    commands.write_message(SceneTabEvent::Switch(tab.id));
+                }
+                if tabs.tabs.len() > 1 && ui.small_button("x").on_hover_text("Close scene").clicked() {
+                    if dirty {
+                        confirm_close = Some(tab.id);
+                    } else {
+                        commands.write_message(SceneTabEvent::Close(tab.id));
+                    }
+                }
+                ui.separator();
+            }
+            if ui.button("+").on_hover_text("New scene (Ctrl+T)").clicked() {
+                commands.write_message(SceneTabEvent::New);
+            }
+        });
+    });
+
+    // The edits of a closed scene are gone for good
+    let closing = confirm_close.and_then(|id| tabs.tabs.iter().find(|tab| tab.id == id));
+    match closing {
+        Some(tab) => {
+            let name = SceneTab::name(tabs.status(tab, &history, &ui_state).0);
+            egui::Window::new("Unsaved Changes")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label(format!("{} has unsaved changes. Close it anyway?", name));
+                    ui.horizontal(|ui| {
+                        if ui.button("Close Without Saving").clicked() {
+                            commands.write_message(SceneTabEvent::Close(tab.id));
+                            confirm_close = None;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            confirm_close = None;
+                        }
+                    });
+                });
+        }
+        None => confirm_close = None,
+    }
+    if tabs.confirm_close != confirm_close {
+        tabs.confirm_close = confirm_close;
+    }
+}
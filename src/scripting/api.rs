@@ -0,0 +1,364 @@
+//! Script API
+//!
+//! Builds the rhai engine scripts run in and the functions it exposes. Scripts work on a copy of
+//! the scene, [`ScriptScene`], which the editor applies once the script finished without errors.
+//!
+//! Shapes are referred to by integer ids, their index in the scene. Coordinates accept integers
+//! and floats.
+//!
+//! - Create: `point(x, y)`, `line(x1, y1, x2, y2)`, `rect(x1, y1, x2, y2)`, `circle(x, y, r)`,
+//!   `polygon([[x, y], ...])`, each returning the id of the new shape
+//! - Query: `shape_count()`, `shape_ids()`, `shape_type(id)`, `shape_name(id)`, `shape_layer(id)`,
+//!   `center(id)`, `find(name)` (-1 if there is no such shape)
+//! - Modify: `set_name(id, name)`, `set_layer(id, layer)`, `translate(id, dx, dy)`, `remove(id)`, `clear()`
+//! - Physics: `add_body(id, mass, restitution, friction)` (mass 0 for a static body), `remove_body(id)`,
+//!   `has_body(id)`, `set_velocity(id, vx, vy)`
+//! - Collision: `collides(a, b)`, `separation(a, b)` (`()` if there is none)
+
+use crate::qphysics::components::{QMotion, QObject, QPhysicsBody};
+use crate::save_load::components::{SerializableQPhysics, SerializableQShape, SerializableQShapeData};
+use crate::save_load::systems::{
+    collision_shape_for, default_editor_shape, degenerate_reason, geometry_collision, shape_data_centroid,
+    translate_shape,
+};
+use crate::shapes::components::{EditorShape, QBboxData, QCircleData, QLineData, QPointData, QPolygonData, ShapeLayer};
+use crate::util::checked_q64;
+use qgeometry::shape::{QBbox, QCircle, QLine, QPoint, QPolygon};
+use qmath::prelude::*;
+use qmath::vec2::QVec2;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, INT};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// Copy of the scene a script works on
+#[derive(Default)]
+pub struct ScriptScene {
+    /// Shapes by id, `None` for removed shapes so ids stay stable
+    pub shapes: Vec<Option<SerializableQShape>>,
+    /// Layer created shapes are put on
+    pub layer: ShapeLayer,
+    /// Whether the script changed the scene
+    pub modified: bool,
+}
+
+impl ScriptScene {
+    pub fn new(shapes: Vec<SerializableQShape>, layer: ShapeLayer) -> Self {
+        Self {
+            shapes: shapes.into_iter().map(Some).collect(),
+            layer,
+            modified: false,
+        }
+    }
+
+    /// Shapes left after running the script
+    pub fn into_shapes(self) -> Vec<SerializableQShape> {
+        self.shapes.into_iter().flatten().collect()
+    }
+
+    fn shape(&self, id: INT) -> ScriptResult<&SerializableQShape> {
+        usize::try_from(id)
+            .ok()
+            .and_then(|index| self.shapes.get(index))
+            .and_then(Option::as_ref)
+            .ok_or_else(|| format!("no shape with id {}", id).into())
+    }
+
+    fn shape_mut(&mut self, id: INT) -> ScriptResult<&mut SerializableQShape> {
+        self.modified = true;
+        usize::try_from(id)
+            .ok()
+            .and_then(|index| self.shapes.get_mut(index))
+            .and_then(Option::as_mut)
+            .ok_or_else(|| format!("no shape with id {}", id).into())
+    }
+
+    /// Add a shape to the scene and return its id, refusing degenerate geometry the editor wouldn't draw
+    fn add(&mut self, data: SerializableQShapeData) -> ScriptResult<INT> {
+        if let Some(reason) = degenerate_reason(&data) {
+            return Err(reason.into());
+        }
+        self.modified = true;
+        self.shapes.push(Some(SerializableQShape {
+            editor_shape: EditorShape {
+                layer: self.layer,
+                ..default_editor_shape(&data)
+            },
+            data,
+            physics: SerializableQPhysics::default(),
+        }));
+        Ok((self.shapes.len() - 1) as INT)
+    }
+
+    fn ids(&self) -> impl Iterator<Item = INT> + '_ {
+        self.shapes
+            .iter()
+            .enumerate()
+            .filter(|(_, shape)| shape.is_some())
+            .map(|(id, _)| id as INT)
+    }
+}
+
+/// Convert a script number to Q64, rejecting values outside its range
+fn number(value: &Dynamic) -> ScriptResult<Q64> {
+    let value = match (value.as_float(), value.as_int()) {
+        (Ok(value), _) => value,
+        (_, Ok(value)) => value as f64,
+        _ => return Err(format!("expected a number, got {}", value.type_name()).into()),
+    };
+    Ok(checked_q64(value)?)
+}
+
+fn vector(x: &Dynamic, y: &Dynamic) -> ScriptResult<QVec2> {
+    Ok(QVec2::new(number(x)?, number(y)?))
+}
+
+/// Convert a vector to a `[x, y]` script array
+fn to_array(vec: QVec2) -> Array {
+    vec![
+        Dynamic::from_float(vec.x.to_num::<f64>()),
+        Dynamic::from_float(vec.y.to_num::<f64>()),
+    ]
+}
+
+/// Operations a script may run before it is stopped. Scripts run on the main thread, so an endless loop
+/// would otherwise freeze the editor.
+pub const MAX_SCRIPT_OPERATIONS: u64 = 10_000_000;
+/// Nesting of function calls a script may reach
+const MAX_SCRIPT_CALL_LEVELS: usize = 64;
+/// Size of the strings, arrays and maps a script may build
+const MAX_SCRIPT_VALUE_SIZE: usize = 1_000_000;
+
+/// Build an engine whose shape functions work on `scene` and whose `print` appends to `output`
+pub fn build_engine(scene: &Rc<RefCell<ScriptScene>>, output: &Rc<RefCell<Vec<String>>>) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_max_operations(MAX_SCRIPT_OPERATIONS)
+        .set_max_call_levels(MAX_SCRIPT_CALL_LEVELS)
+        .set_max_expr_depths(MAX_SCRIPT_CALL_LEVELS, MAX_SCRIPT_CALL_LEVELS)
+        .set_max_string_size(MAX_SCRIPT_VALUE_SIZE)
+        .set_max_array_size(MAX_SCRIPT_VALUE_SIZE)
+        .set_max_map_size(MAX_SCRIPT_VALUE_SIZE);
+    let printed = output.clone();
+    engine.on_print(move |text| printed.borrow_mut().push(text.to_string()));
+    let debugged = output.clone();
+    engine.on_debug(move |text, _, _| debugged.borrow_mut().push(text.to_string()));
+
+    register_create_functions(&mut engine, scene);
+    register_query_functions(&mut engine, scene);
+    register_modify_functions(&mut engine, scene);
+    register_physics_functions(&mut engine, scene);
+    register_collision_functions(&mut engine, scene);
+    engine
+}
+
+fn register_create_functions(engine: &mut Engine, scene: &Rc<RefCell<ScriptScene>>) {
+    let s = scene.clone();
+    engine.register_fn("point", move |x: Dynamic, y: Dynamic| -> ScriptResult<INT> {
+        let data = QPointData {
+            data: QPoint::new(vector(&x, &y)?),
+        };
+        s.borrow_mut().add(SerializableQShapeData::Point(data))
+    });
+    let s = scene.clone();
+    engine.register_fn(
+        "line",
+        move |x1: Dynamic, y1: Dynamic, x2: Dynamic, y2: Dynamic| -> ScriptResult<INT> {
+            let data = QLineData {
+                data: QLine::new(QPoint::new(vector(&x1, &y1)?), QPoint::new(vector(&x2, &y2)?)),
+            };
+            s.borrow_mut().add(SerializableQShapeData::Line(data))
+        },
+    );
+    let s = scene.clone();
+    engine.register_fn(
+        "rect",
+        move |x1: Dynamic, y1: Dynamic, x2: Dynamic, y2: Dynamic| -> ScriptResult<INT> {
+            let (a, b) = (vector(&x1, &y1)?, vector(&x2, &y2)?);
+            let min = QVec2::new(a.x.min(b.x), a.y.min(b.y));
+            let max = QVec2::new(a.x.max(b.x), a.y.max(b.y));
+            let data = QBboxData {
+                data: QBbox::new_from_parts(min, max),
+            };
+            s.borrow_mut().add(SerializableQShapeData::Bbox(data))
+        },
+    );
+    let s = scene.clone();
+    engine.register_fn(
+        "circle",
+        move |x: Dynamic, y: Dynamic, r: Dynamic| -> ScriptResult<INT> {
+            let radius = number(&r)?;
+            if radius <= Q64::ZERO {
+                return Err("circle radius must be positive".into());
+            }
+            let data = QCircleData {
+                data: QCircle::new(QPoint::new(vector(&x, &y)?), radius),
+            };
+            s.borrow_mut().add(SerializableQShapeData::Circle(data))
+        },
+    );
+    let s = scene.clone();
+    engine.register_fn("polygon", move |points: Array| -> ScriptResult<INT> {
+        if points.len() < 3 {
+            return Err("a polygon needs at least 3 points".into());
+        }
+        let points = points
+            .iter()
+            .map(|point| match point.clone().try_cast::<Array>() {
+                Some(xy) if xy.len() == 2 => Ok(QPoint::new(vector(&xy[0], &xy[1])?)),
+                _ => Err("polygon points must be [x, y] arrays".into()),
+            })
+            .collect::<ScriptResult<Vec<_>>>()?;
+        let data = QPolygonData {
+            data: QPolygon::new(points),
+        };
+        s.borrow_mut().add(SerializableQShapeData::Polygon(data))
+    });
+}
+
+fn register_query_functions(engine: &mut Engine, scene: &Rc<RefCell<ScriptScene>>) {
+    let s = scene.clone();
+    engine.register_fn("shape_count", move || s.borrow().ids().count() as INT);
+    let s = scene.clone();
+    engine.register_fn("shape_ids", move || {
+        s.borrow().ids().map(Dynamic::from_int).collect::<Array>()
+    });
+    let s = scene.clone();
+    engine.register_fn("shape_type", move |id: INT| -> ScriptResult<String> {
+        let name = match s.borrow().shape(id)?.data {
+            SerializableQShapeData::Point(_) => "point",
+            SerializableQShapeData::Line(_) => "line",
+            SerializableQShapeData::Bbox(_) => "rect",
+            SerializableQShapeData::Circle(_) => "circle",
+            SerializableQShapeData::Polygon(_) => "polygon",
+        };
+        Ok(name.to_string())
+    });
+    let s = scene.clone();
+    engine.register_fn("shape_name", move |id: INT| -> ScriptResult<String> {
+        Ok(s.borrow().shape(id)?.editor_shape.name.clone())
+    });
+    let s = scene.clone();
+    engine.register_fn("shape_layer", move |id: INT| -> ScriptResult<String> {
+        Ok(format!("{:?}", s.borrow().shape(id)?.editor_shape.layer))
+    });
+    let s = scene.clone();
+    engine.register_fn("center", move |id: INT| -> ScriptResult<Array> {
+        Ok(to_array(shape_data_centroid(&s.borrow().shape(id)?.data)))
+    });
+    let s = scene.clone();
+    engine.register_fn("find", move |name: &str| {
+        let scene = s.borrow();
+        scene
+            .ids()
+            .find(|id| scene.shape(*id).is_ok_and(|shape| shape.editor_shape.name == name))
+            .unwrap_or(-1)
+    });
+}
+
+fn register_modify_functions(engine: &mut Engine, scene: &Rc<RefCell<ScriptScene>>) {
+    let s = scene.clone();
+    engine.register_fn("set_name", move |id: INT, name: &str| -> ScriptResult<()> {
+        s.borrow_mut().shape_mut(id)?.editor_shape.name = name.to_string();
+        Ok(())
+    });
+    let s = scene.clone();
+    engine.register_fn("set_layer", move |id: INT, name: &str| -> ScriptResult<()> {
+        let layer = ShapeLayer::from_name(name).ok_or_else(|| {
+            format!(
+                "unknown layer '{}', expected MainScene, AuxiliaryLine or Generated",
+                name
+            )
+        })?;
+        s.borrow_mut().shape_mut(id)?.editor_shape.layer = layer;
+        Ok(())
+    });
+    let s = scene.clone();
+    engine.register_fn(
+        "translate",
+        move |id: INT, dx: Dynamic, dy: Dynamic| -> ScriptResult<()> {
+            let offset = vector(&dx, &dy)?;
+            let mut scene = s.borrow_mut();
+            let shape = scene.shape_mut(id)?;
+            *shape = translate_shape(shape.clone(), offset);
+            Ok(())
+        },
+    );
+    let s = scene.clone();
+    engine.register_fn("remove", move |id: INT| -> ScriptResult<()> {
+        let mut scene = s.borrow_mut();
+        scene.shape_mut(id)?;
+        scene.shapes[id as usize] = None;
+        Ok(())
+    });
+    let s = scene.clone();
+    engine.register_fn("clear", move || {
+        let mut scene = s.borrow_mut();
+        scene.modified = true;
+        scene.shapes.iter_mut().for_each(|shape| *shape = None);
+    });
+}
+
+fn register_physics_functions(engine: &mut Engine, scene: &Rc<RefCell<ScriptScene>>) {
+    let s = scene.clone();
+    engine.register_fn(
+        "add_body",
+        move |id: INT, mass: Dynamic, restitution: Dynamic, friction: Dynamic| -> ScriptResult<()> {
+            let body = QPhysicsBody::new(number(&mass)?, number(&restitution)?, number(&friction)?);
+            let mut scene = s.borrow_mut();
+            let shape = scene.shape_mut(id)?;
+            shape.physics = SerializableQPhysics {
+                object: Some(shape.physics.object.unwrap_or(QObject {
+                    uuid: id as u64 + 1,
+                    entity: None,
+                })),
+                body: Some(body),
+                collision_shape: Some(collision_shape_for(&shape.data)),
+                collision_flag: Some(shape.physics.collision_flag.clone().unwrap_or_default()),
+                transform: Some(shape.physics.transform.unwrap_or_default()),
+                motion: Some(shape.physics.motion.clone().unwrap_or_default()),
+            };
+            Ok(())
+        },
+    );
+    let s = scene.clone();
+    engine.register_fn("remove_body", move |id: INT| -> ScriptResult<()> {
+        s.borrow_mut().shape_mut(id)?.physics = SerializableQPhysics::default();
+        Ok(())
+    });
+    let s = scene.clone();
+    engine.register_fn("has_body", move |id: INT| -> ScriptResult<bool> {
+        Ok(s.borrow().shape(id)?.physics.body.is_some())
+    });
+    let s = scene.clone();
+    engine.register_fn(
+        "set_velocity",
+        move |id: INT, vx: Dynamic, vy: Dynamic| -> ScriptResult<()> {
+            let velocity = vector(&vx, &vy)?;
+            let mut scene = s.borrow_mut();
+            let shape = scene.shape_mut(id)?;
+            if shape.physics.body.is_none() {
+                return Err(format!("shape {} has no physics body, call add_body first", id).into());
+            }
+            shape.physics.motion.get_or_insert_with(QMotion::default).velocity = velocity;
+            Ok(())
+        },
+    );
+}
+
+fn register_collision_functions(engine: &mut Engine, scene: &Rc<RefCell<ScriptScene>>) {
+    let s = scene.clone();
+    engine.register_fn("collides", move |a: INT, b: INT| -> ScriptResult<bool> {
+        let scene = s.borrow();
+        Ok(geometry_collision(&scene.shape(a)?.data, &scene.shape(b)?.data).0)
+    });
+    let s = scene.clone();
+    engine.register_fn("separation", move |a: INT, b: INT| -> ScriptResult<Dynamic> {
+        let scene = s.borrow();
+        let (_, separation) = geometry_collision(&scene.shape(a)?.data, &scene.shape(b)?.data);
+        Ok(separation
+            .map(|vec| Dynamic::from_array(to_array(vec)))
+            .unwrap_or(Dynamic::UNIT))
+    });
+}
@@ -0,0 +1,20 @@
+//! Scripting components
+//!
+//! This module defines the messages used to run scripts.
+
+use bevy::prelude::*;
+
+/// Where the code of a script comes from
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScriptSource {
+    /// A `.rhai` file on disk
+    File(String),
+    /// Code typed into the script console
+    Inline(String),
+}
+
+/// Event to run a script against the current scene
+#[derive(Message, Clone, Debug)]
+pub struct RunScriptEvent {
+    pub source: ScriptSource,
+}
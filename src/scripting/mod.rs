@@ -0,0 +1,12 @@
+//! Scripting module for the 2D geometry editor
+//!
+//! This module embeds a rhai engine so scenes can be generated and inspected by scripts, run from
+//! files or typed into the script console.
+
+pub mod api;
+pub mod components;
+pub mod plugin;
+pub mod resources;
+pub mod systems;
+
+pub use plugin::ScriptingPlugin;
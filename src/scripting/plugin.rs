@@ -0,0 +1,21 @@
+//! Scripting plugin implementation
+//!
+//! Registers the script console and the systems that run scripts.
+
+use super::components::RunScriptEvent;
+use super::resources::ScriptConsole;
+use super::systems::{draw_script_console, handle_run_script_request};
+use bevy::prelude::*;
+use bevy_egui::EguiPrimaryContextPass;
+
+/// `ScriptingPlugin` runs rhai scripts against the scene and draws the script console.
+pub struct ScriptingPlugin;
+
+impl Plugin for ScriptingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScriptConsole>()
+            .add_message::<RunScriptEvent>()
+            .add_systems(Update, handle_run_script_request)
+            .add_systems(EguiPrimaryContextPass, draw_script_console);
+    }
+}
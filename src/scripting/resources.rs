@@ -0,0 +1,34 @@
+//! Scripting resources
+
+use bevy::prelude::*;
+
+/// A line of script console output
+#[derive(Debug, Clone)]
+pub struct ConsoleLine {
+    pub text: String,
+    /// Whether the line reports a script error
+    pub is_error: bool,
+}
+
+/// Input and output of the script console
+#[derive(Resource, Debug, Default)]
+pub struct ScriptConsole {
+    /// Code typed into the console
+    pub input: String,
+    /// Output of the scripts run so far, oldest first
+    pub lines: Vec<ConsoleLine>,
+}
+
+impl ScriptConsole {
+    /// Number of output lines kept, older lines are dropped
+    pub const MAX_LINES: usize = 500;
+
+    pub fn push(&mut self, text: impl Into<String>, is_error: bool) {
+        self.lines.push(ConsoleLine {
+            text: text.into(),
+            is_error,
+        });
+        let excess = self.lines.len().saturating_sub(Self::MAX_LINES);
+        self.lines.drain(..excess);
+    }
+}
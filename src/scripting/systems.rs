@@ -0,0 +1,162 @@
+//! Scripting systems
+//!
+//! This module defines the systems that run scripts and draw the script console.
+
+use super::api::{MAX_SCRIPT_OPERATIONS, ScriptScene, build_engine};
+use super::components::{RunScriptEvent, ScriptSource};
+use super::resources::ScriptConsole;
+use crate::history::resources::EditHistory;
+use crate::save_load::components::SerializableQShape;
+use crate::save_load::systems::{ShapeSaveData, collect_shapes, shape_record, spawn_scene_shape};
+use crate::shapes::components::{EditorShape, ShapeLayer};
+use crate::ui::resources::UiState;
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+use rhai::EvalAltResult;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// System to run scripts, replacing the scene's shapes with the result of the scripts that changed them
+///
+/// Scripts run in the order they were requested, each seeing the changes of the previous ones.
+/// A script that fails leaves the scene untouched.
+pub fn handle_run_script_request(
+    mut commands: Commands, mut events: MessageReader<RunScriptEvent>, shapes_query: Query<ShapeSaveData>,
+    editor_shapes: Query<(Entity, &EditorShape)>, mut ui_state: ResMut<UiState>, mut console: ResMut<ScriptConsole>,
+    mut history: ResMut<EditHistory>,
+) {
+    let mut scene_shapes: Option<Vec<SerializableQShape>> = None;
+    let mut modified = false;
+    for event in events.read() {
+        let code = match &event.source {
+            ScriptSource::Inline(code) => {
+                for line in code.lines() {
+                    console.push(format!("> {}", line), false);
+                }
+                code.clone()
+            }
+            ScriptSource::File(file_path) => {
+                console.push(format!("> run {}", file_path), false);
+                match std::fs::read_to_string(file_path) {
+                    Ok(code) => code,
+                    Err(e) => {
+                        console.push(format!("Failed to read {}: {}", file_path, e), true);
+                        ui_state.console_visible = true;
+                        continue;
+                    }
+                }
+            }
+        };
+
+        let shapes = scene_shapes
+            .take()
+            .unwrap_or_else(|| collect_shapes(&shapes_query, |shape| shape.layer != ShapeLayer::Generated));
+        let scene = Rc::new(RefCell::new(ScriptScene::new(shapes.clone(), ui_state.selected_layer)));
+        let output = Rc::new(RefCell::new(Vec::new()));
+        let result = build_engine(&scene, &output).run(&code);
+        for line in output.take() {
+            console.push(line, false);
+        }
+
+        match result {
+            Ok(()) => {
+                let scene = scene.take();
+                modified |= scene.modified;
+                scene_shapes = Some(if scene.modified { scene.into_shapes() } else { shapes });
+            }
+            Err(e) => {
+                let message = match *e {
+                    EvalAltResult::ErrorTooManyOperations(_) => format!(
+                        "Error: stopped after {} operations, the script may loop forever",
+                        MAX_SCRIPT_OPERATIONS
+                    ),
+                    _ => format!("Error: {}", e),
+                };
+                console.push(message, true);
+                ui_state.console_visible = true;
+                scene_shapes = Some(shapes);
+            }
+        }
+    }
+
+    if !modified {
+        return;
+    }
+    // The scene is rebuilt from scratch, recorded as a single edit so the whole run can be undone
+    let mut removed = Vec::new();
+    for (entity, shape) in editor_shapes.iter() {
+        if shape.layer != ShapeLayer::Generated {
+            removed.extend(shape_record(&shapes_query, entity).map(|record| (entity, record)));
+            commands.entity(entity).despawn();
+        }
+    }
+    let created = scene_shapes
+        .unwrap_or_default()
+        .into_iter()
+        .map(|shape| spawn_scene_shape(&mut commands, shape))
+        .collect();
+    history.record_replaced("Run Script", removed, created);
+}
+
+/// System to render the script console window
+pub fn draw_script_console(
+    mut contexts: EguiContexts, mut ui_state: ResMut<UiState>, mut console: ResMut<ScriptConsole>,
+    mut commands: Commands,
+) {
+    if !ui_state.panel_visible || !ui_state.console_visible {
+        return;
+    }
+    let Ok(ctx) = contexts.ctx_mut() else {
+        return;
+    };
+
+    let mut open = true;
+    egui::Window::new("Script Console")
+        .open(&mut open)
+        .default_size(egui::Vec2::new(420.0, 320.0))
+        .show(ctx, |ui| {
+            let text_color = ui.visuals().text_color();
+            egui::ScrollArea::vertical()
+                .max_height(180.0)
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for line in &console.lines {
+                        let color = if line.is_error { egui::Color32::RED } else { text_color };
+                        ui.label(egui::RichText::new(&line.text).monospace().color(color));
+                    }
+                });
+            ui.separator();
+
+            let editor = ui.add(
+                egui::TextEdit::multiline(&mut console.input)
+                    .code_editor()
+                    .desired_rows(4)
+                    .desired_width(f32::INFINITY)
+                    .hint_text("circle(0, 0, 1); print(shape_count());"),
+            );
+            let run_shortcut =
+                editor.has_focus() && ui.input(|i| i.modifiers.command && i.key_pressed(egui::Key::Enter));
+            ui.horizontal(|ui| {
+                let run_clicked = ui.button("Run").on_hover_text("Run the code (Ctrl+Enter)").clicked();
+                if (run_clicked || run_shortcut) && !console.input.trim().is_empty() {
+                    commands.write_message(RunScriptEvent {
+                        source: ScriptSource::Inline(console.input.clone()),
+                    });
+                }
+                if ui.button("Clear Output").clicked() {
+                    console.lines.clear();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Script File:");
+                ui.text_edit_singleline(&mut ui_state.script_path);
+                if ui.button("Run File").clicked() {
+                    commands.write_message(RunScriptEvent {
+                        source: ScriptSource::File(ui_state.script_path.clone()),
+                    });
+                }
+            });
+        });
+    ui_state.console_visible = open;
+}
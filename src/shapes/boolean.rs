@@ -0,0 +1,347 @@
+//! Boolean operations between polygons
+//!
+//! Union, intersection and difference of two simple polygons with the Greiner-Hormann clipping algorithm,
+//! computed on fixed-point coordinates. Polygons touching each other only at vertices or along edges are
+//! degenerate for the algorithm; the clip polygon is nudged by a tiny offset until they aren't.
+
+use crate::util::cross;
+use qmath::prelude::*;
+use qmath::vec2::QVec2;
+
+/// Boolean operation between two polygons
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolygonBooleanOp {
+    Union,
+    Intersection,
+    /// The first polygon minus the second
+    Difference,
+}
+
+impl PolygonBooleanOp {
+    pub fn label(self) -> &'static str {
+        match self {
+            PolygonBooleanOp::Union => "Union",
+            PolygonBooleanOp::Intersection => "Intersection",
+            PolygonBooleanOp::Difference => "Difference",
+        }
+    }
+}
+
+/// Number of nudges tried before giving up on degenerate polygons
+const MAX_NUDGES: i32 = 8;
+
+/// Vertex of a polygon's clipping list, either an original vertex or an intersection with the other polygon
+struct ClipNode {
+    pos: QVec2,
+    /// Index of the intersection, shared by its nodes in both lists
+    intersection: Option<usize>,
+    /// Whether following the list forward from this intersection goes inside the result
+    forward: bool,
+}
+
+/// Intersection of an edge of each polygon
+struct EdgeIntersection {
+    pos: QVec2,
+    /// Edge of the subject polygon and position along it, from 0 to 1
+    subject: (usize, Q64),
+    /// Edge of the clip polygon and position along it, from 0 to 1
+    clip: (usize, Q64),
+}
+
+/// Twice the signed area of a polygon, positive for counter-clockwise vertices
+fn signed_area(points: &[QVec2]) -> Q64 {
+    (0..points.len()).fold(Q64::ZERO, |area, i| {
+        area + cross(points[i], points[(i + 1) % points.len()])
+    })
+}
+
+/// Polygon with its vertices in counter-clockwise order
+fn counter_clockwise(mut points: Vec<QVec2>) -> Vec<QVec2> {
+    if signed_area(&points) < Q64::ZERO {
+        points.reverse();
+    }
+    points
+}
+
+/// Whether `point` lies inside the polygon, by counting the edges a ray to the right crosses
+fn contains_point(polygon: &[QVec2], point: QVec2) -> bool {
+    let mut inside = false;
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+        if (a.y > point.y) != (b.y > point.y) {
+            let x = a.x + (point.y - a.y) * (b.x - a.x) / (b.y - a.y);
+            if point.x < x {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+/// Whether `point` lies on the segment from `a` to `b`
+fn on_segment(point: QVec2, a: QVec2, b: QVec2) -> bool {
+    cross(b.saturating_sub(a), point.saturating_sub(a)) == Q64::ZERO
+        && point.x >= a.x.min(b.x)
+        && point.x <= a.x.max(b.x)
+        && point.y >= a.y.min(b.y)
+        && point.y <= a.y.max(b.y)
+}
+
+/// Whether a vertex of either polygon lies on the boundary of the other
+fn is_degenerate(a: &[QVec2], b: &[QVec2]) -> bool {
+    let touches = |points: &[QVec2], polygon: &[QVec2]| {
+        points
+            .iter()
+            .any(|&point| (0..polygon.len()).any(|i| on_segment(point, polygon[i], polygon[(i + 1) % polygon.len()])))
+    };
+    touches(a, b) || touches(b, a)
+}
+
+/// Proper crossings between the edges of two polygons
+fn edge_intersections(subject: &[QVec2], clip: &[QVec2]) -> Vec<EdgeIntersection> {
+    let mut intersections = Vec::new();
+    for i in 0..subject.len() {
+        let (a, b) = (subject[i], subject[(i + 1) % subject.len()]);
+        for j in 0..clip.len() {
+            let (c, d) = (clip[j], clip[(j + 1) % clip.len()]);
+            let (ab, cd) = (b.saturating_sub(a), d.saturating_sub(c));
+            // Both segments have to straddle the other's line
+            let (c_side, d_side) = (cross(ab, c.saturating_sub(a)), cross(ab, d.saturating_sub(a)));
+            let (a_side, b_side) = (cross(cd, a.saturating_sub(c)), cross(cd, b.saturating_sub(c)));
+            let straddles = |u: Q64, v: Q64| (u > Q64::ZERO && v < Q64::ZERO) || (u < Q64::ZERO && v > Q64::ZERO);
+            if !straddles(c_side, d_side) || !straddles(a_side, b_side) {
+                continue;
+            }
+            let t = a_side / (a_side - b_side);
+            let u = c_side / (c_side - d_side);
+            intersections.push(EdgeIntersection {
+                pos: a.saturating_add(QVec2::new(ab.x * t, ab.y * t)),
+                subject: (i, t),
+                clip: (j, u),
+            });
+        }
+    }
+    intersections
+}
+
+/// Clipping list of a polygon: its vertices with the intersections inserted in order along each edge
+fn clip_list(
+    points: &[QVec2], intersections: &[EdgeIntersection], edge: impl Fn(&EdgeIntersection) -> (usize, Q64),
+) -> Vec<ClipNode> {
+    let mut nodes = Vec::with_capacity(points.len() + intersections.len());
+    for (i, &pos) in points.iter().enumerate() {
+        nodes.push(ClipNode {
+            pos,
+            intersection: None,
+            forward: false,
+        });
+        let mut on_edge: Vec<(Q64, usize)> = intersections
+            .iter()
+            .enumerate()
+            .filter_map(|(index, intersection)| {
+                let (edge_index, t) = edge(intersection);
+                (edge_index == i).then_some((t, index))
+            })
+            .collect();
+        on_edge.sort_by(|a, b| a.0.cmp(&b.0));
+        nodes.extend(on_edge.into_iter().map(|(_, index)| ClipNode {
+            pos: intersections[index].pos,
+            intersection: Some(index),
+            forward: false,
+        }));
+    }
+    nodes
+}
+
+/// Mark at each intersection whether following the list forward enters the other polygon, flipped when
+/// the part outside of it is wanted
+fn mark_directions(nodes: &mut [ClipNode], other: &[QVec2], keep_outside: bool) {
+    let mut inside = contains_point(other, nodes[0].pos);
+    for node in nodes.iter_mut().filter(|node| node.intersection.is_some()) {
+        node.forward = !inside != keep_outside;
+        inside = !inside;
+    }
+}
+
+/// Walk the clipping lists from intersection to intersection, collecting the result polygons
+fn trace(subject: &[ClipNode], clip: &[ClipNode], intersection_count: usize) -> Vec<Vec<QVec2>> {
+    let lists = [subject, clip];
+    let node_of = |list: usize, intersection: usize| {
+        lists[list]
+            .iter()
+            .position(|node| node.intersection == Some(intersection))
+            .unwrap_or_default()
+    };
+    let mut visited = vec![false; intersection_count];
+    let mut polygons = Vec::new();
+
+    while let Some(start) = visited.iter().position(|visited| !visited) {
+        let (mut list, mut index) = (0, node_of(0, start));
+        let mut polygon = vec![lists[list][index].pos];
+        while let Some(intersection) = lists[list][index].intersection.filter(|&i| !visited[i]) {
+            visited[intersection] = true;
+            let nodes = lists[list];
+            let step = if nodes[index].forward { 1 } else { nodes.len() - 1 };
+            loop {
+                index = (index + step) % nodes.len();
+                polygon.push(nodes[index].pos);
+                if nodes[index].intersection.is_some() {
+                    break;
+                }
+            }
+            // Continue on the other polygon from the same intersection
+            let intersection = nodes[index].intersection.unwrap_or_default();
+            list = 1 - list;
+            index = node_of(list, intersection);
+        }
+        // The walk ends where it started
+        if polygon.len() > 1 && polygon.first() == polygon.last() {
+            polygon.pop();
+        }
+        if polygon.len() >= 3 {
+            polygons.push(polygon);
+        }
+    }
+    polygons
+}
+
+/// Result of a boolean operation between polygons whose boundaries don't cross
+fn disjoint_boundaries(subject: Vec<QVec2>, clip: Vec<QVec2>, op: PolygonBooleanOp) -> Result<Vec<Vec<QVec2>>, String> {
+    let clip_in_subject = contains_point(&subject, clip[0]);
+    let subject_in_clip = contains_point(&clip, subject[0]);
+    Ok(match op {
+        PolygonBooleanOp::Union if subject_in_clip => vec![clip],
+        PolygonBooleanOp::Union if clip_in_subject => vec![subject],
+        PolygonBooleanOp::Union => vec![subject, clip],
+        PolygonBooleanOp::Intersection if subject_in_clip => vec![subject],
+        PolygonBooleanOp::Intersection if clip_in_subject => vec![clip],
+        PolygonBooleanOp::Intersection => Vec::new(),
+        PolygonBooleanOp::Difference if subject_in_clip => Vec::new(),
+        PolygonBooleanOp::Difference if clip_in_subject => {
+            return Err("the result would have a hole, which polygons can't represent".to_string());
+        }
+        PolygonBooleanOp::Difference => vec![subject],
+    })
+}
+
+/// Compute a boolean operation between two simple polygons. The result can be empty or several polygons;
+/// holes aren't representable and are left out.
+pub fn polygon_boolean(subject: &[QVec2], clip: &[QVec2], op: PolygonBooleanOp) -> Result<Vec<Vec<QVec2>>, String> {
+    if subject.len() < 3 || clip.len() < 3 {
+        return Err("polygons need at least 3 vertices".to_string());
+    }
+    let subject = counter_clockwise(subject.to_vec());
+    let mut clip = counter_clockwise(clip.to_vec());
+
+    let step = Q64::from_num(1e-5);
+    let mut nudges = 0;
+    while is_degenerate(&subject, &clip) {
+        nudges += 1;
+        if nudges > MAX_NUDGES {
+            return Err("the polygons overlap along their edges".to_string());
+        }
+        let offset = QVec2::new(step, step * Q64::from_num(nudges));
+        clip.iter_mut().for_each(|point| *point = point.saturating_add(offset));
+    }
+
+    let intersections = edge_intersections(&subject, &clip);
+    if intersections.is_empty() {
+        return disjoint_boundaries(subject, clip, op);
+    }
+    let mut subject_nodes = clip_list(&subject, &intersections, |intersection| intersection.subject);
+    let mut clip_nodes = clip_list(&clip, &intersections, |intersection| intersection.clip);
+    let (subject_outside, clip_outside) = match op {
+        PolygonBooleanOp::Union => (true, true),
+        PolygonBooleanOp::Intersection => (false, false),
+        PolygonBooleanOp::Difference => (true, false),
+    };
+    mark_directions(&mut subject_nodes, &clip, subject_outside);
+    mark_directions(&mut clip_nodes, &subject, clip_outside);
+
+    // Loops wound clockwise are holes of the result
+    Ok(trace(&subject_nodes, &clip_nodes, intersections.len())
+        .into_iter()
+        .filter(|polygon| signed_area(polygon) > Q64::ZERO)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: i32, y: i32, size: i32) -> Vec<QVec2> {
+        [(0, 0), (size, 0), (size, size), (0, size)]
+            .iter()
+            .map(|(dx, dy)| QVec2::new(Q64::from_num(x + dx), Q64::from_num(y + dy)))
+            .collect()
+    }
+
+    /// Twice the areas of the resulting polygons
+    fn areas(result: Result<Vec<Vec<QVec2>>, String>) -> Vec<Q64> {
+        result.unwrap().iter().map(|polygon| signed_area(polygon)).collect()
+    }
+
+    #[test]
+    fn overlapping_squares() {
+        let (a, b) = (square(0, 0, 2), square(1, 1, 2));
+        assert_eq!(
+            areas(polygon_boolean(&a, &b, PolygonBooleanOp::Union)),
+            vec![Q64::from_num(14)]
+        );
+        assert_eq!(
+            areas(polygon_boolean(&a, &b, PolygonBooleanOp::Intersection)),
+            vec![Q64::from_num(2)]
+        );
+        assert_eq!(
+            areas(polygon_boolean(&a, &b, PolygonBooleanOp::Difference)),
+            vec![Q64::from_num(6)]
+        );
+    }
+
+    #[test]
+    fn clockwise_input_gives_counter_clockwise_output() {
+        let a: Vec<QVec2> = square(0, 0, 2).into_iter().rev().collect();
+        let b = square(1, 1, 2);
+        assert_eq!(
+            areas(polygon_boolean(&a, &b, PolygonBooleanOp::Intersection)),
+            vec![Q64::from_num(2)]
+        );
+    }
+
+    #[test]
+    fn disjoint_squares() {
+        let (a, b) = (square(0, 0, 1), square(5, 5, 1));
+        assert_eq!(polygon_boolean(&a, &b, PolygonBooleanOp::Union).unwrap().len(), 2);
+        assert!(
+            polygon_boolean(&a, &b, PolygonBooleanOp::Intersection)
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(polygon_boolean(&a, &b, PolygonBooleanOp::Difference).unwrap(), vec![a]);
+    }
+
+    #[test]
+    fn nested_squares() {
+        let (outer, inner) = (square(0, 0, 4), square(1, 1, 1));
+        assert_eq!(
+            polygon_boolean(&outer, &inner, PolygonBooleanOp::Union).unwrap(),
+            vec![outer.clone()]
+        );
+        assert_eq!(
+            polygon_boolean(&outer, &inner, PolygonBooleanOp::Intersection).unwrap(),
+            vec![inner.clone()]
+        );
+        assert!(
+            polygon_boolean(&inner, &outer, PolygonBooleanOp::Difference)
+                .unwrap()
+                .is_empty()
+        );
+        assert!(polygon_boolean(&outer, &inner, PolygonBooleanOp::Difference).is_err());
+    }
+
+    #[test]
+    fn rejects_too_few_vertices() {
+        let line = square(0, 0, 1)[..2].to_vec();
+        assert!(polygon_boolean(&line, &square(0, 0, 1), PolygonBooleanOp::Union).is_err());
+    }
+}
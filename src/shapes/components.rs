@@ -3,8 +3,11 @@
 //! This module defines the components used for storing geometric shapes
 //! using the qgeometry library data structures.
 
+use super::boolean::PolygonBooleanOp;
+use crate::save_load::components::SerializableQShapeData;
 use bevy::prelude::*;
-use qgeometry::shape::{QBbox, QCircle, QLine, QPoint, QPolygon, QShapeType};
+use qgeometry::shape::{QBbox, QCircle, QLine, QPoint, QPolygon, QShapeCommon, QShapeType};
+use qmath::vec2::QVec2;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize, Serialize)]
@@ -15,15 +18,32 @@ pub enum ShapeLayer {
     Generated,
 }
 
+impl ShapeLayer {
+    /// Parse a layer from its name, ignoring case
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "mainscene" => Some(ShapeLayer::MainScene),
+            "auxiliaryline" => Some(ShapeLayer::AuxiliaryLine),
+            "generated" => Some(ShapeLayer::Generated),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize, Serialize)]
 pub enum LineAppearance {
     #[default]
     Straight,
     Arrowhead,
+    /// Line placed by the measure tool, labelled with its length
+    Dimension,
 }
 
 #[derive(Component, Debug, Clone, Deserialize, Serialize)]
 pub struct EditorShape {
+    /// Display name of the shape, empty for unnamed shapes
+    #[serde(default)]
+    pub name: String,
     /// The layer of the shape
     pub layer: ShapeLayer,
     /// The type of the shape
@@ -39,6 +59,7 @@ pub struct EditorShape {
 impl Default for EditorShape {
     fn default() -> Self {
         Self {
+            name: String::new(),
             layer: ShapeLayer::MainScene,
             shape_type: QShapeType::QPoint,
             line_appearance: LineAppearance::Straight,
@@ -82,3 +103,120 @@ pub struct QPolygonData {
     /// The polygon data
     pub data: QPolygon,
 }
+
+/// Shape data of an entity, whichever of the shape data components it has
+#[derive(Debug, Clone, Copy)]
+pub enum AnyQShape<'a> {
+    Point(&'a QPoint),
+    Line(&'a QLine),
+    Bbox(&'a QBbox),
+    Circle(&'a QCircle),
+    Polygon(&'a QPolygon),
+}
+
+/// Evaluate `$body` with `$inner` bound to the geometry of `$shape`, whatever its type
+macro_rules! with_inner {
+    ($shape:expr, $inner:ident => $body:expr) => {
+        match $shape {
+            AnyQShape::Point($inner) => $body,
+            AnyQShape::Line($inner) => $body,
+            AnyQShape::Bbox($inner) => $body,
+            AnyQShape::Circle($inner) => $body,
+            AnyQShape::Polygon($inner) => $body,
+        }
+    };
+}
+
+impl<'a> AnyQShape<'a> {
+    /// Shape of an entity from its optional shape data components, `None` for entities without any
+    pub fn from_components(
+        point: Option<&'a QPointData>, line: Option<&'a QLineData>, bbox: Option<&'a QBboxData>,
+        circle: Option<&'a QCircleData>, polygon: Option<&'a QPolygonData>,
+    ) -> Option<Self> {
+        if let Some(point) = point {
+            Some(AnyQShape::Point(&point.data))
+        } else if let Some(line) = line {
+            Some(AnyQShape::Line(&line.data))
+        } else if let Some(bbox) = bbox {
+            Some(AnyQShape::Bbox(&bbox.data))
+        } else if let Some(circle) = circle {
+            Some(AnyQShape::Circle(&circle.data))
+        } else {
+            polygon.map(|polygon| AnyQShape::Polygon(&polygon.data))
+        }
+    }
+
+    pub fn get_bbox(self) -> QBbox {
+        with_inner!(self, shape => shape.get_bbox())
+    }
+
+    pub fn get_centroid(self) -> QPoint {
+        with_inner!(self, shape => shape.get_centroid())
+    }
+
+    /// Outline vertices of the shape, with circles approximated by a polygon
+    pub fn vertices(self) -> Vec<QVec2> {
+        let points = match self {
+            AnyQShape::Point(point) => vec![*point],
+            AnyQShape::Line(line) => line.points().clone(),
+            AnyQShape::Bbox(bbox) => bbox.get_polygon().points().clone(),
+            AnyQShape::Circle(circle) => circle.get_polygon().points().clone(),
+            AnyQShape::Polygon(polygon) => polygon.points().clone(),
+        };
+        points.iter().map(|point| point.pos()).collect()
+    }
+
+    pub fn is_collide(self, other: AnyQShape) -> bool {
+        with_inner!(self, shape => with_inner!(other, other => shape.is_collide(other)))
+    }
+
+    pub fn try_get_seperation_vector(self, other: AnyQShape) -> Option<QVec2> {
+        with_inner!(self, shape => with_inner!(other, other => shape.try_get_seperation_vector(other)))
+    }
+}
+
+/// Event to delete the selected shapes
+#[derive(Message, Debug, Clone, Copy)]
+pub struct DeleteSelectedShapesEvent;
+
+/// Event to replace the geometry of a shape, as typed into the properties panel
+#[derive(Message, Clone)]
+pub struct SetShapeDataEvent {
+    pub entity: Entity,
+    pub data: SerializableQShapeData,
+}
+
+/// Event to combine two polygons, spawning the result as new polygons on the selected layer
+#[derive(Message, Debug, Clone, Copy)]
+pub struct PolygonBooleanEvent {
+    pub op: PolygonBooleanOp,
+    /// The first polygon, the one subtracted from by a difference
+    pub a: Entity,
+    pub b: Entity,
+}
+
+/// Event to spawn the convex hull of the selected shapes on the Generated layer
+#[derive(Message, Debug, Clone, Copy)]
+pub struct ConvexHullEvent;
+
+/// Event to copy, paste or duplicate shapes
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipboardEvent {
+    /// Copy the selected shapes to the clipboard
+    Copy,
+    /// Paste the clipboard, offset from the copied shapes
+    Paste,
+    /// Copy and paste the selected shapes in one step, leaving the clipboard as is
+    Duplicate,
+}
+
+/// Name of a shape type as shown in the editor
+pub fn shape_type_name(shape_type: QShapeType) -> &'static str {
+    match shape_type {
+        QShapeType::QPoint => "Point",
+        QShapeType::QLine => "Line",
+        QShapeType::QBbox => "Rectangle",
+        QShapeType::QCircle => "Circle",
+        QShapeType::QPolygon => "Polygon",
+    }
+}
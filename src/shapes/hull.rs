@@ -0,0 +1,70 @@
+//! Convex hull of a set of points
+//!
+//! Andrew's monotone chain on fixed-point coordinates, so the hull of the same points is the same on every
+//! machine.
+
+use qmath::prelude::*;
+use qmath::vec2::QVec2;
+
+/// Turn direction of `a -> b -> c`, positive for a counter-clockwise turn
+fn turn(a: QVec2, b: QVec2, c: QVec2) -> Q64 {
+    let (ab, ac) = (b.saturating_sub(a), c.saturating_sub(a));
+    ab.x * ac.y - ab.y * ac.x
+}
+
+/// One side of the hull, keeping only the points where the chain turns counter-clockwise
+fn half_hull<'a>(points: impl Iterator<Item = &'a QVec2>) -> Vec<QVec2> {
+    let mut chain: Vec<QVec2> = Vec::new();
+    for &point in points {
+        while chain.len() >= 2 && turn(chain[chain.len() - 2], chain[chain.len() - 1], point) <= Q64::ZERO {
+            chain.pop();
+        }
+        chain.push(point);
+    }
+    chain
+}
+
+/// Convex hull of `points` in counter-clockwise order, without collinear vertices. `None` when the points
+/// are all on one line, which doesn't enclose any area.
+pub fn convex_hull(mut points: Vec<QVec2>) -> Option<Vec<QVec2>> {
+    points.sort_by(|a, b| a.x.cmp(&b.x).then(a.y.cmp(&b.y)));
+    points.dedup();
+
+    // Lower hull from left to right and upper hull back, each ending where the other starts
+    let mut hull = half_hull(points.iter());
+    let mut upper = half_hull(points.iter().rev());
+    hull.pop();
+    upper.pop();
+    hull.extend(upper);
+    (hull.len() >= 3).then_some(hull)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::qvec;
+
+    #[test]
+    fn hull_drops_inner_collinear_and_repeated_points() {
+        let points = vec![
+            qvec(2.0, 2.0),
+            qvec(0.0, 0.0),
+            qvec(1.0, 1.0),
+            qvec(1.0, 0.0),
+            qvec(2.0, 0.0),
+            qvec(0.0, 2.0),
+            qvec(0.0, 0.0),
+        ];
+        assert_eq!(
+            convex_hull(points),
+            Some(vec![qvec(0.0, 0.0), qvec(2.0, 0.0), qvec(2.0, 2.0), qvec(0.0, 2.0)])
+        );
+    }
+
+    #[test]
+    fn hull_of_collinear_points_is_none() {
+        assert_eq!(convex_hull(vec![qvec(0.0, 0.0), qvec(1.0, 1.0), qvec(3.0, 3.0)]), None);
+        assert_eq!(convex_hull(vec![qvec(1.0, 1.0), qvec(1.0, 1.0), qvec(1.0, 1.0)]), None);
+        assert_eq!(convex_hull(Vec::new()), None);
+    }
+}
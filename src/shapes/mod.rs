@@ -3,7 +3,9 @@
 //! This module provides functionality for managing and storing geometric shapes
 //! using the qgeometry library data structures.
 
+pub mod boolean;
 pub mod components;
+pub mod hull;
 pub mod plugin;
 pub mod resources;
 pub mod systems;
@@ -2,8 +2,13 @@
 //!
 //! Registers resources and systems for creating, editing, and rendering shapes.
 
-use super::{resources::*, systems::*};
+use super::{
+    components::{ClipboardEvent, ConvexHullEvent, DeleteSelectedShapesEvent, PolygonBooleanEvent, SetShapeDataEvent},
+    resources::*,
+    systems::*,
+};
 use bevy::prelude::*;
+use bevy_egui::EguiPrimaryContextPass;
 
 /// `ShapesPlugin` registers shape state resources and runtime systems.
 pub struct ShapesPlugin;
@@ -13,7 +18,29 @@ impl Plugin for ShapesPlugin {
         // Initialize the resources with Default implementations.
         app.init_resource::<ShapesSettings>()
             .init_resource::<ShapeDrawingState>()
+            .init_resource::<VertexEditState>()
+            .init_resource::<ShapeClipboard>()
+            .init_resource::<TransformGizmoState>()
+            .add_message::<DeleteSelectedShapesEvent>()
+            .add_message::<ClipboardEvent>()
+            .add_message::<SetShapeDataEvent>()
+            .add_message::<PolygonBooleanEvent>()
+            .add_message::<ConvexHullEvent>()
             // Register interaction and rendering systems.
-            .add_systems(Update, (handle_shape_interaction, draw_shapes));
+            .add_systems(
+                Update,
+                (
+                    (typed_drawing_input, handle_shape_interaction).chain(),
+                    (delete_selected_shortcut, handle_delete_selected_shapes).chain(),
+                    (clipboard_shortcuts, handle_clipboard_events).chain(),
+                    handle_set_shape_data,
+                    handle_polygon_boolean,
+                    handle_convex_hull,
+                    (handle_vertex_editing, draw_vertex_handles).chain(),
+                    (handle_transform_gizmo, draw_transform_gizmo).chain(),
+                    draw_shapes,
+                ),
+            )
+            .add_systems(EguiPrimaryContextPass, draw_typed_input);
     }
 }
@@ -2,9 +2,11 @@
 //!
 //! This module defines the resources used for managing shapes and their interactions.
 
+use crate::save_load::components::SerializableQShape;
 use bevy::prelude::*;
 use qgeometry::shape::QShapeType;
 use qmath::vec2::QVec2;
+use serde::{Deserialize, Serialize};
 
 /// Resource to track the state of shape drawing
 #[derive(Resource, Debug, Default)]
@@ -15,17 +17,84 @@ pub struct ShapeDrawingState {
     pub current_shape: Option<Entity>,
     /// The currently selected shape type
     pub selected_shape_type: Option<QShapeType>,
+    /// Length or coordinates typed for the second point of the shape being drawn
+    pub typed_input: String,
+    /// Second point entered by typing, placed like a click on the next update
+    pub typed_point: Option<QVec2>,
 }
 
-#[derive(Resource, Debug, Clone)]
+impl ShapeDrawingState {
+    /// Whether typed numbers go to the shape being drawn: after the first point of a line, bbox or circle
+    pub fn accepts_typed_input(&self) -> bool {
+        self.start_position.is_some()
+            && matches!(
+                self.selected_shape_type,
+                Some(QShapeType::QLine | QShapeType::QBbox | QShapeType::QCircle)
+            )
+    }
+}
+
+/// Resource to track the vertex drag of the "Edit Vertices" mode
+#[derive(Resource, Default)]
+pub struct VertexEditState {
+    /// The shape and the index of the vertex being dragged
+    pub dragging: Option<(Entity, usize)>,
+    /// The dragged shape before the drag, recorded in the history when the drag ends
+    pub drag_start: Option<SerializableQShape>,
+    /// Time and world position of the last left click, to detect double-clicks
+    pub last_click: Option<(f64, Vec2)>,
+}
+
+/// Handle of the transform gizmo drawn around the selected shapes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransformHandle {
+    /// Ring around the selection, rotates it around its center
+    Rotate,
+    /// Corners of the selection, scale it uniformly around its center
+    Scale,
+}
+
+/// A drag of one of the transform gizmo handles
+pub struct TransformDrag {
+    pub handle: TransformHandle,
+    /// Center of the selection the shapes are rotated and scaled around
+    pub pivot: Vec2,
+    /// Cursor position where the drag started
+    pub start_cursor: Vec2,
+    /// Rotation in radians and scale factor currently applied to the shapes
+    pub applied: (f32, f32),
+    /// Selected shapes before the drag, transformed from scratch every frame so errors don't accumulate
+    pub shapes: Vec<(Entity, SerializableQShape)>,
+}
+
+/// Resource to track the transform gizmo
+#[derive(Resource, Default)]
+pub struct TransformGizmoState {
+    pub drag: Option<TransformDrag>,
+}
+
+/// Resource holding the copied shapes
+#[derive(Resource, Default)]
+pub struct ShapeClipboard {
+    /// Records of the copied shapes, at the position they were copied from
+    pub shapes: Vec<SerializableQShape>,
+    /// Number of times the clipboard was pasted, so repeated pastes don't stack on each other
+    pub pastes: u32,
+}
+
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ShapesSettings {
     pub shape_color_selected: Color,
+    /// Angle step in degrees lines are constrained to while drawing with Shift held
+    pub line_angle_step: f32,
 }
 
 impl Default for ShapesSettings {
     fn default() -> Self {
         Self {
             shape_color_selected: Color::srgba(0.0, 0.0, 1.0, 1.0),
+            line_angle_step: 45.0,
         }
     }
 }
@@ -6,16 +6,37 @@
 use std::cmp::Ordering;
 
 use super::{
-    components::{EditorShape, QBboxData, QCircleData, QLineData, QPointData, QPolygonData},
-    resources::ShapeDrawingState,
+    boolean::polygon_boolean,
+    hull::convex_hull,
+    components::{
+        AnyQShape, ClipboardEvent, ConvexHullEvent, DeleteSelectedShapesEvent, EditorShape, PolygonBooleanEvent,
+        QBboxData, QCircleData, QLineData, QPointData, QPolygonData, SetShapeDataEvent, ShapeLayer, shape_type_name,
+    },
+    resources::{
+        ShapeClipboard, ShapeDrawingState, TransformDrag, TransformGizmoState, TransformHandle, VertexEditState,
+    },
 };
+use crate::camera::components::CameraMovement;
+use crate::camera::resources::CameraSettings;
+use crate::coordinate::resources::{CoordinateSettings, SnapTarget};
+use crate::coordinate::systems::visible_area;
+use crate::history::resources::EditHistory;
+use crate::save_load::components::{SerializableQShape, SerializableQShapeData};
+use crate::save_load::systems::{
+    ShapeSaveData, collect_shapes, collision_shape_for, degenerate_reason, insert_scene_shape, shape_record,
+    spawn_scene_shape, transform_shape, translate_shape,
+};
+use crate::tools::resources::EditorTools;
+use crate::ui::resources::Notifications;
 use crate::{
     qphysics::{components::*, resources::QPhysicsDebugConfig}, shapes::{components::LineAppearance, resources::ShapesSettings}, ui::resources::UiState, util
 };
 use bevy::{ecs::system::command, prelude::*};
-use bevy_egui::EguiContexts;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy_egui::{EguiContexts, egui};
 use qgeometry::shape::{QBbox, QCircle, QLine, QPoint, QPolygon, QShapeCommon, QShapeType};
 use qmath::prelude::*;
+use qmath::dir::QDir;
 use qmath::vec2::QVec2;
 
 /// System to handle shape interaction (creation, selection, etc.)
@@ -23,12 +44,18 @@ pub fn handle_shape_interaction(
     mut commands: Commands,
     mut polygon_query: Query<&mut QPolygonData>,
     mouse_button_input: Res<ButtonInput<MouseButton>>,
-    windows: Query<&Window>,
-    camera_q: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
     ui_state: Res<UiState>,
     mut shape_drawing_state: ResMut<ShapeDrawingState>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    camera_settings: Res<CameraSettings>,
+    snap_target: Res<SnapTarget>,
+    shapes_settings: Res<ShapesSettings>,
+    mut history: ResMut<EditHistory>,
     mut egui_contexts: EguiContexts, // Add EguiContexts to check if mouse is over UI
 ) {
+    // A point typed while drawing places the shape like a click there
+    let typed_point = shape_drawing_state.typed_point.take();
+
     // Check if egui wants pointer input (mouse is over UI)
     let mouse_over_ui = match egui_contexts.ctx_mut() {
         Ok(ctx) => ctx.wants_pointer_input(),
@@ -36,7 +63,12 @@ pub fn handle_shape_interaction(
     };
 
     // If mouse is over UI, don't handle shape interaction
-    if mouse_over_ui {
+    if mouse_over_ui && typed_point.is_none() {
+        return;
+    }
+
+    // Left drags pan the view while Space is held
+    if camera_settings.space_pan_active(&keyboard_input) {
         return;
     }
 
@@ -54,41 +86,19 @@ pub fn handle_shape_interaction(
         shape_drawing_state.selected_shape_type = ui_state.selected_shape;
     }
 
-    // Get the primary window reference
-    let window = if let Ok(window) = windows.single() {
-        window
-    } else {
-        return;
-    };
-
-    // Get camera transform for proper coordinate conversion
-    let (camera, camera_transform) = if let Ok((camera, camera_transform)) = camera_q.single() {
-        (camera, camera_transform)
-    } else {
-        return;
-    };
-
-    // Convert screen coordinates to world coordinates properly using the camera
-    let cursor_pos = if let Some(cursor_pos) = window.cursor_position() {
-        cursor_pos
-    } else {
+    // The snap target is where the snap preview is drawn, and is missing while the cursor is outside
+    // the main viewport
+    // Typed points and snapped shape features are kept exact
+    let Some(mut qworld_pos) = typed_point.or(snap_target.qposition()) else {
         return;
     };
-
-    let world_pos = if let Ok(world_pos) = camera.viewport_to_world_2d(camera_transform, cursor_pos) {
-        world_pos
-    } else {
-        // Fallback calculation if camera conversion fails
-        Vec2::new(
-            cursor_pos.x - window.width() / 2.0,
-            window.height() / 2.0 - cursor_pos.y,
-        )
-    };
-
-    // Convert world coordinates to QVec2
-    let mut qworld_pos = QVec2::new(Q64::from_num(world_pos.x), Q64::from_num(world_pos.y));
-    if ui_state.enable_snap {
-        qworld_pos = qworld_pos.round();
+    // Shift keeps the line being drawn at a multiple of the angle step
+    if let (Some(QShapeType::QLine), Some(start), true) = (
+        shape_drawing_state.selected_shape_type,
+        shape_drawing_state.start_position,
+        typed_point.is_none() && keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]),
+    ) {
+        qworld_pos = constrain_angle(start, qworld_pos, shapes_settings.line_angle_step);
     }
     let qworld_point = QPoint::new(qworld_pos);
 
@@ -143,6 +153,10 @@ pub fn handle_shape_interaction(
                             let dx = qworld_pos.x - start_pos.x;
                             let dy = qworld_pos.y - start_pos.y;
                             let radius = (dx * dx + dy * dy).sqrt();
+                            // Offsets of a few EPS square to zero, leaving the circle without a radius
+                            if radius <= Q64::ZERO {
+                                return;
+                            }
                             let new_circle = QCircle::new(start_point, Q64::from_num(radius));
                             commands.entity(entity).insert(QCircleData { data: new_circle })
                                 .insert(QCollisionShape::Circle(new_circle));
@@ -195,27 +209,44 @@ pub fn handle_shape_interaction(
         }
     }
 
-    // Handle right mouse button for ending polygon drawing
-    if mouse_button_input.just_pressed(MouseButton::Right) {
-        if shape_drawing_state.current_shape.is_some() && shape_type == QShapeType::QPolygon {
+    // Handle right mouse button for ending polygon drawing, or Enter while the right button pans the view
+    let finish = if camera_settings.right_button_pans() {
+        keyboard_input.just_pressed(KeyCode::Enter)
+            && !egui_contexts.ctx_mut().is_ok_and(|ctx| ctx.wants_keyboard_input())
+    } else {
+        mouse_button_input.just_pressed(MouseButton::Right)
+    };
+    if finish {
+        if let Some(entity) = shape_drawing_state.current_shape.filter(|_| shape_type == QShapeType::QPolygon) {
             // End polygon drawing
             shape_drawing_state.start_position = None;
             shape_drawing_state.current_shape = None;
+            // A polygon ended before it has an area is discarded rather than left in the scene
+            let degenerate = polygon_query.get(entity).ok().and_then(|polygon| {
+                degenerate_reason(&SerializableQShapeData::Polygon(polygon.clone()))
+            });
+            if let Some(reason) = degenerate {
+                info!("Discarded the polygon being drawn: {}", reason);
+                commands.entity(entity).despawn();
+                return;
+            }
+            history.record_created(format!("Add {}", shape_type_name(shape_type)), vec![entity]);
             return;
         }
     }
 
     // Handle left mouse button for shape creation
-    if mouse_button_input.just_pressed(MouseButton::Left) {
+    if mouse_button_input.just_pressed(MouseButton::Left) || typed_point.is_some() {
         if shape_drawing_state.current_shape.is_some() {
             // Handle ongoing shape drawing
             match shape_type {
                 QShapeType::QPoint | QShapeType::QLine | QShapeType::QBbox | QShapeType::QCircle => {
                     // Finalize the current shape
-                    if let Some(_entity) = shape_drawing_state.current_shape {
+                    if let Some(entity) = shape_drawing_state.current_shape {
                         // Finalize shape properties based on second click
                         shape_drawing_state.start_position = None;
                         shape_drawing_state.current_shape = None;
+                        history.record_created(format!("Add {}", shape_type_name(shape_type)), vec![entity]);
                     }
                 }
                 QShapeType::QPolygon => {
@@ -337,6 +368,859 @@ pub fn handle_shape_interaction(
     }
 }
 
+/// Point on the ray from `start` at the multiple of `step_degrees` closest to the direction of `end`, as far
+/// along it as `end` reaches. The projection is done in fixed-point so the endpoint is exact on the axes.
+fn constrain_angle(start: QVec2, end: QVec2, step_degrees: f32) -> QVec2 {
+    let offset = end.saturating_sub(start);
+    if step_degrees <= 0.0 || offset == QVec2::ZERO {
+        return end;
+    }
+    let step = (step_degrees as f64).to_radians();
+    let angle = offset.y.to_num::<f64>().atan2(offset.x.to_num::<f64>());
+    let snapped = (angle / step).round() * step;
+    let direction = QVec2::new(Q64::from_num(snapped.cos()), Q64::from_num(snapped.sin()));
+    let length = offset.x * direction.x + offset.y * direction.y;
+    start.saturating_add(QVec2::new(direction.x * length, direction.y * length))
+}
+
+/// Second point of the shape being drawn from typed text, in display units and local coordinates like the
+/// status bar: `x,y` is a position, `@dx,dy` an offset from the first point and a single number a length
+/// towards `towards`. Bboxes grow up and to the right, so a length gives a square.
+fn typed_point(
+    text: &str, start: QVec2, towards: QVec2, shape_type: QShapeType, coordinate_settings: &CoordinateSettings,
+) -> Result<QVec2, String> {
+    let length = |value: &str| -> Result<Q64, String> {
+        let display: f64 = value
+            .trim()
+            .parse()
+            .map_err(|_| format!("'{}' is not a number", value.trim()))?;
+        // Converted in f64 rather than with `DisplayUnit::to_world` so typed decimals stay exact
+        let per_world_unit = coordinate_settings.unit.per_world_unit as f64;
+        let world = if per_world_unit == 0.0 {
+            display
+        } else {
+            display / per_world_unit
+        };
+        util::checked_q64(world).map_err(|_| format!("{} is out of the Q64 range", display))
+    };
+    let (relative, text) = match text.trim().strip_prefix('@') {
+        Some(offset) => (true, offset),
+        None => (false, text),
+    };
+    if let Some((x, y)) = text.split_once(',') {
+        let offset = QVec2::new(length(x)?, length(y)?);
+        let origin = if relative {
+            start
+        } else {
+            let origin = coordinate_settings.origin_offset;
+            QVec2::new(Q64::from_num(origin.x), Q64::from_num(origin.y))
+        };
+        return Ok(origin.saturating_add(offset));
+    }
+    if relative {
+        return Err("an offset needs two values, '@dx,dy'".to_string());
+    }
+
+    let distance = length(text)?;
+    if shape_type == QShapeType::QBbox {
+        return Ok(start.saturating_add(QVec2::new(distance, distance)));
+    }
+    let direction = towards.saturating_sub(start);
+    let direction_length = direction.length();
+    if direction_length == Q64::ZERO {
+        return Ok(start.saturating_add(QVec2::new(distance, Q64::ZERO)));
+    }
+    Ok(start.saturating_add(QVec2::new(
+        direction.x * distance / direction_length,
+        direction.y * distance / direction_length,
+    )))
+}
+
+/// System to type the second point of a line, bbox or circle after placing the first: digits, `,`, `.`,
+/// `-` and `@` build the entry, Backspace and Escape edit it and Enter places the point
+pub fn typed_drawing_input(
+    mut keyboard_events: MessageReader<KeyboardInput>, keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut shape_drawing_state: ResMut<ShapeDrawingState>, snap_target: Res<SnapTarget>,
+    coordinate_settings: Res<CoordinateSettings>, shapes_settings: Res<ShapesSettings>,
+    mut notifications: ResMut<Notifications>, mut egui_contexts: EguiContexts,
+) {
+    if !shape_drawing_state.accepts_typed_input() {
+        shape_drawing_state.typed_input.clear();
+        keyboard_events.clear();
+        return;
+    }
+    if egui_contexts.ctx_mut().is_ok_and(|ctx| ctx.wants_keyboard_input()) {
+        keyboard_events.clear();
+        return;
+    }
+
+    let mut submit = false;
+    for event in keyboard_events.read().filter(|event| event.state.is_pressed()) {
+        match &event.logical_key {
+            Key::Character(text) => shape_drawing_state.typed_input.extend(
+                text.chars()
+                    .filter(|c| c.is_ascii_digit() || matches!(c, ',' | '.' | '-' | '@')),
+            ),
+            Key::Backspace => {
+                shape_drawing_state.typed_input.pop();
+            }
+            Key::Escape => shape_drawing_state.typed_input.clear(),
+            Key::Enter => submit = true,
+            _ => {}
+        }
+    }
+    if !submit || shape_drawing_state.typed_input.is_empty() {
+        return;
+    }
+
+    let (Some(start), Some(shape_type)) = (
+        shape_drawing_state.start_position,
+        shape_drawing_state.selected_shape_type,
+    ) else {
+        return;
+    };
+    // A typed length follows the cursor's direction, kept to the angle step while Shift is held
+    let mut towards = snap_target.qposition().unwrap_or(start);
+    if shape_type == QShapeType::QLine && keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        towards = constrain_angle(start, towards, shapes_settings.line_angle_step);
+    }
+
+    let text = std::mem::take(&mut shape_drawing_state.typed_input);
+    match typed_point(&text, start, towards, shape_type, &coordinate_settings) {
+        Ok(point) if point == start => notifications.push("The typed point is the first point", true),
+        Ok(point) if shape_type == QShapeType::QBbox && (point.x <= start.x || point.y <= start.y) => {
+            notifications.push(
+                "A bbox needs its second corner above and to the right of the first",
+                true,
+            );
+        }
+        Ok(point) => shape_drawing_state.typed_point = Some(point),
+        Err(error) => notifications.push(format!("Invalid entry '{}': {}", text, error), true),
+    }
+}
+
+/// System to show the entry being typed for the second point next to the cursor
+pub fn draw_typed_input(mut contexts: EguiContexts, shape_drawing_state: Res<ShapeDrawingState>) {
+    if shape_drawing_state.typed_input.is_empty() {
+        return;
+    }
+    let Ok(ctx) = contexts.ctx_mut() else {
+        return;
+    };
+    let Some(pointer) = ctx.pointer_latest_pos() else {
+        return;
+    };
+    ctx.layer_painter(egui::LayerId::background()).text(
+        pointer + egui::vec2(16.0, 16.0),
+        egui::Align2::LEFT_TOP,
+        format!("{} (Enter)", shape_drawing_state.typed_input),
+        egui::FontId::monospace(14.0),
+        egui::Color32::WHITE,
+    );
+}
+
+/// System to delete the selected shapes with the Delete key
+pub fn delete_selected_shortcut(
+    mut commands: Commands, keyboard_input: Res<ButtonInput<KeyCode>>, mut egui_contexts: EguiContexts,
+) {
+    if egui_contexts.ctx_mut().is_ok_and(|ctx| ctx.wants_keyboard_input()) {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::Delete) {
+        commands.write_message(DeleteSelectedShapesEvent);
+    }
+}
+
+/// System to delete the selected shapes, recording them so the deletion can be undone
+pub fn handle_delete_selected_shapes(
+    mut commands: Commands, mut events: MessageReader<DeleteSelectedShapesEvent>,
+    shapes: Query<(Entity, &EditorShape)>, shapes_query: Query<ShapeSaveData>, mut history: ResMut<EditHistory>,
+) {
+    if events.read().count() == 0 {
+        return;
+    }
+
+    let deleted: Vec<_> = shapes
+        .iter()
+        .filter(|(_, shape)| shape.selected)
+        .filter_map(|(entity, _)| Some((entity, shape_record(&shapes_query, entity)?)))
+        .collect();
+    let label = match deleted.as_slice() {
+        [] => return,
+        [(_, shape)] => format!("Delete {}", shape_type_name(shape.editor_shape.shape_type)),
+        _ => format!("Delete {} Shapes", deleted.len()),
+    };
+    for (entity, _) in &deleted {
+        commands.entity(*entity).despawn();
+    }
+    history.record_deleted(label, deleted);
+}
+
+/// Time in seconds within which consecutive edits of the same shape are undone together
+const SHAPE_EDIT_MERGE_SECONDS: f64 = 1.0;
+
+/// System to replace the geometry of shapes, keeping their collision shapes in sync. Edits of the same
+/// shape in quick succession, like dragging a value in the properties panel, form a single history entry.
+pub fn handle_set_shape_data(
+    mut commands: Commands, mut events: MessageReader<SetShapeDataEvent>, shapes_query: Query<ShapeSaveData>,
+    mut history: ResMut<EditHistory>, time: Res<Time<Real>>, mut last_edit: Local<Option<(Entity, f64)>>,
+) {
+    let now = time.elapsed_secs_f64();
+    for event in events.read() {
+        let Some(mut shape) = shape_record(&shapes_query, event.entity) else {
+            continue;
+        };
+        let label = format!("Edit {}", shape_type_name(shape.editor_shape.shape_type));
+        let recent = last_edit
+            .is_some_and(|(entity, time)| entity == event.entity && now - time <= SHAPE_EDIT_MERGE_SECONDS);
+        let merged = recent && history.redo_stack.is_empty() && history.undo_label() == Some(label.as_str());
+        if !merged {
+            history.record_modified(label, vec![(event.entity, shape.clone())]);
+        }
+        *last_edit = Some((event.entity, now));
+
+        shape.data = event.data.clone();
+        if shape.physics.collision_shape.is_some() {
+            shape.physics.collision_shape = Some(collision_shape_for(&shape.data));
+        }
+        insert_scene_shape(&mut commands.entity(event.entity), shape);
+    }
+}
+
+/// System to combine two polygons with a boolean operation. The results are spawned as plain polygons on the
+/// selected layer and replace the selection.
+pub fn handle_polygon_boolean(
+    mut commands: Commands, mut events: MessageReader<PolygonBooleanEvent>,
+    polygons: Query<(&EditorShape, &QPolygonData)>, ui_state: Res<UiState>, mut history: ResMut<EditHistory>,
+    mut notifications: ResMut<Notifications>,
+) {
+    for event in events.read() {
+        let (Ok((shape_a, polygon_a)), Ok((shape_b, polygon_b))) = (polygons.get(event.a), polygons.get(event.b))
+        else {
+            notifications.push(format!("{} needs two polygons", event.op.label()), true);
+            continue;
+        };
+        let points = |polygon: &QPolygonData| polygon.data.points().iter().map(|point| point.pos()).collect::<Vec<_>>();
+        let results = match polygon_boolean(&points(polygon_a), &points(polygon_b), event.op) {
+            Ok(results) if results.is_empty() => {
+                notifications.push(format!("{}: the result is empty", event.op.label()), true);
+                continue;
+            }
+            Ok(results) => results,
+            Err(e) => {
+                notifications.push(format!("{} failed: {}", event.op.label(), e), true);
+                continue;
+            }
+        };
+
+        for (entity, shape) in [(event.a, shape_a), (event.b, shape_b)] {
+            commands.entity(entity).insert(EditorShape {
+                selected: false,
+                ..shape.clone()
+            });
+        }
+        let spawned = results
+            .into_iter()
+            .map(|points| {
+                let shape = SerializableQShape {
+                    editor_shape: EditorShape {
+                        layer: ui_state.selected_layer,
+                        shape_type: QShapeType::QPolygon,
+                        selected: true,
+                        ..default()
+                    },
+                    data: SerializableQShapeData::Polygon(QPolygonData {
+                        data: QPolygon::new(points.into_iter().map(QPoint::new).collect()),
+                    }),
+                    physics: default(),
+                };
+                spawn_scene_shape(&mut commands, shape)
+            })
+            .collect();
+        history.record_created(event.op.label(), spawned);
+    }
+}
+
+/// Points a shape contributes to a convex hull, with circles approximated by their sample points
+fn hull_points(data: &SerializableQShapeData) -> Vec<QVec2> {
+    match data {
+        SerializableQShapeData::Point(point) => vec![point.data.pos()],
+        SerializableQShapeData::Line(line) => vec![line.data.start().pos(), line.data.end().pos()],
+        SerializableQShapeData::Bbox(bbox) => {
+            let (min, max) = (bbox.data.left_bottom().pos(), bbox.data.right_top().pos());
+            vec![min, QVec2::new(max.x, min.y), max, QVec2::new(min.x, max.y)]
+        }
+        SerializableQShapeData::Circle(circle) => circle.data.points().iter().map(|point| point.pos()).collect(),
+        SerializableQShapeData::Polygon(polygon) => polygon.data.points().iter().map(|point| point.pos()).collect(),
+    }
+}
+
+/// System to spawn the convex hull of the selected shapes as a polygon on the Generated layer
+pub fn handle_convex_hull(
+    mut commands: Commands, mut events: MessageReader<ConvexHullEvent>, shapes_query: Query<ShapeSaveData>,
+    mut history: ResMut<EditHistory>, mut notifications: ResMut<Notifications>,
+) {
+    if events.read().count() == 0 {
+        return;
+    }
+
+    let points = collect_shapes(&shapes_query, |shape| shape.selected)
+        .iter()
+        .flat_map(|shape| hull_points(&shape.data))
+        .collect();
+    let Some(hull) = convex_hull(points) else {
+        notifications.push("Convex Hull needs selected shapes that don't lie on one line", true);
+        return;
+    };
+    let shape = SerializableQShape {
+        editor_shape: EditorShape {
+            layer: ShapeLayer::Generated,
+            shape_type: QShapeType::QPolygon,
+            ..default()
+        },
+        data: SerializableQShapeData::Polygon(QPolygonData {
+            data: QPolygon::new(hull.into_iter().map(QPoint::new).collect()),
+        }),
+        physics: default(),
+    };
+    let entity = spawn_scene_shape(&mut commands, shape);
+    history.record_created("Convex Hull", vec![entity]);
+}
+
+/// Offset in world units between pasted or duplicated shapes and their originals
+const PASTE_OFFSET: f32 = 1.0;
+
+/// System to copy with Ctrl+C, paste with Ctrl+V and duplicate with Ctrl+D
+pub fn clipboard_shortcuts(
+    mut commands: Commands, keyboard_input: Res<ButtonInput<KeyCode>>, mut egui_contexts: EguiContexts,
+) {
+    if egui_contexts.ctx_mut().is_ok_and(|ctx| ctx.wants_keyboard_input()) {
+        return;
+    }
+    let modifiers = [KeyCode::ControlLeft, KeyCode::ControlRight, KeyCode::SuperLeft, KeyCode::SuperRight];
+    if !keyboard_input.any_pressed(modifiers) {
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::KeyC) {
+        commands.write_message(ClipboardEvent::Copy);
+    } else if keyboard_input.just_pressed(KeyCode::KeyV) {
+        commands.write_message(ClipboardEvent::Paste);
+    } else if keyboard_input.just_pressed(KeyCode::KeyD) {
+        commands.write_message(ClipboardEvent::Duplicate);
+    }
+}
+
+/// System to copy, paste and duplicate shapes. The new shapes replace the selection and their
+/// creation is recorded in the history.
+pub fn handle_clipboard_events(
+    mut commands: Commands, mut events: MessageReader<ClipboardEvent>, mut clipboard: ResMut<ShapeClipboard>,
+    shapes: Query<(Entity, &EditorShape)>, shapes_query: Query<ShapeSaveData>, mut history: ResMut<EditHistory>,
+) {
+    for &event in events.read() {
+        let selected = || collect_shapes(&shapes_query, |shape| shape.selected);
+        let (copies, offset) = match event {
+            ClipboardEvent::Copy => {
+                clipboard.shapes = selected();
+                clipboard.pastes = 0;
+                continue;
+            }
+            ClipboardEvent::Paste => {
+                clipboard.pastes += 1;
+                (clipboard.shapes.clone(), PASTE_OFFSET * clipboard.pastes as f32)
+            }
+            ClipboardEvent::Duplicate => (selected(), PASTE_OFFSET),
+        };
+        if copies.is_empty() {
+            continue;
+        }
+
+        for (entity, shape) in shapes.iter().filter(|(_, shape)| shape.selected) {
+            let mut shape = shape.clone();
+            shape.selected = false;
+            commands.entity(entity).insert(shape);
+        }
+        // Shift right and down, like copies in most drawing tools
+        let offset = QVec2::new(Q64::from_num(offset), Q64::from_num(-offset));
+        let label = match copies.as_slice() {
+            [shape] => format!("{} {}", event_label(event), shape_type_name(shape.editor_shape.shape_type)),
+            _ => format!("{} {} Shapes", event_label(event), copies.len()),
+        };
+        let spawned = copies
+            .into_iter()
+            .map(|shape| {
+                let mut shape = translate_shape(shape, offset);
+                shape.editor_shape.selected = true;
+                spawn_scene_shape(&mut commands, shape)
+            })
+            .collect();
+        history.record_created(label, spawned);
+    }
+}
+
+/// Verb used in the history labels of clipboard edits
+fn event_label(event: ClipboardEvent) -> &'static str {
+    match event {
+        ClipboardEvent::Copy => "Copy",
+        ClipboardEvent::Paste => "Paste",
+        ClipboardEvent::Duplicate => "Duplicate",
+    }
+}
+
+/// Distance in pixels within which the cursor picks a vertex handle or an edge
+const VERTEX_PICK_PIXELS: f32 = 8.0;
+/// Size in pixels of the drawn vertex handles
+const VERTEX_HANDLE_PIXELS: f32 = 8.0;
+/// Maximum time in seconds between the two clicks of a double-click
+const DOUBLE_CLICK_SECONDS: f64 = 0.4;
+
+/// Vertices of a line or polygon, `None` for other shapes
+fn shape_vertices(data: &SerializableQShapeData) -> Option<Vec<QPoint>> {
+    match data {
+        SerializableQShapeData::Line(line) => {
+            Some(vec![QPoint::new(line.data.start().pos()), QPoint::new(line.data.end().pos())])
+        }
+        SerializableQShapeData::Polygon(polygon) => Some(polygon.data.points().clone()),
+        _ => None,
+    }
+}
+
+/// Shape record with its line or polygon vertices replaced, keeping its collision shape in sync
+fn with_vertices(mut shape: SerializableQShape, points: Vec<QPoint>) -> SerializableQShape {
+    shape.data = match shape.data {
+        SerializableQShapeData::Line(_) => SerializableQShapeData::Line(QLineData {
+            data: QLine::new(points[0], points[1]),
+        }),
+        SerializableQShapeData::Polygon(_) => SerializableQShapeData::Polygon(QPolygonData {
+            data: QPolygon::new(points),
+        }),
+        data => data,
+    };
+    if shape.physics.collision_shape.is_some() {
+        shape.physics.collision_shape = Some(collision_shape_for(&shape.data));
+    }
+    shape
+}
+
+/// Distance from `point` to the segment between `start` and `end`
+fn segment_distance(point: Vec2, start: Vec2, end: Vec2) -> f32 {
+    let edge = end - start;
+    if edge.length_squared() == 0.0 {
+        return point.distance(start);
+    }
+    let t = ((point - start).dot(edge) / edge.length_squared()).clamp(0.0, 1.0);
+    point.distance(start + edge * t)
+}
+
+/// Selected lines and polygons whose vertices can be edited, with their records and vertices
+fn editable_shapes(
+    shapes: &Query<(Entity, &EditorShape)>, shapes_query: &Query<ShapeSaveData>, ui_state: &UiState,
+) -> Vec<(Entity, SerializableQShape, Vec<Vec2>)> {
+    shapes
+        .iter()
+        .filter(|(_, shape)| shape.selected)
+        .filter(|(_, shape)| !ui_state.only_show_select_layer || shape.layer == ui_state.selected_layer)
+        .filter_map(|(entity, _)| {
+            let record = shape_record(shapes_query, entity)?;
+            let points = shape_vertices(&record.data)?;
+            let vertices = points.iter().map(|point| util::qvec2vec(point.pos())).collect();
+            Some((entity, record, vertices))
+        })
+        .collect()
+}
+
+/// Vertex within `radius` of the cursor that is closest to it, as the shape index and vertex index
+fn pick_vertex(
+    editable: &[(Entity, SerializableQShape, Vec<Vec2>)], cursor: Vec2, radius: f32,
+) -> Option<(usize, usize)> {
+    editable
+        .iter()
+        .enumerate()
+        .flat_map(|(shape_index, (_, _, vertices))| {
+            vertices.iter().enumerate().map(move |(index, vertex)| (shape_index, index, vertex.distance(cursor)))
+        })
+        .filter(|(_, _, distance)| *distance <= radius)
+        .min_by(|a, b| a.2.total_cmp(&b.2))
+        .map(|(shape_index, index, _)| (shape_index, index))
+}
+
+/// System for the "Edit Vertices" mode: drag the vertices of the selected lines and polygons, insert a
+/// polygon vertex by double-clicking an edge and delete one with a right-click, or an Alt+click while the
+/// right button pans the view
+pub fn handle_vertex_editing(
+    mut commands: Commands, mut ui_state: ResMut<UiState>, mut vertex_edit: ResMut<VertexEditState>,
+    snap_target: Res<SnapTarget>, mouse_button_input: Res<ButtonInput<MouseButton>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>, camera_settings: Res<CameraSettings>, time: Res<Time<Real>>,
+    camera_query: Query<&GlobalTransform, With<CameraMovement>>, shapes: Query<(Entity, &EditorShape)>,
+    shapes_query: Query<ShapeSaveData>, mut history: ResMut<EditHistory>, mut egui_contexts: EguiContexts,
+) {
+    // Drawing a shape ends the mode
+    if ui_state.selected_shape.is_some() {
+        ui_state.edit_vertices = false;
+    }
+    let keyboard_free = !egui_contexts.ctx_mut().is_ok_and(|ctx| ctx.wants_keyboard_input());
+    if keyboard_free && keyboard_input.just_pressed(KeyCode::Escape) {
+        ui_state.edit_vertices = false;
+    }
+
+    // End the drag on release anywhere, or when the mode was left mid-drag
+    if mouse_button_input.just_released(MouseButton::Left) || !ui_state.edit_vertices {
+        if let (Some((entity, _)), Some(before)) = (vertex_edit.dragging.take(), vertex_edit.drag_start.take()) {
+            // Clicking a vertex without moving it isn't an edit
+            let positions = |data: &SerializableQShapeData| {
+                shape_vertices(data).map(|points| points.iter().map(|point| point.pos()).collect::<Vec<_>>())
+            };
+            let moved = shape_record(&shapes_query, entity)
+                .is_some_and(|after| positions(&after.data) != positions(&before.data));
+            if moved {
+                history.record_modified("Move Vertex", vec![(entity, before)]);
+            }
+        }
+    }
+    if !ui_state.edit_vertices {
+        vertex_edit.last_click = None;
+        return;
+    }
+
+    // The snap target is missing while the cursor is over the UI or outside the main viewport
+    let (Some(qposition), Some(cursor)) = (snap_target.qposition(), snap_target.cursor) else {
+        return;
+    };
+
+    if let Some((entity, index)) = vertex_edit.dragging {
+        if !mouse_button_input.pressed(MouseButton::Left) {
+            return;
+        }
+        let Some(record) = shape_record(&shapes_query, entity) else {
+            vertex_edit.dragging = None;
+            vertex_edit.drag_start = None;
+            return;
+        };
+        let Some(mut points) = shape_vertices(&record.data) else {
+            return;
+        };
+        if points[index].pos() != qposition {
+            points[index] = QPoint::new(qposition);
+            insert_scene_shape(&mut commands.entity(entity), with_vertices(record, points));
+        }
+        return;
+    }
+
+    if camera_settings.space_pan_active(&keyboard_input) {
+        return;
+    }
+    let camera_scale = camera_query.single().map_or(1.0, |transform| transform.compute_transform().scale.x);
+    let radius = VERTEX_PICK_PIXELS * camera_scale;
+    let editable = editable_shapes(&shapes, &shapes_query, &ui_state);
+
+    // Right clicks delete vertices, or Alt+clicks while the right button pans the view
+    let delete_click = if camera_settings.right_button_pans() {
+        mouse_button_input.just_pressed(MouseButton::Left)
+            && keyboard_input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight])
+    } else {
+        mouse_button_input.just_pressed(MouseButton::Right)
+    };
+    if delete_click {
+        let Some((shape_index, index)) = pick_vertex(&editable, cursor, radius) else {
+            return;
+        };
+        let (entity, record, _) = &editable[shape_index];
+        let Some(mut points) = shape_vertices(&record.data) else {
+            return;
+        };
+        // Lines always keep both ends, polygons at least a triangle
+        if !matches!(record.data, SerializableQShapeData::Polygon(_)) || points.len() <= 3 {
+            return;
+        }
+        points.remove(index);
+        history.record_modified("Delete Vertex", vec![(*entity, record.clone())]);
+        insert_scene_shape(&mut commands.entity(*entity), with_vertices(record.clone(), points));
+        return;
+    }
+
+    if !mouse_button_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let now = time.elapsed_secs_f64();
+    let double_click = vertex_edit
+        .last_click
+        .is_some_and(|(time, last)| now - time <= DOUBLE_CLICK_SECONDS && last.distance(cursor) <= radius);
+    vertex_edit.last_click = Some((now, cursor));
+
+    if double_click {
+        // Insert a vertex on the closest polygon edge, where the second click landed
+        let closest_edge = editable
+            .iter()
+            .filter(|(_, record, _)| matches!(record.data, SerializableQShapeData::Polygon(_)))
+            .flat_map(|(entity, record, vertices)| {
+                (0..vertices.len()).map(move |index| {
+                    let next = vertices[(index + 1) % vertices.len()];
+                    (*entity, record, index, segment_distance(cursor, vertices[index], next))
+                })
+            })
+            .filter(|(.., distance)| *distance <= radius)
+            .min_by(|a, b| a.3.total_cmp(&b.3));
+        if let Some((entity, record, index, _)) = closest_edge {
+            if let Some(mut points) = shape_vertices(&record.data) {
+                points.insert(index + 1, QPoint::new(qposition));
+                history.record_modified("Insert Vertex", vec![(entity, record.clone())]);
+                insert_scene_shape(&mut commands.entity(entity), with_vertices(record.clone(), points));
+            }
+        }
+        vertex_edit.last_click = None;
+        return;
+    }
+
+    if let Some((shape_index, index)) = pick_vertex(&editable, cursor, radius) {
+        let (entity, record, _) = &editable[shape_index];
+        vertex_edit.dragging = Some((*entity, index));
+        vertex_edit.drag_start = Some(record.clone());
+    }
+}
+
+/// System to draw the vertex handles of the "Edit Vertices" mode
+pub fn draw_vertex_handles(
+    mut gizmos: Gizmos, ui_state: Res<UiState>, vertex_edit: Res<VertexEditState>, snap_target: Res<SnapTarget>,
+    camera_query: Query<&GlobalTransform, With<CameraMovement>>, shapes: Query<(Entity, &EditorShape)>,
+    shapes_query: Query<ShapeSaveData>, shapes_setting: Res<ShapesSettings>,
+) {
+    if !ui_state.edit_vertices {
+        return;
+    }
+    let camera_scale = camera_query.single().map_or(1.0, |transform| transform.compute_transform().scale.x);
+    let editable = editable_shapes(&shapes, &shapes_query, &ui_state);
+    let hovered = match vertex_edit.dragging {
+        Some((entity, index)) => {
+            let shape_index = editable.iter().position(|(editable_entity, ..)| *editable_entity == entity);
+            shape_index.map(|shape_index| (shape_index, index))
+        }
+        None => snap_target
+            .cursor
+            .and_then(|cursor| pick_vertex(&editable, cursor, VERTEX_PICK_PIXELS * camera_scale)),
+    };
+
+    let size = Vec2::splat(VERTEX_HANDLE_PIXELS * camera_scale);
+    for (shape_index, (_, _, vertices)) in editable.iter().enumerate() {
+        for (index, vertex) in vertices.iter().enumerate() {
+            if hovered == Some((shape_index, index)) {
+                gizmos.rect_2d(*vertex, size * 1.5, Color::WHITE);
+            } else {
+                gizmos.rect_2d(*vertex, size, shapes_setting.shape_color_selected);
+            }
+        }
+    }
+}
+
+/// Padding in pixels between the selection and the transform gizmo box
+const TRANSFORM_PADDING_PIXELS: f32 = 6.0;
+/// Distance in pixels between the corners of the transform gizmo box and its rotation ring
+const TRANSFORM_RING_PIXELS: f32 = 16.0;
+/// Step rotations snap to while Shift is held
+const ROTATION_SNAP_DEGREES: f32 = 15.0;
+
+/// Points bounding a shape's geometry
+fn shape_extent_points(data: &SerializableQShapeData) -> Vec<Vec2> {
+    match data {
+        SerializableQShapeData::Point(point) => vec![util::qvec2vec(point.data.pos())],
+        SerializableQShapeData::Line(line) => {
+            vec![util::qvec2vec(line.data.start().pos()), util::qvec2vec(line.data.end().pos())]
+        }
+        SerializableQShapeData::Bbox(bbox) => {
+            vec![util::qvec2vec(bbox.data.left_bottom().pos()), util::qvec2vec(bbox.data.right_top().pos())]
+        }
+        SerializableQShapeData::Circle(circle) => {
+            let center = util::qvec2vec(circle.data.center().pos());
+            let radius = Vec2::splat(circle.data.radius().to_num::<f32>());
+            vec![center - radius, center + radius]
+        }
+        SerializableQShapeData::Polygon(polygon) => {
+            polygon.data.points().iter().map(|point| util::qvec2vec(point.pos())).collect()
+        }
+    }
+}
+
+/// Gizmo box around the selected shapes, `None` without a selection
+fn selection_bounds(shapes: &[(Entity, SerializableQShape)], camera_scale: f32) -> Option<Rect> {
+    let mut points = shapes.iter().flat_map(|(_, shape)| shape_extent_points(&shape.data));
+    let first = points.next()?;
+    let bounds = points.fold(Rect::from_center_size(first, Vec2::ZERO), |bounds, point| bounds.union_point(point));
+    Some(bounds.inflate(TRANSFORM_PADDING_PIXELS * camera_scale))
+}
+
+/// Radius of the rotation ring around the gizmo box
+fn rotation_ring_radius(bounds: Rect, camera_scale: f32) -> f32 {
+    bounds.half_size().length() + TRANSFORM_RING_PIXELS * camera_scale
+}
+
+/// Corners of the gizmo box, the scale handles
+fn bounds_corners(bounds: Rect) -> [Vec2; 4] {
+    [bounds.min, Vec2::new(bounds.max.x, bounds.min.y), bounds.max, Vec2::new(bounds.min.x, bounds.max.y)]
+}
+
+/// Gizmo handle under the cursor, corners taking precedence over the ring
+fn pick_transform_handle(bounds: Rect, cursor: Vec2, camera_scale: f32) -> Option<TransformHandle> {
+    let radius = VERTEX_PICK_PIXELS * camera_scale;
+    if bounds_corners(bounds).iter().any(|corner| corner.distance(cursor) <= radius) {
+        Some(TransformHandle::Scale)
+    } else if (cursor.distance(bounds.center()) - rotation_ring_radius(bounds, camera_scale)).abs() <= radius {
+        Some(TransformHandle::Rotate)
+    } else {
+        None
+    }
+}
+
+/// Whether the transform gizmo is shown, which is when no other tool takes the clicks
+fn transform_gizmo_enabled(ui_state: &UiState, tools: Option<&EditorTools>) -> bool {
+    ui_state.selected_shape.is_none()
+        && !ui_state.edit_vertices
+        && tools.is_none_or(|tools| tools.active.is_none())
+}
+
+/// System for the transform gizmo: drag the ring around the selected shapes to rotate them and a corner
+/// to scale them, baking the change into their geometry. Shift snaps the rotation, Escape cancels.
+pub fn handle_transform_gizmo(
+    mut commands: Commands, ui_state: Res<UiState>, mut gizmo_state: ResMut<TransformGizmoState>,
+    snap_target: Res<SnapTarget>, mouse_button_input: Res<ButtonInput<MouseButton>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>, camera_settings: Res<CameraSettings>,
+    camera_query: Query<&GlobalTransform, With<CameraMovement>>, shapes: Query<(Entity, &EditorShape)>,
+    shapes_query: Query<ShapeSaveData>, mut history: ResMut<EditHistory>, tools: Option<Res<EditorTools>>,
+) {
+    let enabled = transform_gizmo_enabled(&ui_state, tools.as_deref());
+    if let Some(drag) = gizmo_state.drag.take() {
+        let changed = drag.applied != (0.0, 1.0);
+        if !enabled || keyboard_input.just_pressed(KeyCode::Escape) {
+            // Cancel, putting the shapes back where they were
+            for (entity, shape) in drag.shapes {
+                if changed && shapes.contains(entity) {
+                    insert_scene_shape(&mut commands.entity(entity), shape);
+                }
+            }
+            return;
+        }
+        if !mouse_button_input.pressed(MouseButton::Left) {
+            if changed {
+                let verb = match drag.handle {
+                    TransformHandle::Rotate => "Rotate",
+                    TransformHandle::Scale => "Scale",
+                };
+                let label = match drag.shapes.as_slice() {
+                    [(_, shape)] => format!("{} {}", verb, shape_type_name(shape.editor_shape.shape_type)),
+                    _ => format!("{} {} Shapes", verb, drag.shapes.len()),
+                };
+                history.record_modified(label, drag.shapes);
+            }
+            return;
+        }
+        gizmo_state.drag = Some(drag_transform(drag, snap_target.cursor, &keyboard_input, &mut commands));
+        return;
+    }
+
+    if !enabled || !mouse_button_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+    // The cursor is missing while over the UI or outside the main viewport
+    let Some(cursor) = snap_target.cursor.filter(|_| !camera_settings.space_pan_active(&keyboard_input)) else {
+        return;
+    };
+    let camera_scale = camera_query.single().map_or(1.0, |transform| transform.compute_transform().scale.x);
+    let selected: Vec<_> = shapes
+        .iter()
+        .filter(|(_, shape)| shape.selected)
+        .filter_map(|(entity, _)| Some((entity, shape_record(&shapes_query, entity)?)))
+        .collect();
+    let Some(bounds) = selection_bounds(&selected, camera_scale) else {
+        return;
+    };
+    if let Some(handle) = pick_transform_handle(bounds, cursor, camera_scale) {
+        gizmo_state.drag = Some(TransformDrag {
+            handle,
+            pivot: bounds.center(),
+            start_cursor: cursor,
+            applied: (0.0, 1.0),
+            shapes: selected,
+        });
+    }
+}
+
+/// Apply the rotation or scale of a gizmo drag for the current cursor position
+fn drag_transform(
+    mut drag: TransformDrag, cursor: Option<Vec2>, keyboard_input: &ButtonInput<KeyCode>, commands: &mut Commands,
+) -> TransformDrag {
+    let Some(cursor) = cursor else {
+        return drag;
+    };
+    let (from, to) = (drag.start_cursor - drag.pivot, cursor - drag.pivot);
+    if from.length() < f32::EPSILON || to.length() < f32::EPSILON {
+        return drag;
+    }
+    let applied = match drag.handle {
+        TransformHandle::Rotate => {
+            let mut angle = from.angle_to(to);
+            if keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+                let step = ROTATION_SNAP_DEGREES.to_radians();
+                angle = (angle / step).round() * step;
+            }
+            (angle, 1.0)
+        }
+        TransformHandle::Scale => (0.0, (to.length() / from.length()).max(0.01)),
+    };
+    if applied == drag.applied {
+        return drag;
+    }
+    drag.applied = applied;
+
+    let (angle, scale) = applied;
+    let pivot = QVec2::new(Q64::from_num(drag.pivot.x), Q64::from_num(drag.pivot.y));
+    let (sin, cos) = angle.sin_cos();
+    let rotation = QDir::new_from_vec(QVec2::new(Q64::from_num(cos), Q64::from_num(sin)));
+    let quarter_turns = angle / std::f32::consts::FRAC_PI_2;
+    let axis_aligned = (quarter_turns - quarter_turns.round()).abs() < 1e-4;
+    for (entity, shape) in &drag.shapes {
+        let shape = transform_shape(shape.clone(), pivot, rotation, Q64::from_num(scale), axis_aligned);
+        insert_scene_shape(&mut commands.entity(*entity), shape);
+    }
+    drag
+}
+
+/// System to draw the transform gizmo around the selected shapes
+pub fn draw_transform_gizmo(
+    mut gizmos: Gizmos, ui_state: Res<UiState>, gizmo_state: Res<TransformGizmoState>, snap_target: Res<SnapTarget>,
+    camera_query: Query<&GlobalTransform, With<CameraMovement>>, shapes: Query<(Entity, &EditorShape)>,
+    shapes_query: Query<ShapeSaveData>, shapes_setting: Res<ShapesSettings>, tools: Option<Res<EditorTools>>,
+) {
+    if !transform_gizmo_enabled(&ui_state, tools.as_deref()) {
+        return;
+    }
+    let camera_scale = camera_query.single().map_or(1.0, |transform| transform.compute_transform().scale.x);
+    let selected: Vec<_> = shapes
+        .iter()
+        .filter(|(_, shape)| shape.selected)
+        .filter_map(|(entity, _)| Some((entity, shape_record(&shapes_query, entity)?)))
+        .collect();
+    let Some(bounds) = selection_bounds(&selected, camera_scale) else {
+        return;
+    };
+
+    let active = match &gizmo_state.drag {
+        Some(drag) => Some(drag.handle),
+        None => snap_target.cursor.and_then(|cursor| pick_transform_handle(bounds, cursor, camera_scale)),
+    };
+    let color = shapes_setting.shape_color_selected;
+    let highlight = |handle| if active == Some(handle) { Color::WHITE } else { color };
+    gizmos.rect_2d(bounds.center(), bounds.size(), color.with_alpha(0.5));
+    gizmos.circle_2d(bounds.center(), rotation_ring_radius(bounds, camera_scale), highlight(TransformHandle::Rotate));
+    let size = Vec2::splat(VERTEX_HANDLE_PIXELS * camera_scale);
+    for corner in bounds_corners(bounds) {
+        gizmos.rect_2d(corner, size, highlight(TransformHandle::Scale));
+    }
+    if let (Some(drag), Some(cursor)) = (&gizmo_state.drag, snap_target.cursor) {
+        gizmos.line_2d(drag.pivot, cursor, Color::WHITE);
+    }
+}
+
+/// World units shapes are grown by before culling, covering point markers and arrowheads
+const CULL_MARGIN: f32 = 0.2;
+
 /// System to draw shapes using gizmos
 pub fn draw_shapes(
     mut gizmos: Gizmos, ui_state: Res<UiState>,
@@ -351,14 +1235,30 @@ pub fn draw_shapes(
         &QTransform
     )>,
     shapes_setting: Res<ShapesSettings>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
 ) {
     fn qvec_to_vec2(v: QVec2) -> Vec2 {
         Vec2::new(v.x.to_num::<f32>(), v.y.to_num::<f32>())
     }
+    // Shapes outside every view aren't drawn, which keeps large scenes fast. The split view and image
+    // exports render through their own cameras, so all of them count.
+    let mut visible = Some(Rect::EMPTY);
+    for (camera, camera_transform) in cameras.iter().filter(|(camera, _)| camera.is_active) {
+        visible = visible.zip(visible_area(camera, camera_transform)).map(|(visible, area)| visible.union(area));
+    }
     for (shape, point_opt, line_opt, bbox_opt, circle_opt, polygon_opt, collision_shape, transform) in shapes.iter() {
         if ui_state.only_show_select_layer && shape.layer != ui_state.selected_layer {
             continue;
         }
+        if let (Some(visible), Some(any_shape)) =
+            (visible, AnyQShape::from_components(point_opt, line_opt, bbox_opt, circle_opt, polygon_opt))
+        {
+            let bbox = any_shape.get_bbox();
+            let (min, max) = (qvec_to_vec2(bbox.left_bottom().pos()), qvec_to_vec2(bbox.right_top().pos()));
+            if visible.intersect(Rect::from_corners(min, max).inflate(CULL_MARGIN)).is_empty() {
+                continue;
+            }
+        }
 
         // Set color based on selection state
         let color = if shape.selected {
@@ -451,7 +1351,7 @@ pub fn draw_shapes(
 fn draw_line(gizmos: &mut Gizmos, start: Vec2, end: Vec2, color: Color, appearance: LineAppearance) {
     gizmos.line_2d(start, end, color);
     match appearance {
-        LineAppearance::Straight => {}
+        LineAppearance::Straight | LineAppearance::Dimension => {}
         LineAppearance::Arrowhead => {
             draw_arrowhead(gizmos, start, end, color);
         }
@@ -0,0 +1,13 @@
+//! Tools module for the 2D geometry editor
+//!
+//! This module provides the extension API for custom editor tools and panels. Downstream crates
+//! implement [`EditorTool`] or [`EditorPanel`] and register them with
+//! [`EditorToolsAppExt`](plugin::EditorToolsAppExt), and the editor lists them in its "Tools" window.
+
+pub mod plugin;
+pub mod resources;
+pub mod systems;
+pub mod traits;
+
+pub use plugin::{EditorToolsAppExt, ToolsPlugin};
+pub use traits::{EditorPanel, EditorTool, ToolInput};
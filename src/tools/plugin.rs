@@ -0,0 +1,40 @@
+//! Tools plugin implementation
+//!
+//! Registers the tool registry and the systems that run the active tool and draw the tools window.
+
+use super::resources::EditorTools;
+use super::systems::{draw_active_tool_gizmos, draw_tools_window, run_active_tool};
+use super::traits::{EditorPanel, EditorTool};
+use bevy::prelude::*;
+use bevy_egui::EguiPrimaryContextPass;
+
+/// `ToolsPlugin` runs the custom tools and panels registered with [`EditorToolsAppExt`].
+pub struct ToolsPlugin;
+
+impl Plugin for ToolsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EditorTools>()
+            .add_systems(Update, (run_active_tool, draw_active_tool_gizmos).chain())
+            .add_systems(EguiPrimaryContextPass, draw_tools_window);
+    }
+}
+
+/// Registration of custom tools and panels on the app
+pub trait EditorToolsAppExt {
+    fn register_editor_tool(&mut self, tool: impl EditorTool) -> &mut Self;
+    fn register_editor_panel(&mut self, panel: impl EditorPanel) -> &mut Self;
+}
+
+impl EditorToolsAppExt for App {
+    fn register_editor_tool(&mut self, tool: impl EditorTool) -> &mut Self {
+        self.init_resource::<EditorTools>();
+        self.world_mut().resource_mut::<EditorTools>().register_tool(tool);
+        self
+    }
+
+    fn register_editor_panel(&mut self, panel: impl EditorPanel) -> &mut Self {
+        self.init_resource::<EditorTools>();
+        self.world_mut().resource_mut::<EditorTools>().register_panel(panel);
+        self
+    }
+}
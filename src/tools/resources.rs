@@ -0,0 +1,60 @@
+//! Tools resources
+
+use super::traits::{EditorPanel, EditorTool, ToolInput};
+use bevy::prelude::*;
+
+/// A registered panel and whether its window is open
+pub struct RegisteredPanel {
+    pub panel: Box<dyn EditorPanel>,
+    pub open: bool,
+}
+
+/// Registry of the custom tools and panels, and the active tool
+#[derive(Resource, Default)]
+pub struct EditorTools {
+    pub tools: Vec<Box<dyn EditorTool>>,
+    pub panels: Vec<RegisteredPanel>,
+    /// Index of the active tool in `tools`
+    pub active: Option<usize>,
+    /// Input the active tool was given this frame
+    pub input: ToolInput,
+}
+
+impl EditorTools {
+    pub fn register_tool(&mut self, tool: impl EditorTool) {
+        self.tools.push(Box::new(tool));
+    }
+
+    pub fn register_panel(&mut self, panel: impl EditorPanel) {
+        self.panels.push(RegisteredPanel {
+            panel: Box::new(panel),
+            open: false,
+        });
+    }
+
+    /// Make the tool at `index` the active tool, deactivating the previous one
+    pub fn activate(&mut self, index: usize, commands: &mut Commands) {
+        if self.active == Some(index) || index >= self.tools.len() {
+            return;
+        }
+        self.deactivate(commands);
+        self.tools[index].activate(commands);
+        self.active = Some(index);
+    }
+
+    /// Deactivate the active tool, if any
+    pub fn deactivate(&mut self, commands: &mut Commands) {
+        if let Some(index) = self.active.take() {
+            self.tools[index].deactivate(commands);
+        }
+        self.input = ToolInput::default();
+    }
+
+    pub fn active_tool(&self) -> Option<&dyn EditorTool> {
+        self.active.map(|index| self.tools[index].as_ref())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty() && self.panels.is_empty()
+    }
+}
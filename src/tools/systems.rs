@@ -0,0 +1,97 @@
+//! Tools systems
+//!
+//! This module defines the systems that feed input to the active tool and draw the tools window and
+//! the registered panels.
+
+use super::resources::EditorTools;
+use super::traits::ToolInput;
+use crate::camera::resources::CameraSettings;
+use crate::coordinate::resources::SnapTarget;
+use crate::ui::resources::UiState;
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+/// System to pass the cursor state to the active tool, deactivating it on Escape or when a built-in
+/// shape tool or vertex editing is picked
+pub fn run_active_tool(
+    mut commands: Commands, mut tools: ResMut<EditorTools>, ui_state: Res<UiState>, snap_target: Res<SnapTarget>,
+    mouse_button_input: Res<ButtonInput<MouseButton>>, keyboard_input: Res<ButtonInput<KeyCode>>,
+    camera_settings: Res<CameraSettings>,
+) {
+    let Some(index) = tools.active else {
+        return;
+    };
+    let builtin_active = ui_state.selected_shape.is_some() || ui_state.edit_vertices;
+    if builtin_active || keyboard_input.just_pressed(KeyCode::Escape) {
+        tools.deactivate(&mut commands);
+        return;
+    }
+
+    // The snap target is missing while the cursor is over the UI or outside the main viewport.
+    // Releases are passed on anywhere so drags always end.
+    let pointer_free = snap_target.position.is_some() && !camera_settings.space_pan_active(&keyboard_input);
+    let input = ToolInput {
+        cursor: snap_target.position,
+        pressed: pointer_free && mouse_button_input.just_pressed(MouseButton::Left),
+        held: pointer_free && mouse_button_input.pressed(MouseButton::Left),
+        released: mouse_button_input.just_released(MouseButton::Left),
+    };
+    tools.input = input;
+    tools.tools[index].cursor(&input, &mut commands);
+}
+
+/// System to draw the previews of the active tool
+pub fn draw_active_tool_gizmos(tools: Res<EditorTools>, mut gizmos: Gizmos) {
+    if let Some(tool) = tools.active_tool() {
+        tool.draw_gizmos(&tools.input, &mut gizmos);
+    }
+}
+
+/// System to render the tools window and the open panels
+pub fn draw_tools_window(
+    mut contexts: EguiContexts, mut tools: ResMut<EditorTools>, mut ui_state: ResMut<UiState>, mut commands: Commands,
+) {
+    if !ui_state.panel_visible || tools.is_empty() {
+        return;
+    }
+    let Ok(ctx) = contexts.ctx_mut() else {
+        return;
+    };
+
+    let tools = &mut *tools;
+    egui::Window::new("Tools").resizable(false).show(ctx, |ui| {
+        for index in 0..tools.tools.len() {
+            let active = tools.active == Some(index);
+            if !ui.selectable_label(active, tools.tools[index].name()).clicked() {
+                continue;
+            }
+            if active {
+                tools.deactivate(&mut commands);
+            } else {
+                ui_state.selected_shape = None;
+                ui_state.edit_vertices = false;
+                tools.activate(index, &mut commands);
+            }
+        }
+        if let Some(index) = tools.active {
+            ui.separator();
+            tools.tools[index].options_ui(ui, &mut commands);
+        }
+
+        if !tools.panels.is_empty() {
+            ui.separator();
+            ui.label("Panels:");
+            for registered in &mut tools.panels {
+                ui.checkbox(&mut registered.open, registered.panel.title());
+            }
+        }
+    });
+
+    for registered in tools.panels.iter_mut().filter(|registered| registered.open) {
+        let mut open = true;
+        egui::Window::new(registered.panel.title().to_string())
+            .open(&mut open)
+            .show(ctx, |ui| registered.panel.ui(ui, &mut commands));
+        registered.open = open;
+    }
+}
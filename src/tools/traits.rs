@@ -0,0 +1,54 @@
+//! Editor tool and panel traits
+//!
+//! Tools and panels change the scene through `Commands`, like the editor's own systems do, so they
+//! can spawn shapes or write any of the editor's messages.
+
+use bevy::prelude::*;
+use bevy_egui::egui;
+
+/// Cursor and key state passed to the active tool each frame
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ToolInput {
+    /// Snapped world position of the cursor, `None` outside the main viewport or over the UI
+    pub cursor: Option<Vec2>,
+    /// Whether the left mouse button was pressed this frame
+    pub pressed: bool,
+    /// Whether the left mouse button is held down
+    pub held: bool,
+    /// Whether the left mouse button was released this frame
+    pub released: bool,
+}
+
+/// A tool that takes over the cursor in the viewport while it is active
+///
+/// Only one tool is active at a time, and picking a built-in shape tool deactivates it. Escape
+/// deactivates it too.
+pub trait EditorTool: Send + Sync + 'static {
+    /// Name shown in the tools window
+    fn name(&self) -> &str;
+
+    /// Called when the tool becomes the active tool
+    fn activate(&mut self, _commands: &mut Commands) {}
+
+    /// Called when another tool is picked or the tool is cancelled
+    fn deactivate(&mut self, _commands: &mut Commands) {}
+
+    /// Called every frame while the tool is active. The button state is only set while the cursor is
+    /// in the viewport and not over the UI or panning the view.
+    fn cursor(&mut self, _input: &ToolInput, _commands: &mut Commands) {}
+
+    /// Draw previews of the tool every frame while it is active
+    fn draw_gizmos(&self, _input: &ToolInput, _gizmos: &mut Gizmos) {}
+
+    /// Draw the tool's options below the tool list while it is active
+    fn options_ui(&mut self, _ui: &mut egui::Ui, _commands: &mut Commands) {}
+}
+
+/// A window of custom UI, opened from the tools window
+pub trait EditorPanel: Send + Sync + 'static {
+    /// Title of the panel's window
+    fn title(&self) -> &str;
+
+    /// Draw the panel's contents
+    fn ui(&mut self, ui: &mut egui::Ui, commands: &mut Commands);
+}
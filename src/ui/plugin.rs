@@ -2,8 +2,11 @@
 //!
 //! Registers the egui UI state resource and the systems that render the editor UI.
 
-use super::resources::UiState;
-use super::systems::{draw_editor_ui, toggle_ui_visibility};
+use super::resources::{Notifications, UiState};
+use super::systems::{
+    collect_save_load_notifications, draw_editor_ui, draw_notifications, draw_settings_window, draw_status_bar,
+    toggle_ui_visibility,
+};
 use bevy::prelude::*;
 use bevy_egui::EguiPrimaryContextPass;
 
@@ -14,7 +17,18 @@ impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
         // Initialize the UI state (Default) resource consistently.
         app.init_resource::<UiState>()
+            .init_resource::<Notifications>()
+            .add_systems(Update, collect_save_load_notifications)
             // Register UI systems that require egui context
-            .add_systems(EguiPrimaryContextPass, (draw_editor_ui, toggle_ui_visibility));
+            .add_systems(
+                EguiPrimaryContextPass,
+                (
+                    draw_editor_ui,
+                    draw_settings_window,
+                    toggle_ui_visibility,
+                    draw_status_bar,
+                    draw_notifications.after(draw_status_bar),
+                ),
+            );
     }
 }
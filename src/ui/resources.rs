@@ -1,3 +1,4 @@
+use crate::save_load::components::{CsvImportOptions, LoadMode, SaveScope};
 use crate::shapes::components::ShapeLayer;
 use bevy::prelude::*;
 use qgeometry::shape::QShapeType;
@@ -14,16 +15,68 @@ pub struct UiState {
     pub editor_mode: EditorMode,
     /// Whether the graphics editor panel is visible
     pub panel_visible: bool,
+    /// Whether the settings window is open
+    pub settings_visible: bool,
+    /// Whether the script console is open
+    pub console_visible: bool,
     /// Currently selected shape type for drawing
     pub selected_shape: Option<QShapeType>,
+    /// Whether the vertices of the selected lines and polygons are edited instead of drawing shapes
+    pub edit_vertices: bool,
     /// Currently selected shape layer
     pub selected_layer: ShapeLayer,
     /// File path for saving/loading shapes
     pub file_path: String,
+    /// Which shapes "Save Shapes" writes
+    pub save_scope: SaveScope,
+    /// Whether loading replaces the scene or merges into it, and the offset of merged shapes
+    pub load_mode: LoadMode,
+    /// File path for saving/loading project files
+    pub project_path: String,
+    /// File path for saving and inserting prefabs
+    pub prefab_path: String,
+    /// File path for importing CSV/TSV points
+    pub csv_path: String,
+    /// Column mapping and scaling for CSV imports
+    pub csv_options: CsvImportOptions,
+    /// File path for importing SVG drawings
+    pub svg_path: String,
+    /// Editor units per SVG user unit
+    pub svg_scale: f64,
+    /// File path for exporting DXF drawings
+    pub dxf_path: String,
+    /// File path for exporting Rapier2D colliders
+    pub rapier_path: String,
+    /// File path for exporting shapes as Rust source
+    pub rust_path: String,
+    /// File path for exporting collision fixtures
+    pub fixture_path: String,
+    /// File path for exporting PNG images
+    pub png_path: String,
+    /// Whether the PNG export covers the current viewport instead of `png_region`
+    pub png_use_viewport: bool,
+    /// World rectangle exported when not using the viewport
+    pub png_region: Rect,
+    /// Size of the exported image in pixels when not using the viewport
+    pub png_resolution: UVec2,
+    /// Multiple of the window resolution screenshots are rendered at
+    pub screenshot_scale: u32,
+    /// File path of the reference image shown behind the scene
+    pub reference_image_path: String,
+    /// File path of the script run from the script console
+    pub script_path: String,
+    /// File path for saving/loading physics snapshots
+    pub snapshot_path: String,
+    /// File path for saving/verifying determinism recordings
+    pub recording_path: String,
     /// Whether to enable snap to grid
     pub enable_snap: bool,
     /// Whether to only show shapes in the selected layer
     pub only_show_select_layer: bool,
+    /// Number of steps to run when pressing "Run" in the physics panel
+    pub run_steps: u32,
+    /// Collision layer used when adding a new gravity override in the physics panel
+    pub new_gravity_layer: u32,
 }
 
 impl Default for UiState {
@@ -31,11 +84,66 @@ impl Default for UiState {
         Self {
             editor_mode: EditorMode::Shape,
             panel_visible: false,
+            settings_visible: false,
+            console_visible: false,
             selected_shape: None,
+            edit_vertices: false,
             selected_layer: ShapeLayer::MainScene,
             file_path: "assets/saves/default.json".to_string(),
+            save_scope: SaveScope::default(),
+            load_mode: LoadMode::default(),
+            project_path: "assets/saves/default.project.json".to_string(),
+            prefab_path: "assets/prefabs/prefab.json".to_string(),
+            csv_path: "assets/saves/points.csv".to_string(),
+            csv_options: CsvImportOptions::default(),
+            svg_path: "assets/saves/drawing.svg".to_string(),
+            svg_scale: 0.1,
+            dxf_path: "assets/saves/default.dxf".to_string(),
+            rapier_path: "assets/saves/colliders.ron".to_string(),
+            rust_path: "assets/saves/scene.rs".to_string(),
+            fixture_path: "assets/fixtures/collision_fixture.rs".to_string(),
+            png_path: "assets/screenshots/export.png".to_string(),
+            png_use_viewport: true,
+            png_region: Rect::new(-10.0, -10.0, 10.0, 10.0),
+            png_resolution: UVec2::new(1920, 1080),
+            screenshot_scale: 2,
+            reference_image_path: "assets/reference.png".to_string(),
+            script_path: "assets/scripts/scene.rhai".to_string(),
+            snapshot_path: "assets/saves/snapshot.json".to_string(),
+            recording_path: "assets/saves/recording.json".to_string(),
             enable_snap: true,
             only_show_select_layer: false,
+            run_steps: 10,
+            new_gravity_layer: 1,
         }
     }
 }
+
+/// A message shown briefly in the corner of the editor
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub text: String,
+    /// Whether the notification reports a failure
+    pub is_error: bool,
+    /// Seconds left before the notification disappears
+    pub remaining: f32,
+}
+
+/// Resource holding the notifications currently on screen
+#[derive(Resource, Debug, Default)]
+pub struct Notifications {
+    pub entries: Vec<Notification>,
+}
+
+impl Notifications {
+    /// How long a notification stays on screen, in seconds
+    pub const DURATION: f32 = 4.0;
+
+    pub fn push(&mut self, text: impl Into<String>, is_error: bool) {
+        self.entries.push(Notification {
+            text: text.into(),
+            is_error,
+            remaining: Self::DURATION,
+        });
+    }
+}
@@ -3,21 +3,62 @@
 //! This module defines the systems used for the egui-based user interface,
 //! including the graphics editing panel.
 
-use super::resources::{EditorMode, UiState};
-use crate::save_load::components::{LoadShapesFromFileEvent, SaveSelectedShapesEvent};
-use crate::shapes::components::{EditorShape, QBboxData, QCircleData, QLineData, QPointData, QPolygonData, ShapeLayer};
+use super::resources::{EditorMode, Notifications, UiState};
+use crate::camera::components::{CameraFollowEvent, CameraMovement, CameraTarget, FrameShapesEvent, ResetViewEvent};
+use crate::camera::resources::{CameraSettings, SplitOrientation, SplitView};
+use crate::camera::systems::{ZOOM_PRESETS, camera_angle, zoom_from_percent, zoom_percent};
+use crate::collision_detection::gjk::GjkPhase;
+use crate::collision_detection::resources::{CollisionDetectionSettings, GjkDebugger};
+use crate::coordinate::components::ReferenceImageEvent;
+use crate::coordinate::resources::{
+    CoordinateSettings, DisplayUnit, GridMode, GridStyle, Guides, MeasureTool, ReferenceImage, SnapTarget,
+};
+use crate::history::components::HistoryEvent;
+use crate::history::resources::EditHistory;
+use crate::qphysics::components::QOverflowDiagnostic;
+use crate::qphysics::messages::{QRecorderCommand, QResetSimulation};
+use crate::qphysics::resources::{
+    QCombineRule, QPhysicsConfig, QPhysicsDebugConfig, QPhysicsRecorder, QPhysicsStats, QRecorderMode,
+    QSimulationControl,
+};
+use crate::save_load::components::{
+    CsvImportTarget, ExportCollisionFixtureEvent, ExportDxfEvent, ExportPngEvent, ExportRapierEvent, ExportRustEvent,
+    ImportCsvEvent, ImportSvgEvent, LoadMode, LoadPhysicsSnapshotEvent, LoadProjectEvent, LoadShapesFromFileEvent,
+    SaveFormat, SaveLoadResultEvent, SavePhysicsSnapshotEvent, SavePrefabEvent, SaveProjectEvent, SaveRecordingEvent,
+    SaveScope, SaveSelectedShapesEvent, SerializableQShapeData, TakeScreenshotEvent, VerifyRecordingEvent,
+};
+use crate::save_load::resources::{LaunchOptions, PrefabPlacement, SceneFileWatcher};
+use crate::shapes::boolean::PolygonBooleanOp;
+use crate::shapes::components::{
+    ClipboardEvent, ConvexHullEvent, DeleteSelectedShapesEvent, EditorShape, PolygonBooleanEvent, QBboxData,
+    QCircleData, QLineData, QPointData, QPolygonData, SetShapeDataEvent, ShapeLayer, shape_type_name,
+};
+use crate::shapes::resources::ShapesSettings;
 use bevy::prelude::*;
 use bevy_egui::{
     EguiContexts,
     egui::{self, Ui},
 };
-use qgeometry::shape::QShapeType;
+use qgeometry::shape::{QBbox, QCircle, QLine, QPoint, QPolygon, QShapeType};
+use qmath::prelude::*;
+use qmath::vec2::QVec2;
 
 /// System to render the egui UI
 pub fn draw_editor_ui(
     mut contexts: EguiContexts,
-    commands: Commands,
+    mut commands: Commands,
     mut ui_state: ResMut<UiState>,
+    mut physics_config: ResMut<QPhysicsConfig>,
+    recorder: Res<QPhysicsRecorder>,
+    mut simulation_control: ResMut<QSimulationControl>,
+    mut debug_config: ResMut<QPhysicsDebugConfig>,
+    overflow_query: Query<(Entity, &QOverflowDiagnostic)>,
+    physics_stats: Res<QPhysicsStats>,
+    mut file_watcher: ResMut<SceneFileWatcher>,
+    mut prefab_placement: ResMut<PrefabPlacement>,
+    launch_options: Res<LaunchOptions>,
+    coordinate_settings: Res<CoordinateSettings>,
+    history: Res<EditHistory>,
     // Query all shapes to display in the list
     shapes_query: Query<(
         Entity,
@@ -41,24 +82,507 @@ pub fn draw_editor_ui(
                 ui.horizontal(|ui| {
                     ui.selectable_value(&mut ui_state.editor_mode, EditorMode::Shape, "Shape");
                     ui.selectable_value(&mut ui_state.editor_mode, EditorMode::Physics, "Physics");
+                    ui.separator();
+                    if ui.button("Home").on_hover_text("Reset the view (Home)").clicked() {
+                        commands.write_message(ResetViewEvent);
+                    }
+                    if ui.button("Fit").on_hover_text("Frame all shapes (Shift+F)").clicked() {
+                        commands.write_message(FrameShapesEvent { all: true });
+                    }
+                    if ui
+                        .button("Fit Selection")
+                        .on_hover_text("Frame the selected shapes (F)")
+                        .clicked()
+                    {
+                        commands.write_message(FrameShapesEvent { all: false });
+                    }
+                    ui.toggle_value(&mut ui_state.settings_visible, "Settings");
+                    ui.toggle_value(&mut ui_state.console_visible, "Console");
+                    ui.separator();
+                    let undo = ui.add_enabled(history.undo_label().is_some(), egui::Button::new("Undo"));
+                    if let Some(label) = history.undo_label() {
+                        if undo.on_hover_text(format!("Undo {} (Ctrl+Z)", label)).clicked() {
+                            commands.write_message(HistoryEvent::Undo);
+                        }
+                    }
+                    let redo = ui.add_enabled(history.redo_label().is_some(), egui::Button::new("Redo"));
+                    if let Some(label) = history.redo_label() {
+                        if redo.on_hover_text(format!("Redo {} (Ctrl+Y)", label)).clicked() {
+                            commands.write_message(HistoryEvent::Redo);
+                        }
+                    }
                 });
+                if launch_options.readonly {
+                    ui.colored_label(egui::Color32::YELLOW, "Read-only: saving and exporting are disabled");
+                }
 
                 match ui_state.editor_mode {
-                    EditorMode::Shape => draw_shape_editor(ui, commands, &mut ui_state, shapes_query),
-                    EditorMode::Physics => draw_physics_editor(ui, commands, &mut ui_state),
+                    EditorMode::Shape => draw_shape_editor(
+                        ui,
+                        commands,
+                        &mut ui_state,
+                        &mut file_watcher,
+                        &mut prefab_placement,
+                        &coordinate_settings,
+                        shapes_query,
+                    ),
+                    EditorMode::Physics => draw_physics_editor(
+                        ui,
+                        commands,
+                        &mut ui_state,
+                        &mut physics_config,
+                        &recorder,
+                        &mut simulation_control,
+                        &mut debug_config,
+                        overflow_query,
+                        &physics_stats,
+                        &coordinate_settings.unit,
+                    ),
                 }
             });
     }
 }
 
-fn draw_physics_editor(ui: &mut Ui, mut commands: Commands, ui_state: &mut UiState) {
+fn draw_physics_editor(
+    ui: &mut Ui, mut commands: Commands, ui_state: &mut UiState, physics_config: &mut QPhysicsConfig,
+    recorder: &QPhysicsRecorder, simulation_control: &mut QSimulationControl, debug_config: &mut QPhysicsDebugConfig,
+    overflow_query: Query<(Entity, &QOverflowDiagnostic)>, physics_stats: &QPhysicsStats, unit: &DisplayUnit,
+) {
     ui.heading("Physics Editor");
+
+    // Simulation playback controls
+    ui.horizontal(|ui| {
+        let play_label = if simulation_control.paused { "Play" } else { "Pause" };
+        if ui.button(play_label).clicked() {
+            simulation_control.paused = !simulation_control.paused;
+            simulation_control.pending_steps = 0;
+        }
+        if ui.button("Step").clicked() {
+            simulation_control.step(1);
+        }
+        if ui.button("Run").clicked() {
+            simulation_control.step(ui_state.run_steps);
+        }
+        ui.add(
+            egui::DragValue::new(&mut ui_state.run_steps)
+                .range(1..=10000)
+                .suffix(" steps"),
+        );
+        if ui.button("Reset").clicked() {
+            commands.write_message(QResetSimulation);
+        }
+    });
+    if simulation_control.paused && simulation_control.pending_steps > 0 {
+        ui.label(format!("Pending steps: {}", simulation_control.pending_steps));
+    }
+    ui.horizontal(|ui| {
+        ui.label("Camera:");
+        if ui
+            .button("Follow Selected")
+            .on_hover_text("Keep the selected body centered")
+            .clicked()
+        {
+            commands.write_message(CameraFollowEvent::FollowSelected);
+        }
+        if ui.button("Stop Following").clicked() {
+            commands.write_message(CameraFollowEvent::Stop);
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::Slider::new(&mut simulation_control.time_scale, 0.1..=10.0)
+                .logarithmic(true)
+                .text("Time Scale"),
+        );
+        if ui.small_button("1x").clicked() {
+            simulation_control.time_scale = 1.0;
+        }
+    });
+
+    // Global forces
+    ui.separator();
+    ui.label("Global Forces:");
+    ui.horizontal(|ui| {
+        ui.label("Gravity");
+        qvec2_drag_value(ui, &mut physics_config.gravity, unit, "/s²");
+    });
+    ui.horizontal(|ui| {
+        ui.label("Wind Force");
+        qvec2_drag_value(ui, &mut physics_config.wind_force, unit, "/s²");
+    });
+
+    // Material combine rules
+    ui.separator();
+    ui.label("Material Combine Rules:");
+    combine_rule_combo(ui, "Restitution", &mut physics_config.restitution_combine);
+    combine_rule_combo(ui, "Friction", &mut physics_config.friction_combine);
+
+    // Positional correction
+    ui.separator();
+    ui.label("Positional Correction:");
+    ui.horizontal(|ui| {
+        ui.label("Slop");
+        q64_drag_value(ui, &mut physics_config.position_correction_slop, unit, "");
+    });
+    ui.horizontal(|ui| {
+        ui.label("Percent");
+        let mut percent = physics_config.position_correction_percent.to_num::<f32>();
+        if ui.add(egui::Slider::new(&mut percent, 0.0..=1.0)).changed() {
+            physics_config.position_correction_percent = Q64::from_num(percent);
+        }
+    });
+
+    // Per-layer gravity overrides, sorted by layer so the list doesn't jump around
+    ui.label("Layer Gravity Overrides:");
+    let mut layers: Vec<u32> = physics_config.layer_gravity.keys().copied().collect();
+    layers.sort();
+    let mut removed_layer = None;
+    for layer in layers {
+        ui.horizontal(|ui| {
+            ui.label(format!("Layer {:#x}", layer));
+            if let Some(gravity) = physics_config.layer_gravity.get_mut(&layer) {
+                qvec2_drag_value(ui, gravity, unit, "/s²");
+            }
+            if ui.small_button("x").clicked() {
+                removed_layer = Some(layer);
+            }
+        });
+    }
+    if let Some(layer) = removed_layer {
+        physics_config.layer_gravity.remove(&layer);
+    }
+    ui.horizontal(|ui| {
+        ui.label("Add Layer:");
+        ui.add(egui::DragValue::new(&mut ui_state.new_gravity_layer).range(1..=u32::MAX));
+        if ui.button("Add").clicked() {
+            let gravity = physics_config.gravity;
+            physics_config
+                .layer_gravity
+                .entry(ui_state.new_gravity_layer)
+                .or_insert(gravity);
+        }
+    });
+
+    // Determinism recording and verification
+    ui.separator();
+    ui.label("Determinism:");
+    ui.horizontal(|ui| {
+        match recorder.mode {
+            QRecorderMode::Recording => {
+                if ui.button("Stop Recording").clicked() {
+                    commands.write_message(QRecorderCommand::StopRecording);
+                }
+            }
+            _ => {
+                if ui.button("Start Recording").clicked() {
+                    commands.write_message(QRecorderCommand::StartRecording);
+                }
+            }
+        }
+        let can_verify = recorder.mode == QRecorderMode::Idle && !recorder.hashes.is_empty();
+        if ui.add_enabled(can_verify, egui::Button::new("Verify Replay")).clicked() {
+            commands.write_message(QRecorderCommand::Verify);
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.text_edit_singleline(&mut ui_state.recording_path);
+        save_format_combo(ui, "recording_format", &mut ui_state.recording_path);
+    });
+    ui.horizontal(|ui| {
+        let can_save = recorder.mode == QRecorderMode::Idle && !recorder.hashes.is_empty();
+        if ui
+            .add_enabled(can_save, egui::Button::new("Save Recording"))
+            .on_hover_text("Save the recording with the world it started from")
+            .clicked()
+            && !ui_state.recording_path.is_empty()
+        {
+            commands.write_message(SaveRecordingEvent {
+                file_path: ui_state.recording_path.clone(),
+            });
+        }
+        if ui
+            .button("Verify File")
+            .on_hover_text("Replace the physics world with a saved recording's and replay it")
+            .clicked()
+            && !ui_state.recording_path.is_empty()
+        {
+            commands.write_message(VerifyRecordingEvent {
+                file_path: ui_state.recording_path.clone(),
+            });
+        }
+    });
+    match recorder.mode {
+        QRecorderMode::Idle => ui.label(format!("Recorded steps: {}", recorder.hashes.len())),
+        QRecorderMode::Recording => ui.label(format!("Recording... {} steps", recorder.hashes.len())),
+        QRecorderMode::Replaying => ui.label(format!(
+            "Verifying step {}/{}",
+            recorder.replay_step,
+            recorder.hashes.len()
+        )),
+    };
+    match recorder.verify_result {
+        Some(Ok(steps)) => {
+            ui.colored_label(
+                egui::Color32::DARK_GREEN,
+                format!("Deterministic: {} steps matched", steps),
+            );
+        }
+        Some(Err(step)) => {
+            ui.colored_label(egui::Color32::RED, format!("Diverged at step {}", step));
+        }
+        None => {}
+    }
+
+    // Debug visualization and diagnostics
+    ui.separator();
+    ui.label("Debug:");
+    ui.checkbox(&mut debug_config.show_colliders, "Show Colliders");
+    ui.checkbox(&mut debug_config.show_velocity, "Show Velocity");
+    ui.checkbox(&mut debug_config.interpolate_rendering, "Interpolate Rendering");
+    ui.checkbox(&mut debug_config.detect_overflow, "Detect Q64 Overflow");
+    if debug_config.detect_overflow {
+        let mut cleared = false;
+        for (entity, diagnostic) in overflow_query.iter() {
+            ui.colored_label(
+                egui::Color32::RED,
+                format!("{:?} saturated in {}", entity, diagnostic.context),
+            );
+        }
+        if !overflow_query.is_empty() && ui.button("Clear Overflow Marks").clicked() {
+            cleared = true;
+        }
+        if cleared {
+            for (entity, _) in overflow_query.iter() {
+                commands.entity(entity).remove::<QOverflowDiagnostic>();
+            }
+        }
+    }
+
+    // Profiling of the last physics step
+    ui.separator();
+    egui::CollapsingHeader::new("Statistics").show(ui, |ui| draw_physics_stats(ui, physics_stats));
+
+    // Physics world snapshots
+    ui.separator();
+    ui.label("Snapshot:");
+    ui.horizontal(|ui| {
+        ui.text_edit_singleline(&mut ui_state.snapshot_path);
+        save_format_combo(ui, "snapshot_format", &mut ui_state.snapshot_path);
+    });
+    ui.horizontal(|ui| {
+        if ui.button("Save Snapshot").clicked() && !ui_state.snapshot_path.is_empty() {
+            commands.write_message(SavePhysicsSnapshotEvent {
+                file_path: ui_state.snapshot_path.clone(),
+            });
+        }
+        if ui.button("Load Snapshot").clicked() && !ui_state.snapshot_path.is_empty() {
+            commands.write_message(LoadPhysicsSnapshotEvent {
+                file_path: ui_state.snapshot_path.clone(),
+            });
+        }
+    });
+}
+
+/// Draw the counts and per-phase timings of the last physics step
+fn draw_physics_stats(ui: &mut Ui, stats: &QPhysicsStats) {
+    ui.label(format!("Step: {}", stats.steps));
+    egui::Grid::new("physics_stats_grid").striped(true).show(ui, |ui| {
+        let phases = [
+            ("Velocity Integration", stats.velocity_integration_time),
+            ("Broad Phase", stats.broad_phase_time),
+            ("Narrow Phase", stats.narrow_phase_time),
+            ("Collision Resolution", stats.collision_resolution_time),
+            ("Position Integration", stats.position_integration_time),
+            ("Total", stats.total_time()),
+        ];
+        for (name, time) in phases {
+            ui.label(name);
+            ui.label(format!("{:.3} ms", time.as_secs_f64() * 1000.0));
+            ui.end_row();
+        }
+    });
+    ui.label(format!("Pairs tested: {}", stats.pairs_tested));
+    ui.label(format!("Pairs kept: {}", stats.pairs_kept));
+    ui.label(format!("Contacts found: {}", stats.contacts_found));
+    ui.label(format!("Contacts solved: {}", stats.contacts_solved));
+    ui.label(format!("Bodies awake: {}", stats.bodies_awake));
+}
+
+/// Draw a combo box to pick a material combine rule
+fn combine_rule_combo(ui: &mut Ui, label: &str, rule: &mut QCombineRule) {
+    egui::ComboBox::from_label(label)
+        .selected_text(format!("{:?}", rule))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(rule, QCombineRule::Average, "Average");
+            ui.selectable_value(rule, QCombineRule::Min, "Min");
+            ui.selectable_value(rule, QCombineRule::Max, "Max");
+            ui.selectable_value(rule, QCombineRule::Multiply, "Multiply");
+        });
+}
+
+/// Draw a save format selector that switches the extension of `file_path`
+fn save_format_combo(ui: &mut Ui, id: &str, file_path: &mut String) {
+    let mut format = SaveFormat::from_path(file_path);
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(format.extension())
+        .width(60.0)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut format, SaveFormat::Json, "json");
+            ui.selectable_value(&mut format, SaveFormat::Ron, "ron");
+            ui.selectable_value(&mut format, SaveFormat::Binary, "bin");
+        });
+    if format != SaveFormat::from_path(file_path) {
+        let path = std::path::Path::new(file_path.as_str()).with_extension(format.extension());
+        *file_path = path.to_string_lossy().into_owned();
+    }
+}
+
+/// Draw an editable drag value for a Q64 length in display units, returning whether it changed. `rate` is
+/// appended to the unit suffix, like "/s" for speeds.
+fn q64_drag_value(ui: &mut Ui, value: &mut Q64, unit: &DisplayUnit, rate: &str) -> bool {
+    let mut proxy = unit.to_display(value.to_num::<f32>());
+    let suffix = if unit.suffix.is_empty() {
+        String::new()
+    } else {
+        format!("{}{}", unit.suffix, rate)
+    };
+    let changed = ui
+        .add(egui::DragValue::new(&mut proxy).speed(0.1).suffix(suffix))
+        .changed();
+    if changed {
+        *value = Q64::from_num(unit.to_world(proxy));
+    }
+    changed
+}
+
+/// Draw editable x/y drag values for a QVec2 in display units, returning whether it changed
+fn qvec2_drag_value(ui: &mut Ui, value: &mut QVec2, unit: &DisplayUnit, rate: &str) -> bool {
+    let x_changed = q64_drag_value(ui, &mut value.x, unit, rate);
+    let y_changed = q64_drag_value(ui, &mut value.y, unit, rate);
+    x_changed || y_changed
+}
+
+/// Draw editable x/y drag values for a world position, shown relative to the origin offset, returning whether
+/// it changed
+fn position_drag_value(ui: &mut Ui, value: &mut QVec2, coordinate_settings: &CoordinateSettings) -> bool {
+    let offset = coordinate_settings.origin_offset;
+    let offset = QVec2::new(Q64::from_num(offset.x), Q64::from_num(offset.y));
+    let mut local = value.saturating_sub(offset);
+    let changed = qvec2_drag_value(ui, &mut local, &coordinate_settings.unit, "");
+    if changed {
+        *value = local.saturating_add(offset);
+    }
+    changed
+}
+
+/// Draw the editable geometry of a shape, sending the edited geometry when a value changes
+fn draw_shape_properties(
+    ui: &mut Ui, commands: &mut Commands,
+    (entity, shape, point_opt, line_opt, bbox_opt, circle_opt, polygon_opt): (
+        Entity,
+        &EditorShape,
+        Option<&QPointData>,
+        Option<&QLineData>,
+        Option<&QBboxData>,
+        Option<&QCircleData>,
+        Option<&QPolygonData>,
+    ),
+    coordinate_settings: &CoordinateSettings,
+) {
+    ui.label(format!("Properties: {}", shape_type_name(shape.shape_type)));
+    let unit = &coordinate_settings.unit;
+    let mut data = None;
+
+    if let Some(point) = point_opt {
+        let mut position = point.data.pos();
+        ui.horizontal(|ui| {
+            ui.label("Position:");
+            if position_drag_value(ui, &mut position, coordinate_settings) {
+                data = Some(SerializableQShapeData::Point(QPointData {
+                    data: QPoint::new(position),
+                }));
+            }
+        });
+    } else if let Some(line) = line_opt {
+        let (mut start, mut end) = (line.data.start().pos(), line.data.end().pos());
+        let start_changed = ui.horizontal(|ui| {
+            ui.label("Start:");
+            position_drag_value(ui, &mut start, coordinate_settings)
+        });
+        let end_changed = ui.horizontal(|ui| {
+            ui.label("End:");
+            position_drag_value(ui, &mut end, coordinate_settings)
+        });
+        // A line needs two distinct ends
+        if (start_changed.inner || end_changed.inner) && start != end {
+            data = Some(SerializableQShapeData::Line(QLineData {
+                data: QLine::new(QPoint::new(start), QPoint::new(end)),
+            }));
+        }
+    } else if let Some(bbox) = bbox_opt {
+        let (mut min, mut max) = (bbox.data.left_bottom().pos(), bbox.data.right_top().pos());
+        let min_changed = ui.horizontal(|ui| {
+            ui.label("Min:");
+            position_drag_value(ui, &mut min, coordinate_settings)
+        });
+        let max_changed = ui.horizontal(|ui| {
+            ui.label("Max:");
+            position_drag_value(ui, &mut max, coordinate_settings)
+        });
+        // Edits that would turn the rectangle inside out are ignored
+        if (min_changed.inner || max_changed.inner) && min.x < max.x && min.y < max.y {
+            data = Some(SerializableQShapeData::Bbox(QBboxData {
+                data: QBbox::new_from_parts(min, max),
+            }));
+        }
+    } else if let Some(circle) = circle_opt {
+        let (mut center, mut radius) = (circle.data.center().pos(), circle.data.radius());
+        let center_changed = ui.horizontal(|ui| {
+            ui.label("Center:");
+            position_drag_value(ui, &mut center, coordinate_settings)
+        });
+        let radius_changed = ui.horizontal(|ui| {
+            ui.label("Radius:");
+            q64_drag_value(ui, &mut radius, unit, "")
+        });
+        if (center_changed.inner || radius_changed.inner) && radius > Q64::ZERO {
+            data = Some(SerializableQShapeData::Circle(QCircleData {
+                data: QCircle::new(QPoint::new(center), radius),
+            }));
+        }
+    } else if let Some(polygon) = polygon_opt {
+        let mut points: Vec<QVec2> = polygon.data.points().iter().map(|point| point.pos()).collect();
+        let mut changed = false;
+        egui::ScrollArea::vertical()
+            .id_salt("polygon_vertices")
+            .max_height(150.0)
+            .show(ui, |ui| {
+                for (index, point) in points.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{}:", index));
+                        changed |= position_drag_value(ui, point, coordinate_settings);
+                    });
+                }
+            });
+        if changed {
+            data = Some(SerializableQShapeData::Polygon(QPolygonData {
+                data: QPolygon::new(points.into_iter().map(QPoint::new).collect()),
+            }));
+        }
+    }
+
+    if let Some(data) = data {
+        commands.write_message(SetShapeDataEvent { entity, data });
+    }
 }
 
 fn draw_shape_editor(
     ui: &mut Ui,
     mut commands: Commands,
     ui_state: &mut UiState,
+    file_watcher: &mut SceneFileWatcher,
+    prefab_placement: &mut PrefabPlacement,
+    coordinate_settings: &CoordinateSettings,
     // Query selected shape to edit
     shapes_query: Query<(
         Entity,
@@ -81,6 +605,15 @@ fn draw_shape_editor(
         ui.selectable_value(&mut ui_state.selected_shape, Some(QShapeType::QPolygon), "Polygon");
         ui.selectable_value(&mut ui_state.selected_shape, None, "None");
     });
+    let edit_vertices = ui
+        .toggle_value(&mut ui_state.edit_vertices, "Edit Vertices")
+        .on_hover_text(
+            "Drag the vertices of the selected lines and polygons, double-click a polygon edge to insert a vertex \
+         and right-click a vertex to delete it, or Alt+click while the right button pans",
+        );
+    if edit_vertices.changed() && ui_state.edit_vertices {
+        ui_state.selected_shape = None;
+    }
 
     // Layer selection buttons
     ui.separator();
@@ -95,6 +628,12 @@ fn draw_shape_editor(
     ui.separator();
     ui.label("Drawn Shapes:");
 
+    let format_point = |point: QVec2| {
+        let local = coordinate_settings.to_local(Vec2::new(point.x.to_num(), point.y.to_num()));
+        let unit = &coordinate_settings.unit;
+        format!("({}, {})", unit.format(local.x, 2), unit.format(local.y, 2))
+    };
+
     // Scroll area for the shapes list
     egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
         // Iterate through shapes and display only those in the selected layer
@@ -104,15 +643,11 @@ fn draw_shape_editor(
                 continue;
             }
 
-            // Create a descriptive label for each shape
+            // Create a descriptive label for each shape, with coordinates relative to the origin offset
             let shape_label = match shape.shape_type {
                 QShapeType::QPoint => {
                     if let Some(point) = point_opt {
-                        format!(
-                            "Point ({:.2}, {:.2})",
-                            point.data.pos().x.to_num::<f32>(),
-                            point.data.pos().y.to_num::<f32>()
-                        )
+                        format!("Point {}", format_point(point.data.pos()))
                     } else {
                         "Point".to_string()
                     }
@@ -120,11 +655,9 @@ fn draw_shape_editor(
                 QShapeType::QLine => {
                     if let Some(line) = line_opt {
                         format!(
-                            "Line ({:.2}, {:.2}) -> ({:.2}, {:.2})",
-                            line.data.start().pos().x.to_num::<f32>(),
-                            line.data.start().pos().y.to_num::<f32>(),
-                            line.data.end().pos().x.to_num::<f32>(),
-                            line.data.end().pos().y.to_num::<f32>()
+                            "Line {} -> {}",
+                            format_point(line.data.start().pos()),
+                            format_point(line.data.end().pos())
                         )
                     } else {
                         "Line".to_string()
@@ -133,11 +666,9 @@ fn draw_shape_editor(
                 QShapeType::QBbox => {
                     if let Some(bbox) = bbox_opt {
                         format!(
-                            "Rectangle ({:.2}, {:.2}) -> ({:.2}, {:.2})",
-                            bbox.data.left_bottom().pos().x.to_num::<f32>(),
-                            bbox.data.left_bottom().pos().y.to_num::<f32>(),
-                            bbox.data.right_top().pos().x.to_num::<f32>(),
-                            bbox.data.right_top().pos().y.to_num::<f32>()
+                            "Rectangle {} -> {}",
+                            format_point(bbox.data.left_bottom().pos()),
+                            format_point(bbox.data.right_top().pos())
                         )
                     } else {
                         "Rectangle".to_string()
@@ -146,10 +677,9 @@ fn draw_shape_editor(
                 QShapeType::QCircle => {
                     if let Some(circle) = circle_opt {
                         format!(
-                            "Circle ({:.2}, {:.2}), r={:.2}",
-                            circle.data.center().pos().x.to_num::<f32>(),
-                            circle.data.center().pos().y.to_num::<f32>(),
-                            circle.data.radius().to_num::<f32>()
+                            "Circle {}, r={}",
+                            format_point(circle.data.center().pos()),
+                            coordinate_settings.unit.format(circle.data.radius().to_num::<f32>(), 2)
                         )
                     } else {
                         "Circle".to_string()
@@ -164,6 +694,12 @@ fn draw_shape_editor(
                 }
             };
 
+            let shape_label = if shape.name.is_empty() {
+                shape_label
+            } else {
+                format!("{}: {}", shape.name, shape_label)
+            };
+
             // Handle click on the shape in the list
             if ui.selectable_label(shape.selected, shape_label).clicked() {
                 // Toggle selection state of the clicked shape
@@ -186,32 +722,350 @@ fn draw_shape_editor(
             ui.label("No shapes in the selected layer");
         }
     });
+    let any_selected = shapes_query.iter().any(|(_, shape, ..)| shape.selected);
+    ui.horizontal(|ui| {
+        let delete = ui.add_enabled(any_selected, egui::Button::new("Delete Selected"));
+        if delete.on_hover_text("Delete the selected shapes (Delete)").clicked() {
+            commands.write_message(DeleteSelectedShapesEvent);
+        }
+        let copy = ui.add_enabled(any_selected, egui::Button::new("Copy"));
+        if copy.on_hover_text("Copy the selected shapes (Ctrl+C)").clicked() {
+            commands.write_message(ClipboardEvent::Copy);
+        }
+        if ui
+            .button("Paste")
+            .on_hover_text("Paste the copied shapes (Ctrl+V)")
+            .clicked()
+        {
+            commands.write_message(ClipboardEvent::Paste);
+        }
+        let duplicate = ui.add_enabled(any_selected, egui::Button::new("Duplicate"));
+        if duplicate
+            .on_hover_text("Duplicate the selected shapes (Ctrl+D)")
+            .clicked()
+        {
+            commands.write_message(ClipboardEvent::Duplicate);
+        }
+    });
+    let hull = ui.add_enabled(any_selected, egui::Button::new("Convex Hull"));
+    if hull
+        .on_hover_text("Add the convex hull of the selected shapes to the Generated layer")
+        .clicked()
+    {
+        commands.write_message(ConvexHullEvent);
+    }
+
+    // Boolean operations need exactly two selected polygons, A being the first one in the list
+    let selected_polygons: Vec<Entity> = shapes_query
+        .iter()
+        .filter(|(_, shape, .., polygon_opt)| shape.selected && polygon_opt.is_some())
+        .map(|(entity, ..)| entity)
+        .collect();
+    if let [a, b] = selected_polygons[..] {
+        ui.horizontal(|ui| {
+            ui.label("Polygons:");
+            let ops = [
+                (PolygonBooleanOp::Union, a, b, "Union", "Merge both polygons"),
+                (
+                    PolygonBooleanOp::Intersection,
+                    a,
+                    b,
+                    "Intersection",
+                    "Keep the overlap of both polygons",
+                ),
+                (
+                    PolygonBooleanOp::Difference,
+                    a,
+                    b,
+                    "A - B",
+                    "Cut the second polygon in the list out of the first",
+                ),
+                (
+                    PolygonBooleanOp::Difference,
+                    b,
+                    a,
+                    "B - A",
+                    "Cut the first polygon in the list out of the second",
+                ),
+            ];
+            for (op, a, b, label, hover) in ops {
+                if ui.button(label).on_hover_text(hover).clicked() {
+                    commands.write_message(PolygonBooleanEvent { op, a, b });
+                }
+            }
+        });
+    }
+
+    // Exact geometry of a single selected shape
+    let mut selected = shapes_query.iter().filter(|(_, shape, ..)| shape.selected);
+    if let (Some(selected_shape), None) = (selected.next(), selected.next()) {
+        ui.separator();
+        draw_shape_properties(ui, &mut commands, selected_shape, coordinate_settings);
+    }
 
     // Add save/load functionality
     ui.separator();
-    ui.label("Save/Load Selected Shapes:");
+    ui.label("Save/Load Shapes:");
 
-    // File path input
-    ui.text_edit_singleline(&mut ui_state.file_path);
+    // File path input, the format follows the file extension
+    ui.horizontal(|ui| {
+        ui.text_edit_singleline(&mut ui_state.file_path);
+        save_format_combo(ui, "shapes_format", &mut ui_state.file_path);
+    });
 
-    // Save button
-    if ui.button("Save Selected Shapes").clicked() {
+    // Save scope and button
+    let current_layer = SaveScope::Layer(ui_state.selected_layer);
+    if let SaveScope::Layer(_) = ui_state.save_scope {
+        // Follow the layer selected above
+        ui_state.save_scope = current_layer;
+    }
+    ui.horizontal(|ui| {
+        ui.radio_value(&mut ui_state.save_scope, SaveScope::Selection, "Selection");
+        ui.radio_value(&mut ui_state.save_scope, current_layer, "Current Layer");
+        ui.radio_value(&mut ui_state.save_scope, SaveScope::EntireScene, "Entire Scene");
+    });
+    if ui.button("Save Shapes").clicked() {
         if !ui_state.file_path.is_empty() {
             commands.write_message(SaveSelectedShapesEvent {
                 file_path: ui_state.file_path.clone(),
+                scope: ui_state.save_scope,
             });
         }
     }
 
     // Load button
+    ui.horizontal(|ui| {
+        ui.radio_value(&mut ui_state.load_mode, LoadMode::Replace, "Replace Scene");
+        ui.radio_value(&mut ui_state.load_mode, LoadMode::Merge, "Merge");
+        let with_offset = matches!(ui_state.load_mode, LoadMode::MergeWithOffset(_));
+        if ui.radio(with_offset, "Merge with Offset").clicked() && !with_offset {
+            ui_state.load_mode = LoadMode::MergeWithOffset(Vec2::new(1.0, -1.0));
+        }
+    });
+    if let LoadMode::MergeWithOffset(offset) = &mut ui_state.load_mode {
+        ui.horizontal(|ui| {
+            ui.label("Offset:");
+            ui.add(egui::DragValue::new(&mut offset.x).speed(0.1).prefix("x: "));
+            ui.add(egui::DragValue::new(&mut offset.y).speed(0.1).prefix("y: "));
+        });
+    }
     if ui.button("Load Shapes from File").clicked() {
         if !ui_state.file_path.is_empty() {
             commands.write_message(LoadShapesFromFileEvent {
                 file_path: ui_state.file_path.clone(),
+                mode: ui_state.load_mode,
             });
         }
     }
 
+    // Watch the loaded file so external tools can feed the editor
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut file_watcher.enabled, "Watch Loaded File");
+        ui.add_enabled(
+            file_watcher.enabled,
+            egui::Checkbox::new(&mut file_watcher.auto_reload, "Auto Reload"),
+        );
+    });
+    if file_watcher.enabled && file_watcher.change_pending {
+        if let Some(file_path) = file_watcher.file_path.clone() {
+            ui.colored_label(egui::Color32::YELLOW, format!("{} changed on disk", file_path));
+            ui.horizontal(|ui| {
+                if ui.button("Reload").clicked() {
+                    commands.write_message(LoadShapesFromFileEvent {
+                        file_path,
+                        mode: LoadMode::Replace,
+                    });
+                    file_watcher.change_pending = false;
+                }
+                if ui.button("Ignore").clicked() {
+                    file_watcher.change_pending = false;
+                }
+            });
+        }
+    }
+
+    // Prefabs store the selection around its centroid and are inserted where the viewport is clicked
+    ui.separator();
+    ui.label("Prefabs:");
+    ui.horizontal(|ui| {
+        ui.text_edit_singleline(&mut ui_state.prefab_path);
+        save_format_combo(ui, "prefab_format", &mut ui_state.prefab_path);
+    });
+    ui.horizontal(|ui| {
+        if ui.button("Save Selection as Prefab").clicked() && !ui_state.prefab_path.is_empty() {
+            commands.write_message(SavePrefabEvent {
+                file_path: ui_state.prefab_path.clone(),
+            });
+        }
+        if ui.button("Insert Prefab").clicked() && !ui_state.prefab_path.is_empty() {
+            // Stop drawing so the placing click doesn't also start a shape
+            ui_state.selected_shape = None;
+            prefab_placement.file_path = Some(ui_state.prefab_path.clone());
+        }
+    });
+    if prefab_placement.file_path.is_some() {
+        ui.label("Click in the viewport to place the prefab, Esc to cancel");
+    }
+
+    // Project files keep the camera, settings and UI state with the scene
+    ui.separator();
+    ui.label("Project:");
+    ui.horizontal(|ui| {
+        ui.text_edit_singleline(&mut ui_state.project_path);
+        save_format_combo(ui, "project_format", &mut ui_state.project_path);
+    });
+    ui.horizontal(|ui| {
+        if ui.button("Save Project").clicked() && !ui_state.project_path.is_empty() {
+            commands.write_message(SaveProjectEvent {
+                file_path: ui_state.project_path.clone(),
+            });
+        }
+        if ui.button("Open Project").clicked() && !ui_state.project_path.is_empty() {
+            commands.write_message(LoadProjectEvent {
+                file_path: ui_state.project_path.clone(),
+            });
+        }
+    });
+
+    // CSV/TSV point import
+    ui.separator();
+    ui.label("Import CSV:");
+    ui.text_edit_singleline(&mut ui_state.csv_path);
+    ui.horizontal(|ui| {
+        ui.label("X Column:");
+        ui.add(egui::DragValue::new(&mut ui_state.csv_options.x_column));
+        ui.label("Y Column:");
+        ui.add(egui::DragValue::new(&mut ui_state.csv_options.y_column));
+    });
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut ui_state.csv_options.has_header, "Header Row");
+        ui.label("Scale:");
+        ui.add(egui::DragValue::new(&mut ui_state.csv_options.scale).speed(0.01));
+    });
+    ui.horizontal(|ui| {
+        ui.radio_value(&mut ui_state.csv_options.target, CsvImportTarget::Points, "Points");
+        ui.radio_value(&mut ui_state.csv_options.target, CsvImportTarget::Polygon, "Polygon");
+        ui.radio_value(&mut ui_state.csv_options.target, CsvImportTarget::Polyline, "Polyline");
+    });
+    if ui.button("Import CSV").clicked() && !ui_state.csv_path.is_empty() {
+        let mut options = ui_state.csv_options.clone();
+        options.layer = ui_state.selected_layer;
+        commands.write_message(ImportCsvEvent {
+            file_path: ui_state.csv_path.clone(),
+            options,
+        });
+    }
+
+    // SVG import of basic shapes
+    ui.separator();
+    ui.label("Import SVG:");
+    ui.text_edit_singleline(&mut ui_state.svg_path);
+    ui.horizontal(|ui| {
+        ui.label("Scale:");
+        ui.add(egui::DragValue::new(&mut ui_state.svg_scale).speed(0.01))
+            .on_hover_text("Editor units per SVG unit");
+    });
+    if ui.button("Import SVG").clicked() && !ui_state.svg_path.is_empty() {
+        commands.write_message(ImportSvgEvent {
+            file_path: ui_state.svg_path.clone(),
+            scale: ui_state.svg_scale,
+            layer: ui_state.selected_layer,
+        });
+    }
+
+    // DXF export for CAD tools
+    ui.separator();
+    ui.label("Export DXF:");
+    ui.text_edit_singleline(&mut ui_state.dxf_path);
+    if ui.button("Export DXF").clicked() && !ui_state.dxf_path.is_empty() {
+        commands.write_message(ExportDxfEvent {
+            file_path: ui_state.dxf_path.clone(),
+        });
+    }
+
+    // Collider descriptions for games using bevy_rapier2d
+    ui.label("Export Rapier2D Colliders:");
+    ui.text_edit_singleline(&mut ui_state.rapier_path);
+    if ui.button("Export Colliders").clicked() && !ui_state.rapier_path.is_empty() {
+        commands.write_message(ExportRapierEvent {
+            file_path: ui_state.rapier_path.clone(),
+        });
+    }
+
+    // Rust source export of the shapes in the save scope, for test fixtures
+    ui.separator();
+    ui.label("Export Rust:");
+    ui.text_edit_singleline(&mut ui_state.rust_path);
+    if ui.button("Export Rust").clicked() && !ui_state.rust_path.is_empty() {
+        commands.write_message(ExportRustEvent {
+            file_path: ui_state.rust_path.clone(),
+            scope: ui_state.save_scope,
+        });
+    }
+
+    // qgeometry regression test for the two selected shapes or the first colliding pair
+    ui.label("Export Collision Fixture:");
+    ui.text_edit_singleline(&mut ui_state.fixture_path);
+    if ui.button("Export Collision Fixture").clicked() && !ui_state.fixture_path.is_empty() {
+        commands.write_message(ExportCollisionFixtureEvent {
+            file_path: ui_state.fixture_path.clone(),
+        });
+    }
+
+    // PNG export of the viewport or a world rectangle
+    ui.separator();
+    ui.label("Export Image:");
+    ui.text_edit_singleline(&mut ui_state.png_path);
+    ui.checkbox(&mut ui_state.png_use_viewport, "Current Viewport");
+    if !ui_state.png_use_viewport {
+        ui.horizontal(|ui| {
+            ui.label("Min:");
+            ui.add(egui::DragValue::new(&mut ui_state.png_region.min.x).speed(0.1));
+            ui.add(egui::DragValue::new(&mut ui_state.png_region.min.y).speed(0.1));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Max:");
+            ui.add(egui::DragValue::new(&mut ui_state.png_region.max.x).speed(0.1));
+            ui.add(egui::DragValue::new(&mut ui_state.png_region.max.y).speed(0.1));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Size:");
+            ui.add(
+                egui::DragValue::new(&mut ui_state.png_resolution.x)
+                    .range(1..=16384)
+                    .suffix(" px"),
+            );
+            ui.add(
+                egui::DragValue::new(&mut ui_state.png_resolution.y)
+                    .range(1..=16384)
+                    .suffix(" px"),
+            );
+        });
+    }
+    let export = ui
+        .button("Export Image")
+        .on_hover_text("Render the scene with grid and shapes to a PNG file");
+    if export.clicked() && !ui_state.png_path.is_empty() {
+        commands.write_message(ExportPngEvent {
+            file_path: ui_state.png_path.clone(),
+            region: (!ui_state.png_use_viewport).then_some(ui_state.png_region),
+            resolution: (!ui_state.png_use_viewport).then_some(ui_state.png_resolution),
+        });
+    }
+    ui.horizontal(|ui| {
+        let screenshot = ui
+            .button("Screenshot")
+            .on_hover_text("Save the viewport without the UI (F12)");
+        ui.add(
+            egui::DragValue::new(&mut ui_state.screenshot_scale)
+                .range(1..=4)
+                .prefix("x"),
+        );
+        if screenshot.clicked() {
+            commands.write_message(TakeScreenshotEvent);
+        }
+    });
+
     // Snap to grid checkbox
     ui.separator();
     ui.label("Options:");
@@ -225,3 +1079,554 @@ pub fn toggle_ui_visibility(mut ui_state: ResMut<UiState>, keyboard_input: Res<B
         ui_state.panel_visible = !ui_state.panel_visible;
     }
 }
+
+/// System to render the settings window
+pub fn draw_settings_window(
+    mut contexts: EguiContexts, mut ui_state: ResMut<UiState>, mut camera_settings: ResMut<CameraSettings>,
+    mut split_view: ResMut<SplitView>, mut camera_query: Query<&mut Transform, With<CameraMovement>>,
+    mut coordinate_settings: ResMut<CoordinateSettings>, mut guides: ResMut<Guides>,
+    mut reference_image: ResMut<ReferenceImage>, mut collision_settings: ResMut<CollisionDetectionSettings>,
+    gjk_debugger: Res<GjkDebugger>, mut measure: ResMut<MeasureTool>, mut shapes_settings: ResMut<ShapesSettings>,
+    mut commands: Commands,
+) {
+    if !ui_state.panel_visible || !ui_state.settings_visible {
+        return;
+    }
+    let Ok(ctx) = contexts.ctx_mut() else {
+        return;
+    };
+
+    let mut open = true;
+    egui::Window::new("Settings")
+        .open(&mut open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.label("Camera:");
+            ui.horizontal(|ui| {
+                ui.label("Home Position");
+                ui.add(
+                    egui::DragValue::new(&mut camera_settings.home_position.x)
+                        .speed(0.1)
+                        .prefix("x: "),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut camera_settings.home_position.y)
+                        .speed(0.1)
+                        .prefix("y: "),
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.label("Home Zoom");
+                ui.add(
+                    egui::DragValue::new(&mut camera_settings.home_zoom)
+                        .speed(0.001)
+                        .range(0.001..=10.0),
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.label("Easing Duration");
+                ui.add(egui::Slider::new(&mut camera_settings.easing_duration, 0.0..=1.0).suffix(" s"))
+                    .on_hover_text("Time camera movements take to ease into place, 0 jumps instantly");
+            });
+            ui.horizontal(|ui| {
+                ui.label("Zoom Range");
+                let max_zoom = camera_settings.max_zoom;
+                ui.add(
+                    egui::DragValue::new(&mut camera_settings.min_zoom)
+                        .speed(0.001)
+                        .range(0.0001..=max_zoom),
+                );
+                let min_zoom = camera_settings.min_zoom;
+                ui.add(
+                    egui::DragValue::new(&mut camera_settings.max_zoom)
+                        .speed(0.01)
+                        .range(min_zoom..=1000.0),
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.label("Zoom Speed");
+                ui.add(egui::Slider::new(&mut camera_settings.zoom_speed, 0.01..=0.5));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Pan Sensitivity");
+                ui.add(egui::Slider::new(&mut camera_settings.pan_sensitivity, 0.1..=5.0));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Pan Button");
+                ui.radio_value(&mut camera_settings.pan_button, MouseButton::Middle, "Middle");
+                ui.radio_value(&mut camera_settings.pan_button, MouseButton::Right, "Right")
+                    .on_hover_text("Polygons are then finished with Enter and vertices deleted with Alt+click");
+            });
+            ui.checkbox(&mut camera_settings.pan_inertia, "Pan Inertia")
+                .on_hover_text("Keep gliding after a drag is released");
+            ui.checkbox(&mut camera_settings.invert_wheel, "Invert Mouse Wheel");
+            ui.checkbox(&mut camera_settings.space_drag_pan, "Pan with Space + Left Drag");
+            ui.checkbox(&mut camera_settings.touchpad_pan, "Pan with Touchpad Scrolling")
+                .on_hover_text("Two-finger scrolling pans, hold Ctrl to zoom instead");
+            ui.horizontal(|ui| {
+                ui.label("Follow Smoothing");
+                ui.add(egui::Slider::new(&mut camera_settings.follow_smoothing, 0.0..=2.0).suffix(" s"));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Follow Dead Zone");
+                ui.add(egui::Slider::new(&mut camera_settings.follow_dead_zone, 0.0..=400.0).suffix(" px"));
+            });
+            if let Ok(mut transform) = camera_query.single_mut() {
+                ui.horizontal(|ui| {
+                    ui.label("Rotation");
+                    let mut degrees = camera_angle(&transform).to_degrees();
+                    let response = ui.add(
+                        egui::DragValue::new(&mut degrees)
+                            .speed(0.5)
+                            .range(-180.0..=180.0)
+                            .suffix("°"),
+                    );
+                    if response.changed() {
+                        transform.rotation = Quat::from_rotation_z(degrees.to_radians());
+                    }
+                    if ui.button("Reset Rotation").clicked() {
+                        transform.rotation = Quat::IDENTITY;
+                    }
+                })
+                .response
+                .on_hover_text("Alt+middle-drag in the viewport also rotates the view");
+            }
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut camera_settings.shake_enabled, "Shake on Impacts")
+                    .on_hover_text("Debug aid: shake the view when a contact impulse exceeds the threshold");
+                ui.add_enabled(
+                    camera_settings.shake_enabled,
+                    egui::DragValue::new(&mut camera_settings.shake_impulse_threshold)
+                        .speed(1.0)
+                        .range(0.0..=f32::MAX),
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut split_view.enabled, "Split View")
+                    .on_hover_text("Show a second viewport with its own pan and zoom");
+                ui.add_enabled_ui(split_view.enabled, |ui| {
+                    ui.selectable_value(
+                        &mut split_view.orientation,
+                        SplitOrientation::Horizontal,
+                        "Side by Side",
+                    );
+                    ui.selectable_value(&mut split_view.orientation, SplitOrientation::Vertical, "Stacked");
+                });
+            });
+            if ui.button("Set Home to Current View").clicked() {
+                if let Ok(transform) = camera_query.single() {
+                    camera_settings.home_position = transform.translation.truncate();
+                    camera_settings.home_zoom = transform.scale.x;
+                }
+            }
+
+            ui.separator();
+            ui.label("Grid:");
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut coordinate_settings.show_grid, "Grid")
+                    .on_hover_text("G");
+                ui.checkbox(&mut coordinate_settings.show_axes, "Axes")
+                    .on_hover_text("X");
+            });
+            ui.horizontal(|ui| {
+                ui.label("Mode");
+                ui.selectable_value(&mut coordinate_settings.grid_mode, GridMode::Cartesian, "Cartesian");
+                ui.selectable_value(&mut coordinate_settings.grid_mode, GridMode::Polar, "Polar");
+                ui.selectable_value(&mut coordinate_settings.grid_mode, GridMode::Isometric, "Isometric");
+                ui.selectable_value(&mut coordinate_settings.grid_mode, GridMode::Hex, "Hex");
+            });
+            ui.horizontal(|ui| {
+                ui.label("Style");
+                ui.selectable_value(&mut coordinate_settings.grid_style, GridStyle::Lines, "Lines");
+                ui.selectable_value(&mut coordinate_settings.grid_style, GridStyle::Dots, "Dots");
+            });
+            if coordinate_settings.grid_mode == GridMode::Polar {
+                ui.horizontal(|ui| {
+                    ui.label("Polar Origin");
+                    ui.add(
+                        egui::DragValue::new(&mut coordinate_settings.polar_origin.x)
+                            .speed(0.1)
+                            .prefix("x: "),
+                    );
+                    ui.add(
+                        egui::DragValue::new(&mut coordinate_settings.polar_origin.y)
+                            .speed(0.1)
+                            .prefix("y: "),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Spokes");
+                    ui.add(egui::DragValue::new(&mut coordinate_settings.polar_spokes).range(1..=360));
+                });
+            }
+            ui.horizontal(|ui| {
+                ui.label("Minor Spacing");
+                ui.add(
+                    egui::DragValue::new(&mut coordinate_settings.grid_spacing)
+                        .speed(0.1)
+                        .range(0.001..=10000.0),
+                );
+                color_edit(ui, &mut coordinate_settings.grid_color);
+            });
+            ui.horizontal(|ui| {
+                ui.label("Major Spacing");
+                ui.add(
+                    egui::DragValue::new(&mut coordinate_settings.chunk_spacing)
+                        .speed(1.0)
+                        .range(0.001..=100000.0),
+                );
+                color_edit(ui, &mut coordinate_settings.chunk_color);
+                ui.add(
+                    egui::DragValue::new(&mut coordinate_settings.chunk_line_width)
+                        .speed(0.1)
+                        .range(0.5..=10.0)
+                        .suffix(" px"),
+                );
+            });
+            ui.checkbox(&mut coordinate_settings.show_axis_labels, "Axis Labels")
+                .on_hover_text("L");
+            ui.checkbox(
+                &mut coordinate_settings.show_intersection_labels,
+                "Grid Intersection Labels",
+            );
+            ui.checkbox(&mut coordinate_settings.show_chunk_overlay, "Chunk Overlay")
+                .on_hover_text("Show the chunk boundaries in every grid mode and label the chunks");
+            ui.checkbox(&mut coordinate_settings.show_origin_marker, "Origin Marker");
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut coordinate_settings.show_crosshair, "Cursor Crosshair");
+                color_edit(ui, &mut coordinate_settings.crosshair_color);
+            });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut coordinate_settings.show_rulers, "Rulers")
+                    .on_hover_text("Drag from a ruler into the scene to add a guide line");
+                if ui
+                    .selectable_label(measure.active, "Measure (M)")
+                    .on_hover_text("Click two points to add a dimension line to the AuxiliaryLine layer")
+                    .clicked()
+                {
+                    measure.active = !measure.active;
+                    measure.start = None;
+                    if measure.active {
+                        ui_state.selected_shape = None;
+                        ui_state.edit_vertices = false;
+                    }
+                }
+                let guide_count = guides.horizontal.len() + guides.vertical.len();
+                if ui
+                    .add_enabled(
+                        guide_count > 0,
+                        egui::Button::new(format!("Clear Guides ({})", guide_count)),
+                    )
+                    .clicked()
+                {
+                    guides.horizontal.clear();
+                    guides.vertical.clear();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Origin");
+                ui.add(
+                    egui::DragValue::new(&mut coordinate_settings.origin_offset.x)
+                        .speed(0.1)
+                        .prefix("x: "),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut coordinate_settings.origin_offset.y)
+                        .speed(0.1)
+                        .prefix("y: "),
+                );
+                if ui.button("Reset").clicked() {
+                    coordinate_settings.origin_offset = Vec2::ZERO;
+                }
+            })
+            .response
+            .on_hover_text("Displayed coordinates are relative to this point, O moves it to the cursor");
+            ui.separator();
+            // Snap targets are toggled one by one, the distance applies to all of them
+            let settings = &mut *coordinate_settings;
+            ui.checkbox(&mut settings.object_snap.enabled, "Object Snap")
+                .on_hover_text("Snap to shape features near the cursor, ahead of the grid");
+            ui.add_enabled_ui(settings.object_snap.enabled, |ui| {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut settings.object_snap.vertices, "Vertices");
+                    ui.checkbox(&mut settings.object_snap.midpoints, "Midpoints");
+                    ui.checkbox(&mut settings.object_snap.centers, "Centers");
+                    ui.checkbox(&mut settings.object_snap.intersections, "Intersections");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Snap Distance");
+                    ui.add(egui::Slider::new(&mut settings.object_snap_pixels, 2.0..=30.0).suffix(" px"));
+                });
+            });
+            ui.separator();
+            ui.label("Units:");
+            ui.horizontal(|ui| {
+                let presets = [
+                    (DisplayUnit::world(), "World"),
+                    (DisplayUnit::meters(), "Meters"),
+                    (DisplayUnit::pixels(), "Pixels"),
+                ];
+                for (preset, name) in presets {
+                    let unit = &mut coordinate_settings.unit;
+                    if ui.selectable_label(*unit == preset, name).clicked() {
+                        *unit = preset;
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("1 world unit =");
+                let per_world_unit = &mut coordinate_settings.unit.per_world_unit;
+                ui.add(egui::DragValue::new(per_world_unit).speed(0.1).range(0.0001..=100000.0));
+                ui.add(egui::TextEdit::singleline(&mut coordinate_settings.unit.suffix).desired_width(40.0))
+                    .on_hover_text("Unit suffix, starting with a space");
+            });
+
+            ui.separator();
+            ui.label("Drawing:");
+            ui.horizontal(|ui| {
+                ui.label("Line Angle Step");
+                ui.add(
+                    egui::DragValue::new(&mut shapes_settings.line_angle_step)
+                        .speed(1.0)
+                        .range(1.0..=90.0)
+                        .suffix("°"),
+                )
+                .on_hover_text("Lines snap to multiples of this angle while Shift is held");
+            });
+
+            ui.separator();
+            ui.label("Collision:");
+            ui.horizontal(|ui| {
+                ui.checkbox(
+                    &mut collision_settings.show_minkowski_difference,
+                    "Minkowski Difference",
+                )
+                .on_hover_text("Shown for two selected polygons");
+                color_edit(ui, &mut collision_settings.shape_color_minkowski_difference);
+            });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut collision_settings.show_minkowski_sum, "Minkowski Sum")
+                    .on_hover_text("Shown for two selected polygons");
+                color_edit(ui, &mut collision_settings.shape_color_minkowski_sum);
+            });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut collision_settings.show_closest_points, "Closest Points")
+                    .on_hover_text("Shortest segment between two selected shapes, with its length");
+                color_edit(ui, &mut collision_settings.shape_color_closest_points);
+            });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut collision_settings.show_gjk_steps, "GJK/EPA Steps")
+                    .on_hover_text("Step through the collision algorithm for two selected shapes with . and ,");
+                color_edit(ui, &mut collision_settings.shape_color_gjk);
+            });
+            if let Some(step) = gjk_debugger.steps.get(gjk_debugger.current) {
+                let phase = match step.phase {
+                    GjkPhase::Gjk => "GJK",
+                    GjkPhase::Epa => "EPA",
+                };
+                ui.label(format!(
+                    "{} step {}/{}",
+                    phase,
+                    gjk_debugger.current + 1,
+                    gjk_debugger.steps.len()
+                ));
+                ui.label(&step.description);
+            } else if collision_settings.show_gjk_steps {
+                ui.label("Select two shapes");
+            }
+
+            ui.separator();
+            ui.label("Reference Image:");
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut ui_state.reference_image_path);
+                if ui.button("Load").on_hover_text("PNG or JPG").clicked() {
+                    commands.write_message(ReferenceImageEvent::Load {
+                        file_path: ui_state.reference_image_path.clone(),
+                    });
+                }
+                if ui
+                    .add_enabled(reference_image.file_path.is_some(), egui::Button::new("Clear"))
+                    .clicked()
+                {
+                    commands.write_message(ReferenceImageEvent::Clear);
+                }
+            });
+            if reference_image.file_path.is_some() {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut reference_image.visible, "Visible");
+                    ui.checkbox(&mut reference_image.locked, "Locked");
+                });
+                ui.add(egui::Slider::new(&mut reference_image.opacity, 0.0..=1.0).text("Opacity"));
+                let locked = reference_image.locked;
+                ui.add_enabled_ui(!locked, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Position");
+                        ui.add(
+                            egui::DragValue::new(&mut reference_image.position.x)
+                                .speed(0.1)
+                                .prefix("x: "),
+                        );
+                        ui.add(
+                            egui::DragValue::new(&mut reference_image.position.y)
+                                .speed(0.1)
+                                .prefix("y: "),
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Scale");
+                        ui.add(
+                            egui::DragValue::new(&mut reference_image.scale)
+                                .speed(0.001)
+                                .range(0.0001..=100.0),
+                        )
+                        .on_hover_text("World units per image pixel");
+                    });
+                });
+            }
+        });
+    ui_state.settings_visible = open;
+}
+
+/// Color picker button editing a Bevy color
+fn color_edit(ui: &mut Ui, color: &mut Color) {
+    let [r, g, b, a] = color.to_srgba().to_u8_array();
+    let mut color32 = egui::Color32::from_rgba_unmultiplied(r, g, b, a);
+    if egui::color_picker::color_edit_button_srgba(ui, &mut color32, egui::color_picker::Alpha::OnlyBlend).changed() {
+        let [r, g, b, a] = color32.to_srgba_unmultiplied();
+        *color = Color::srgba_u8(r, g, b, a);
+    }
+}
+
+/// System to turn save/load results into notifications
+pub fn collect_save_load_notifications(
+    mut results: MessageReader<SaveLoadResultEvent>, mut notifications: ResMut<Notifications>,
+) {
+    for result in results.read() {
+        match result {
+            SaveLoadResultEvent::SaveCompleted { file_path } => {
+                notifications.push(format!("Saved {}", file_path), false);
+            }
+            SaveLoadResultEvent::SaveFailed { file_path, error } => {
+                notifications.push(format!("Failed to save {}: {}", file_path, error), true);
+            }
+            SaveLoadResultEvent::LoadCompleted {
+                file_path,
+                count,
+                skipped,
+            } => {
+                notifications.push(format!("Loaded {} items from {}", count, file_path), false);
+                if *skipped > 0 {
+                    notifications.push(
+                        format!("Skipped {} degenerate shapes in {}, see the log", skipped, file_path),
+                        true,
+                    );
+                }
+            }
+            SaveLoadResultEvent::LoadFailed { file_path, error } => {
+                notifications.push(format!("Failed to load {}: {}", file_path, error), true);
+            }
+        }
+    }
+}
+
+/// System to draw the status bar along the bottom of the window
+pub fn draw_status_bar(
+    mut contexts: EguiContexts, mut commands: Commands, ui_state: Res<UiState>, camera_settings: Res<CameraSettings>,
+    mut camera_query: Query<(Entity, &mut Transform), With<CameraMovement>>,
+    coordinate_settings: Res<CoordinateSettings>, snap_target: Res<SnapTarget>,
+) {
+    if !ui_state.panel_visible {
+        return;
+    }
+    let (Ok(ctx), Ok((camera, mut transform))) = (contexts.ctx_mut(), camera_query.single_mut()) else {
+        return;
+    };
+
+    egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Zoom");
+            let mut percent = zoom_percent(transform.scale.x);
+            let response = ui.add(
+                egui::DragValue::new(&mut percent)
+                    .speed(1.0)
+                    .range(0.1..=100_000.0)
+                    .max_decimals(1)
+                    .suffix("%"),
+            );
+            if response.changed() {
+                // Typed values apply immediately instead of easing
+                let zoom = camera_settings.clamp_zoom(zoom_from_percent(percent));
+                commands.entity(camera).remove::<CameraTarget>();
+                transform.scale = Vec3::new(zoom, zoom, transform.scale.z);
+            }
+            ui.menu_button("Presets", |ui| {
+                for preset in ZOOM_PRESETS {
+                    if ui.button(format!("{}%", preset)).clicked() {
+                        let zoom = camera_settings.clamp_zoom(zoom_from_percent(preset));
+                        let position = transform.translation.truncate();
+                        commands
+                            .entity(camera)
+                            .insert(CameraTarget::new(&transform, position, zoom));
+                        ui.close();
+                    }
+                }
+            })
+            .response
+            .on_hover_text("+/- step through the presets");
+            if let Some(position) = snap_target.position {
+                let (unit, local) = (&coordinate_settings.unit, coordinate_settings.to_local(position));
+                ui.separator();
+                ui.label(format!(
+                    "x: {}  y: {}",
+                    unit.format(local.x, 2),
+                    unit.format(local.y, 2)
+                ));
+                // The raw cursor position, when snapping moved the point a click would place
+                if let Some(cursor) = snap_target.cursor.filter(|cursor| *cursor != position) {
+                    let local = coordinate_settings.to_local(cursor);
+                    ui.weak(format!(
+                        "cursor x: {}  y: {}",
+                        unit.format(local.x, 2),
+                        unit.format(local.y, 2)
+                    ))
+                    .on_hover_text("Cursor position before snapping");
+                }
+            }
+        });
+    });
+}
+
+/// System to draw notifications in the bottom right corner and expire old ones
+pub fn draw_notifications(mut contexts: EguiContexts, mut notifications: ResMut<Notifications>, time: Res<Time>) {
+    let delta = time.delta_secs();
+    notifications.entries.retain_mut(|notification| {
+        notification.remaining -= delta;
+        notification.remaining > 0.0
+    });
+    if notifications.entries.is_empty() {
+        return;
+    }
+
+    if let Ok(ctx) = contexts.ctx_mut() {
+        // Stay above the status bar
+        let bottom_margin = ctx.screen_rect().bottom() - ctx.available_rect().bottom();
+        egui::Area::new(egui::Id::new("notifications"))
+            .anchor(
+                egui::Align2::RIGHT_BOTTOM,
+                egui::Vec2::new(-10.0, -10.0 - bottom_margin),
+            )
+            .show(ctx, |ui| {
+                for notification in notifications.entries.iter() {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        let color = if notification.is_error {
+                            egui::Color32::RED
+                        } else {
+                            ui.visuals().text_color()
+                        };
+                        ui.colored_label(color, &notification.text);
+                    });
+                }
+            });
+    }
+}
@@ -1,6 +1,47 @@
 use bevy::prelude::*;
+use qmath::dir::QDir;
+use qmath::prelude::*;
 use qmath::vec2::QVec2;
 
 pub fn qvec2vec(qvec: QVec2) -> Vec2 {
     Vec2::new(qvec.x.to_num::<f32>(), qvec.y.to_num::<f32>())
 }
+
+pub fn qdir2angle(qdir: QDir) -> f32 {
+    let dir = qvec2vec(qdir.to_vec());
+    dir.y.atan2(dir.x)
+}
+
+/// Convert a float to Q64, refusing values that aren't finite or are outside the Q64 range
+pub fn checked_q64(value: f64) -> Result<Q64, String> {
+    Q64::checked_from_num(value).ok_or_else(|| format!("{} is out of the Q64 range", value))
+}
+
+/// Dot product of two vectors
+pub fn dot(a: QVec2, b: QVec2) -> Q64 {
+    a.x.saturating_mul(b.x).saturating_add(a.y.saturating_mul(b.y))
+}
+
+/// 2D cross product of two vectors, returning the z component
+pub fn cross(a: QVec2, b: QVec2) -> Q64 {
+    a.x.saturating_mul(b.y).saturating_sub(a.y.saturating_mul(b.x))
+}
+
+/// Point of the segment from `a` to `b` closest to `point`
+pub fn closest_on_segment(point: QVec2, (a, b): (QVec2, QVec2)) -> QVec2 {
+    let ab = b.saturating_sub(a);
+    let length_squared = dot(ab, ab);
+    if length_squared == Q64::ZERO {
+        return a;
+    }
+    let t = dot(point.saturating_sub(a), ab)
+        .saturating_div(length_squared)
+        .clamp(Q64::ZERO, Q64::ONE);
+    a.saturating_add(ab.saturating_mul_num(t))
+}
+
+/// Vector from float coordinates, to write test positions briefly
+#[cfg(test)]
+pub fn qvec(x: f64, y: f64) -> QVec2 {
+    QVec2::new(Q64::from_num(x), Q64::from_num(y))
+}